@@ -0,0 +1,549 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    process::Stdio,
+};
+
+use anyhow::{Context, bail};
+use lyra_probe::{ProbeData, StreamKind, get_ffmpeg_path};
+use tokio::{io::AsyncReadExt, process::Command as TokioCommand, task::spawn_blocking};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info};
+
+use crate::{
+    COMMERCIAL_ASPECT_TOLERANCE, COMMERCIAL_CUT_TOLERANCE_SECONDS, COMMERCIAL_FRAME_HEIGHT,
+    COMMERCIAL_FRAME_WIDTH, COMMERCIAL_MAX_BLOCK_SECONDS, COMMERCIAL_MAX_BREAK_SECONDS,
+    COMMERCIAL_MIN_BLACK_SECONDS, COMMERCIAL_MIN_BREAK_SECONDS, COMMERCIAL_MIN_SILENCE_SECONDS,
+    COMMERCIAL_SPOT_LENGTHS_SECONDS, COMMERCIAL_SPOT_TOLERANCE_SECONDS,
+};
+
+// logo detection only looks at the corners of the downscaled frame, where channel bugs sit
+const CORNER_WIDTH: usize = COMMERCIAL_FRAME_WIDTH / 4;
+const CORNER_HEIGHT: usize = COMMERCIAL_FRAME_HEIGHT / 4;
+const CORNER_PIXELS: usize = CORNER_WIDTH * CORNER_HEIGHT * 4;
+const FRAME_PIXELS: usize = COMMERCIAL_FRAME_WIDTH * COMMERCIAL_FRAME_HEIGHT;
+const EDGE_THRESHOLD: u16 = 40;
+// a pixel is part of the logo when it's an edge in most sampled frames. ads usually make up
+// well under half of a recording, so the logo still wins the vote.
+const LOGO_PIXEL_RATIO: f32 = 0.6;
+const MIN_LOGO_PIXELS: usize = 8;
+const MIN_LOGO_FRAMES: usize = 120;
+const LOGO_PRESENT_RATIO: f32 = 0.5;
+// act breaks in ordinary programmes also fade to black with silence, sometimes a spot length
+// apart, so length alone never marks a block. it takes the logo going away or the picture
+// changing shape on top of a spot length, or both of those together.
+const SCORE_STANDARD_LENGTH: f32 = 1.0;
+const SCORE_LOGO_ABSENT: f32 = 2.0;
+const SCORE_LOGO_PRESENT: f32 = -2.0;
+const SCORE_ASPECT_MISMATCH: f32 = 2.0;
+const COMMERCIAL_SCORE_THRESHOLD: f32 = 3.0;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileCommercialDetection {
+    pub path: PathBuf,
+    pub commercials: Vec<CommercialRange>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CommercialRange {
+    pub start_seconds: f32,
+    pub end_seconds: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Interval {
+    start_seconds: f64,
+    end_seconds: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct AspectSample {
+    time_seconds: f64,
+    ratio: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Block {
+    start_seconds: f64,
+    end_seconds: f64,
+    score: f32,
+}
+
+#[derive(Debug, Default)]
+struct DetectLog {
+    black: Vec<Interval>,
+    silence: Vec<Interval>,
+    aspect: Vec<AspectSample>,
+}
+
+// comskip-style detection for recorded tv. breaks are bounded by black frames that line up
+// with silence, and the blocks between those cuts are scored on spot-like lengths, the
+// channel logo disappearing and the picture changing aspect ratio.
+pub async fn detect_commercials(
+    file_path: &Path,
+    probe_data: &ProbeData,
+    cancellation_token: Option<&CancellationToken>,
+) -> anyhow::Result<Option<FileCommercialDetection>> {
+    let duration_seconds = probe_data
+        .duration_secs
+        .context("missing file duration from probe")?;
+    if probe_data.get_video_stream().is_none() {
+        return Ok(Some(FileCommercialDetection {
+            path: file_path.to_path_buf(),
+            commercials: Vec::new(),
+        }));
+    }
+
+    let has_audio = probe_data
+        .streams
+        .iter()
+        .any(|stream| stream.kind() == StreamKind::Audio);
+
+    debug!(
+        path = %file_path.display(),
+        duration_seconds,
+        has_audio,
+        "scanning recording for commercial breaks"
+    );
+
+    // one pass decodes everything: black/crop detection run at full rate, then the video is
+    // sampled at 1fps into tiny grayscale frames for the logo check.
+    let mut filter_graph = format!(
+        "[0:v:0]blackdetect=d={COMMERCIAL_MIN_BLACK_SECONDS}:pix_th=0.10,fps=1,\
+         cropdetect=limit=24:round=2:reset=1,\
+         scale={COMMERCIAL_FRAME_WIDTH}:{COMMERCIAL_FRAME_HEIGHT},format=gray[v]"
+    );
+    if has_audio {
+        filter_graph.push_str(&format!(
+            ";[0:a:0]silencedetect=noise=-50dB:d={COMMERCIAL_MIN_SILENCE_SECONDS}[a]"
+        ));
+    }
+
+    let mut command = TokioCommand::new(get_ffmpeg_path());
+    command
+        .kill_on_drop(true)
+        .arg("-hide_banner")
+        .arg("-nostats")
+        .arg("-loglevel")
+        .arg("info")
+        .arg("-nostdin")
+        .arg("-i")
+        .arg(file_path)
+        .arg("-filter_complex")
+        .arg(filter_graph)
+        .arg("-map")
+        .arg("[v]")
+        .arg("-f")
+        .arg("rawvideo")
+        .arg("-pix_fmt")
+        .arg("gray")
+        .arg("pipe:1");
+    if has_audio {
+        command
+            .arg("-map")
+            .arg("[a]")
+            .arg("-f")
+            .arg("null")
+            .arg("-");
+    }
+
+    let mut ffmpeg = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to start ffmpeg for '{}'", file_path.display()))?;
+    let mut stdout = ffmpeg.stdout.take().context("ffmpeg stdout missing")?;
+    let mut stderr = ffmpeg.stderr.take().context("ffmpeg stderr missing")?;
+
+    let output = async move {
+        let mut frames = Vec::new();
+        let mut log = Vec::new();
+        tokio::try_join!(
+            stdout.read_to_end(&mut frames),
+            stderr.read_to_end(&mut log)
+        )?;
+        let status = ffmpeg.wait().await?;
+        anyhow::Ok((frames, log, status))
+    };
+    tokio::pin!(output);
+    let (frames, log, status) = if let Some(cancellation_token) = cancellation_token {
+        tokio::select! {
+            output = &mut output => output?,
+            _ = cancellation_token.cancelled() => return Ok(None),
+        }
+    } else {
+        output.await?
+    };
+
+    let log = String::from_utf8_lossy(&log).into_owned();
+    if !status.success() {
+        bail!(
+            "ffmpeg failed for '{}': {}",
+            file_path.display(),
+            log.trim()
+        );
+    }
+
+    let commercials = spawn_blocking(move || {
+        let detect_log = parse_detect_output(&log, duration_seconds);
+        let logo = logo_presence(&frames);
+        let cuts = find_cuts(&detect_log.black, &detect_log.silence, has_audio);
+        select_commercial_breaks(&cuts, duration_seconds, logo.as_deref(), &detect_log.aspect)
+    })
+    .await
+    .context("commercial detection worker failed to join")?;
+
+    info!(
+        path = %file_path.display(),
+        breaks = commercials.len(),
+        "commercial detection complete"
+    );
+
+    Ok(Some(FileCommercialDetection {
+        path: file_path.to_path_buf(),
+        commercials,
+    }))
+}
+
+fn parse_detect_output(stderr: &str, duration_seconds: f64) -> DetectLog {
+    let mut output = DetectLog::default();
+    let mut silence_start = None::<f64>;
+
+    for line in stderr.lines() {
+        if let (Some(start_seconds), Some(end_seconds)) = (
+            parse_field(line, "black_start:"),
+            parse_field(line, "black_end:"),
+        ) {
+            output.black.push(Interval {
+                start_seconds,
+                end_seconds,
+            });
+        } else if let Some(start_seconds) = parse_field(line, "silence_start:") {
+            silence_start = Some(start_seconds);
+        } else if let Some(end_seconds) = parse_field(line, "silence_end:")
+            && let Some(start_seconds) = silence_start.take()
+        {
+            output.silence.push(Interval {
+                start_seconds,
+                end_seconds,
+            });
+        } else if line.contains("crop=")
+            && let (Some(width), Some(height), Some(time_seconds)) = (
+                parse_field(line, " w:"),
+                parse_field(line, " h:"),
+                parse_field(line, " t:"),
+            )
+            && width > 0.0
+            && height > 0.0
+        {
+            output.aspect.push(AspectSample {
+                time_seconds,
+                ratio: width / height,
+            });
+        }
+    }
+
+    if let Some(start_seconds) = silence_start {
+        output.silence.push(Interval {
+            start_seconds,
+            end_seconds: duration_seconds,
+        });
+    }
+
+    output
+}
+
+fn parse_field(line: &str, key: &str) -> Option<f64> {
+    let (_, rest) = line.split_once(key)?;
+    rest.split_whitespace().next()?.parse().ok()
+}
+
+// marks which corner pixels have a strong gradient. the channel logo is the set of pixels
+// that stay edges across most of the recording.
+fn corner_edges(frame: &[u8]) -> Vec<bool> {
+    let mut edges = Vec::with_capacity(CORNER_PIXELS);
+    let corners = [
+        (0, 0),
+        (COMMERCIAL_FRAME_WIDTH - CORNER_WIDTH, 0),
+        (0, COMMERCIAL_FRAME_HEIGHT - CORNER_HEIGHT),
+        (
+            COMMERCIAL_FRAME_WIDTH - CORNER_WIDTH,
+            COMMERCIAL_FRAME_HEIGHT - CORNER_HEIGHT,
+        ),
+    ];
+    for (corner_x, corner_y) in corners {
+        for y in corner_y..corner_y + CORNER_HEIGHT {
+            for x in corner_x..corner_x + CORNER_WIDTH {
+                let pixel = u16::from(frame[y * COMMERCIAL_FRAME_WIDTH + x]);
+                let right =
+                    frame[y * COMMERCIAL_FRAME_WIDTH + (x + 1).min(COMMERCIAL_FRAME_WIDTH - 1)];
+                let below =
+                    frame[(y + 1).min(COMMERCIAL_FRAME_HEIGHT - 1) * COMMERCIAL_FRAME_WIDTH + x];
+                let gradient = pixel.abs_diff(u16::from(right)) + pixel.abs_diff(u16::from(below));
+                edges.push(gradient > EDGE_THRESHOLD);
+            }
+        }
+    }
+
+    edges
+}
+
+// per-second logo visibility, or None when the recording doesn't have a stable logo to go by
+fn logo_presence(frames: &[u8]) -> Option<Vec<bool>> {
+    let edges = frames
+        .chunks_exact(FRAME_PIXELS)
+        .map(corner_edges)
+        .collect::<Vec<_>>();
+    if edges.len() < MIN_LOGO_FRAMES {
+        return None;
+    }
+
+    let mut counts = vec![0usize; CORNER_PIXELS];
+    for frame in &edges {
+        for (count, edge) in counts.iter_mut().zip(frame) {
+            *count += usize::from(*edge);
+        }
+    }
+
+    let mask = counts
+        .iter()
+        .enumerate()
+        .filter(|(_, count)| **count as f32 / edges.len() as f32 >= LOGO_PIXEL_RATIO)
+        .map(|(index, _)| index)
+        .collect::<Vec<_>>();
+    if mask.len() < MIN_LOGO_PIXELS {
+        return None;
+    }
+
+    Some(
+        edges
+            .iter()
+            .map(|frame| {
+                let visible = mask.iter().filter(|index| frame[**index]).count();
+                visible as f32 / mask.len() as f32 >= LOGO_PRESENT_RATIO
+            })
+            .collect(),
+    )
+}
+
+// a cut is a black frame with the audio dropping out at the same time. recordings without
+// audio only have the black frames to go on.
+fn find_cuts(black: &[Interval], silence: &[Interval], has_audio: bool) -> Vec<f64> {
+    let mut cuts = black
+        .iter()
+        .filter(|black| {
+            !has_audio
+                || silence.iter().any(|silence| {
+                    silence.start_seconds <= black.end_seconds + COMMERCIAL_CUT_TOLERANCE_SECONDS
+                        && silence.end_seconds
+                            >= black.start_seconds - COMMERCIAL_CUT_TOLERANCE_SECONDS
+                })
+        })
+        .map(|black| (black.start_seconds + black.end_seconds) / 2.0)
+        .collect::<Vec<_>>();
+    cuts.sort_by(f64::total_cmp);
+    cuts.dedup_by(|next, previous| *next - *previous < COMMERCIAL_CUT_TOLERANCE_SECONDS);
+    cuts
+}
+
+fn select_commercial_breaks(
+    cuts: &[f64],
+    duration_seconds: f64,
+    logo: Option<&[bool]>,
+    aspect: &[AspectSample],
+) -> Vec<CommercialRange> {
+    let dominant_ratio = dominant_aspect_ratio(aspect);
+    let boundaries = std::iter::once(0.0)
+        .chain(
+            cuts.iter()
+                .copied()
+                .filter(|cut| *cut > 0.0 && *cut < duration_seconds),
+        )
+        .chain(std::iter::once(duration_seconds))
+        .collect::<Vec<_>>();
+
+    let blocks = boundaries
+        .windows(2)
+        .filter(|window| window[1] > window[0])
+        .map(|window| Block {
+            start_seconds: window[0],
+            end_seconds: window[1],
+            score: score_block(window[0], window[1], logo, aspect, dominant_ratio),
+        })
+        .collect::<Vec<_>>();
+
+    let mut breaks = Vec::<Interval>::new();
+    for block in blocks {
+        let is_commercial = block.end_seconds - block.start_seconds <= COMMERCIAL_MAX_BLOCK_SECONDS
+            && block.score >= COMMERCIAL_SCORE_THRESHOLD;
+        if !is_commercial {
+            continue;
+        }
+
+        if let Some(last) = breaks.last_mut()
+            && block.start_seconds - last.end_seconds <= COMMERCIAL_CUT_TOLERANCE_SECONDS
+        {
+            last.end_seconds = block.end_seconds;
+            continue;
+        }
+        breaks.push(Interval {
+            start_seconds: block.start_seconds,
+            end_seconds: block.end_seconds,
+        });
+    }
+
+    breaks
+        .into_iter()
+        .filter(|interval| {
+            let length = interval.end_seconds - interval.start_seconds;
+            (COMMERCIAL_MIN_BREAK_SECONDS..=COMMERCIAL_MAX_BREAK_SECONDS).contains(&length)
+        })
+        .map(|interval| CommercialRange {
+            start_seconds: interval.start_seconds as f32,
+            end_seconds: interval.end_seconds as f32,
+        })
+        .collect()
+}
+
+fn score_block(
+    start_seconds: f64,
+    end_seconds: f64,
+    logo: Option<&[bool]>,
+    aspect: &[AspectSample],
+    dominant_ratio: Option<f64>,
+) -> f32 {
+    let mut score = 0.0;
+    if is_standard_length(end_seconds - start_seconds) {
+        score += SCORE_STANDARD_LENGTH;
+    }
+
+    if let Some(logo) = logo {
+        let first = (start_seconds.ceil() as usize).min(logo.len());
+        let last = (end_seconds.floor() as usize).min(logo.len());
+        let frames = &logo[first..last.max(first)];
+        if !frames.is_empty() {
+            let visible =
+                frames.iter().filter(|visible| **visible).count() as f32 / frames.len() as f32;
+            if visible < 1.0 - LOGO_PRESENT_RATIO {
+                score += SCORE_LOGO_ABSENT;
+            } else if visible > LOGO_PRESENT_RATIO {
+                score += SCORE_LOGO_PRESENT;
+            }
+        }
+    }
+
+    if let Some(dominant_ratio) = dominant_ratio {
+        let samples = aspect
+            .iter()
+            .filter(|sample| {
+                sample.time_seconds >= start_seconds && sample.time_seconds < end_seconds
+            })
+            .collect::<Vec<_>>();
+        let mismatched = samples
+            .iter()
+            .filter(|sample| {
+                (sample.ratio - dominant_ratio).abs() / dominant_ratio > COMMERCIAL_ASPECT_TOLERANCE
+            })
+            .count();
+        if !samples.is_empty() && mismatched * 2 > samples.len() {
+            score += SCORE_ASPECT_MISMATCH;
+        }
+    }
+
+    score
+}
+
+fn is_standard_length(length_seconds: f64) -> bool {
+    COMMERCIAL_SPOT_LENGTHS_SECONDS
+        .iter()
+        .any(|spot| (length_seconds - spot).abs() <= COMMERCIAL_SPOT_TOLERANCE_SECONDS)
+}
+
+fn dominant_aspect_ratio(aspect: &[AspectSample]) -> Option<f64> {
+    let mut counts = BTreeMap::<i64, usize>::new();
+    for sample in aspect {
+        *counts
+            .entry((sample.ratio * 100.0).round() as i64)
+            .or_default() += 1;
+    }
+
+    counts
+        .into_iter()
+        .max_by_key(|(_, count)| *count)
+        .map(|(ratio, _)| ratio as f64 / 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AspectSample, CommercialRange, find_cuts, select_commercial_breaks};
+
+    #[test]
+    fn runs_of_spot_length_blocks_without_the_logo_become_a_break() {
+        let cuts = [300.0, 330.0, 360.0, 375.0, 1000.0, 1020.0];
+        let logo = (0..1300)
+            .map(|second| !(300..375).contains(&second))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            select_commercial_breaks(&cuts, 1300.0, Some(&logo), &[]),
+            vec![CommercialRange {
+                start_seconds: 300.0,
+                end_seconds: 375.0,
+            }]
+        );
+
+        // a single 4:3 spot in a 16:9 recording is enough once the aspect change is counted
+        let aspect = (0..1300)
+            .map(|second| AspectSample {
+                time_seconds: f64::from(second),
+                ratio: if (600..630).contains(&second) {
+                    4.0 / 3.0
+                } else {
+                    16.0 / 9.0
+                },
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            select_commercial_breaks(&[600.0, 630.0], 1300.0, None, &aspect),
+            vec![CommercialRange {
+                start_seconds: 600.0,
+                end_seconds: 630.0,
+            }]
+        );
+    }
+
+    #[test]
+    fn cuts_need_black_and_silence_together() {
+        let black = [
+            super::Interval {
+                start_seconds: 10.0,
+                end_seconds: 10.2,
+            },
+            super::Interval {
+                start_seconds: 40.0,
+                end_seconds: 40.5,
+            },
+        ];
+        let silence = [super::Interval {
+            start_seconds: 39.9,
+            end_seconds: 40.6,
+        }];
+        assert_eq!(find_cuts(&black, &silence, true), vec![40.25]);
+        assert_eq!(find_cuts(&black, &silence, false).len(), 2);
+    }
+
+    #[test]
+    fn scene_cuts_a_spot_length_apart_are_not_breaks() {
+        // act breaks that happen to land 30s and 60s apart, with the logo on screen throughout
+        let cuts = [300.0, 330.0, 390.0, 420.0];
+        let logo = vec![true; 1300];
+        assert_eq!(
+            select_commercial_breaks(&cuts, 1300.0, Some(&logo), &[]),
+            Vec::new()
+        );
+        // without a logo or aspect change to go by, cut spacing alone isn't enough either
+        assert_eq!(
+            select_commercial_breaks(&cuts, 1300.0, None, &[]),
+            Vec::new()
+        );
+    }
+}
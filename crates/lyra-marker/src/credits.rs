@@ -0,0 +1,217 @@
+use std::{path::Path, process::Stdio};
+
+use anyhow::{Context, bail};
+use lyra_probe::{ProbeData, get_ffmpeg_path};
+use tokio::process::Command as TokioCommand;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info};
+
+use crate::{
+    BLACK_SILENCE_OVERLAP_TOLERANCE_SECONDS, CreditsRange, FileCreditsDetection,
+    MAX_MOVIE_CREDITS_SCAN_SECONDS, MIN_BLACK_FRAME_SECONDS, MIN_MOVIE_CREDITS_SECONDS,
+    MIN_SILENCE_SECONDS, MOVIE_CREDITS_SCAN_RATIO,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Interval {
+    start_seconds: f64,
+    end_seconds: f64,
+}
+
+// fallback for files without siblings to match against (movies). the end of the film is
+// usually a fade to black with the audio dropping out, so the last black frame that lines
+// up with silence and still leaves room for credits is treated as the start of the credits.
+// earlier fades inside the scan window are usually scene transitions in the final act.
+pub async fn detect_credits_fallback(
+    file_path: &Path,
+    probe_data: &ProbeData,
+    cancellation_token: Option<&CancellationToken>,
+) -> anyhow::Result<Option<FileCreditsDetection>> {
+    let duration_seconds = probe_data
+        .duration_secs
+        .context("missing file duration from probe")?;
+    let scan_seconds =
+        (duration_seconds * MOVIE_CREDITS_SCAN_RATIO).min(MAX_MOVIE_CREDITS_SCAN_SECONDS);
+    let start_seconds = (duration_seconds - scan_seconds).max(0.0);
+
+    debug!(
+        path = %file_path.display(),
+        duration_seconds,
+        start_seconds,
+        "scanning for black frames and silence"
+    );
+
+    let ffmpeg = TokioCommand::new(get_ffmpeg_path())
+        .kill_on_drop(true)
+        .arg("-hide_banner")
+        .arg("-nostats")
+        .arg("-loglevel")
+        .arg("info")
+        .arg("-nostdin")
+        .arg("-ss")
+        .arg(format!("{start_seconds:.4}"))
+        .arg("-i")
+        .arg(file_path)
+        .arg("-map")
+        .arg("0:v:0")
+        .arg("-map")
+        .arg("0:a:0?")
+        .arg("-sn")
+        .arg("-dn")
+        .arg("-vf")
+        .arg(format!(
+            "blackdetect=d={MIN_BLACK_FRAME_SECONDS}:pix_th=0.10"
+        ))
+        .arg("-af")
+        .arg(format!("silencedetect=noise=-50dB:d={MIN_SILENCE_SECONDS}"))
+        .arg("-f")
+        .arg("null")
+        .arg("-")
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to start ffmpeg for '{}'", file_path.display()))?;
+
+    let output = ffmpeg.wait_with_output();
+    tokio::pin!(output);
+    let output = if let Some(cancellation_token) = cancellation_token {
+        tokio::select! {
+            output = &mut output => output?,
+            _ = cancellation_token.cancelled() => return Ok(None),
+        }
+    } else {
+        output.await?
+    };
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        bail!(
+            "ffmpeg failed for '{}': {}",
+            file_path.display(),
+            stderr.trim()
+        );
+    }
+
+    let (black, silence) = parse_detect_output(&stderr, scan_seconds);
+    let credits =
+        select_credits_boundary(&black, &silence, scan_seconds).map(|boundary| CreditsRange {
+            start_seconds: (start_seconds + boundary) as f32,
+            end_seconds: duration_seconds as f32,
+        });
+
+    if let Some(credits) = credits {
+        info!(
+            path = %file_path.display(),
+            start_seconds = credits.start_seconds,
+            "credits detected from black frames"
+        );
+    } else {
+        info!(path = %file_path.display(), "credits not found");
+    }
+
+    Ok(Some(FileCreditsDetection {
+        path: file_path.to_path_buf(),
+        credits: credits.filter(|credits| credits.end_seconds > credits.start_seconds),
+    }))
+}
+
+fn parse_detect_output(stderr: &str, scan_seconds: f64) -> (Vec<Interval>, Vec<Interval>) {
+    let mut black = Vec::new();
+    let mut silence = Vec::new();
+    let mut silence_start = None::<f64>;
+
+    for line in stderr.lines() {
+        if let (Some(start_seconds), Some(end_seconds)) = (
+            parse_field(line, "black_start:"),
+            parse_field(line, "black_end:"),
+        ) {
+            black.push(Interval {
+                start_seconds,
+                end_seconds,
+            });
+        } else if let Some(start_seconds) = parse_field(line, "silence_start:") {
+            silence_start = Some(start_seconds);
+        } else if let Some(end_seconds) = parse_field(line, "silence_end:")
+            && let Some(start_seconds) = silence_start.take()
+        {
+            silence.push(Interval {
+                start_seconds,
+                end_seconds,
+            });
+        }
+    }
+
+    // silence that runs to the end of the file never gets a silence_end line
+    if let Some(start_seconds) = silence_start {
+        silence.push(Interval {
+            start_seconds,
+            end_seconds: scan_seconds,
+        });
+    }
+
+    (black, silence)
+}
+
+fn parse_field(line: &str, key: &str) -> Option<f64> {
+    let (_, rest) = line.split_once(key)?;
+    rest.split_whitespace().next()?.parse().ok()
+}
+
+fn select_credits_boundary(
+    black: &[Interval],
+    silence: &[Interval],
+    scan_seconds: f64,
+) -> Option<f64> {
+    black
+        .iter()
+        .filter(|black| scan_seconds - black.end_seconds >= MIN_MOVIE_CREDITS_SECONDS)
+        .filter(|black| {
+            silence.iter().any(|silence| {
+                silence.start_seconds <= black.end_seconds + BLACK_SILENCE_OVERLAP_TOLERANCE_SECONDS
+                    && silence.end_seconds
+                        >= black.start_seconds - BLACK_SILENCE_OVERLAP_TOLERANCE_SECONDS
+            })
+        })
+        .map(|black| black.end_seconds)
+        .max_by(f64::total_cmp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_detect_output, select_credits_boundary};
+
+    #[test]
+    fn black_frames_without_silence_are_ignored() {
+        let stderr = "\
+[blackdetect @ 0x1] black_start:12.5 black_end:13.2 black_duration:0.7
+[silencedetect @ 0x2] silence_start: 40.1
+[silencedetect @ 0x2] silence_end: 42.0 | silence_duration: 1.9
+[blackdetect @ 0x1] black_start:41.0 black_end:42.4 black_duration:1.4
+[silencedetect @ 0x2] silence_start: 300.0
+";
+        let (black, silence) = parse_detect_output(stderr, 320.0);
+        assert_eq!(black.len(), 2);
+        assert_eq!(silence.len(), 2);
+        assert_eq!(silence[1].end_seconds, 320.0);
+        assert_eq!(select_credits_boundary(&black, &silence, 320.0), Some(42.4));
+    }
+
+    #[test]
+    fn early_fade_to_black_is_skipped_for_the_later_boundary() {
+        let stderr = "\
+[blackdetect @ 0x1] black_start:20.0 black_end:21.5 black_duration:1.5
+[silencedetect @ 0x2] silence_start: 19.8
+[silencedetect @ 0x2] silence_end: 22.0 | silence_duration: 2.2
+[blackdetect @ 0x1] black_start:180.0 black_end:182.0 black_duration:2.0
+[silencedetect @ 0x2] silence_start: 179.5
+[silencedetect @ 0x2] silence_end: 182.5 | silence_duration: 3.0
+[blackdetect @ 0x1] black_start:590.0 black_end:599.0 black_duration:9.0
+[silencedetect @ 0x2] silence_start: 589.0
+";
+        let (black, silence) = parse_detect_output(stderr, 600.0);
+        assert_eq!(
+            select_credits_boundary(&black, &silence, 600.0),
+            Some(182.0)
+        );
+    }
+}
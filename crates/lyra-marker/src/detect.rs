@@ -7,8 +7,9 @@ use tokio_util::sync::CancellationToken;
 use tracing::{debug, info};
 
 use crate::{
-    Fingerprint, MAX_MATCH_DURATION_SECONDS, MERGE_SEGMENT_GAP_SECONDS, MIN_INTRO_EPISODE_COUNT,
-    MIN_MATCH_DURATION_SECONDS, chromaprint_config,
+    Fingerprint, MAX_MATCH_DURATION_SECONDS, MERGE_SEGMENT_GAP_SECONDS, MIN_CREDITS_EPISODE_COUNT,
+    MIN_INTRO_EPISODE_COUNT, MIN_MATCH_DURATION_SECONDS, chromaprint_config,
+    generate::credits_scan_start_seconds,
 };
 
 #[derive(Clone, Debug, PartialEq)]
@@ -23,6 +24,26 @@ pub struct IntroRange {
     pub end_seconds: f32,
 }
 
+#[derive(Clone, Debug)]
+pub struct CreditsInput {
+    pub path: PathBuf,
+    // fingerprint of the file tail, as produced by `fingerprint_tail`
+    pub fingerprint: Fingerprint,
+    pub duration_seconds: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileCreditsDetection {
+    pub path: PathBuf,
+    pub credits: Option<CreditsRange>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CreditsRange {
+    pub start_seconds: f32,
+    pub end_seconds: f32,
+}
+
 #[derive(Clone, Debug)]
 struct InputFingerprint {
     path: PathBuf,
@@ -30,14 +51,14 @@ struct InputFingerprint {
 }
 
 #[derive(Clone, Debug)]
-struct IntroSegmentCandidate {
+struct SegmentCandidate {
     start_seconds: f32,
     end_seconds: f32,
     supporting_file_indexes: Vec<usize>,
     best_score: f64,
 }
 
-impl IntroSegmentCandidate {
+impl SegmentCandidate {
     fn duration_seconds(&self) -> f32 {
         self.end_seconds - self.start_seconds
     }
@@ -53,8 +74,114 @@ pub async fn detect_intros(
 ) -> anyhow::Result<Option<Vec<FileIntroDetection>>> {
     info!(file_count = input_files.len(), "starting intro detection");
 
-    let mut fingerprints = Vec::with_capacity(input_files.len());
-    for (index, (path, fingerprint)) in input_files.iter().enumerate() {
+    let Some(fingerprints) = decode_fingerprints(
+        input_files
+            .iter()
+            .map(|(path, fingerprint)| (path, fingerprint)),
+        cancellation_token,
+    )?
+    else {
+        return Ok(None);
+    };
+    let Some(file_segments) = match_all_pairs(&fingerprints, cancellation_token).await? else {
+        return Ok(None);
+    };
+
+    let mut output = Vec::with_capacity(fingerprints.len());
+    for (index, file) in fingerprints.iter().enumerate() {
+        if cancellation_token.is_some_and(CancellationToken::is_cancelled) {
+            return Ok(None);
+        }
+
+        let merged = merge_segments(file_segments[index].clone());
+        let intro = select_intro_segment(&merged).map(|segment| IntroRange {
+            start_seconds: segment.start_seconds,
+            end_seconds: segment.end_seconds,
+        });
+
+        if let Some(intro) = intro {
+            info!(
+                path = %file.path.display(),
+                start_seconds = intro.start_seconds,
+                end_seconds = intro.end_seconds,
+                "intro detected"
+            );
+        } else {
+            info!(path = %file.path.display(), "intro not found");
+        }
+
+        output.push(FileIntroDetection {
+            path: file.path.clone(),
+            intro,
+        });
+    }
+
+    info!("intro detection complete");
+    Ok(Some(output))
+}
+
+pub async fn detect_credits(
+    input_files: &[CreditsInput],
+    cancellation_token: Option<&CancellationToken>,
+) -> anyhow::Result<Option<Vec<FileCreditsDetection>>> {
+    info!(file_count = input_files.len(), "starting credits detection");
+
+    let Some(fingerprints) = decode_fingerprints(
+        input_files
+            .iter()
+            .map(|input| (&input.path, &input.fingerprint)),
+        cancellation_token,
+    )?
+    else {
+        return Ok(None);
+    };
+    let Some(file_segments) = match_all_pairs(&fingerprints, cancellation_token).await? else {
+        return Ok(None);
+    };
+
+    let mut output = Vec::with_capacity(fingerprints.len());
+    for (index, input) in input_files.iter().enumerate() {
+        if cancellation_token.is_some_and(CancellationToken::is_cancelled) {
+            return Ok(None);
+        }
+
+        // tail fingerprints start partway into the file, so matches are shifted back into
+        // file time before they're returned.
+        let offset_seconds = credits_scan_start_seconds(input.duration_seconds) as f32;
+        let duration_seconds = input.duration_seconds as f32;
+        let merged = merge_segments(file_segments[index].clone());
+        let credits = select_credits_segment(&merged).map(|segment| CreditsRange {
+            start_seconds: segment.start_seconds + offset_seconds,
+            end_seconds: (segment.end_seconds + offset_seconds).min(duration_seconds),
+        });
+
+        if let Some(credits) = credits {
+            info!(
+                path = %input.path.display(),
+                start_seconds = credits.start_seconds,
+                end_seconds = credits.end_seconds,
+                "credits detected"
+            );
+        } else {
+            info!(path = %input.path.display(), "credits not found");
+        }
+
+        output.push(FileCreditsDetection {
+            path: input.path.clone(),
+            credits,
+        });
+    }
+
+    info!("credits detection complete");
+    Ok(Some(output))
+}
+
+fn decode_fingerprints<'a>(
+    input_files: impl Iterator<Item = (&'a PathBuf, &'a Fingerprint)>,
+    cancellation_token: Option<&CancellationToken>,
+) -> anyhow::Result<Option<Vec<InputFingerprint>>> {
+    let mut fingerprints = Vec::new();
+    for (index, (path, fingerprint)) in input_files.enumerate() {
         if cancellation_token.is_some_and(CancellationToken::is_cancelled) {
             return Ok(None);
         }
@@ -74,9 +201,16 @@ pub async fn detect_intros(
         });
     }
 
-    let pair_total = fingerprints.len() * (fingerprints.len() - 1) / 2;
+    Ok(Some(fingerprints))
+}
+
+async fn match_all_pairs(
+    fingerprints: &[InputFingerprint],
+    cancellation_token: Option<&CancellationToken>,
+) -> anyhow::Result<Option<Vec<Vec<SegmentCandidate>>>> {
+    let pair_total = fingerprints.len() * fingerprints.len().saturating_sub(1) / 2;
     let mut pair_index = 0usize;
-    let mut file_segments = vec![Vec::<IntroSegmentCandidate>::new(); fingerprints.len()];
+    let mut file_segments = vec![Vec::<SegmentCandidate>::new(); fingerprints.len()];
     for left in 0..fingerprints.len() {
         for right in (left + 1)..fingerprints.len() {
             if cancellation_token.is_some_and(CancellationToken::is_cancelled) {
@@ -111,37 +245,7 @@ pub async fn detect_intros(
         }
     }
 
-    let mut output = Vec::with_capacity(fingerprints.len());
-    for (index, file) in fingerprints.iter().enumerate() {
-        if cancellation_token.is_some_and(CancellationToken::is_cancelled) {
-            return Ok(None);
-        }
-
-        let merged = merge_segments(file_segments[index].clone());
-        let intro = select_intro_segment(&merged).map(|segment| IntroRange {
-            start_seconds: segment.start_seconds,
-            end_seconds: segment.end_seconds,
-        });
-
-        if let Some(intro) = intro {
-            info!(
-                path = %file.path.display(),
-                start_seconds = intro.start_seconds,
-                end_seconds = intro.end_seconds,
-                "intro detected"
-            );
-        } else {
-            info!(path = %file.path.display(), "intro not found");
-        }
-
-        output.push(FileIntroDetection {
-            path: file.path.clone(),
-            intro,
-        });
-    }
-
-    info!("intro detection complete");
-    Ok(Some(output))
+    Ok(Some(file_segments))
 }
 
 async fn match_fingerprint_pair(
@@ -149,11 +253,7 @@ async fn match_fingerprint_pair(
     left_fingerprint: Vec<u32>,
     right_index: usize,
     right_fingerprint: Vec<u32>,
-) -> anyhow::Result<(
-    Vec<IntroSegmentCandidate>,
-    Vec<IntroSegmentCandidate>,
-    usize,
-)> {
+) -> anyhow::Result<(Vec<SegmentCandidate>, Vec<SegmentCandidate>, usize)> {
     spawn_blocking(move || {
         let config = chromaprint_config();
         let segments = match_fingerprints(&left_fingerprint, &right_fingerprint, &config)?;
@@ -174,7 +274,7 @@ async fn match_fingerprint_pair(
             let start1 = segment.start1(&config);
             let end1 = segment.end1(&config);
             if end1 > start1 {
-                left_candidates.push(IntroSegmentCandidate {
+                left_candidates.push(SegmentCandidate {
                     start_seconds: start1,
                     end_seconds: end1,
                     supporting_file_indexes: vec![right_index],
@@ -185,7 +285,7 @@ async fn match_fingerprint_pair(
             let start2 = segment.start2(&config);
             let end2 = segment.end2(&config);
             if end2 > start2 {
-                right_candidates.push(IntroSegmentCandidate {
+                right_candidates.push(SegmentCandidate {
                     start_seconds: start2,
                     end_seconds: end2,
                     supporting_file_indexes: vec![left_index],
@@ -200,7 +300,7 @@ async fn match_fingerprint_pair(
     .context("fingerprint match worker failed to join")?
 }
 
-fn merge_segments(mut segments: Vec<IntroSegmentCandidate>) -> Vec<IntroSegmentCandidate> {
+fn merge_segments(mut segments: Vec<SegmentCandidate>) -> Vec<SegmentCandidate> {
     if segments.is_empty() {
         return segments;
     }
@@ -211,7 +311,7 @@ fn merge_segments(mut segments: Vec<IntroSegmentCandidate>) -> Vec<IntroSegmentC
             .then_with(|| a.end_seconds.total_cmp(&b.end_seconds))
     });
 
-    let mut merged = Vec::<IntroSegmentCandidate>::with_capacity(segments.len());
+    let mut merged = Vec::<SegmentCandidate>::with_capacity(segments.len());
     for segment in segments {
         if let Some(last) = merged.last_mut() {
             if segment.start_seconds - last.end_seconds <= MERGE_SEGMENT_GAP_SECONDS {
@@ -232,7 +332,7 @@ fn merge_segments(mut segments: Vec<IntroSegmentCandidate>) -> Vec<IntroSegmentC
     merged
 }
 
-fn select_intro_segment(segments: &[IntroSegmentCandidate]) -> Option<IntroSegmentCandidate> {
+fn select_intro_segment(segments: &[SegmentCandidate]) -> Option<SegmentCandidate> {
     segments
         .iter()
         .filter(|segment| segment.episode_count() >= MIN_INTRO_EPISODE_COUNT)
@@ -246,26 +346,41 @@ fn select_intro_segment(segments: &[IntroSegmentCandidate]) -> Option<IntroSegme
         })
 }
 
+// credits are the shared segment closest to the end of the file; anything after them
+// (stingers, next-episode previews) differs between episodes and won't match.
+fn select_credits_segment(segments: &[SegmentCandidate]) -> Option<SegmentCandidate> {
+    segments
+        .iter()
+        .filter(|segment| segment.episode_count() >= MIN_CREDITS_EPISODE_COUNT)
+        .cloned()
+        .max_by(|a, b| {
+            a.start_seconds
+                .total_cmp(&b.start_seconds)
+                .then_with(|| a.episode_count().cmp(&b.episode_count()))
+                .then_with(|| a.best_score.total_cmp(&b.best_score))
+        })
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{IntroSegmentCandidate, merge_segments, select_intro_segment};
+    use super::{SegmentCandidate, merge_segments, select_credits_segment, select_intro_segment};
 
     #[test]
     fn merged_segments_count_distinct_supporting_episodes() {
         let merged = merge_segments(vec![
-            IntroSegmentCandidate {
+            SegmentCandidate {
                 start_seconds: 0.0,
                 end_seconds: 10.0,
                 supporting_file_indexes: vec![1],
                 best_score: 0.8,
             },
-            IntroSegmentCandidate {
+            SegmentCandidate {
                 start_seconds: 0.5,
                 end_seconds: 10.5,
                 supporting_file_indexes: vec![1],
                 best_score: 0.9,
             },
-            IntroSegmentCandidate {
+            SegmentCandidate {
                 start_seconds: 1.0,
                 end_seconds: 11.0,
                 supporting_file_indexes: vec![2],
@@ -281,13 +396,13 @@ mod tests {
     #[test]
     fn intro_selection_requires_three_total_episodes() {
         let intro = select_intro_segment(&[
-            IntroSegmentCandidate {
+            SegmentCandidate {
                 start_seconds: 0.0,
                 end_seconds: 20.0,
                 supporting_file_indexes: vec![1],
                 best_score: 0.9,
             },
-            IntroSegmentCandidate {
+            SegmentCandidate {
                 start_seconds: 40.0,
                 end_seconds: 60.0,
                 supporting_file_indexes: vec![1, 2],
@@ -300,4 +415,33 @@ mod tests {
             Some((40.0, 60.0))
         );
     }
+
+    #[test]
+    fn credits_selection_prefers_latest_shared_segment() {
+        let credits = select_credits_segment(&[
+            SegmentCandidate {
+                start_seconds: 30.0,
+                end_seconds: 60.0,
+                supporting_file_indexes: vec![1, 2, 3],
+                best_score: 0.9,
+            },
+            SegmentCandidate {
+                start_seconds: 200.0,
+                end_seconds: 260.0,
+                supporting_file_indexes: vec![1, 2],
+                best_score: 0.8,
+            },
+            SegmentCandidate {
+                start_seconds: 280.0,
+                end_seconds: 290.0,
+                supporting_file_indexes: vec![1],
+                best_score: 0.9,
+            },
+        ]);
+
+        assert_eq!(
+            credits.map(|segment| (segment.start_seconds, segment.end_seconds)),
+            Some((200.0, 260.0))
+        );
+    }
 }
@@ -1,8 +1,8 @@
-use anyhow::Context;
+use anyhow::{Context, bail};
 
 use crate::{
     AUDIO_FINGERPRINT_CACHE_MAGIC, AUDIO_FINGERPRINT_CACHE_SCHEMA_VERSION,
-    AUDIO_FINGERPRINT_VERSION,
+    AUDIO_FINGERPRINT_VERSION, chromaprint_config,
 };
 
 #[derive(Clone, Debug)]
@@ -37,6 +37,23 @@ impl Fingerprint {
         Self { cache }
     }
 
+    // cuts out part of a fingerprint, e.g. a detected intro, so it can be matched on its own
+    pub fn slice_seconds(&self, start_seconds: f32, end_seconds: f32) -> anyhow::Result<Self> {
+        let values = self.decode()?;
+        let item_seconds = chromaprint_config().item_duration_in_seconds();
+        let start = ((start_seconds.max(0.0) / item_seconds) as usize).min(values.len());
+        let end = ((end_seconds.max(0.0) / item_seconds).ceil() as usize).min(values.len());
+        if end <= start {
+            bail!("fingerprint slice {start_seconds}-{end_seconds}s is empty");
+        }
+
+        Ok(Self::from_values(&values[start..end]))
+    }
+
+    pub fn duration_seconds(&self) -> anyhow::Result<f32> {
+        Ok(self.decode()?.len() as f32 * chromaprint_config().item_duration_in_seconds())
+    }
+
     pub(crate) fn decode(&self) -> anyhow::Result<Vec<u32>> {
         Self::decode_cache(&self.cache).context("invalid fingerprint cache")
     }
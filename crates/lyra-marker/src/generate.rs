@@ -9,8 +9,8 @@ use tokio_util::sync::CancellationToken;
 use tracing::debug;
 
 use crate::{
-    FINGERPRINT_CHANNELS, FINGERPRINT_SAMPLE_RATE, FINGERPRINT_SCAN_RATIO, Fingerprint,
-    chromaprint_config,
+    CREDITS_SCAN_RATIO, FINGERPRINT_CHANNELS, FINGERPRINT_SAMPLE_RATE, FINGERPRINT_SCAN_RATIO,
+    Fingerprint, MAX_CREDITS_SCAN_SECONDS, chromaprint_config,
 };
 
 pub async fn fingerprint(
@@ -30,6 +30,74 @@ pub async fn fingerprint(
         "preparing fingerprint decode"
     );
 
+    decode_fingerprint(file_path, 0.0, scan_seconds, cancellation_token).await
+}
+
+// fingerprints the end of the file, used for credits detection
+pub async fn fingerprint_tail(
+    file_path: &Path,
+    probe_data: &ProbeData,
+    cancellation_token: Option<&CancellationToken>,
+) -> anyhow::Result<Option<Fingerprint>> {
+    let duration_seconds = probe_data
+        .duration_secs
+        .context("missing file duration from probe")?;
+    let start_seconds = credits_scan_start_seconds(duration_seconds);
+    let scan_seconds = duration_seconds - start_seconds;
+
+    debug!(
+        path = %file_path.display(),
+        duration_seconds,
+        start_seconds,
+        scan_seconds,
+        "preparing tail fingerprint decode"
+    );
+
+    decode_fingerprint(file_path, start_seconds, scan_seconds, cancellation_token).await
+}
+
+// fingerprints everything between the head and tail windows. recaps pull footage from anywhere
+// in earlier episodes, so together the three cover the whole file.
+pub async fn fingerprint_body(
+    file_path: &Path,
+    probe_data: &ProbeData,
+    cancellation_token: Option<&CancellationToken>,
+) -> anyhow::Result<Option<Fingerprint>> {
+    let duration_seconds = probe_data
+        .duration_secs
+        .context("missing file duration from probe")?;
+    let start_seconds = body_scan_start_seconds(duration_seconds);
+    let scan_seconds = credits_scan_start_seconds(duration_seconds) - start_seconds;
+    if scan_seconds <= 0.0 {
+        return Ok(Some(Fingerprint::from_values(&[])));
+    }
+
+    debug!(
+        path = %file_path.display(),
+        duration_seconds,
+        start_seconds,
+        scan_seconds,
+        "preparing body fingerprint decode"
+    );
+
+    decode_fingerprint(file_path, start_seconds, scan_seconds, cancellation_token).await
+}
+
+pub(crate) fn body_scan_start_seconds(duration_seconds: f64) -> f64 {
+    duration_seconds * FINGERPRINT_SCAN_RATIO
+}
+
+pub(crate) fn credits_scan_start_seconds(duration_seconds: f64) -> f64 {
+    let scan_seconds = (duration_seconds * CREDITS_SCAN_RATIO).min(MAX_CREDITS_SCAN_SECONDS);
+    (duration_seconds - scan_seconds).max(0.0)
+}
+
+async fn decode_fingerprint(
+    file_path: &Path,
+    start_seconds: f64,
+    scan_seconds: f64,
+    cancellation_token: Option<&CancellationToken>,
+) -> anyhow::Result<Option<Fingerprint>> {
     let ffmpeg_path = get_ffmpeg_path();
     let (samples_tx, samples_rx) = std::sync::mpsc::channel::<Vec<i16>>();
     let fingerprint_worker = spawn_blocking(move || -> anyhow::Result<Vec<u32>> {
@@ -49,12 +117,18 @@ pub async fn fingerprint(
         Ok(printer.fingerprint().to_vec())
     });
 
-    let mut ffmpeg = TokioCommand::new(&ffmpeg_path)
+    let mut command = TokioCommand::new(&ffmpeg_path);
+    command
         .kill_on_drop(true)
         .arg("-hide_banner")
         .arg("-loglevel")
         .arg("error")
-        .arg("-nostdin")
+        .arg("-nostdin");
+    if start_seconds > 0.0 {
+        command.arg("-ss").arg(format!("{start_seconds:.4}"));
+    }
+
+    let mut ffmpeg = command
         .arg("-i")
         .arg(file_path)
         .arg("-map")
@@ -0,0 +1,174 @@
+use anyhow::Context;
+use rusty_chromaprint::match_fingerprints;
+use tokio::task::spawn_blocking;
+
+use crate::{
+    Fingerprint, IntroRange, KNOWN_INTRO_MIN_COVERAGE, MIN_MATCH_DURATION_SECONDS,
+    chromaprint_config,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KnownIntroMatch {
+    // index into the `known` slice passed to `match_known_intros`
+    pub known_index: usize,
+    pub intro: IntroRange,
+    pub coverage: f32,
+}
+
+// matches a file against intros that were already found elsewhere (earlier seasons, other
+// roots), for files that don't have enough siblings for `detect_intros` to work.
+pub async fn match_known_intros(
+    fingerprint: &Fingerprint,
+    known: &[Fingerprint],
+) -> anyhow::Result<Option<KnownIntroMatch>> {
+    if known.is_empty() {
+        return Ok(None);
+    }
+
+    let values = fingerprint.decode()?;
+    let known_values = known
+        .iter()
+        .map(Fingerprint::decode)
+        .collect::<anyhow::Result<Vec<_>>>()
+        .context("invalid known intro fingerprint")?;
+
+    spawn_blocking(move || {
+        let config = chromaprint_config();
+        let item_seconds = config.item_duration_in_seconds();
+        let mut best = None::<KnownIntroMatch>;
+        for (known_index, known_values) in known_values.iter().enumerate() {
+            let known_seconds = known_values.len() as f32 * item_seconds;
+            if known_seconds <= 0.0 {
+                continue;
+            }
+
+            let segments = match_fingerprints(&values, known_values, &config)?
+                .into_iter()
+                .filter(|segment| segment.duration(&config) >= MIN_MATCH_DURATION_SECONDS)
+                .collect::<Vec<_>>();
+            let (Some(start_seconds), Some(end_seconds)) = (
+                segments
+                    .iter()
+                    .map(|segment| segment.start1(&config))
+                    .min_by(f32::total_cmp),
+                segments
+                    .iter()
+                    .map(|segment| segment.end1(&config))
+                    .max_by(f32::total_cmp),
+            ) else {
+                continue;
+            };
+
+            // several alignments can land on the same stretch of the known intro, so coverage
+            // is measured on the union of the matched ranges
+            let matched_seconds = covered_seconds(
+                segments
+                    .iter()
+                    .map(|segment| (segment.start2(&config), segment.end2(&config)))
+                    .collect(),
+            );
+            let coverage = (matched_seconds / known_seconds).min(1.0);
+            if coverage < KNOWN_INTRO_MIN_COVERAGE {
+                continue;
+            }
+
+            if best.is_none_or(|best| coverage > best.coverage) {
+                best = Some(KnownIntroMatch {
+                    known_index,
+                    intro: IntroRange {
+                        start_seconds,
+                        end_seconds,
+                    },
+                    coverage,
+                });
+            }
+        }
+
+        Ok(best)
+    })
+    .await
+    .context("known intro match worker failed to join")?
+}
+
+fn covered_seconds(mut ranges: Vec<(f32, f32)>) -> f32 {
+    ranges.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut covered = 0.0;
+    let mut current = None::<(f32, f32)>;
+    for (start, end) in ranges {
+        match current {
+            Some((current_start, current_end)) if start <= current_end => {
+                current = Some((current_start, current_end.max(end)));
+            }
+            _ => {
+                if let Some((current_start, current_end)) = current {
+                    covered += current_end - current_start;
+                }
+                current = Some((start, end));
+            }
+        }
+    }
+    if let Some((current_start, current_end)) = current {
+        covered += current_end - current_start;
+    }
+
+    covered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{covered_seconds, match_known_intros};
+    use crate::{Fingerprint, chromaprint_config};
+
+    fn noise(seed: u32, len: usize) -> Vec<u32> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state
+            })
+            .collect()
+    }
+
+    #[test]
+    fn overlapping_ranges_are_only_counted_once() {
+        assert_eq!(
+            covered_seconds(vec![(10.0, 20.0), (0.0, 12.0), (15.0, 18.0), (30.0, 35.0)]),
+            25.0
+        );
+        assert_eq!(covered_seconds(Vec::new()), 0.0);
+    }
+
+    #[tokio::test]
+    async fn known_intros_match_the_best_covered_entry() {
+        let item_seconds = chromaprint_config().item_duration_in_seconds();
+        let intro = noise(3, 400);
+        let other = noise(5, 400);
+        let file = [noise(7, 100), intro.clone(), noise(11, 1000)].concat();
+
+        let matched = match_known_intros(
+            &Fingerprint::from_values(&file),
+            &[
+                Fingerprint::from_values(&other),
+                Fingerprint::from_values(&intro),
+            ],
+        )
+        .await
+        .unwrap()
+        .expect("intro should match");
+        assert_eq!(matched.known_index, 1);
+        assert!(matched.coverage > 0.9 && matched.coverage <= 1.0);
+        assert!((matched.intro.start_seconds - 100.0 * item_seconds).abs() < 1.0);
+        assert!((matched.intro.end_seconds - 500.0 * item_seconds).abs() < 1.0);
+
+        let unrelated = match_known_intros(
+            &Fingerprint::from_values(&noise(13, 1500)),
+            &[Fingerprint::from_values(&intro)],
+        )
+        .await
+        .unwrap();
+        assert_eq!(unrelated, None);
+    }
+}
@@ -1,22 +1,71 @@
+mod commercials;
+mod credits;
 mod detect;
 mod fingerprint;
 mod generate;
+mod known;
+mod recap;
 
-pub use detect::{FileIntroDetection, IntroRange, detect_intros};
+pub use commercials::{CommercialRange, FileCommercialDetection, detect_commercials};
+pub use credits::detect_credits_fallback;
+pub use detect::{
+    CreditsInput, CreditsRange, FileCreditsDetection, FileIntroDetection, IntroRange,
+    detect_credits, detect_intros,
+};
 pub use fingerprint::Fingerprint;
-pub use generate::fingerprint;
+pub use generate::{fingerprint, fingerprint_body, fingerprint_tail};
+pub use known::{KnownIntroMatch, match_known_intros};
+pub use recap::{FileRecapDetection, PreviewRange, RecapInput, RecapRange, detect_recaps};
 use rusty_chromaprint::Configuration;
 
 pub(crate) const AUDIO_FINGERPRINT_VERSION: u32 = 1;
 pub(crate) const AUDIO_FINGERPRINT_CACHE_MAGIC: [u8; 4] = *b"LAFP";
 pub(crate) const AUDIO_FINGERPRINT_CACHE_SCHEMA_VERSION: u32 = 1;
 pub(crate) const FINGERPRINT_SCAN_RATIO: f64 = 0.40;
+// the tail window is capped so long episodes don't decode minutes of unrelated audio
+pub(crate) const CREDITS_SCAN_RATIO: f64 = 0.20;
+pub(crate) const MAX_CREDITS_SCAN_SECONDS: f64 = 600.0;
+// movies have no siblings to match against, so the black/silence fallback scans a smaller window
+pub(crate) const MOVIE_CREDITS_SCAN_RATIO: f64 = 0.12;
+pub(crate) const MAX_MOVIE_CREDITS_SCAN_SECONDS: f64 = 900.0;
+// a boundary this close to the end is the final fade out, not the start of the credits
+pub(crate) const MIN_MOVIE_CREDITS_SECONDS: f64 = 30.0;
 pub(crate) const FINGERPRINT_SAMPLE_RATE: u32 = 48_000;
 pub(crate) const FINGERPRINT_CHANNELS: u32 = 2;
 pub(crate) const MIN_MATCH_DURATION_SECONDS: f32 = 8.0;
 pub(crate) const MAX_MATCH_DURATION_SECONDS: f32 = 180.0;
 pub(crate) const MERGE_SEGMENT_GAP_SECONDS: f32 = 2.0;
 pub(crate) const MIN_INTRO_EPISODE_COUNT: usize = 3;
+pub(crate) const MIN_CREDITS_EPISODE_COUNT: usize = 3;
+// share of a known intro that has to be heard in a file before it counts as that intro
+pub(crate) const KNOWN_INTRO_MIN_COVERAGE: f32 = 0.6;
+// recap clips are short and cut together, so they're matched in small windows with a lower
+// minimum length than shared intros
+pub(crate) const RECAP_SCAN_SECONDS: f32 = 300.0;
+pub(crate) const RECAP_WINDOW_SECONDS: f32 = 20.0;
+pub(crate) const RECAP_MIN_CLIP_SECONDS: f32 = 3.0;
+pub(crate) const RECAP_MERGE_GAP_SECONDS: f32 = 10.0;
+pub(crate) const RECAP_MAX_START_SECONDS: f32 = 15.0;
+pub(crate) const RECAP_LOOKBACK_EPISODES: usize = 3;
+pub(crate) const PREVIEW_SCAN_SECONDS: f32 = 180.0;
+pub(crate) const PREVIEW_MAX_END_GAP_SECONDS: f32 = 45.0;
+pub(crate) const PREVIEW_LOOKAHEAD_EPISODES: usize = 1;
+pub(crate) const MIN_BLACK_FRAME_SECONDS: f64 = 0.5;
+pub(crate) const MIN_SILENCE_SECONDS: f64 = 0.5;
+pub(crate) const BLACK_SILENCE_OVERLAP_TOLERANCE_SECONDS: f64 = 1.0;
+// broadcast breaks are separated by a few frames of black, much shorter than a fade out
+pub(crate) const COMMERCIAL_MIN_BLACK_SECONDS: f64 = 0.08;
+pub(crate) const COMMERCIAL_MIN_SILENCE_SECONDS: f64 = 0.15;
+pub(crate) const COMMERCIAL_CUT_TOLERANCE_SECONDS: f64 = 0.5;
+pub(crate) const COMMERCIAL_FRAME_WIDTH: usize = 64;
+pub(crate) const COMMERCIAL_FRAME_HEIGHT: usize = 36;
+pub(crate) const COMMERCIAL_SPOT_LENGTHS_SECONDS: [f64; 8] =
+    [10.0, 15.0, 20.0, 30.0, 45.0, 60.0, 90.0, 120.0];
+pub(crate) const COMMERCIAL_SPOT_TOLERANCE_SECONDS: f64 = 1.0;
+pub(crate) const COMMERCIAL_ASPECT_TOLERANCE: f64 = 0.05;
+pub(crate) const COMMERCIAL_MAX_BLOCK_SECONDS: f64 = 120.0;
+pub(crate) const COMMERCIAL_MIN_BREAK_SECONDS: f64 = 30.0;
+pub(crate) const COMMERCIAL_MAX_BREAK_SECONDS: f64 = 600.0;
 
 pub(crate) fn chromaprint_config() -> Configuration {
     Configuration::preset_test1()
@@ -1,7 +1,7 @@
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, bail};
-use lyra_marker::{detect_intros, fingerprint};
+use lyra_marker::{CreditsInput, detect_credits, detect_intros, fingerprint, fingerprint_tail};
 use lyra_probe::probe;
 
 #[tokio::main]
@@ -26,28 +26,45 @@ async fn main() -> anyhow::Result<()> {
     input_files.sort();
 
     let mut detection_inputs = Vec::with_capacity(input_files.len());
+    let mut credits_inputs = Vec::with_capacity(input_files.len());
     for path in &input_files {
         let probe_data = probe(path).await?;
         detection_inputs.push((
             path.clone(),
             fingerprint(path, &probe_data, None).await.unwrap().unwrap(),
         ));
+        credits_inputs.push(CreditsInput {
+            path: path.clone(),
+            fingerprint: fingerprint_tail(path, &probe_data, None)
+                .await
+                .unwrap()
+                .unwrap(),
+            duration_seconds: probe_data
+                .duration_secs
+                .context("missing file duration from probe")?,
+        });
     }
 
     let intros = detect_intros(&detection_inputs, None)
         .await?
         .context("intro detection cancelled unexpectedly")?;
-    for detection in intros {
+    let credits = detect_credits(&credits_inputs, None)
+        .await?
+        .context("credits detection cancelled unexpectedly")?;
+    for (detection, credits) in intros.into_iter().zip(credits) {
         println!("{}", detection.path.display());
-        if let Some(intro) = detection.intro {
-            println!(
-                "  {} -- {}",
-                format_to_duration(f64::from(intro.start_seconds)),
-                format_to_duration(f64::from(intro.end_seconds)),
-            );
-        } else {
-            println!("  none");
-        }
+        print_range(
+            "intro",
+            detection
+                .intro
+                .map(|intro| (intro.start_seconds, intro.end_seconds)),
+        );
+        print_range(
+            "credits",
+            credits
+                .credits
+                .map(|credits| (credits.start_seconds, credits.end_seconds)),
+        );
     }
 
     Ok(())
@@ -76,6 +93,18 @@ fn collect_input_files(input_dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
     Ok(files)
 }
 
+fn print_range(label: &str, range: Option<(f32, f32)>) {
+    if let Some((start_seconds, end_seconds)) = range {
+        println!(
+            "  {label}: {} -- {}",
+            format_to_duration(f64::from(start_seconds)),
+            format_to_duration(f64::from(end_seconds)),
+        );
+    } else {
+        println!("  {label}: none");
+    }
+}
+
 fn format_to_duration(seconds: f64) -> String {
     let centiseconds = (seconds * 100.0).round() as u64;
     let total_secs = centiseconds / 100;
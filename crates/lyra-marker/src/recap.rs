@@ -0,0 +1,396 @@
+use std::{path::PathBuf, sync::Arc};
+
+use anyhow::Context;
+use rusty_chromaprint::match_fingerprints;
+use tokio::task::spawn_blocking;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info};
+
+use crate::{
+    CreditsRange, Fingerprint, IntroRange, PREVIEW_LOOKAHEAD_EPISODES, PREVIEW_MAX_END_GAP_SECONDS,
+    PREVIEW_SCAN_SECONDS, RECAP_LOOKBACK_EPISODES, RECAP_MAX_START_SECONDS,
+    RECAP_MERGE_GAP_SECONDS, RECAP_MIN_CLIP_SECONDS, RECAP_SCAN_SECONDS, RECAP_WINDOW_SECONDS,
+    chromaprint_config,
+    generate::{body_scan_start_seconds, credits_scan_start_seconds},
+};
+
+#[derive(Clone, Debug)]
+pub struct RecapInput {
+    pub path: PathBuf,
+    pub fingerprint: Fingerprint,
+    pub body_fingerprint: Fingerprint,
+    pub tail_fingerprint: Fingerprint,
+    pub duration_seconds: f64,
+    // known shared segments are excluded so the intro/credits of two episodes never count
+    // as repeated content
+    pub intro: Option<IntroRange>,
+    pub credits: Option<CreditsRange>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileRecapDetection {
+    pub path: PathBuf,
+    pub recap: Option<RecapRange>,
+    pub preview: Option<PreviewRange>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RecapRange {
+    pub start_seconds: f32,
+    pub end_seconds: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PreviewRange {
+    pub start_seconds: f32,
+    pub end_seconds: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Clip {
+    start_seconds: f32,
+    end_seconds: f32,
+}
+
+#[derive(Debug)]
+struct DecodedEpisode {
+    head: Vec<u32>,
+    body: Vec<u32>,
+    body_offset_seconds: f32,
+    tail: Vec<u32>,
+    tail_offset_seconds: f32,
+    duration_seconds: f32,
+    intro: Option<Clip>,
+    excluded: Vec<Clip>,
+}
+
+// recaps and previews reuse footage from other episodes instead of sharing audio across the
+// whole season, so each episode's opening is matched against the episodes before it and its
+// ending against the episodes after it. inputs must be in episode order.
+pub async fn detect_recaps(
+    input_files: &[RecapInput],
+    cancellation_token: Option<&CancellationToken>,
+) -> anyhow::Result<Option<Vec<FileRecapDetection>>> {
+    info!(
+        file_count = input_files.len(),
+        "starting recap and preview detection"
+    );
+
+    let mut episodes = Vec::with_capacity(input_files.len());
+    for input in input_files {
+        if cancellation_token.is_some_and(CancellationToken::is_cancelled) {
+            return Ok(None);
+        }
+
+        let head = input
+            .fingerprint
+            .decode()
+            .with_context(|| format!("invalid fingerprint cache for '{}'", input.path.display()))?;
+        let body = input.body_fingerprint.decode().with_context(|| {
+            format!(
+                "invalid body fingerprint cache for '{}'",
+                input.path.display()
+            )
+        })?;
+        let tail = input.tail_fingerprint.decode().with_context(|| {
+            format!(
+                "invalid tail fingerprint cache for '{}'",
+                input.path.display()
+            )
+        })?;
+
+        let intro = input.intro.map(|intro| Clip {
+            start_seconds: intro.start_seconds,
+            end_seconds: intro.end_seconds,
+        });
+        let excluded = intro
+            .into_iter()
+            .chain(input.credits.map(|credits| Clip {
+                start_seconds: credits.start_seconds,
+                end_seconds: credits.end_seconds,
+            }))
+            .collect();
+
+        episodes.push(DecodedEpisode {
+            head,
+            body,
+            body_offset_seconds: body_scan_start_seconds(input.duration_seconds) as f32,
+            tail,
+            tail_offset_seconds: credits_scan_start_seconds(input.duration_seconds) as f32,
+            duration_seconds: input.duration_seconds as f32,
+            intro,
+            excluded,
+        });
+    }
+
+    let episodes = Arc::new(episodes);
+    let mut output = Vec::with_capacity(input_files.len());
+    for (index, input) in input_files.iter().enumerate() {
+        if cancellation_token.is_some_and(CancellationToken::is_cancelled) {
+            return Ok(None);
+        }
+
+        let worker_episodes = Arc::clone(&episodes);
+        let (recap, preview) = spawn_blocking(move || {
+            let episode = &worker_episodes[index];
+            let recap_clips = find_recap_clips(&worker_episodes, index, RECAP_LOOKBACK_EPISODES)?;
+            let preview_clips =
+                find_preview_clips(&worker_episodes, index, PREVIEW_LOOKAHEAD_EPISODES)?;
+
+            anyhow::Ok((
+                select_recap(recap_clips, episode.intro),
+                select_preview(preview_clips, episode.duration_seconds),
+            ))
+        })
+        .await
+        .context("recap match worker failed to join")??;
+
+        debug!(
+            path = %input.path.display(),
+            recap = ?recap,
+            preview = ?preview,
+            "recap detection complete for file"
+        );
+
+        output.push(FileRecapDetection {
+            path: input.path.clone(),
+            recap: recap.map(|clip| RecapRange {
+                start_seconds: clip.start_seconds,
+                end_seconds: clip.end_seconds,
+            }),
+            preview: preview.map(|clip| PreviewRange {
+                start_seconds: clip.start_seconds,
+                end_seconds: clip.end_seconds,
+            }),
+        });
+    }
+
+    info!("recap and preview detection complete");
+    Ok(Some(output))
+}
+
+fn find_recap_clips(
+    episodes: &[DecodedEpisode],
+    index: usize,
+    lookback: usize,
+) -> anyhow::Result<Vec<Clip>> {
+    let episode = &episodes[index];
+    let others = (index.saturating_sub(lookback)..index).collect::<Vec<_>>();
+    match_windows(
+        episodes,
+        &episode.head,
+        0.0,
+        0.0,
+        RECAP_SCAN_SECONDS,
+        &episode.excluded,
+        &others,
+    )
+}
+
+fn find_preview_clips(
+    episodes: &[DecodedEpisode],
+    index: usize,
+    lookahead: usize,
+) -> anyhow::Result<Vec<Clip>> {
+    let episode = &episodes[index];
+    let others = ((index + 1)..episodes.len().min(index + 1 + lookahead)).collect::<Vec<_>>();
+    let tail_seconds = episode.duration_seconds - episode.tail_offset_seconds;
+    match_windows(
+        episodes,
+        &episode.tail,
+        episode.tail_offset_seconds,
+        (tail_seconds - PREVIEW_SCAN_SECONDS).max(0.0),
+        tail_seconds,
+        &episode.excluded,
+        &others,
+    )
+}
+
+// match_fingerprints only reports the best alignment for a pair, so the scanned range is cut
+// into short windows to pick up each clip of a montage separately.
+fn match_windows(
+    episodes: &[DecodedEpisode],
+    fingerprint: &[u32],
+    fingerprint_offset_seconds: f32,
+    scan_start_seconds: f32,
+    scan_end_seconds: f32,
+    excluded: &[Clip],
+    others: &[usize],
+) -> anyhow::Result<Vec<Clip>> {
+    let config = chromaprint_config();
+    let item_seconds = config.item_duration_in_seconds();
+    let window_items = ((RECAP_WINDOW_SECONDS / item_seconds) as usize).max(1);
+    let scan_start = ((scan_start_seconds / item_seconds) as usize).min(fingerprint.len());
+    let scan_end = ((scan_end_seconds / item_seconds) as usize).min(fingerprint.len());
+
+    let mut clips = Vec::new();
+    let mut window_start = scan_start;
+    while window_start < scan_end {
+        let window_end = (window_start + window_items).min(scan_end);
+        let window = &fingerprint[window_start..window_end];
+        let window_offset_seconds = fingerprint_offset_seconds + window_start as f32 * item_seconds;
+
+        for &other_index in others {
+            let other = &episodes[other_index];
+            for (other_fingerprint, other_offset_seconds) in [
+                (&other.head, 0.0),
+                (&other.body, other.body_offset_seconds),
+                (&other.tail, other.tail_offset_seconds),
+            ] {
+                if other_fingerprint.is_empty() {
+                    continue;
+                }
+
+                for segment in match_fingerprints(window, other_fingerprint, &config)? {
+                    if segment.duration(&config) < RECAP_MIN_CLIP_SECONDS {
+                        continue;
+                    }
+
+                    let clip = Clip {
+                        start_seconds: window_offset_seconds + segment.start1(&config),
+                        end_seconds: window_offset_seconds + segment.end1(&config),
+                    };
+                    let source = Clip {
+                        start_seconds: other_offset_seconds + segment.start2(&config),
+                        end_seconds: other_offset_seconds + segment.end2(&config),
+                    };
+                    if overlaps_any(clip, excluded) || overlaps_any(source, &other.excluded) {
+                        continue;
+                    }
+
+                    clips.push(clip);
+                }
+            }
+        }
+
+        window_start = window_end;
+    }
+
+    Ok(clips)
+}
+
+fn overlaps_any(clip: Clip, excluded: &[Clip]) -> bool {
+    excluded.iter().any(|excluded| {
+        clip.start_seconds < excluded.end_seconds && excluded.start_seconds < clip.end_seconds
+    })
+}
+
+fn merge_clips(mut clips: Vec<Clip>) -> Vec<Clip> {
+    clips.sort_by(|a, b| a.start_seconds.total_cmp(&b.start_seconds));
+
+    let mut merged = Vec::<Clip>::with_capacity(clips.len());
+    for clip in clips {
+        if let Some(last) = merged.last_mut()
+            && clip.start_seconds - last.end_seconds <= RECAP_MERGE_GAP_SECONDS
+        {
+            last.end_seconds = last.end_seconds.max(clip.end_seconds);
+            continue;
+        }
+        merged.push(clip);
+    }
+
+    merged
+}
+
+// a recap opens the episode, so only a run of clips starting right at the beginning counts.
+// it never runs into the intro.
+fn select_recap(clips: Vec<Clip>, intro: Option<Clip>) -> Option<Clip> {
+    let mut recap = merge_clips(clips)
+        .into_iter()
+        .find(|clip| clip.start_seconds <= RECAP_MAX_START_SECONDS)?;
+    if let Some(intro) = intro
+        && intro.start_seconds > recap.start_seconds
+    {
+        recap.end_seconds = recap.end_seconds.min(intro.start_seconds);
+    }
+
+    (recap.end_seconds > recap.start_seconds).then_some(recap)
+}
+
+fn select_preview(clips: Vec<Clip>, duration_seconds: f32) -> Option<Clip> {
+    merge_clips(clips)
+        .into_iter()
+        .rev()
+        .find(|clip| duration_seconds - clip.end_seconds <= PREVIEW_MAX_END_GAP_SECONDS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Clip, DecodedEpisode, find_recap_clips, select_preview, select_recap};
+    use crate::chromaprint_config;
+
+    fn clip(start_seconds: f32, end_seconds: f32) -> Clip {
+        Clip {
+            start_seconds,
+            end_seconds,
+        }
+    }
+
+    fn noise(seed: u32, len: usize) -> Vec<u32> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state
+            })
+            .collect()
+    }
+
+    fn episode(head: Vec<u32>, body: Vec<u32>, tail: Vec<u32>) -> DecodedEpisode {
+        let item_seconds = chromaprint_config().item_duration_in_seconds();
+        let body_offset_seconds = head.len() as f32 * item_seconds;
+        let tail_offset_seconds = body_offset_seconds + body.len() as f32 * item_seconds;
+        DecodedEpisode {
+            duration_seconds: tail_offset_seconds + tail.len() as f32 * item_seconds,
+            head,
+            body,
+            body_offset_seconds,
+            tail,
+            tail_offset_seconds,
+            intro: None,
+            excluded: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn recap_merges_opening_clips_and_stops_at_intro() {
+        let recap = select_recap(
+            vec![
+                clip(22.0, 30.0),
+                clip(2.0, 10.0),
+                clip(14.0, 20.0),
+                clip(300.0, 320.0),
+            ],
+            Some(clip(26.0, 86.0)),
+        );
+
+        assert_eq!(recap, Some(clip(2.0, 26.0)));
+    }
+
+    #[test]
+    fn preview_must_end_near_the_end_of_the_file() {
+        assert_eq!(
+            select_preview(vec![clip(1000.0, 1020.0), clip(1300.0, 1330.0)], 1350.0),
+            Some(clip(1300.0, 1330.0))
+        );
+        assert_eq!(select_preview(vec![clip(1000.0, 1020.0)], 1350.0), None);
+    }
+
+    #[test]
+    fn recap_clips_match_the_middle_of_earlier_episodes() {
+        // the recap replays a scene from the middle of the previous episode, outside both the
+        // head and tail windows
+        let scene = noise(7, 120);
+        let previous_body = [noise(11, 400), scene.clone(), noise(13, 400)].concat();
+        let previous = episode(noise(17, 800), previous_body, noise(19, 800));
+        let current_head = [scene, noise(23, 1200)].concat();
+        let current = episode(current_head, noise(29, 800), noise(31, 800));
+
+        let clips = find_recap_clips(&[previous, current], 1, 1).unwrap();
+        let recap = select_recap(clips, None).expect("recap from the previous episode's body");
+        assert!(recap.start_seconds < 1.0);
+        assert!(recap.end_seconds > 10.0);
+    }
+}
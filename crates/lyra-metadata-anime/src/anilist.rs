@@ -0,0 +1,211 @@
+use anyhow::Result;
+use lyra_metadata::{
+    ImageSet, MetadataGenre, MetadataImage, MetadataImageKind, MetadataStatus, ResponseCache,
+    SeriesMetadata, score_display,
+};
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Duration;
+
+const ANILIST_API: &str = "https://graphql.anilist.co";
+const CACHE_TTL: Duration = Duration::from_hours(24);
+const MEDIA_QUERY: &str = r#"
+query ($search: String) {
+    Media(search: $search, type: ANIME) {
+        id
+        description(asHtml: false)
+        genres
+        averageScore
+        status
+        startDate { year month day }
+        endDate { year month day }
+        nextAiringEpisode { airingAt }
+        coverImage { extraLarge }
+        bannerImage
+    }
+}
+"#;
+
+// anilist has no anidb lookup, so it is searched by title. everything it returns only fills
+// gaps in tmdb's data, so a fuzzy hit can't overwrite anything tmdb already knew.
+pub(crate) async fn lookup_series_metadata(
+    client: &Client,
+    cache: &ResponseCache,
+    provider_id: &str,
+    title: &str,
+) -> Result<Option<SeriesMetadata>> {
+    let request = client
+        .post(ANILIST_API)
+        .json(&json!({ "query": MEDIA_QUERY, "variables": { "search": title } }));
+    let body = match cache
+        .fetch(
+            &format!("anilist:{title}"),
+            CACHE_TTL,
+            request,
+            std::future::ready(()),
+        )
+        .await
+    {
+        Ok(body) => body,
+        // anilist answers a search miss with a 404 and an error body
+        Err(error)
+            if error
+                .downcast_ref::<reqwest::Error>()
+                .and_then(reqwest::Error::status)
+                == Some(StatusCode::NOT_FOUND) =>
+        {
+            return Ok(None);
+        }
+        Err(error) => return Err(error),
+    };
+
+    let body: AniListResponse = serde_json::from_slice(&body)?;
+    let Some(media) = body.data.and_then(|data| data.media) else {
+        return Ok(None);
+    };
+
+    Ok(Some(SeriesMetadata {
+        imdb_id: None,
+        tmdb_id: None,
+        anidb_id: None,
+        name: title.to_owned(),
+        description: media.description.as_deref().map(strip_html),
+        score_display: score_display(media.average_score.map(|score| score as f64 / 10.0)),
+        score_normalized: media.average_score,
+        first_aired: media.start_date.and_then(FuzzyDate::timestamp),
+        last_aired: media.end_date.and_then(FuzzyDate::timestamp),
+        status: map_status(media.status.as_deref()),
+        tagline: None,
+        next_aired: media.next_airing_episode.map(|episode| episode.airing_at),
+        original_title: None,
+        original_language: None,
+        runtime_minutes: None,
+        genres: media
+            .genres
+            .into_iter()
+            .map(|name| MetadataGenre {
+                provider_id: provider_id.to_owned(),
+                external_id: None,
+                name,
+            })
+            .collect(),
+        content_ratings: Vec::new(),
+        studios: Vec::new(),
+        networks: Vec::new(),
+        keywords: Vec::new(),
+        cast: Vec::new(),
+        crew: Vec::new(),
+        recommendations: Vec::new(),
+        images: ImageSet {
+            posters: media
+                .cover_image
+                .and_then(|cover| cover.extra_large)
+                .map(|url| vec![image(MetadataImageKind::Poster, url)])
+                .unwrap_or_default(),
+            thumbnails: Vec::new(),
+            backdrops: media
+                .banner_image
+                .map(|url| vec![image(MetadataImageKind::Backdrop, url)])
+                .unwrap_or_default(),
+            logos: Vec::new(),
+        },
+        localizations: Vec::new(),
+    }))
+}
+
+fn image(kind: MetadataImageKind, url: String) -> MetadataImage {
+    MetadataImage {
+        kind,
+        url,
+        language: None,
+        vote_average: None,
+        vote_count: None,
+        width: None,
+        height: None,
+        file_type: None,
+    }
+}
+
+fn map_status(status: Option<&str>) -> Option<MetadataStatus> {
+    match status? {
+        "FINISHED" => Some(MetadataStatus::Finished),
+        "RELEASING" => Some(MetadataStatus::Airing),
+        "NOT_YET_RELEASED" => Some(MetadataStatus::Upcoming),
+        "CANCELLED" => Some(MetadataStatus::Cancelled),
+        "HIATUS" => Some(MetadataStatus::Returning),
+        _ => None,
+    }
+}
+
+// descriptions come back with <br> and <i> tags even with asHtml disabled
+fn strip_html(input: &str) -> String {
+    let input = input.replace("<br>", "\n").replace("<br />", "\n");
+    let mut output = String::with_capacity(input.len());
+    let mut in_tag = false;
+    for ch in input.chars() {
+        match ch {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => output.push(ch),
+            _ => {}
+        }
+    }
+    output.trim().to_owned()
+}
+
+#[derive(Debug, Deserialize)]
+struct AniListResponse {
+    data: Option<AniListData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AniListData {
+    #[serde(rename = "Media")]
+    media: Option<AniListMedia>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AniListMedia {
+    description: Option<String>,
+    #[serde(default)]
+    genres: Vec<String>,
+    average_score: Option<i64>,
+    status: Option<String>,
+    start_date: Option<FuzzyDate>,
+    end_date: Option<FuzzyDate>,
+    next_airing_episode: Option<AiringEpisode>,
+    cover_image: Option<CoverImage>,
+    banner_image: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FuzzyDate {
+    year: Option<i32>,
+    month: Option<u32>,
+    day: Option<u32>,
+}
+
+impl FuzzyDate {
+    fn timestamp(self) -> Option<i64> {
+        let date = chrono::NaiveDate::from_ymd_opt(
+            self.year?,
+            self.month.unwrap_or(1),
+            self.day.unwrap_or(1),
+        )?;
+        date.and_hms_opt(0, 0, 0).map(|ts| ts.and_utc().timestamp())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AiringEpisode {
+    airing_at: i64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CoverImage {
+    extra_large: Option<String>,
+}
@@ -0,0 +1,268 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use flate2::read::GzDecoder;
+use lyra_metadata::{
+    MetadataProvider, MovieCandidate, MovieMetadata, MovieRootMatchRequest, PersonMetadata,
+    ResponseCache, RootMatchHint, Scored, SeasonEpisodeCount, SeriesCandidate, SeriesItemsRequest,
+    SeriesItemsResult, SeriesMetadata, SeriesRootMatchRequest,
+};
+use mapping::{MappingIndex, needs_episode_counts, remap_item};
+use reqwest::Client;
+use std::{
+    collections::HashSet,
+    io::Read,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use titles::TitleIndex;
+use tokio::sync::Mutex;
+
+mod anilist;
+mod mapping;
+mod titles;
+
+const ANIDB_TITLES_URL: &str = "https://anidb.net/api/anime-titles.xml.gz";
+const ANIME_LIST_URL: &str =
+    "https://raw.githubusercontent.com/Anime-Lists/anime-lists/master/anime-list-master.xml";
+// anidb bans clients that fetch the title dump more than once a day. the dumps barely change,
+// so they are only refreshed weekly.
+const DUMP_TTL: Duration = Duration::from_hours(24 * 7);
+const USER_AGENT: &str = "lyra";
+
+struct LoadedIndex<T> {
+    loaded_at: Instant,
+    index: Arc<T>,
+}
+
+// anime-aware series matching on top of tmdb. roots resolve to an anidb id (from the file name
+// or anidb's title dump), the anime-lists mapping turns that into a tmdb show and season, and
+// absolute episode numbers are spread over tmdb's seasons before tmdb does the actual lookup.
+pub struct AnimeMetadataProvider {
+    tmdb: Arc<dyn MetadataProvider>,
+    client: Client,
+    cache: Arc<ResponseCache>,
+    titles: Mutex<Option<LoadedIndex<TitleIndex>>>,
+    mappings: Mutex<Option<LoadedIndex<MappingIndex>>>,
+}
+
+impl AnimeMetadataProvider {
+    pub fn new(tmdb: Arc<dyn MetadataProvider>, cache: Arc<ResponseCache>) -> Self {
+        let client = Client::builder()
+            .user_agent(USER_AGENT)
+            .build()
+            .expect("failed to build anime metadata client");
+        Self {
+            tmdb,
+            client,
+            cache,
+            titles: Mutex::new(None),
+            mappings: Mutex::new(None),
+        }
+    }
+
+    async fn titles(&self) -> Result<Arc<TitleIndex>> {
+        let mut titles = self.titles.lock().await;
+        if let Some(loaded) = titles.as_ref()
+            && loaded.loaded_at.elapsed() < DUMP_TTL
+        {
+            return Ok(loaded.index.clone());
+        }
+
+        let bytes = self.download(ANIDB_TITLES_URL).await?;
+        let index = Arc::new(
+            tokio::task::spawn_blocking(move || {
+                let mut xml = String::new();
+                GzDecoder::new(bytes.as_slice())
+                    .read_to_string(&mut xml)
+                    .context("failed to decompress anidb title dump")?;
+                TitleIndex::parse(&xml)
+            })
+            .await??,
+        );
+        *titles = Some(LoadedIndex {
+            loaded_at: Instant::now(),
+            index: index.clone(),
+        });
+        Ok(index)
+    }
+
+    async fn mappings(&self) -> Result<Arc<MappingIndex>> {
+        let mut mappings = self.mappings.lock().await;
+        if let Some(loaded) = mappings.as_ref()
+            && loaded.loaded_at.elapsed() < DUMP_TTL
+        {
+            return Ok(loaded.index.clone());
+        }
+
+        let bytes = self.download(ANIME_LIST_URL).await?;
+        let index = Arc::new(
+            tokio::task::spawn_blocking(move || {
+                MappingIndex::parse(&String::from_utf8_lossy(&bytes))
+            })
+            .await??,
+        );
+        *mappings = Some(LoadedIndex {
+            loaded_at: Instant::now(),
+            index: index.clone(),
+        });
+        Ok(index)
+    }
+
+    async fn download(&self, url: &str) -> Result<Vec<u8>> {
+        self.cache
+            .fetch(url, DUMP_TTL, self.client.get(url), std::future::ready(()))
+            .await
+    }
+
+    async fn resolve_anidb_id(&self, hint: &RootMatchHint) -> Result<Option<u64>> {
+        if let Some(pinned_id) = hint.pinned_id.as_deref() {
+            return pinned_id
+                .parse()
+                .map(Some)
+                .with_context(|| format!("invalid pinned anidb id '{pinned_id}'"));
+        }
+        if hint.anidb_id.is_some() {
+            return Ok(hint.anidb_id);
+        }
+        Ok(self.titles().await?.find(&hint.title))
+    }
+}
+
+#[async_trait]
+impl MetadataProvider for AnimeMetadataProvider {
+    fn id(&self) -> &'static str {
+        "anidb"
+    }
+
+    fn set_metadata_languages(&self, languages: &[String]) {
+        self.tmdb.set_metadata_languages(languages);
+    }
+
+    async fn match_series_root(
+        &self,
+        req: SeriesRootMatchRequest,
+    ) -> Result<Vec<Scored<SeriesCandidate>>> {
+        if !req.hint.anime && req.hint.pinned_id.is_none() {
+            return Ok(Vec::new());
+        }
+        let Some(anidb_id) = self.resolve_anidb_id(&req.hint).await? else {
+            return Ok(Vec::new());
+        };
+
+        let mapping = self.mappings().await?.get(anidb_id).cloned();
+        let hint = RootMatchHint {
+            tmdb_id: mapping
+                .as_ref()
+                .and_then(|mapping| mapping.tmdb_tv_id)
+                .or(req.hint.tmdb_id),
+            imdb_id: mapping
+                .as_ref()
+                .and_then(|mapping| mapping.imdb_id.clone())
+                .or(req.hint.imdb_id.clone()),
+            pinned_id: None,
+            ..req.hint
+        };
+
+        let candidates = self
+            .tmdb
+            .match_series_root(SeriesRootMatchRequest { hint })
+            .await?;
+        Ok(candidates
+            .into_iter()
+            .take(1)
+            .map(|Scored { value, score }| Scored {
+                value: SeriesCandidate {
+                    anidb_id: Some(anidb_id),
+                    ..value
+                },
+                score,
+            })
+            .collect())
+    }
+
+    async fn lookup_series_metadata(&self, candidate: &SeriesCandidate) -> Result<SeriesMetadata> {
+        let mut metadata = self.tmdb.lookup_series_metadata(candidate).await?;
+        metadata.anidb_id = candidate.anidb_id;
+        match anilist::lookup_series_metadata(&self.client, &self.cache, self.id(), &candidate.name)
+            .await
+        {
+            Ok(Some(anilist)) => metadata.fill_gaps(anilist),
+            Ok(None) => {}
+            Err(error) => {
+                tracing::warn!(name = candidate.name, "anilist lookup failed: {error:#}");
+            }
+        }
+        Ok(metadata)
+    }
+
+    async fn lookup_series_items(&self, req: SeriesItemsRequest) -> Result<SeriesItemsResult> {
+        let mappings = self.mappings().await?;
+        let mapping = req
+            .candidate
+            .anidb_id
+            .and_then(|anidb_id| mappings.get(anidb_id));
+        let counts = if needs_episode_counts(&req.items, mapping) {
+            self.tmdb
+                .lookup_season_episode_counts(&req.candidate)
+                .await?
+        } else {
+            Vec::new()
+        };
+
+        let local_seasons = req
+            .items
+            .iter()
+            .filter_map(|item| item.season_number)
+            .collect::<HashSet<_>>();
+        let absolute = req.items.iter().any(|item| item.season_number.is_none());
+        let items = req
+            .items
+            .iter()
+            .filter_map(|item| remap_item(item, mapping, &counts))
+            .collect();
+
+        let mut result = self
+            .tmdb
+            .lookup_series_items(SeriesItemsRequest { items, ..req })
+            .await?;
+        // seasons pulled in by remapping have no local node to attach to
+        result
+            .seasons
+            .retain(|season| local_seasons.contains(&season.season_number));
+        // an anidb entry usually covers a single tmdb season, and missing episodes come back in
+        // tmdb's numbering, which means nothing next to files numbered in absolute order
+        if absolute {
+            result.missing.clear();
+        }
+        result
+            .missing
+            .retain(|episode| local_seasons.contains(&episode.season_number));
+        Ok(result)
+    }
+
+    async fn lookup_season_episode_counts(
+        &self,
+        candidate: &SeriesCandidate,
+    ) -> Result<Vec<SeasonEpisodeCount>> {
+        self.tmdb.lookup_season_episode_counts(candidate).await
+    }
+
+    async fn lookup_people_metadata(
+        &self,
+        provider_person_ids: &[String],
+    ) -> Result<Vec<PersonMetadata>> {
+        self.tmdb.lookup_people_metadata(provider_person_ids).await
+    }
+
+    // anime films have no episode numbering to fix, tmdb handles them directly
+    async fn match_movie_root(
+        &self,
+        _req: MovieRootMatchRequest,
+    ) -> Result<Vec<Scored<MovieCandidate>>> {
+        Ok(Vec::new())
+    }
+
+    async fn lookup_movie_metadata(&self, _candidate: &MovieCandidate) -> Result<MovieMetadata> {
+        anyhow::bail!("anime provider does not match movies")
+    }
+}
@@ -0,0 +1,189 @@
+use anyhow::{Context, Result};
+use lyra_metadata::{SeasonEpisodeCount, SeriesItem};
+use roxmltree::Document;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SeasonMapping {
+    Absolute,
+    Season(i32),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct AnimeMapping {
+    pub tmdb_tv_id: Option<u64>,
+    pub imdb_id: Option<String>,
+    pub season: Option<SeasonMapping>,
+    pub episode_offset: i32,
+}
+
+// anime-lists style mapping from anidb ids to tmdb/tvdb. entries without explicit tmdb season
+// attributes fall back to the tvdb ones, since tmdb mirrors tvdb's season layout for nearly
+// every anime.
+#[derive(Debug, Default)]
+pub(crate) struct MappingIndex {
+    by_anidb_id: HashMap<u64, AnimeMapping>,
+}
+
+impl MappingIndex {
+    pub fn parse(xml: &str) -> Result<Self> {
+        let document = Document::parse(xml).context("invalid anime mapping list")?;
+        let mut index = Self::default();
+        for anime in document
+            .root_element()
+            .children()
+            .filter(|node| node.has_tag_name("anime"))
+        {
+            let Some(anidb_id) = anime.attribute("anidbid").and_then(|id| id.parse().ok()) else {
+                continue;
+            };
+
+            let tmdb_season = anime.attribute("tmdbseason").and_then(parse_season);
+            let (season, offset) = match tmdb_season {
+                Some(season) => (Some(season), anime.attribute("tmdboffset")),
+                None => (
+                    anime.attribute("defaulttvdbseason").and_then(parse_season),
+                    anime.attribute("episodeoffset"),
+                ),
+            };
+
+            index.by_anidb_id.insert(
+                anidb_id,
+                AnimeMapping {
+                    tmdb_tv_id: anime.attribute("tmdbtv").and_then(|id| id.parse().ok()),
+                    imdb_id: anime
+                        .attribute("imdbid")
+                        .filter(|id| id.starts_with("tt"))
+                        .map(str::to_owned),
+                    season,
+                    episode_offset: offset.and_then(|value| value.parse().ok()).unwrap_or(0),
+                },
+            );
+        }
+
+        Ok(index)
+    }
+
+    pub fn get(&self, anidb_id: u64) -> Option<&AnimeMapping> {
+        self.by_anidb_id.get(&anidb_id)
+    }
+}
+
+fn parse_season(value: &str) -> Option<SeasonMapping> {
+    match value.trim() {
+        "a" => Some(SeasonMapping::Absolute),
+        value => value.parse().ok().map(SeasonMapping::Season),
+    }
+}
+
+// items that already carry a season are left alone. season-less items use absolute numbering,
+// which either maps onto a single provider season or gets spread across every regular season
+// using the provider's episode counts.
+pub(crate) fn remap_item(
+    item: &SeriesItem,
+    mapping: Option<&AnimeMapping>,
+    counts: &[SeasonEpisodeCount],
+) -> Option<SeriesItem> {
+    if item.season_number.is_some() {
+        return Some(item.clone());
+    }
+
+    let episode_number = item.episode_number?;
+    let (season_number, episode_number) = match mapping.and_then(|mapping| mapping.season) {
+        Some(SeasonMapping::Season(season_number)) => (
+            season_number,
+            episode_number + mapping.map_or(0, |mapping| mapping.episode_offset),
+        ),
+        Some(SeasonMapping::Absolute) | None => resolve_absolute(
+            episode_number + mapping.map_or(0, |mapping| mapping.episode_offset),
+            counts,
+        )?,
+    };
+
+    Some(SeriesItem {
+        season_number: Some(season_number),
+        episode_number: Some(episode_number),
+        ..item.clone()
+    })
+}
+
+pub(crate) fn needs_episode_counts(items: &[SeriesItem], mapping: Option<&AnimeMapping>) -> bool {
+    !matches!(
+        mapping.and_then(|mapping| mapping.season),
+        Some(SeasonMapping::Season(_))
+    ) && items.iter().any(|item| item.season_number.is_none())
+}
+
+fn resolve_absolute(episode_number: i32, counts: &[SeasonEpisodeCount]) -> Option<(i32, i32)> {
+    let mut remaining = episode_number;
+    for count in counts
+        .iter()
+        .filter(|count| count.season_number > 0 && count.episode_count > 0)
+    {
+        if remaining <= count.episode_count {
+            return Some((count.season_number, remaining));
+        }
+        remaining -= count.episode_count;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(episode_number: i32) -> SeriesItem {
+        SeriesItem {
+            item_id: format!("episode-{episode_number}"),
+            season_number: None,
+            episode_number: Some(episode_number),
+            name: format!("Episode {episode_number}"),
+            file_paths: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn absolute_episodes_spread_across_provider_seasons() {
+        let index = MappingIndex::parse(
+            r#"<anime-list>
+                <anime anidbid="69" tvdbid="81797" defaulttvdbseason="a" episodeoffset="" tmdbtv="37854" imdbid="tt0388629">
+                    <name>One Piece</name>
+                </anime>
+                <anime anidbid="9541" tvdbid="267440" defaulttvdbseason="1" episodeoffset="13" tmdbtv="1429">
+                    <name>Shingeki no Kyojin</name>
+                </anime>
+            </anime-list>"#,
+        )
+        .unwrap();
+
+        let one_piece = index.get(69).unwrap();
+        assert_eq!(one_piece.tmdb_tv_id, Some(37854));
+        assert_eq!(one_piece.season, Some(SeasonMapping::Absolute));
+
+        let counts = [
+            SeasonEpisodeCount {
+                season_number: 0,
+                episode_count: 30,
+            },
+            SeasonEpisodeCount {
+                season_number: 1,
+                episode_count: 61,
+            },
+            SeasonEpisodeCount {
+                season_number: 2,
+                episode_count: 16,
+            },
+        ];
+        assert!(needs_episode_counts(&[item(62)], Some(one_piece)));
+        let remapped = remap_item(&item(62), Some(one_piece), &counts).unwrap();
+        assert_eq!(remapped.season_number, Some(2));
+        assert_eq!(remapped.episode_number, Some(1));
+        assert!(remap_item(&item(200), Some(one_piece), &counts).is_none());
+
+        let offset = index.get(9541).unwrap();
+        assert!(!needs_episode_counts(&[item(1)], Some(offset)));
+        let remapped = remap_item(&item(2), Some(offset), &[]).unwrap();
+        assert_eq!(remapped.season_number, Some(1));
+        assert_eq!(remapped.episode_number, Some(15));
+    }
+}
@@ -0,0 +1,112 @@
+use anyhow::{Context, Result};
+use roxmltree::Document;
+use std::collections::HashMap;
+
+// anidb's title dump lists every main, official, synonym and short title per anime. lookups go
+// through a normalized key so release-group spellings still land on the right entry.
+#[derive(Debug, Default)]
+pub(crate) struct TitleIndex {
+    by_title: HashMap<String, Vec<(u8, u64)>>,
+}
+
+impl TitleIndex {
+    pub fn parse(xml: &str) -> Result<Self> {
+        let document = Document::parse(xml).context("invalid anidb title dump")?;
+        let mut index = Self::default();
+        for anime in document
+            .root_element()
+            .children()
+            .filter(|node| node.has_tag_name("anime"))
+        {
+            let Some(anidb_id) = anime.attribute("aid").and_then(|aid| aid.parse().ok()) else {
+                continue;
+            };
+
+            for title in anime.children().filter(|node| node.has_tag_name("title")) {
+                let rank = match title.attribute("type") {
+                    Some("main") => 0,
+                    Some("official") => 1,
+                    Some("syn") => 2,
+                    _ => 3,
+                };
+                let key = normalize_title(title.text().unwrap_or_default());
+                if !key.is_empty() {
+                    index
+                        .by_title
+                        .entry(key)
+                        .or_default()
+                        .push((rank, anidb_id));
+                }
+            }
+        }
+
+        Ok(index)
+    }
+
+    // main titles beat synonyms, and on a tie the older entry is usually the series rather than
+    // a later sequel reusing the name
+    pub fn find(&self, title: &str) -> Option<u64> {
+        self.by_title
+            .get(&normalize_title(title))?
+            .iter()
+            .min()
+            .map(|(_, anidb_id)| *anidb_id)
+    }
+}
+
+fn normalize_title(input: &str) -> String {
+    input
+        .chars()
+        .filter(|ch| ch.is_alphanumeric() || ch.is_whitespace())
+        .collect::<String>()
+        .to_lowercase()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn release_spellings_find_the_main_title_first() {
+        let index = TitleIndex::parse(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+            <animetitles>
+                <anime aid="9541">
+                    <title xml:lang="x-jat" type="main">Shingeki no Kyojin</title>
+                    <title xml:lang="en" type="official">Attack on Titan</title>
+                </anime>
+                <anime aid="10944">
+                    <title xml:lang="x-jat" type="main">Shingeki no Kyojin (2013) OVA</title>
+                    <title xml:lang="en" type="syn">Attack on Titan</title>
+                </anime>
+                <anime aid="bad">
+                    <title type="main">Broken</title>
+                </anime>
+            </animetitles>"#,
+        )
+        .unwrap();
+
+        assert_eq!(index.find("shingeki  no kyojin"), Some(9541));
+        assert_eq!(index.find("Attack on Titan!"), Some(9541));
+        assert_eq!(index.find("Shingeki no Kyojin (2013) OVA"), Some(10944));
+        assert_eq!(index.find("Broken"), None);
+        assert_eq!(index.find("Unknown Show"), None);
+    }
+
+    #[test]
+    fn ties_prefer_the_older_entry() {
+        let index = TitleIndex::parse(
+            r#"<animetitles>
+                <anime aid="200"><title type="syn">Reused Name</title></anime>
+                <anime aid="100"><title type="syn">Reused Name</title></anime>
+            </animetitles>"#,
+        )
+        .unwrap();
+
+        assert_eq!(index.find("reused name"), Some(100));
+        assert!(TitleIndex::parse("<animetitles>").is_err());
+    }
+}
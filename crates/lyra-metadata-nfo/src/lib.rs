@@ -0,0 +1,572 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use lyra_metadata::{
+    CastCredit, ContentRating, EpisodeMetadata, ImageSet, MetadataCompany, MetadataGenre,
+    MetadataImage, MetadataImageKind, MetadataProvider, MetadataStatus, MovieCandidate,
+    MovieMetadata, MovieRootMatchRequest, PersonMetadata, RootMatchHint, Scored, SeasonMetadata,
+    SeriesCandidate, SeriesItem, SeriesItemsRequest, SeriesItemsResult, SeriesMetadata,
+    SeriesRootMatchRequest, score_display, score_normalized,
+};
+use parse::{NfoDetails, parse_nfo};
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+mod parse;
+
+const PROVIDER_ID: &str = "nfo";
+// roots sync one after another, so a few roots worth of actors is plenty to answer the people
+// lookup that follows each parse
+const MAX_REMEMBERED_PEOPLE: usize = 2048;
+
+// reads kodi style .nfo files stored next to media. there is nothing to search, so roots only
+// match when a file exists, and it runs before remote providers so hand-curated metadata wins.
+#[derive(Default)]
+pub struct NfoMetadataProvider {
+    // nfo actors have no ids, so people are keyed by name and remembered from the last parse
+    people: Mutex<HashMap<String, PersonMetadata>>,
+}
+
+impl NfoMetadataProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn remember_people(&self, details: &NfoDetails) {
+        let mut people = self.people.lock().unwrap();
+        if people.len() + details.actors.len() > MAX_REMEMBERED_PEOPLE {
+            people.clear();
+        }
+        for actor in &details.actors {
+            people.insert(
+                actor.name.clone(),
+                PersonMetadata {
+                    provider_person_id: actor.name.clone(),
+                    name: actor.name.clone(),
+                    birthday: None,
+                    description: None,
+                    profile_image_url: actor.thumb.clone(),
+                },
+            );
+        }
+    }
+}
+
+#[async_trait]
+impl MetadataProvider for NfoMetadataProvider {
+    fn id(&self) -> &'static str {
+        PROVIDER_ID
+    }
+
+    async fn match_series_root(
+        &self,
+        req: SeriesRootMatchRequest,
+    ) -> Result<Vec<Scored<SeriesCandidate>>> {
+        // episode files are either directly in the show folder or one level down in a season
+        // folder, so tvshow.nfo is checked in both
+        let search_dirs = req
+            .hint
+            .file_paths
+            .iter()
+            .filter_map(|path| path.parent())
+            .flat_map(|dir| [Some(dir), dir.parent()])
+            .flatten()
+            .collect::<BTreeSet<_>>();
+        let candidates = search_dirs
+            .into_iter()
+            .map(|dir| dir.join("tvshow.nfo"))
+            .collect::<Vec<_>>();
+
+        let Some((path, details)) = find_nfo(&req.hint, &candidates, "tvshow").await? else {
+            return Ok(Vec::new());
+        };
+
+        Ok(vec![Scored {
+            value: SeriesCandidate {
+                tmdb_id: details.tmdb_id,
+                name: details.title.clone().unwrap_or(req.hint.title),
+                first_air_year: details
+                    .year
+                    .or_else(|| year_from_timestamp(details.premiered)),
+                poster_url: first_poster(&details),
+                source_path: Some(path),
+                anidb_id: None,
+            },
+            score: 1.0,
+        }])
+    }
+
+    async fn lookup_series_metadata(&self, candidate: &SeriesCandidate) -> Result<SeriesMetadata> {
+        let path = candidate_path(candidate.source_path.as_deref())?;
+        let details = read_nfo(path, "tvshow")
+            .await?
+            .with_context(|| format!("missing tvshow entry in {}", path.display()))?;
+        self.remember_people(&details);
+
+        Ok(SeriesMetadata {
+            imdb_id: details.imdb_id.clone(),
+            tmdb_id: details.tmdb_id,
+            anidb_id: None,
+            name: details
+                .title
+                .clone()
+                .unwrap_or_else(|| candidate.name.clone()),
+            description: details.plot.clone(),
+            score_display: score_display(details.rating),
+            score_normalized: score_normalized(details.rating),
+            first_aired: details.premiered,
+            last_aired: None,
+            status: map_status(details.status.as_deref()),
+            tagline: details.tagline.clone(),
+            next_aired: None,
+            original_title: details.original_title.clone(),
+            original_language: None,
+            runtime_minutes: details.runtime,
+            genres: map_genres(&details.genres),
+            content_ratings: map_content_ratings(details.mpaa.as_deref()),
+            // kodi writes a show's network into <studio>
+            studios: Vec::new(),
+            networks: map_companies(&details.studios),
+            keywords: Vec::new(),
+            cast: map_cast(&details),
+            crew: map_crew(&details),
+            recommendations: Vec::new(),
+            images: images_from_details(&details, None),
+            localizations: Vec::new(),
+        })
+    }
+
+    async fn lookup_series_items(&self, req: SeriesItemsRequest) -> Result<SeriesItemsResult> {
+        let show_path = candidate_path(req.candidate.source_path.as_deref())?;
+        let show = read_nfo(show_path, "tvshow").await?.unwrap_or_default();
+        let show_dir = show_path.parent();
+
+        let mut episodes = Vec::new();
+        let mut season_dirs = HashMap::new();
+        for item in &req.items {
+            for file_path in &item.file_paths {
+                if let Some(dir) = file_path.parent()
+                    && Some(dir) != show_dir
+                    && let Some(season_number) = item.season_number
+                {
+                    season_dirs.entry(season_number).or_insert(dir);
+                }
+            }
+
+            if let Some(episode) = read_episode(item).await? {
+                episodes.push(episode);
+            }
+        }
+
+        let season_numbers = req
+            .items
+            .iter()
+            .filter_map(|item| item.season_number)
+            .collect::<BTreeSet<_>>();
+        let mut seasons = Vec::new();
+        for season_number in season_numbers {
+            let season_nfo = match season_dirs.get(&season_number) {
+                Some(dir) => read_nfo(&dir.join("season.nfo"), "season").await?,
+                None => None,
+            };
+            if let Some(season) = season_metadata(&req.root_id, season_number, &show, season_nfo) {
+                seasons.push(season);
+            }
+        }
+
+        Ok(SeriesItemsResult {
+            seasons,
+            episodes,
+            positions: Vec::new(),
+            missing: Vec::new(),
+        })
+    }
+
+    async fn match_movie_root(
+        &self,
+        req: MovieRootMatchRequest,
+    ) -> Result<Vec<Scored<MovieCandidate>>> {
+        let candidates = req
+            .hint
+            .file_paths
+            .iter()
+            .flat_map(|path| {
+                [
+                    Some(path.with_extension("nfo")),
+                    path.parent().map(|dir| dir.join("movie.nfo")),
+                ]
+            })
+            .flatten()
+            .collect::<Vec<_>>();
+
+        let Some((path, details)) = find_nfo(&req.hint, &candidates, "movie").await? else {
+            return Ok(Vec::new());
+        };
+
+        Ok(vec![Scored {
+            value: MovieCandidate {
+                tmdb_id: details.tmdb_id,
+                name: details.title.clone().unwrap_or(req.hint.title),
+                release_year: details
+                    .year
+                    .or_else(|| year_from_timestamp(details.premiered)),
+                poster_url: first_poster(&details),
+                source_path: Some(path),
+            },
+            score: 1.0,
+        }])
+    }
+
+    async fn lookup_movie_metadata(&self, candidate: &MovieCandidate) -> Result<MovieMetadata> {
+        let path = candidate_path(candidate.source_path.as_deref())?;
+        let details = read_nfo(path, "movie")
+            .await?
+            .with_context(|| format!("missing movie entry in {}", path.display()))?;
+        self.remember_people(&details);
+
+        Ok(MovieMetadata {
+            imdb_id: details.imdb_id.clone(),
+            tmdb_id: details.tmdb_id,
+            name: details
+                .title
+                .clone()
+                .unwrap_or_else(|| candidate.name.clone()),
+            description: details.plot.clone(),
+            score_display: score_display(details.rating),
+            score_normalized: score_normalized(details.rating),
+            first_aired: details.premiered,
+            last_aired: details.premiered,
+            status: details.premiered.map(|_| MetadataStatus::Released),
+            tagline: details.tagline.clone(),
+            original_title: details.original_title.clone(),
+            original_language: None,
+            runtime_minutes: details.runtime,
+            budget: None,
+            genres: map_genres(&details.genres),
+            content_ratings: map_content_ratings(details.mpaa.as_deref()),
+            studios: map_companies(&details.studios),
+            keywords: Vec::new(),
+            cast: map_cast(&details),
+            crew: map_crew(&details),
+            recommendations: Vec::new(),
+            images: images_from_details(&details, None),
+            localizations: Vec::new(),
+            collection: None,
+        })
+    }
+
+    async fn lookup_people_metadata(
+        &self,
+        provider_person_ids: &[String],
+    ) -> Result<Vec<PersonMetadata>> {
+        let people = self.people.lock().unwrap();
+        Ok(provider_person_ids
+            .iter()
+            .filter_map(|id| people.get(id).cloned())
+            .collect())
+    }
+}
+
+// a pinned nfo root stores the path of the file it was pinned to
+async fn find_nfo(
+    hint: &RootMatchHint,
+    candidates: &[PathBuf],
+    kind: &str,
+) -> Result<Option<(PathBuf, NfoDetails)>> {
+    if let Some(pinned_id) = hint.pinned_id.as_deref() {
+        let path = PathBuf::from(pinned_id);
+        let details = read_nfo(&path, kind)
+            .await?
+            .with_context(|| format!("pinned nfo {} has no {kind} entry", path.display()))?;
+        return Ok(Some((path, details)));
+    }
+
+    for path in candidates {
+        match read_nfo(path, kind).await {
+            Ok(Some(details)) => return Ok(Some((path.clone(), details))),
+            Ok(None) => {}
+            Err(error) => {
+                tracing::warn!(path = %path.display(), "ignoring unreadable nfo: {error:#}");
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+async fn read_nfo(path: &Path, kind: &str) -> Result<Option<NfoDetails>> {
+    Ok(read_nfo_entries(path)
+        .await?
+        .into_iter()
+        .find(|details| details.kind == kind))
+}
+
+async fn read_nfo_entries(path: &Path) -> Result<Vec<NfoDetails>> {
+    if !tokio::fs::try_exists(path).await? {
+        return Ok(Vec::new());
+    }
+
+    let bytes = tokio::fs::read(path)
+        .await
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    parse_nfo(&String::from_utf8_lossy(&bytes))
+        .with_context(|| format!("failed to parse {}", path.display()))
+}
+
+async fn read_episode(item: &SeriesItem) -> Result<Option<EpisodeMetadata>> {
+    for file_path in &item.file_paths {
+        let path = file_path.with_extension("nfo");
+        let entries = match read_nfo_entries(&path).await {
+            Ok(entries) => entries,
+            Err(error) => {
+                tracing::warn!(path = %path.display(), "ignoring unreadable nfo: {error:#}");
+                continue;
+            }
+        };
+
+        // multi-episode files list every episode, so pick the one for this item
+        let mut entries = entries
+            .into_iter()
+            .filter(|details| details.kind == "episodedetails")
+            .collect::<Vec<_>>();
+        let position = entries
+            .iter()
+            .position(|details| {
+                details.episode.is_some()
+                    && details.episode == item.episode_number
+                    && (details.season.is_none() || details.season == item.season_number)
+            })
+            .unwrap_or(0);
+        if entries.is_empty() {
+            continue;
+        }
+        let details = entries.swap_remove(position);
+
+        return Ok(Some(EpisodeMetadata {
+            item_id: item.item_id.clone(),
+            name: details.title.clone().unwrap_or_else(|| item.name.clone()),
+            description: details.plot.clone(),
+            score_display: score_display(details.rating),
+            score_normalized: score_normalized(details.rating),
+            first_aired: details.premiered,
+            last_aired: details.premiered,
+            status: None,
+            tagline: None,
+            next_aired: None,
+            genres: Vec::new(),
+            content_ratings: Vec::new(),
+            recommendations: Vec::new(),
+            images: ImageSet {
+                posters: Vec::new(),
+                thumbnails: details
+                    .thumbs
+                    .iter()
+                    .map(|thumb| image(MetadataImageKind::Thumbnail, &thumb.url))
+                    .collect(),
+                backdrops: Vec::new(),
+                logos: Vec::new(),
+            },
+            localizations: Vec::new(),
+        }));
+    }
+
+    Ok(None)
+}
+
+// seasons only get a row when there is something local to say about them, otherwise the next
+// provider fills them in entirely
+fn season_metadata(
+    root_id: &str,
+    season_number: i32,
+    show: &NfoDetails,
+    season_nfo: Option<NfoDetails>,
+) -> Option<SeasonMetadata> {
+    let season_nfo = season_nfo.unwrap_or_default();
+    let name = season_nfo.title.clone().or_else(|| {
+        show.named_seasons
+            .iter()
+            .find(|(number, _)| *number == season_number)
+            .map(|(_, name)| name.clone())
+    });
+    let mut images = images_from_details(&season_nfo, None);
+    images.fill_gaps(images_from_details(show, Some(season_number)));
+
+    let has_images =
+        !images.posters.is_empty() || !images.thumbnails.is_empty() || !images.backdrops.is_empty();
+    if name.is_none() && season_nfo.plot.is_none() && !has_images {
+        return None;
+    }
+
+    Some(SeasonMetadata {
+        root_id: root_id.to_owned(),
+        season_number,
+        name: name.unwrap_or_else(|| format!("Season {season_number}")),
+        description: season_nfo.plot.clone(),
+        score_display: None,
+        score_normalized: None,
+        first_aired: season_nfo.premiered,
+        last_aired: season_nfo.premiered,
+        status: None,
+        tagline: None,
+        next_aired: None,
+        genres: Vec::new(),
+        content_ratings: Vec::new(),
+        recommendations: Vec::new(),
+        images: ImageSet {
+            logos: Vec::new(),
+            ..images
+        },
+        localizations: Vec::new(),
+    })
+}
+
+fn candidate_path(source_path: Option<&Path>) -> Result<&Path> {
+    source_path.context("candidate has no nfo path")
+}
+
+// thumbs with a season attribute belong to that season; the rest describe the item itself
+fn images_from_details(details: &NfoDetails, season: Option<i32>) -> ImageSet {
+    let mut images = ImageSet::default();
+    for thumb in details.thumbs.iter().filter(|thumb| thumb.season == season) {
+        match thumb.aspect.as_deref() {
+            None | Some("poster") => images
+                .posters
+                .push(image(MetadataImageKind::Poster, &thumb.url)),
+            Some("landscape") => images
+                .thumbnails
+                .push(image(MetadataImageKind::Thumbnail, &thumb.url)),
+            Some("clearlogo") => images
+                .logos
+                .push(image(MetadataImageKind::Logo, &thumb.url)),
+            _ => {}
+        }
+    }
+    if season.is_none() {
+        images.backdrops = details
+            .fanart
+            .iter()
+            .map(|url| image(MetadataImageKind::Backdrop, url))
+            .collect();
+    }
+    images
+}
+
+fn first_poster(details: &NfoDetails) -> Option<String> {
+    images_from_details(details, None)
+        .posters
+        .into_iter()
+        .next()
+        .map(|image| image.url)
+}
+
+fn image(kind: MetadataImageKind, url: &str) -> MetadataImage {
+    MetadataImage {
+        kind,
+        url: url.to_owned(),
+        language: None,
+        vote_average: None,
+        vote_count: None,
+        width: None,
+        height: None,
+        file_type: None,
+    }
+}
+
+fn map_cast(details: &NfoDetails) -> Vec<CastCredit> {
+    details
+        .actors
+        .iter()
+        .map(|actor| CastCredit {
+            provider_person_id: actor.name.clone(),
+            name: actor.name.clone(),
+            character_name: actor.role.clone(),
+            department: None,
+            job: None,
+        })
+        .collect()
+}
+
+fn map_crew(details: &NfoDetails) -> Vec<CastCredit> {
+    let directors = details
+        .directors
+        .iter()
+        .map(|name| ("Directing", "Director", name));
+    let writers = details
+        .writers
+        .iter()
+        .map(|name| ("Writing", "Writer", name));
+    directors
+        .chain(writers)
+        .map(|(department, job, name)| CastCredit {
+            provider_person_id: name.clone(),
+            name: name.clone(),
+            character_name: None,
+            department: Some(department.to_owned()),
+            job: Some(job.to_owned()),
+        })
+        .collect()
+}
+
+fn map_genres(genres: &[String]) -> Vec<MetadataGenre> {
+    genres
+        .iter()
+        .map(|name| MetadataGenre {
+            provider_id: PROVIDER_ID.to_owned(),
+            external_id: None,
+            name: name.clone(),
+        })
+        .collect()
+}
+
+fn map_companies(names: &[String]) -> Vec<MetadataCompany> {
+    names
+        .iter()
+        .map(|name| MetadataCompany {
+            provider_id: PROVIDER_ID.to_owned(),
+            external_id: None,
+            name: name.clone(),
+            origin_country: None,
+        })
+        .collect()
+}
+
+// kodi writes "US:TV-14", "Rated PG-13" or just "PG-13", sometimes several joined with " / "
+fn map_content_ratings(mpaa: Option<&str>) -> Vec<ContentRating> {
+    let Some(mpaa) = mpaa else {
+        return Vec::new();
+    };
+
+    mpaa.split(" / ")
+        .filter_map(|value| {
+            let value = value.trim();
+            let (country_code, rating) = match value.split_once(':') {
+                Some((country_code, rating)) => (country_code.trim(), rating.trim()),
+                None => ("US", value.strip_prefix("Rated ").unwrap_or(value).trim()),
+            };
+            (!rating.is_empty()).then(|| ContentRating {
+                country_code: country_code.to_ascii_uppercase(),
+                rating: rating.to_owned(),
+                release_date: None,
+                release_type: None,
+                descriptors: Vec::new(),
+            })
+        })
+        .collect()
+}
+
+fn map_status(status: Option<&str>) -> Option<MetadataStatus> {
+    match status?.to_ascii_lowercase().as_str() {
+        "continuing" | "returning series" => Some(MetadataStatus::Returning),
+        "ended" => Some(MetadataStatus::Finished),
+        "canceled" | "cancelled" => Some(MetadataStatus::Cancelled),
+        "upcoming" | "in production" | "planned" => Some(MetadataStatus::Upcoming),
+        _ => None,
+    }
+}
+
+fn year_from_timestamp(timestamp: Option<i64>) -> Option<i32> {
+    use chrono::Datelike;
+    chrono::DateTime::from_timestamp(timestamp?, 0).map(|date| date.year())
+}
@@ -0,0 +1,332 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use roxmltree::{Document, Node};
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct NfoDetails {
+    pub kind: String,
+    pub title: Option<String>,
+    pub original_title: Option<String>,
+    pub plot: Option<String>,
+    pub tagline: Option<String>,
+    pub rating: Option<f64>,
+    pub year: Option<i32>,
+    pub premiered: Option<i64>,
+    pub status: Option<String>,
+    pub mpaa: Option<String>,
+    pub genres: Vec<String>,
+    // in minutes
+    pub runtime: Option<i64>,
+    pub studios: Vec<String>,
+    pub imdb_id: Option<String>,
+    pub tmdb_id: Option<u64>,
+    pub season: Option<i32>,
+    pub episode: Option<i32>,
+    pub actors: Vec<NfoActor>,
+    pub directors: Vec<String>,
+    // kodi stores writers as <credits>
+    pub writers: Vec<String>,
+    pub thumbs: Vec<NfoThumb>,
+    pub fanart: Vec<String>,
+    pub named_seasons: Vec<(i32, String)>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct NfoActor {
+    pub name: String,
+    pub role: Option<String>,
+    pub thumb: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct NfoThumb {
+    pub aspect: Option<String>,
+    pub season: Option<i32>,
+    pub url: String,
+}
+
+// kodi writes one <episodedetails> per episode into the same file for multi-episode files, and
+// allows a scraper url after the closing tag. wrapping everything in a synthetic root handles
+// both without a custom parser.
+pub(crate) fn parse_nfo(text: &str) -> Result<Vec<NfoDetails>> {
+    let mut body = String::with_capacity(text.len() + 11);
+    let mut rest = text.trim_start_matches('\u{feff}');
+    while let Some(start) = rest.find("<?xml") {
+        body.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find("?>") else {
+            rest = "";
+            break;
+        };
+        rest = &rest[start + end + 2..];
+    }
+    body.push_str(rest);
+
+    let wrapped = format!("<nfo>{body}</nfo>");
+    let document = Document::parse(&wrapped).context("invalid nfo xml")?;
+    Ok(document
+        .root_element()
+        .children()
+        .filter(Node::is_element)
+        .map(parse_details)
+        .collect())
+}
+
+fn parse_details(node: Node) -> NfoDetails {
+    let mut details = NfoDetails {
+        kind: node.tag_name().name().to_owned(),
+        ..Default::default()
+    };
+    let mut outline = None;
+    let mut aired = None;
+    let mut actors = Vec::new();
+
+    for child in node.children().filter(Node::is_element) {
+        match child.tag_name().name() {
+            "title" => details.title = text(child),
+            "originaltitle" => details.original_title = text(child),
+            "plot" => details.plot = text(child),
+            "outline" => outline = text(child),
+            "tagline" => details.tagline = text(child),
+            "rating" => details.rating = details.rating.or(parse_number(child)),
+            "ratings" => details.rating = parse_ratings(child).or(details.rating),
+            "year" => details.year = text(child).and_then(|value| value.parse().ok()),
+            "premiered" => details.premiered = text(child).as_deref().and_then(parse_date),
+            "aired" => aired = text(child).as_deref().and_then(parse_date),
+            "status" => details.status = text(child),
+            "mpaa" => details.mpaa = text(child),
+            // some scrapers write "Drama / Comedy" into a single element
+            "genre" => details.genres.extend(split_list(child)),
+            "studio" => details.studios.extend(split_list(child)),
+            "runtime" => {
+                details.runtime = text(child)
+                    .and_then(|value| value.parse().ok())
+                    .filter(|minutes| *minutes > 0)
+            }
+            "uniqueid" => {
+                let id_type = child.attribute("type").unwrap_or("").to_ascii_lowercase();
+                apply_unique_id(&mut details, &id_type, text(child));
+            }
+            "imdbid" => apply_unique_id(&mut details, "imdb", text(child)),
+            "tmdbid" => apply_unique_id(&mut details, "tmdb", text(child)),
+            "id" => {
+                if let Some(value) = text(child)
+                    && value.starts_with("tt")
+                {
+                    apply_unique_id(&mut details, "imdb", Some(value));
+                }
+            }
+            "season" => details.season = text(child).and_then(|value| value.parse().ok()),
+            "episode" => details.episode = text(child).and_then(|value| value.parse().ok()),
+            "actor" => {
+                if let Some(actor) = parse_actor(child) {
+                    actors.push(actor);
+                }
+            }
+            "director" => details.directors.extend(text(child)),
+            "credits" => details.writers.extend(text(child)),
+            "thumb" => {
+                if let Some(url) = text(child).filter(|url| is_remote_url(url)) {
+                    details.thumbs.push(NfoThumb {
+                        aspect: child.attribute("aspect").map(str::to_ascii_lowercase),
+                        season: child
+                            .attribute("season")
+                            .and_then(|value| value.parse().ok()),
+                        url,
+                    });
+                }
+            }
+            "fanart" => {
+                let base = child.attribute("url").unwrap_or("");
+                for thumb in child.children().filter(|node| node.has_tag_name("thumb")) {
+                    if let Some(url) = text(thumb).map(|path| format!("{base}{path}"))
+                        && is_remote_url(&url)
+                    {
+                        details.fanart.push(url);
+                    }
+                }
+            }
+            "namedseason" => {
+                if let Some(number) = child.attribute("number").and_then(|n| n.parse().ok())
+                    && let Some(name) = text(child)
+                {
+                    details.named_seasons.push((number, name));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    details.plot = details.plot.or(outline);
+    details.premiered = details.premiered.or(aired);
+    actors.sort_by_key(|(order, _)| *order);
+    details.actors = actors.into_iter().map(|(_, actor)| actor).collect();
+    details
+}
+
+fn parse_actor(node: Node) -> Option<(i64, NfoActor)> {
+    let mut name = None;
+    let mut role = None;
+    let mut thumb = None;
+    let mut order = i64::MAX;
+    for child in node.children().filter(Node::is_element) {
+        match child.tag_name().name() {
+            "name" => name = text(child),
+            "role" => role = text(child),
+            "thumb" => thumb = text(child).filter(|url| is_remote_url(url)),
+            "order" => {
+                order = text(child)
+                    .and_then(|value| value.parse().ok())
+                    .unwrap_or(order)
+            }
+            _ => {}
+        }
+    }
+
+    Some((
+        order,
+        NfoActor {
+            name: name?,
+            role,
+            thumb,
+        },
+    ))
+}
+
+// prefer the rating kodi marks as default, falling back to whichever comes first
+fn parse_ratings(node: Node) -> Option<f64> {
+    let ratings = node
+        .children()
+        .filter(|child| child.has_tag_name("rating"))
+        .collect::<Vec<_>>();
+    let rating = ratings
+        .iter()
+        .find(|rating| rating.attribute("default") == Some("true"))
+        .or(ratings.first())?;
+    let max = rating
+        .attribute("max")
+        .and_then(|value| value.parse::<f64>().ok())
+        .filter(|max| *max > 0.0)
+        .unwrap_or(10.0);
+    let value = rating
+        .children()
+        .find(|child| child.has_tag_name("value"))
+        .and_then(parse_number)?;
+    Some(value * 10.0 / max)
+}
+
+fn apply_unique_id(details: &mut NfoDetails, id_type: &str, value: Option<String>) {
+    let Some(value) = value else {
+        return;
+    };
+    match id_type {
+        "imdb" if value.starts_with("tt") => details.imdb_id = Some(value),
+        "tmdb" => details.tmdb_id = value.parse().ok().or(details.tmdb_id),
+        _ => {}
+    }
+}
+
+fn split_list(node: Node) -> Vec<String> {
+    text(node)
+        .iter()
+        .flat_map(|value| value.split('/'))
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+fn parse_number(node: Node) -> Option<f64> {
+    text(node)?.parse::<f64>().ok().filter(|value| *value > 0.0)
+}
+
+fn text(node: Node) -> Option<String> {
+    let value = node.text()?.trim();
+    (!value.is_empty()).then(|| value.to_owned())
+}
+
+// local artwork paths are handled by the scanner, only remote urls are useful here
+fn is_remote_url(value: &str) -> bool {
+    value.starts_with("http://") || value.starts_with("https://")
+}
+
+pub(crate) fn parse_date(value: &str) -> Option<i64> {
+    let trimmed = value.get(..10).unwrap_or(value);
+    let date = NaiveDate::parse_from_str(trimmed, "%Y-%m-%d").ok()?;
+    date.and_hms_opt(0, 0, 0).map(|ts| ts.and_utc().timestamp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_original_title_runtime_and_studios() {
+        let text = r#"<movie>
+    <title>Spirited Away</title>
+    <originaltitle>千と千尋の神隠し</originaltitle>
+    <runtime>125</runtime>
+    <studio>Studio Ghibli / Tokuma Shoten</studio>
+    <studio>Dentsu</studio>
+</movie>"#;
+
+        let parsed = parse_nfo(text).unwrap();
+        assert_eq!(
+            parsed[0].original_title.as_deref(),
+            Some("千と千尋の神隠し")
+        );
+        assert_eq!(parsed[0].runtime, Some(125));
+        assert_eq!(
+            parsed[0].studios,
+            ["Studio Ghibli", "Tokuma Shoten", "Dentsu"]
+        );
+    }
+
+    #[test]
+    fn parses_multi_episode_files_and_kodi_fields() {
+        let text = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes" ?>
+<episodedetails>
+    <title>Pilot</title>
+    <outline>Short outline</outline>
+    <season>1</season>
+    <episode>1</episode>
+    <ratings>
+        <rating name="imdb" max="10"><value>6.0</value></rating>
+        <rating name="themoviedb" max="10" default="true"><value>8.25</value></rating>
+    </ratings>
+    <uniqueid type="tmdb">62085</uniqueid>
+    <aired>2008-01-20</aired>
+    <thumb>https://example.com/still.jpg</thumb>
+    <actor><name>Second</name><order>1</order></actor>
+    <actor><name>First</name><role>Lead</role><order>0</order></actor>
+    <director>Some Director</director>
+    <credits>Some Writer</credits>
+</episodedetails>
+<?xml version="1.0" encoding="UTF-8" standalone="yes" ?>
+<episodedetails>
+    <title>Part Two</title>
+    <season>1</season>
+    <episode>2</episode>
+    <thumb>/local/still.jpg</thumb>
+</episodedetails>
+https://www.themoviedb.org/tv/1396"#;
+
+        let parsed = parse_nfo(text).unwrap();
+        assert_eq!(parsed.len(), 2);
+
+        let first = &parsed[0];
+        assert_eq!(first.kind, "episodedetails");
+        assert_eq!(first.title.as_deref(), Some("Pilot"));
+        assert_eq!(first.plot.as_deref(), Some("Short outline"));
+        assert_eq!(first.rating, Some(8.25));
+        assert_eq!(first.tmdb_id, Some(62085));
+        assert_eq!(first.premiered, Some(1_200_787_200));
+        assert_eq!(first.thumbs.len(), 1);
+        assert_eq!(first.actors[0].name, "First");
+        assert_eq!(first.actors[1].name, "Second");
+        assert_eq!(first.directors, ["Some Director"]);
+        assert_eq!(first.writers, ["Some Writer"]);
+
+        assert_eq!(parsed[1].episode, Some(2));
+        assert!(parsed[1].thumbs.is_empty());
+    }
+}
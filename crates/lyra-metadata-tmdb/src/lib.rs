@@ -2,11 +2,13 @@ use anyhow::{Context, Result};
 use async_trait::async_trait;
 use chrono::NaiveDate;
 use lyra_metadata::{
-    CastCredit, ContentRating, EpisodeMetadata, ImageSet, MetadataGenre, MetadataImage,
-    MetadataImageKind, MetadataProvider, MetadataStatus, MovieCandidate, MovieMetadata,
-    MovieRootMatchRequest, PersonMetadata, Recommendation, RecommendedMediaKind, RootMatchHint,
-    Scored, SeasonMetadata, SeriesCandidate, SeriesItem, SeriesItemsRequest, SeriesItemsResult,
-    SeriesMetadata, SeriesRootMatchRequest,
+    CastCredit, ContentRating, EpisodeMetadata, EpisodeOrdering, ImageSet, ItemPosition,
+    LocalizedText, MetadataCompany, MetadataGenre, MetadataImage, MetadataImageKind,
+    MetadataKeyword, MetadataProvider, MetadataStatus, MissingEpisode, MovieCandidate,
+    MovieCollection, MovieMetadata, MovieRootMatchRequest, PersonMetadata, Recommendation,
+    RecommendedMediaKind, ResponseCache, RootMatchHint, Scored, SeasonEpisodeCount, SeasonMetadata,
+    SeriesCandidate, SeriesItem, SeriesItemsRequest, SeriesItemsResult, SeriesMetadata,
+    SeriesRootMatchRequest, score_display, score_normalized,
 };
 use ratelimit::Ratelimiter;
 use reqwest::Client;
@@ -14,38 +16,74 @@ use serde::Deserialize;
 use serde::de::DeserializeOwned;
 use std::{
     collections::{HashMap, HashSet},
-    sync::Arc,
-    time::{Duration, Instant},
+    sync::{Arc, PoisonError, RwLock},
+    time::Duration,
 };
-use tokio::sync::Mutex;
 use tokio::time::sleep;
 
-const TMDB_API_BASE: &str = "https://api.themoviedb.org/3";
-const TMDB_IMAGE_BASE: &str = "https://image.tmdb.org/t/p";
-const TMDB_API_KEY: &str = "f81a38fe9eba82e5dc3695a7406068bd";
 const CACHE_TTL: Duration = Duration::from_hours(24);
+const CANDIDATE_POSTER_SIZE: &str = "w342";
+
+#[derive(Debug, Clone)]
+pub struct TmdbSettings {
+    pub api_key: Option<String>,
+    // v4 read access token, sent as a bearer token and preferred over the api key
+    pub access_token: Option<String>,
+    pub api_base_url: String,
+    pub image_base_url: String,
+    pub language: String,
+    // tried in order for fields left empty in `language`
+    pub fallback_languages: Vec<String>,
+    // iso 639-1 codes in order of preference, defaulting to the metadata languages. textless
+    // images are always accepted.
+    pub image_languages: Vec<String>,
+}
+
+impl TmdbSettings {
+    // the primary language leads so its copy is always stored next to the others. nothing extra
+    // is stored when every viewer reads the primary language anyway.
+    fn localized_languages(&self, metadata_languages: &[String]) -> Vec<String> {
+        let mut languages = vec![self.language.clone()];
+        for language in metadata_languages {
+            if !languages
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(language))
+            {
+                languages.push(language.clone());
+            }
+        }
+        if languages.len() == 1 {
+            return Vec::new();
+        }
+        languages
+    }
+
+    fn image_languages(&self) -> Vec<String> {
+        if !self.image_languages.is_empty() {
+            return self.image_languages.clone();
+        }
+        let mut languages = Vec::new();
+        for language in std::iter::once(&self.language).chain(&self.fallback_languages) {
+            let code = language.split('-').next().unwrap_or_default().to_owned();
+            if !code.is_empty() && !languages.contains(&code) {
+                languages.push(code);
+            }
+        }
+        languages
+    }
+}
 
 #[derive(Clone)]
 pub struct TmdbMetadataProvider {
     client: Client,
     ratelimiter: Arc<Ratelimiter>,
-    cache: Arc<Mutex<HashMap<String, CachedResponse>>>,
-}
-
-#[derive(Clone)]
-struct CachedResponse {
-    value: serde_json::Value,
-    expires_at: Instant,
-}
-
-impl Default for TmdbMetadataProvider {
-    fn default() -> Self {
-        Self::new()
-    }
+    cache: Arc<ResponseCache>,
+    settings: Arc<TmdbSettings>,
+    metadata_languages: Arc<RwLock<Vec<String>>>,
 }
 
 impl TmdbMetadataProvider {
-    pub fn new() -> Self {
+    pub fn new(settings: TmdbSettings, cache: Arc<ResponseCache>) -> Self {
         let ratelimiter = Ratelimiter::builder(1, Duration::from_secs(1))
             .max_tokens(5)
             .initial_available(1)
@@ -55,10 +93,20 @@ impl TmdbMetadataProvider {
         Self {
             client: Client::new(),
             ratelimiter: Arc::new(ratelimiter),
-            cache: Arc::new(Mutex::new(HashMap::new())),
+            cache,
+            settings: Arc::new(settings),
+            metadata_languages: Arc::new(RwLock::new(Vec::new())),
         }
     }
 
+    fn localized_languages(&self) -> Vec<String> {
+        let metadata_languages = self
+            .metadata_languages
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        self.settings.localized_languages(&metadata_languages)
+    }
+
     async fn rate_limit_wait(&self) {
         loop {
             match self.ratelimiter.try_wait() {
@@ -73,50 +121,164 @@ impl TmdbMetadataProvider {
         path: &str,
         query: &[(&str, String)],
     ) -> Result<T> {
-        let cache_key = cache_key(path, query);
-        if let Some(cached_value) = self.get_cached_json(&cache_key).await {
-            return serde_json::from_value(cached_value)
-                .context("failed to decode cached TMDb data");
-        }
-
-        self.rate_limit_wait().await;
         let mut params = query.to_vec();
-        params.push(("api_key", TMDB_API_KEY.to_string()));
+        if !params.iter().any(|(key, _)| *key == "language") {
+            params.push(("language", self.settings.language.clone()));
+        }
+        // credentials stay out of the cache key so rotating them keeps cached responses
+        let key = cache_key(path, &params);
 
-        let url = format!("{TMDB_API_BASE}{path}");
-        let value = self
+        let mut request = self
             .client
-            .get(url)
-            .query(&params)
-            .send()
-            .await
-            .context("failed to send TMDb request")?
-            .error_for_status()
-            .context("TMDb returned an error response")?
-            .json::<serde_json::Value>()
+            .get(format!("{}{path}", self.settings.api_base_url));
+        if let Some(access_token) = self.settings.access_token.as_deref() {
+            request = request.bearer_auth(access_token);
+        } else if let Some(api_key) = self.settings.api_key.as_deref() {
+            params.push(("api_key", api_key.to_owned()));
+        } else {
+            anyhow::bail!("no TMDb api key or access token configured");
+        }
+        let request = request.query(&params);
+
+        let body = self
+            .cache
+            .fetch(&key, CACHE_TTL, request, self.rate_limit_wait())
             .await
-            .context("failed to decode TMDb response body")?;
+            .context("TMDb request failed")?;
+        serde_json::from_slice(&body).context("failed to decode TMDb response into target type")
+    }
 
-        {
-            let mut cache = self.cache.lock().await;
-            cache.insert(
-                cache_key,
-                CachedResponse {
-                    value: value.clone(),
-                    expires_at: Instant::now() + CACHE_TTL,
-                },
-            );
+    // tmdb leaves untranslated text fields empty rather than falling back itself, so anything
+    // still missing is filled from the same path in each fallback language. the refetch drops
+    // `append_to_response` since only the translatable fields are read from it.
+    async fn get_localized_json<T: DeserializeOwned + Localized>(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<T> {
+        let mut value: T = self.get_json(path, query).await?;
+        for language in &self.settings.fallback_languages {
+            if !value.has_gaps() {
+                break;
+            }
+            let fallback: T = self
+                .get_json(path, &[("language", language.clone())])
+                .await?;
+            value.fill_gaps(fallback);
+        }
+        Ok(value)
+    }
+
+    fn include_image_language(&self) -> (&'static str, String) {
+        let mut languages = self.settings.image_languages();
+        languages.push("null".to_owned());
+        ("include_image_language", languages.join(","))
+    }
+
+    fn image_base(&self) -> &str {
+        &self.settings.image_base_url
+    }
+
+    // seasons have no translations endpoint that covers their episodes, so the whole season is
+    // fetched again per language. fallbacks are only fetched while the primary copy has gaps,
+    // and every raw copy in a localized language, primary included, is returned for storing.
+    async fn get_season_translations(
+        &self,
+        path: &str,
+    ) -> Result<(TvSeasonDetails, Vec<(String, TvSeasonDetails)>)> {
+        let mut season: TvSeasonDetails = self.get_json(path, &[]).await?;
+        let localized = self.localized_languages();
+        let mut translated = Vec::new();
+        if let Some(primary) = localized.first() {
+            translated.push((primary.clone(), season.clone()));
+        }
+
+        let mut fallbacks = Vec::new();
+        for language in &self.settings.fallback_languages {
+            if !season.has_gaps() {
+                break;
+            }
+            let fallback: TvSeasonDetails = self
+                .get_json(path, &[("language", language.clone())])
+                .await?;
+            season.fill_gaps(fallback.clone());
+            fallbacks.push((language, fallback));
+        }
+
+        for language in localized.into_iter().skip(1) {
+            let copy = match fallbacks
+                .iter()
+                .find(|(fallback, _)| fallback.eq_ignore_ascii_case(&language))
+            {
+                Some((_, fallback)) => fallback.clone(),
+                None => {
+                    self.get_json(path, &[("language", language.clone())])
+                        .await?
+                }
+            };
+            translated.push((language, copy));
         }
 
-        serde_json::from_value(value).context("failed to decode TMDb response into target type")
+        Ok((season, translated))
     }
 
-    async fn get_cached_json(&self, cache_key: &str) -> Option<serde_json::Value> {
-        let mut cache = self.cache.lock().await;
-        let now = Instant::now();
-        cache.retain(|_, entry| entry.expires_at > now);
-        cache.get(cache_key).map(|entry| entry.value.clone())
+    fn localizations_from_translations(
+        &self,
+        translations: Option<TmdbTranslations>,
+    ) -> Vec<LocalizedText> {
+        let translations = translations
+            .map(|translations| translations.translations)
+            .unwrap_or_default();
+        self.localized_languages()
+            .into_iter()
+            .filter_map(|language| {
+                let translation = translations
+                    .iter()
+                    .find(|translation| translation.matches(&language))?;
+                Some(LocalizedText {
+                    language,
+                    name: empty_to_none(
+                        translation
+                            .data
+                            .name
+                            .clone()
+                            .or_else(|| translation.data.title.clone()),
+                    ),
+                    description: empty_to_none(translation.data.overview.clone()),
+                    tagline: empty_to_none(translation.data.tagline.clone()),
+                })
+            })
+            .collect()
+    }
+}
+
+fn first_localized(
+    localizations: &[LocalizedText],
+    field: impl Fn(&LocalizedText) -> &Option<String>,
+) -> Option<String> {
+    localizations
+        .iter()
+        .find_map(|localized| field(localized).clone())
+}
+
+fn episode_localizations(
+    translated: &[(String, TvSeasonDetails)],
+) -> HashMap<i32, Vec<LocalizedText>> {
+    let mut by_episode = HashMap::<i32, Vec<LocalizedText>>::new();
+    for (language, season) in translated {
+        for episode in &season.episodes {
+            by_episode
+                .entry(episode.episode_number)
+                .or_default()
+                .push(LocalizedText {
+                    language: language.clone(),
+                    name: empty_to_none(episode.name.clone()),
+                    description: empty_to_none(episode.overview.clone()),
+                    tagline: None,
+                });
+        }
     }
+    by_episode
 }
 
 #[async_trait]
@@ -125,18 +287,32 @@ impl MetadataProvider for TmdbMetadataProvider {
         "tmdb"
     }
 
+    fn set_metadata_languages(&self, languages: &[String]) {
+        *self
+            .metadata_languages
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = languages.to_vec();
+    }
+
     async fn match_series_root(
         &self,
         req: SeriesRootMatchRequest,
     ) -> Result<Vec<Scored<SeriesCandidate>>> {
         let mut candidates = Vec::new();
-        if let Some(tmdb_id) = req.hint.tmdb_id {
+        if let Some(tmdb_id) = pinned_or_hinted_tmdb_id(&req.hint)? {
             let details: TvSearchResult = self.get_json(&format!("/tv/{tmdb_id}"), &[]).await?;
             candidates.push(Scored {
                 value: SeriesCandidate {
-                    tmdb_id: details.id,
+                    tmdb_id: Some(details.id),
                     name: details.name,
                     first_air_year: parse_year(details.first_air_date.as_deref()),
+                    poster_url: image_url(
+                        self.image_base(),
+                        details.poster_path.as_deref(),
+                        CANDIDATE_POSTER_SIZE,
+                    ),
+                    source_path: None,
+                    anidb_id: None,
                 },
                 score: 1.0,
             });
@@ -153,9 +329,16 @@ impl MetadataProvider for TmdbMetadataProvider {
             if let Some(first) = found.tv_results.first() {
                 candidates.push(Scored {
                     value: SeriesCandidate {
-                        tmdb_id: first.id,
+                        tmdb_id: Some(first.id),
                         name: first.name.clone(),
                         first_air_year: parse_year(first.first_air_date.as_deref()),
+                        poster_url: image_url(
+                            self.image_base(),
+                            first.poster_path.as_deref(),
+                            CANDIDATE_POSTER_SIZE,
+                        ),
+                        source_path: None,
+                        anidb_id: None,
                     },
                     score: 0.98,
                 });
@@ -169,72 +352,108 @@ impl MetadataProvider for TmdbMetadataProvider {
                 &search_query_params(&req.hint, "first_air_date_year"),
             )
             .await?;
-        Ok(score_series_candidates(&req.hint, search.results))
+        Ok(score_series_candidates(
+            self.image_base(),
+            &req.hint,
+            search.results,
+        ))
     }
 
     async fn lookup_series_metadata(&self, candidate: &SeriesCandidate) -> Result<SeriesMetadata> {
         let details: TvDetails = self
             .get_json(
-                &format!("/tv/{}", candidate.tmdb_id),
-                &[(
-                    "append_to_response",
-                    "external_ids,content_ratings,aggregate_credits,recommendations,images"
-                        .to_string(),
-                )],
+                &format!("/tv/{}", candidate_tmdb_id(candidate.tmdb_id)?),
+                &[
+                    (
+                        "append_to_response",
+                        "external_ids,content_ratings,aggregate_credits,recommendations,images,translations,keywords"
+                            .to_string(),
+                    ),
+                    self.include_image_language(),
+                ],
             )
             .await?;
+        let localizations = self.localizations_from_translations(details.translations);
+        let (cast, crew) = details
+            .aggregate_credits
+            .map(|credits| (map_cast(credits.cast), credits.crew))
+            .unwrap_or_default();
+        let crew = map_tv_crew(details.created_by, crew);
 
         Ok(SeriesMetadata {
             imdb_id: empty_to_none(details.external_ids.and_then(|ids| ids.imdb_id)),
             tmdb_id: Some(details.id),
+            anidb_id: None,
             name: details.name,
-            description: empty_to_none(details.overview),
+            description: empty_to_none(details.overview)
+                .or_else(|| first_localized(&localizations, |text| &text.description)),
             score_display: score_display(details.vote_average),
             score_normalized: score_normalized(details.vote_average),
             first_aired: parse_date(details.first_air_date.as_deref()),
             last_aired: parse_date(details.last_air_date.as_deref()),
             status: map_tv_status(details.status.as_deref()),
-            tagline: empty_to_none(details.tagline),
+            tagline: empty_to_none(details.tagline)
+                .or_else(|| first_localized(&localizations, |text| &text.tagline)),
             next_aired: details
                 .next_episode_to_air
                 .and_then(|episode| parse_date(episode.air_date.as_deref())),
+            original_title: empty_to_none(details.original_name),
+            original_language: empty_to_none(details.original_language),
+            // tmdb lists every runtime the show has used, the first is the usual one
+            runtime_minutes: details
+                .episode_run_time
+                .first()
+                .copied()
+                .filter(|minutes| *minutes > 0),
             genres: map_genres(self.id(), details.genres),
             content_ratings: details
                 .content_ratings
                 .map(|ratings| map_tv_content_ratings(ratings.results))
                 .unwrap_or_default(),
-            cast: details
-                .aggregate_credits
-                .map(|credits| map_cast(credits.cast))
+            studios: map_companies(self.id(), details.production_companies),
+            networks: map_companies(self.id(), details.networks),
+            keywords: details
+                .keywords
+                .map(|keywords| map_keywords(self.id(), keywords.results))
                 .unwrap_or_default(),
+            cast,
+            crew,
             recommendations: details
                 .recommendations
                 .map(|rows| map_tv_recommendations(rows.results))
                 .unwrap_or_default(),
             images: images_from_details(
+                self.image_base(),
+                &self.settings.image_languages(),
                 details.poster_path.as_deref(),
                 details.backdrop_path.as_deref(),
                 details.images,
             ),
+            localizations,
         })
     }
 
     async fn lookup_series_items(&self, req: SeriesItemsRequest) -> Result<SeriesItemsResult> {
+        if let Some(ordering_id) = req.ordering_id.as_deref() {
+            return self.lookup_series_items_in_group(&req, ordering_id).await;
+        }
+
+        let series_tmdb_id = candidate_tmdb_id(req.candidate.tmdb_id)?;
         let season_numbers = req
             .items
             .iter()
             .filter_map(|item| item.season_number)
             .collect::<HashSet<_>>();
+        let local_episodes = local_episode_numbers(&req.items);
 
         let mut season_rows = Vec::new();
         let mut episode_rows = Vec::new();
-        for season_number in season_numbers {
-            let season_details: TvSeasonDetails = self
-                .get_json(
-                    &format!("/tv/{}/season/{season_number}", req.candidate.tmdb_id),
-                    &[],
-                )
+        let mut missing = Vec::new();
+        for &season_number in &season_numbers {
+            let (season_details, translated) = self
+                .get_season_translations(&format!("/tv/{series_tmdb_id}/season/{season_number}"))
                 .await?;
+            let episode_localizations = episode_localizations(&translated);
 
             season_rows.push(SeasonMetadata {
                 root_id: req.root_id.clone(),
@@ -253,11 +472,13 @@ impl MetadataProvider for TmdbMetadataProvider {
                 recommendations: Vec::new(),
                 images: ImageSet {
                     posters: collect_single_image(
+                        self.image_base(),
                         MetadataImageKind::Poster,
                         season_details.poster_path.as_deref(),
                         "w780",
                     ),
                     thumbnails: collect_single_image(
+                        self.image_base(),
                         MetadataImageKind::Thumbnail,
                         season_details.poster_path.as_deref(),
                         "w342",
@@ -265,8 +486,22 @@ impl MetadataProvider for TmdbMetadataProvider {
                     backdrops: Vec::new(),
                     logos: Vec::new(),
                 },
+                localizations: translated
+                    .iter()
+                    .map(|(language, season)| LocalizedText {
+                        language: language.clone(),
+                        name: empty_to_none(Some(season.name.clone())),
+                        description: empty_to_none(season.overview.clone()),
+                        tagline: None,
+                    })
+                    .collect(),
             });
 
+            missing.extend(missing_episodes(
+                season_number,
+                &season_details.episodes,
+                &local_episodes,
+            ));
             let episodes_by_number = season_details
                 .episodes
                 .into_iter()
@@ -284,13 +519,64 @@ impl MetadataProvider for TmdbMetadataProvider {
                 let Some(tmdb_episode) = episodes_by_number.get(&episode_number) else {
                     continue;
                 };
-                episode_rows.push(episode_metadata_from_item(item, tmdb_episode));
+                let mut episode = episode_metadata_from_item(self.image_base(), item, tmdb_episode);
+                episode.localizations = episode_localizations
+                    .get(&episode_number)
+                    .cloned()
+                    .unwrap_or_default();
+                episode_rows.push(episode);
+            }
+        }
+
+        // seasons with nothing on disk are only fetched for their episode list. that list is a
+        // nice to have, so a failure here shouldn't cost the episodes we do have their metadata
+        let other_seasons = match self
+            .get_json::<TvDetails>(&format!("/tv/{series_tmdb_id}"), &[])
+            .await
+        {
+            Ok(details) => details.seasons,
+            Err(error) => {
+                tracing::warn!(
+                    series_tmdb_id,
+                    "failed to list series seasons for missing episodes: {error:#}"
+                );
+                Vec::new()
             }
+        };
+        for season in other_seasons
+            .into_iter()
+            .filter(|season| !season_numbers.contains(&season.season_number))
+        {
+            let season_details = match self
+                .get_json::<TvSeasonDetails>(
+                    &format!("/tv/{series_tmdb_id}/season/{}", season.season_number),
+                    &[],
+                )
+                .await
+            {
+                Ok(season_details) => season_details,
+                Err(error) => {
+                    tracing::warn!(
+                        series_tmdb_id,
+                        season_number = season.season_number,
+                        "failed to look up missing season episodes: {error:#}"
+                    );
+                    continue;
+                }
+            };
+            missing.extend(missing_episodes(
+                season.season_number,
+                &season_details.episodes,
+                &local_episodes,
+            ));
         }
+        missing.sort_by_key(|episode| (episode.season_number, episode.episode_number));
 
         Ok(SeriesItemsResult {
             seasons: season_rows,
             episodes: episode_rows,
+            positions: Vec::new(),
+            missing,
         })
     }
 
@@ -299,14 +585,20 @@ impl MetadataProvider for TmdbMetadataProvider {
         req: MovieRootMatchRequest,
     ) -> Result<Vec<Scored<MovieCandidate>>> {
         let mut candidates = Vec::new();
-        if let Some(tmdb_id) = req.hint.tmdb_id {
+        if let Some(tmdb_id) = pinned_or_hinted_tmdb_id(&req.hint)? {
             let details: MovieSearchResult =
                 self.get_json(&format!("/movie/{tmdb_id}"), &[]).await?;
             candidates.push(Scored {
                 value: MovieCandidate {
-                    tmdb_id: details.id,
+                    tmdb_id: Some(details.id),
                     name: details.title,
                     release_year: parse_year(details.release_date.as_deref()),
+                    poster_url: image_url(
+                        self.image_base(),
+                        details.poster_path.as_deref(),
+                        CANDIDATE_POSTER_SIZE,
+                    ),
+                    source_path: None,
                 },
                 score: 1.0,
             });
@@ -323,9 +615,15 @@ impl MetadataProvider for TmdbMetadataProvider {
             if let Some(first) = found.movie_results.first() {
                 candidates.push(Scored {
                     value: MovieCandidate {
-                        tmdb_id: first.id,
+                        tmdb_id: Some(first.id),
                         name: first.title.clone(),
                         release_year: parse_year(first.release_date.as_deref()),
+                        poster_url: image_url(
+                            self.image_base(),
+                            first.poster_path.as_deref(),
+                            CANDIDATE_POSTER_SIZE,
+                        ),
+                        source_path: None,
                     },
                     score: 0.98,
                 });
@@ -336,52 +634,147 @@ impl MetadataProvider for TmdbMetadataProvider {
         let search: SearchResponse<MovieSearchResult> = self
             .get_json("/search/movie", &search_query_params(&req.hint, "year"))
             .await?;
-        Ok(score_movie_candidates(&req.hint, search.results))
+        Ok(score_movie_candidates(
+            self.image_base(),
+            &req.hint,
+            search.results,
+        ))
     }
 
     async fn lookup_movie_metadata(&self, candidate: &MovieCandidate) -> Result<MovieMetadata> {
         let details: MovieDetails = self
             .get_json(
-                &format!("/movie/{}", candidate.tmdb_id),
-                &[(
-                    "append_to_response",
-                    "external_ids,release_dates,credits,recommendations,images".to_string(),
-                )],
+                &format!("/movie/{}", candidate_tmdb_id(candidate.tmdb_id)?),
+                &[
+                    (
+                        "append_to_response",
+                        "external_ids,release_dates,credits,recommendations,images,translations,keywords"
+                            .to_string(),
+                    ),
+                    self.include_image_language(),
+                ],
             )
             .await?;
+        let localizations = self.localizations_from_translations(details.translations);
+        let (cast, crew) = details
+            .credits
+            .map(|credits| (map_cast(credits.cast), map_movie_crew(credits.crew)))
+            .unwrap_or_default();
+        // the collection is a nice to have, it shouldn't cost the movie its metadata
+        let collection = match details.belongs_to_collection {
+            Some(summary) => {
+                let collection_id = summary.id;
+                match self.lookup_movie_collection(summary).await {
+                    Ok(collection) => Some(collection),
+                    Err(error) => {
+                        tracing::warn!(
+                            collection_id,
+                            "failed to look up movie collection: {error:#}"
+                        );
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
 
         Ok(MovieMetadata {
             imdb_id: empty_to_none(details.external_ids.and_then(|ids| ids.imdb_id)),
             tmdb_id: Some(details.id),
             name: details.title,
-            description: empty_to_none(details.overview),
+            description: empty_to_none(details.overview)
+                .or_else(|| first_localized(&localizations, |text| &text.description)),
             score_display: score_display(details.vote_average),
             score_normalized: score_normalized(details.vote_average),
             first_aired: parse_date(details.release_date.as_deref()),
             last_aired: parse_date(details.release_date.as_deref()),
             status: map_movie_status(details.status.as_deref()),
-            tagline: empty_to_none(details.tagline),
+            tagline: empty_to_none(details.tagline)
+                .or_else(|| first_localized(&localizations, |text| &text.tagline)),
+            original_title: empty_to_none(details.original_title),
+            original_language: empty_to_none(details.original_language),
+            // tmdb reports unknown runtimes and budgets as 0
+            runtime_minutes: details.runtime.filter(|minutes| *minutes > 0),
+            budget: details.budget.filter(|budget| *budget > 0),
             genres: map_genres(self.id(), details.genres),
             content_ratings: details
                 .release_dates
                 .map(|dates| map_movie_content_ratings(dates.results))
                 .unwrap_or_default(),
-            cast: details
-                .credits
-                .map(|credits| map_cast(credits.cast))
+            studios: map_companies(self.id(), details.production_companies),
+            keywords: details
+                .keywords
+                .map(|keywords| map_keywords(self.id(), keywords.keywords))
                 .unwrap_or_default(),
+            cast,
+            crew,
             recommendations: details
                 .recommendations
                 .map(|rows| map_movie_recommendations(rows.results))
                 .unwrap_or_default(),
             images: images_from_details(
+                self.image_base(),
+                &self.settings.image_languages(),
                 details.poster_path.as_deref(),
                 details.backdrop_path.as_deref(),
                 details.images,
             ),
+            localizations,
+            collection,
         })
     }
 
+    async fn lookup_season_episode_counts(
+        &self,
+        candidate: &SeriesCandidate,
+    ) -> Result<Vec<SeasonEpisodeCount>> {
+        let details: TvDetails = self
+            .get_json(
+                &format!("/tv/{}", candidate_tmdb_id(candidate.tmdb_id)?),
+                &[],
+            )
+            .await?;
+
+        let mut counts = details
+            .seasons
+            .into_iter()
+            .map(|season| SeasonEpisodeCount {
+                season_number: season.season_number,
+                episode_count: season.episode_count,
+            })
+            .collect::<Vec<_>>();
+        counts.sort_by_key(|count| count.season_number);
+        Ok(counts)
+    }
+
+    async fn lookup_episode_orderings(
+        &self,
+        candidate: &SeriesCandidate,
+    ) -> Result<Vec<EpisodeOrdering>> {
+        let response: SearchResponse<TvEpisodeGroupSummary> = self
+            .get_json(
+                &format!(
+                    "/tv/{}/episode_groups",
+                    candidate_tmdb_id(candidate.tmdb_id)?
+                ),
+                &[],
+            )
+            .await?;
+
+        Ok(response
+            .results
+            .into_iter()
+            .map(|group| EpisodeOrdering {
+                id: group.id,
+                name: group.name,
+                kind: episode_group_kind(group.kind).to_owned(),
+                description: empty_to_none(group.description),
+                group_count: group.group_count,
+                episode_count: group.episode_count,
+            })
+            .collect())
+    }
+
     async fn lookup_people_metadata(
         &self,
         provider_person_ids: &[String],
@@ -393,16 +786,223 @@ impl MetadataProvider for TmdbMetadataProvider {
                 continue;
             };
             let details: TmdbPersonDetails = self
-                .get_json(&format!("/person/{tmdb_person_id}"), &[])
+                .get_localized_json(&format!("/person/{tmdb_person_id}"), &[])
                 .await?;
-            people.push(map_person_metadata(details));
+            people.push(map_person_metadata(self.image_base(), details));
         }
 
         Ok(people)
     }
 }
 
-fn episode_metadata_from_item(item: &SeriesItem, episode: &TvEpisodeDetails) -> EpisodeMetadata {
+impl TmdbMetadataProvider {
+    // movie details only carry the collection's name and artwork, the overview needs its own
+    // request
+    async fn lookup_movie_collection(
+        &self,
+        summary: TmdbCollectionSummary,
+    ) -> Result<MovieCollection> {
+        let details: TmdbCollectionDetails = self
+            .get_localized_json(&format!("/collection/{}", summary.id), &[])
+            .await?;
+        Ok(MovieCollection {
+            provider_id: self.id().to_owned(),
+            external_id: summary.id.to_string(),
+            name: empty_to_none(details.name)
+                .or_else(|| empty_to_none(Some(summary.name)))
+                .unwrap_or_default(),
+            description: empty_to_none(details.overview),
+            poster_url: image_url(self.image_base(), summary.poster_path.as_deref(), "w780"),
+            backdrop_url: image_url(self.image_base(), summary.backdrop_path.as_deref(), "w1280"),
+        })
+    }
+
+    // episode groups list every episode with its air order details already attached, so items
+    // are matched against the group layout directly instead of fetching seasons.
+    async fn lookup_series_items_in_group(
+        &self,
+        req: &SeriesItemsRequest,
+        group_id: &str,
+    ) -> Result<SeriesItemsResult> {
+        let details: TvEpisodeGroupDetails = self
+            .get_json(&format!("/tv/episode_group/{group_id}"), &[])
+            .await?;
+        let groups = number_episode_groups(details.groups);
+
+        let local_seasons = req
+            .items
+            .iter()
+            .filter_map(|item| item.season_number)
+            .collect::<HashSet<_>>();
+        let seasons = groups
+            .iter()
+            .filter(|(season_number, _)| local_seasons.contains(season_number))
+            .map(|(season_number, group)| SeasonMetadata {
+                root_id: req.root_id.clone(),
+                season_number: *season_number,
+                name: group.name.clone(),
+                description: None,
+                score_display: None,
+                score_normalized: None,
+                first_aired: None,
+                last_aired: None,
+                status: None,
+                tagline: None,
+                next_aired: None,
+                genres: Vec::new(),
+                content_ratings: Vec::new(),
+                recommendations: Vec::new(),
+                images: ImageSet::default(),
+                localizations: Vec::new(),
+            })
+            .collect();
+
+        // items without a season count through every regular group in order
+        let absolute = groups
+            .iter()
+            .filter(|(season_number, _)| *season_number > 0)
+            .flat_map(|(season_number, group)| {
+                group
+                    .episodes
+                    .iter()
+                    .enumerate()
+                    .map(move |(index, episode)| (*season_number, index as i32 + 1, episode))
+            })
+            .collect::<Vec<_>>();
+
+        let mut episodes = Vec::new();
+        let mut positions = Vec::new();
+        for item in &req.items {
+            let Some(episode_number) = item.episode_number.filter(|number| *number > 0) else {
+                continue;
+            };
+            let found = match item.season_number {
+                Some(season_number) => groups
+                    .iter()
+                    .find(|(number, _)| *number == season_number)
+                    .and_then(|(_, group)| group.episodes.get(episode_number as usize - 1))
+                    .map(|episode| (season_number, episode_number, episode)),
+                None => absolute.get(episode_number as usize - 1).copied(),
+            };
+            let Some((season_number, position, episode)) = found else {
+                continue;
+            };
+
+            episodes.push(episode_metadata_from_item(self.image_base(), item, episode));
+            positions.push(ItemPosition {
+                item_id: item.item_id.clone(),
+                season_number,
+                episode_number: position,
+            });
+        }
+
+        let matched = positions
+            .iter()
+            .map(|position| (position.season_number, position.episode_number))
+            .chain(local_episode_numbers(&req.items))
+            .collect::<HashSet<_>>();
+        let missing = groups
+            .iter()
+            .flat_map(|(season_number, group)| {
+                group
+                    .episodes
+                    .iter()
+                    .enumerate()
+                    .map(move |(index, episode)| (*season_number, index as i32 + 1, episode))
+            })
+            .filter(|(season_number, position, _)| !matched.contains(&(*season_number, *position)))
+            .map(|(season_number, position, episode)| {
+                missing_episode(season_number, position, episode)
+            })
+            .collect();
+
+        Ok(SeriesItemsResult {
+            seasons,
+            episodes,
+            positions,
+            missing,
+        })
+    }
+}
+
+// groups are numbered like seasons in their listed order. a group of specials becomes season 0
+// so it lines up with how specials are usually laid out on disk. group names are free text in
+// any language, so a group made of season 0 episodes counts as specials too.
+fn number_episode_groups(mut groups: Vec<TvEpisodeGroup>) -> Vec<(i32, TvEpisodeGroup)> {
+    groups.sort_by_key(|group| group.order);
+    let mut next_season = 1;
+    groups
+        .into_iter()
+        .map(|mut group| {
+            group.episodes.sort_by_key(|episode| episode.order);
+            let specials = !group.episodes.is_empty()
+                && group
+                    .episodes
+                    .iter()
+                    .all(|episode| episode.season_number == Some(0));
+            let season_number = if specials || group.name.to_lowercase().contains("special") {
+                0
+            } else {
+                let season_number = next_season;
+                next_season += 1;
+                season_number
+            };
+            (season_number, group)
+        })
+        .collect()
+}
+
+fn local_episode_numbers(items: &[SeriesItem]) -> HashSet<(i32, i32)> {
+    items
+        .iter()
+        .filter_map(|item| Some((item.season_number?, item.episode_number?)))
+        .collect()
+}
+
+fn missing_episodes(
+    season_number: i32,
+    episodes: &[TvEpisodeDetails],
+    local_episodes: &HashSet<(i32, i32)>,
+) -> Vec<MissingEpisode> {
+    episodes
+        .iter()
+        .filter(|episode| !local_episodes.contains(&(season_number, episode.episode_number)))
+        .map(|episode| missing_episode(season_number, episode.episode_number, episode))
+        .collect()
+}
+
+fn missing_episode(
+    season_number: i32,
+    episode_number: i32,
+    episode: &TvEpisodeDetails,
+) -> MissingEpisode {
+    MissingEpisode {
+        season_number,
+        episode_number,
+        name: empty_to_none(episode.name.clone()),
+        description: empty_to_none(episode.overview.clone()),
+        first_aired: parse_date(episode.air_date.as_deref()),
+    }
+}
+
+fn episode_group_kind(kind: i32) -> &'static str {
+    match kind {
+        1 => "original_air_date",
+        2 => "absolute",
+        3 => "dvd",
+        4 => "digital",
+        5 => "story_arc",
+        6 => "production",
+        7 => "tv",
+        _ => "unknown",
+    }
+}
+
+fn episode_metadata_from_item(
+    image_base: &str,
+    item: &SeriesItem,
+    episode: &TvEpisodeDetails,
+) -> EpisodeMetadata {
     EpisodeMetadata {
         item_id: item.item_id.clone(),
         name: empty_to_none(episode.name.clone()).unwrap_or_else(|| item.name.clone()),
@@ -420,6 +1020,7 @@ fn episode_metadata_from_item(item: &SeriesItem, episode: &TvEpisodeDetails) ->
         images: ImageSet {
             posters: Vec::new(),
             thumbnails: collect_single_image(
+                image_base,
                 MetadataImageKind::Thumbnail,
                 episode.still_path.as_deref(),
                 "w300",
@@ -427,6 +1028,22 @@ fn episode_metadata_from_item(item: &SeriesItem, episode: &TvEpisodeDetails) ->
             backdrops: Vec::new(),
             logos: Vec::new(),
         },
+        localizations: Vec::new(),
+    }
+}
+
+fn candidate_tmdb_id(tmdb_id: Option<u64>) -> Result<u64> {
+    tmdb_id.context("candidate has no tmdb id")
+}
+
+// a pinned match always wins over ids parsed from the file name
+fn pinned_or_hinted_tmdb_id(hint: &RootMatchHint) -> Result<Option<u64>> {
+    match hint.pinned_id.as_deref() {
+        Some(pinned_id) => pinned_id
+            .parse()
+            .map(Some)
+            .with_context(|| format!("invalid pinned tmdb id '{pinned_id}'")),
+        None => Ok(hint.tmdb_id),
     }
 }
 
@@ -445,6 +1062,7 @@ fn search_query_params(
 }
 
 fn score_series_candidates(
+    image_base: &str,
     hint: &RootMatchHint,
     rows: Vec<TvSearchResult>,
 ) -> Vec<Scored<SeriesCandidate>> {
@@ -455,18 +1073,26 @@ fn score_series_candidates(
                 row.id,
                 row.name,
                 parse_year(row.first_air_date.as_deref()),
-                row.first_air_date,
+                image_url(
+                    image_base,
+                    row.poster_path.as_deref(),
+                    CANDIDATE_POSTER_SIZE,
+                ),
             )
         }),
-        |tmdb_id, name, year| SeriesCandidate {
-            tmdb_id,
+        |tmdb_id, name, year, poster_url| SeriesCandidate {
+            tmdb_id: Some(tmdb_id),
             name,
             first_air_year: year,
+            poster_url,
+            source_path: None,
+            anidb_id: None,
         },
     )
 }
 
 fn score_movie_candidates(
+    image_base: &str,
     hint: &RootMatchHint,
     rows: Vec<MovieSearchResult>,
 ) -> Vec<Scored<MovieCandidate>> {
@@ -477,13 +1103,19 @@ fn score_movie_candidates(
                 row.id,
                 row.title,
                 parse_year(row.release_date.as_deref()),
-                row.release_date,
+                image_url(
+                    image_base,
+                    row.poster_path.as_deref(),
+                    CANDIDATE_POSTER_SIZE,
+                ),
             )
         }),
-        |tmdb_id, name, year| MovieCandidate {
-            tmdb_id,
+        |tmdb_id, name, year, poster_url| MovieCandidate {
+            tmdb_id: Some(tmdb_id),
             name,
             release_year: year,
+            poster_url,
+            source_path: None,
         },
     )
 }
@@ -491,12 +1123,12 @@ fn score_movie_candidates(
 fn score_candidates<T, I, F>(hint: &RootMatchHint, rows: I, map_fn: F) -> Vec<Scored<T>>
 where
     I: IntoIterator<Item = (u64, String, Option<i32>, Option<String>)>,
-    F: Fn(u64, String, Option<i32>) -> T,
+    F: Fn(u64, String, Option<i32>, Option<String>) -> T,
 {
     let expected = normalize_title(&hint.title);
     let mut scored = rows
         .into_iter()
-        .map(|(tmdb_id, name, year, _date)| {
+        .map(|(tmdb_id, name, year, poster_url)| {
             let actual = normalize_title(&name);
             let mut score = if expected == actual {
                 0.92
@@ -509,7 +1141,7 @@ where
                 score += 0.08;
             }
             Scored {
-                value: map_fn(tmdb_id, name, year),
+                value: map_fn(tmdb_id, name, year, poster_url),
                 score,
             }
         })
@@ -553,6 +1185,30 @@ fn map_genres(provider_id: &str, genres: Vec<TmdbGenre>) -> Vec<MetadataGenre> {
         .collect()
 }
 
+fn map_companies(provider_id: &str, companies: Vec<TmdbCompany>) -> Vec<MetadataCompany> {
+    companies
+        .into_iter()
+        .filter(|company| !company.name.trim().is_empty())
+        .map(|company| MetadataCompany {
+            provider_id: provider_id.to_string(),
+            external_id: Some(company.id.to_string()),
+            name: company.name,
+            origin_country: empty_to_none(company.origin_country),
+        })
+        .collect()
+}
+
+fn map_keywords(provider_id: &str, keywords: Vec<TmdbKeyword>) -> Vec<MetadataKeyword> {
+    keywords
+        .into_iter()
+        .map(|keyword| MetadataKeyword {
+            provider_id: provider_id.to_string(),
+            external_id: Some(keyword.id.to_string()),
+            name: keyword.name,
+        })
+        .collect()
+}
+
 fn map_tv_content_ratings(rows: Vec<TvContentRating>) -> Vec<ContentRating> {
     rows.into_iter()
         .filter_map(|row| {
@@ -562,6 +1218,7 @@ fn map_tv_content_ratings(rows: Vec<TvContentRating>) -> Vec<ContentRating> {
                 rating,
                 release_date: None,
                 release_type: None,
+                descriptors: row.descriptors,
             })
         })
         .collect()
@@ -579,6 +1236,7 @@ fn map_movie_content_ratings(rows: Vec<MovieReleaseDatesCountry>) -> Vec<Content
                 rating,
                 release_date: parse_date(release.release_date.as_deref()),
                 release_type: release.release_type,
+                descriptors: release.descriptors,
             });
         }
     }
@@ -593,17 +1251,110 @@ fn map_cast<T: CreditLike>(rows: Vec<T>) -> Vec<CastCredit> {
             name: row.name().to_string(),
             character_name: empty_to_none(Some(row.character().to_string())),
             department: None,
+            job: None,
         })
         .collect()
 }
 
-fn map_person_metadata(person: TmdbPersonDetails) -> PersonMetadata {
+// tmdb lists everyone down to the catering crew, only the jobs people browse by are kept. the
+// order here is the order they're listed in.
+const CREW_JOBS: &[&str] = &[
+    "Creator",
+    "Director",
+    "Screenplay",
+    "Writer",
+    "Story",
+    "Original Music Composer",
+];
+// long running shows credit a different director for almost every episode
+const MAX_CREW_PER_JOB: usize = 5;
+
+fn map_movie_crew(rows: Vec<TmdbCrew>) -> Vec<CastCredit> {
+    sort_crew(
+        rows.into_iter()
+            .filter_map(|row| Some(crew_credit(row.id, row.name, row.department, row.job?, 0))),
+    )
+}
+
+fn map_tv_crew(created_by: Vec<TmdbCreator>, rows: Vec<TmdbAggregateCrew>) -> Vec<CastCredit> {
+    let creators = created_by.into_iter().map(|creator| {
+        crew_credit(
+            creator.id,
+            creator.name,
+            None,
+            "Creator".to_owned(),
+            i64::MAX,
+        )
+    });
+    let crew = rows.into_iter().flat_map(|row| {
+        row.jobs.into_iter().filter_map(move |job| {
+            Some(crew_credit(
+                row.id,
+                row.name.clone(),
+                row.department.clone(),
+                job.job?,
+                job.episode_count,
+            ))
+        })
+    });
+    sort_crew(creators.chain(crew))
+}
+
+fn crew_credit(
+    id: u64,
+    name: String,
+    department: Option<String>,
+    job: String,
+    weight: i64,
+) -> (i64, CastCredit) {
+    (
+        weight,
+        CastCredit {
+            provider_person_id: id.to_string(),
+            name,
+            character_name: None,
+            department,
+            job: Some(job),
+        },
+    )
+}
+
+// keeps wanted jobs in CREW_JOBS order, heaviest first (episode count for shows, tmdb's order for
+// movies), and drops people listed twice for the same job
+fn sort_crew(rows: impl Iterator<Item = (i64, CastCredit)>) -> Vec<CastCredit> {
+    let mut rows = rows
+        .filter_map(|(weight, credit)| {
+            let rank = CREW_JOBS
+                .iter()
+                .position(|job| credit.job.as_deref() == Some(*job))?;
+            Some((rank, weight, credit))
+        })
+        .collect::<Vec<_>>();
+    rows.sort_by_key(|(rank, weight, _)| (*rank, std::cmp::Reverse(*weight)));
+
+    let mut seen = HashSet::new();
+    let mut per_job = HashMap::<usize, usize>::new();
+    let mut crew = Vec::new();
+    for (rank, _, credit) in rows {
+        if !seen.insert((rank, credit.provider_person_id.clone())) {
+            continue;
+        }
+        let count = per_job.entry(rank).or_default();
+        if *count < MAX_CREW_PER_JOB {
+            *count += 1;
+            crew.push(credit);
+        }
+    }
+    crew
+}
+
+fn map_person_metadata(image_base: &str, person: TmdbPersonDetails) -> PersonMetadata {
     PersonMetadata {
         provider_person_id: person.id.to_string(),
         name: person.name,
         birthday: parse_date_str(person.birthday.as_deref()),
         description: empty_to_none(person.biography),
-        profile_image_url: image_url(person.profile_path.as_deref(), "w342"),
+        profile_image_url: image_url(image_base, person.profile_path.as_deref(), "w342"),
     }
 }
 
@@ -632,31 +1383,52 @@ fn map_movie_recommendations(rows: Vec<MovieSearchResult>) -> Vec<Recommendation
 }
 
 fn images_from_details(
+    image_base: &str,
+    image_languages: &[String],
     primary_poster_path: Option<&str>,
     primary_backdrop_path: Option<&str>,
     images: Option<TmdbImages>,
 ) -> ImageSet {
-    let mut posters = collect_single_image(MetadataImageKind::Poster, primary_poster_path, "w780");
-    let mut thumbnails =
-        collect_single_image(MetadataImageKind::Thumbnail, primary_poster_path, "w342");
-    let mut backdrops =
-        collect_single_image(MetadataImageKind::Backdrop, primary_backdrop_path, "w1280");
+    let mut posters = collect_single_image(
+        image_base,
+        MetadataImageKind::Poster,
+        primary_poster_path,
+        "w780",
+    );
+    let mut thumbnails = collect_single_image(
+        image_base,
+        MetadataImageKind::Thumbnail,
+        primary_poster_path,
+        "w342",
+    );
+    let mut backdrops = collect_single_image(
+        image_base,
+        MetadataImageKind::Backdrop,
+        primary_backdrop_path,
+        "w1280",
+    );
     let mut logos = Vec::new();
 
     if let Some(images) = images {
+        // the sort is stable, so tmdb's vote ordering survives inside each language
+        let mut poster_rows = images.posters;
+        poster_rows.sort_by_key(|row| image_language_rank(image_languages, row));
         extend_unique_images(
+            image_base,
             &mut posters,
-            images.posters.clone(),
+            poster_rows.clone(),
             MetadataImageKind::Poster,
             "w780",
         );
         extend_unique_images(
+            image_base,
             &mut thumbnails,
-            images.posters,
+            poster_rows,
             MetadataImageKind::Thumbnail,
             "w342",
         );
         extend_unique_images(
+            image_base,
             &mut backdrops,
             images.backdrops,
             MetadataImageKind::Backdrop,
@@ -664,8 +1436,14 @@ fn images_from_details(
         );
 
         let mut logo_rows = images.logos;
-        logo_rows.sort_by_key(|row| logo_sort_key(row));
-        extend_unique_images(&mut logos, logo_rows, MetadataImageKind::Logo, "original");
+        logo_rows.sort_by_key(|row| logo_sort_key(image_languages, row));
+        extend_unique_images(
+            image_base,
+            &mut logos,
+            logo_rows,
+            MetadataImageKind::Logo,
+            "original",
+        );
     }
 
     ImageSet {
@@ -676,11 +1454,25 @@ fn images_from_details(
     }
 }
 
-fn logo_sort_key(row: &TmdbImage) -> (u8, i64, i64, u8) {
+// preferred languages in order, then textless images, then anything else
+fn image_language_rank(image_languages: &[String], row: &TmdbImage) -> usize {
+    match row.iso_639_1.as_deref() {
+        Some(language) => image_languages
+            .iter()
+            .position(|preferred| preferred == language)
+            .unwrap_or(image_languages.len() + 1),
+        None => image_languages.len(),
+    }
+}
+
+// logos are text by nature, so one in any language beats a textless one
+fn logo_sort_key(image_languages: &[String], row: &TmdbImage) -> (usize, i64, i64, u8) {
     let language_rank = match row.iso_639_1.as_deref() {
-        Some("en") => 0,
-        Some(_) => 1,
-        None => 2,
+        Some(language) => image_languages
+            .iter()
+            .position(|preferred| preferred == language)
+            .unwrap_or(image_languages.len()),
+        None => image_languages.len() + 1,
     };
     let vote_average_rank = -(row.vote_average * 1000.0).round() as i64;
     let vote_count_rank = -row.vote_count;
@@ -694,11 +1486,12 @@ fn logo_sort_key(row: &TmdbImage) -> (u8, i64, i64, u8) {
 }
 
 fn collect_single_image(
+    image_base: &str,
     kind: MetadataImageKind,
     path: Option<&str>,
     size: &str,
 ) -> Vec<MetadataImage> {
-    image_url(path, size)
+    image_url(image_base, path, size)
         .map(|url| {
             vec![MetadataImage {
                 kind,
@@ -715,6 +1508,7 @@ fn collect_single_image(
 }
 
 fn extend_unique_images(
+    image_base: &str,
     target: &mut Vec<MetadataImage>,
     rows: Vec<TmdbImage>,
     kind: MetadataImageKind,
@@ -725,7 +1519,7 @@ fn extend_unique_images(
         .map(|image| image.url.clone())
         .collect::<HashSet<_>>();
     for row in rows {
-        let Some(url) = image_url(row.file_path.as_deref(), size) else {
+        let Some(url) = image_url(image_base, row.file_path.as_deref(), size) else {
             continue;
         };
         if !seen.insert(url.clone()) {
@@ -783,17 +1577,9 @@ fn parse_year(value: Option<&str>) -> Option<i32> {
     value.and_then(|raw| raw.split('-').next())?.parse().ok()
 }
 
-fn image_url(path: Option<&str>, size: &str) -> Option<String> {
+fn image_url(image_base: &str, path: Option<&str>, size: &str) -> Option<String> {
     let path = path?;
-    Some(format!("{TMDB_IMAGE_BASE}/{size}{path}"))
-}
-
-fn score_display(vote: Option<f64>) -> Option<String> {
-    vote.map(|score| format!("{score:.1}/10"))
-}
-
-fn score_normalized(vote: Option<f64>) -> Option<i64> {
-    vote.map(|score| (score * 10.0).round() as i64)
+    Some(format!("{image_base}/{size}{path}"))
 }
 
 fn empty_to_none(value: Option<String>) -> Option<String> {
@@ -803,6 +1589,74 @@ fn empty_to_none(value: Option<String>) -> Option<String> {
     })
 }
 
+trait Localized {
+    fn has_gaps(&self) -> bool;
+    fn fill_gaps(&mut self, fallback: Self);
+}
+
+fn is_blank(value: &Option<String>) -> bool {
+    value.as_deref().is_none_or(|value| value.trim().is_empty())
+}
+
+fn fill_blank(target: &mut Option<String>, fallback: Option<String>) {
+    if is_blank(target) && !is_blank(&fallback) {
+        *target = fallback;
+    }
+}
+
+impl Localized for TvSeasonDetails {
+    // episodes that haven't aired have no text in any language yet, counting them would fetch
+    // every fallback language for every airing season
+    fn has_gaps(&self) -> bool {
+        let now = chrono::Utc::now().timestamp();
+        is_blank(&self.overview)
+            || self
+                .episodes
+                .iter()
+                .filter(|episode| {
+                    parse_date(episode.air_date.as_deref()).is_some_and(|aired| aired <= now)
+                })
+                .any(|episode| is_blank(&episode.name) || is_blank(&episode.overview))
+    }
+
+    fn fill_gaps(&mut self, fallback: Self) {
+        fill_blank(&mut self.overview, fallback.overview);
+        let mut fallback_episodes = fallback
+            .episodes
+            .into_iter()
+            .map(|episode| (episode.episode_number, episode))
+            .collect::<HashMap<_, _>>();
+        for episode in &mut self.episodes {
+            let Some(fallback) = fallback_episodes.remove(&episode.episode_number) else {
+                continue;
+            };
+            fill_blank(&mut episode.name, fallback.name);
+            fill_blank(&mut episode.overview, fallback.overview);
+        }
+    }
+}
+
+impl Localized for TmdbCollectionDetails {
+    fn has_gaps(&self) -> bool {
+        is_blank(&self.name) || is_blank(&self.overview)
+    }
+
+    fn fill_gaps(&mut self, fallback: Self) {
+        fill_blank(&mut self.name, fallback.name);
+        fill_blank(&mut self.overview, fallback.overview);
+    }
+}
+
+impl Localized for TmdbPersonDetails {
+    fn has_gaps(&self) -> bool {
+        is_blank(&self.biography)
+    }
+
+    fn fill_gaps(&mut self, fallback: Self) {
+        fill_blank(&mut self.biography, fallback.biography);
+    }
+}
+
 trait CreditLike {
     fn id(&self) -> u64;
     fn name(&self) -> &str;
@@ -833,6 +1687,7 @@ struct TvSearchResult {
     #[serde(default)]
     name: String,
     first_air_date: Option<String>,
+    poster_path: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -841,6 +1696,7 @@ struct MovieSearchResult {
     #[serde(default)]
     title: String,
     release_date: Option<String>,
+    poster_path: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -848,6 +1704,10 @@ struct TvDetails {
     id: u64,
     #[serde(default)]
     name: String,
+    original_name: Option<String>,
+    original_language: Option<String>,
+    #[serde(default)]
+    episode_run_time: Vec<i64>,
     overview: Option<String>,
     vote_average: Option<f64>,
     first_air_date: Option<String>,
@@ -858,12 +1718,29 @@ struct TvDetails {
     tagline: Option<String>,
     #[serde(default)]
     genres: Vec<TmdbGenre>,
+    #[serde(default)]
+    production_companies: Vec<TmdbCompany>,
+    #[serde(default)]
+    networks: Vec<TmdbCompany>,
+    keywords: Option<TvKeywordsResponse>,
     external_ids: Option<ExternalIds>,
     content_ratings: Option<TvContentRatingsResponse>,
     aggregate_credits: Option<TvAggregateCredits>,
+    #[serde(default)]
+    created_by: Vec<TmdbCreator>,
     recommendations: Option<SearchResponse<TvSearchResult>>,
     images: Option<TmdbImages>,
+    translations: Option<TmdbTranslations>,
     next_episode_to_air: Option<NextEpisode>,
+    #[serde(default)]
+    seasons: Vec<TvSeasonSummary>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TvSeasonSummary {
+    season_number: i32,
+    #[serde(default)]
+    episode_count: i32,
 }
 
 #[derive(Debug, Deserialize)]
@@ -871,6 +1748,10 @@ struct MovieDetails {
     id: u64,
     #[serde(default)]
     title: String,
+    original_title: Option<String>,
+    original_language: Option<String>,
+    runtime: Option<i64>,
+    budget: Option<i64>,
     overview: Option<String>,
     vote_average: Option<f64>,
     release_date: Option<String>,
@@ -880,14 +1761,34 @@ struct MovieDetails {
     tagline: Option<String>,
     #[serde(default)]
     genres: Vec<TmdbGenre>,
+    #[serde(default)]
+    production_companies: Vec<TmdbCompany>,
+    keywords: Option<MovieKeywordsResponse>,
     external_ids: Option<ExternalIds>,
     release_dates: Option<MovieReleaseDatesResponse>,
     credits: Option<MovieCredits>,
     recommendations: Option<SearchResponse<MovieSearchResult>>,
     images: Option<TmdbImages>,
+    translations: Option<TmdbTranslations>,
+    belongs_to_collection: Option<TmdbCollectionSummary>,
 }
 
 #[derive(Debug, Deserialize)]
+struct TmdbCollectionSummary {
+    id: u64,
+    #[serde(default)]
+    name: String,
+    poster_path: Option<String>,
+    backdrop_path: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TmdbCollectionDetails {
+    name: Option<String>,
+    overview: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
 struct TvSeasonDetails {
     #[serde(default)]
     name: String,
@@ -898,9 +1799,15 @@ struct TvSeasonDetails {
     episodes: Vec<TvEpisodeDetails>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Clone)]
 struct TvEpisodeDetails {
     episode_number: i32,
+    // the episode's place in the default ordering, which episode groups keep for reference
+    #[serde(default)]
+    season_number: Option<i32>,
+    // position inside an episode group, absent on regular season episodes
+    #[serde(default)]
+    order: i32,
     name: Option<String>,
     overview: Option<String>,
     vote_average: Option<f64>,
@@ -908,12 +1815,69 @@ struct TvEpisodeDetails {
     still_path: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+struct TvEpisodeGroupSummary {
+    id: String,
+    #[serde(default)]
+    name: String,
+    #[serde(rename = "type")]
+    kind: i32,
+    description: Option<String>,
+    #[serde(default)]
+    group_count: i32,
+    #[serde(default)]
+    episode_count: i32,
+}
+
+#[derive(Debug, Deserialize)]
+struct TvEpisodeGroupDetails {
+    #[serde(default)]
+    groups: Vec<TvEpisodeGroup>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TvEpisodeGroup {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    order: i32,
+    #[serde(default)]
+    episodes: Vec<TvEpisodeDetails>,
+}
+
 #[derive(Debug, Deserialize)]
 struct TmdbGenre {
     id: u64,
     name: String,
 }
 
+#[derive(Debug, Deserialize)]
+struct TmdbCompany {
+    id: u64,
+    #[serde(default)]
+    name: String,
+    origin_country: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TmdbKeyword {
+    id: u64,
+    name: String,
+}
+
+// the keywords endpoint names its list differently for tv and movies
+#[derive(Debug, Deserialize)]
+struct TvKeywordsResponse {
+    #[serde(default)]
+    results: Vec<TmdbKeyword>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MovieKeywordsResponse {
+    #[serde(default)]
+    keywords: Vec<TmdbKeyword>,
+}
+
 #[derive(Debug, Deserialize)]
 struct TvContentRatingsResponse {
     #[serde(default)]
@@ -924,6 +1888,8 @@ struct TvContentRatingsResponse {
 struct TvContentRating {
     iso_3166_1: String,
     rating: Option<String>,
+    #[serde(default)]
+    descriptors: Vec<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -944,18 +1910,54 @@ struct MovieReleaseDate {
     certification: Option<String>,
     release_date: Option<String>,
     release_type: Option<i64>,
+    #[serde(default)]
+    descriptors: Vec<String>,
 }
 
 #[derive(Debug, Deserialize)]
 struct MovieCredits {
     #[serde(default)]
     cast: Vec<TmdbCast>,
+    #[serde(default)]
+    crew: Vec<TmdbCrew>,
 }
 
 #[derive(Debug, Deserialize)]
 struct TvAggregateCredits {
     #[serde(default)]
     cast: Vec<TmdbAggregateCast>,
+    #[serde(default)]
+    crew: Vec<TmdbAggregateCrew>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TmdbCrew {
+    id: u64,
+    name: String,
+    department: Option<String>,
+    job: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TmdbAggregateCrew {
+    id: u64,
+    name: String,
+    department: Option<String>,
+    #[serde(default)]
+    jobs: Vec<TmdbAggregateJob>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TmdbAggregateJob {
+    job: Option<String>,
+    #[serde(default)]
+    episode_count: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct TmdbCreator {
+    id: u64,
+    name: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -1030,6 +2032,41 @@ struct TmdbImage {
     file_type: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+struct TmdbTranslations {
+    #[serde(default)]
+    translations: Vec<TmdbTranslation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TmdbTranslation {
+    iso_3166_1: String,
+    iso_639_1: String,
+    data: TmdbTranslationData,
+}
+
+impl TmdbTranslation {
+    // "pt-BR" only matches brazilian portuguese, a bare "pt" takes any region
+    fn matches(&self, language: &str) -> bool {
+        let mut parts = language.split('-');
+        let code = parts.next().unwrap_or_default();
+        if !self.iso_639_1.eq_ignore_ascii_case(code) {
+            return false;
+        }
+        parts
+            .next()
+            .is_none_or(|region| self.iso_3166_1.eq_ignore_ascii_case(region))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TmdbTranslationData {
+    name: Option<String>,
+    title: Option<String>,
+    overview: Option<String>,
+    tagline: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 struct NextEpisode {
     air_date: Option<String>,
@@ -1043,3 +2080,262 @@ struct TmdbPersonDetails {
     birthday: Option<String>,
     profile_path: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn episode_groups_number_like_seasons_with_specials_at_zero() {
+        let groups: Vec<TvEpisodeGroup> = serde_json::from_value(json!([
+            {
+                "name": "Part 2",
+                "order": 2,
+                "episodes": [
+                    { "episode_number": 4, "season_number": 1, "order": 1 },
+                    { "episode_number": 3, "season_number": 1, "order": 0 }
+                ]
+            },
+            {
+                "name": "Extras",
+                "order": 3,
+                "episodes": [{ "episode_number": 1, "season_number": 0, "order": 0 }]
+            },
+            {
+                "name": "Part 1",
+                "order": 1,
+                "episodes": [{ "episode_number": 1, "season_number": 1, "order": 0 }]
+            },
+            { "name": "Specials", "order": 4, "episodes": [] }
+        ]))
+        .unwrap();
+
+        let numbered = number_episode_groups(groups)
+            .into_iter()
+            .map(|(season_number, group)| {
+                (
+                    season_number,
+                    group.name,
+                    group
+                        .episodes
+                        .iter()
+                        .map(|episode| episode.episode_number)
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            numbered,
+            [
+                (1, "Part 1".to_owned(), vec![1]),
+                (2, "Part 2".to_owned(), vec![3, 4]),
+                (0, "Extras".to_owned(), vec![1]),
+                (0, "Specials".to_owned(), vec![]),
+            ]
+        );
+    }
+
+    fn settings(language: &str, fallback_languages: &[&str]) -> TmdbSettings {
+        TmdbSettings {
+            api_key: None,
+            access_token: None,
+            api_base_url: String::new(),
+            image_base_url: String::new(),
+            language: language.to_owned(),
+            fallback_languages: fallback_languages.iter().map(|l| l.to_string()).collect(),
+            image_languages: Vec::new(),
+        }
+    }
+
+    fn image(language: Option<&str>, vote_average: f64, file_type: &str) -> TmdbImage {
+        TmdbImage {
+            file_path: Some(format!(
+                "/{}-{vote_average}{file_type}",
+                language.unwrap_or("none")
+            )),
+            iso_639_1: language.map(str::to_owned),
+            vote_average,
+            vote_count: 1,
+            width: None,
+            height: None,
+            file_type: Some(file_type.to_owned()),
+        }
+    }
+
+    #[test]
+    fn metadata_languages_drive_localized_and_image_languages() {
+        let chained = settings("pt-BR", &["pt", "en-US"]);
+        assert_eq!(chained.image_languages(), ["pt", "en"]);
+        assert!(chained.localized_languages(&[]).is_empty());
+        assert_eq!(
+            chained.localized_languages(&["de".to_owned(), "PT-br".to_owned(), "ja".to_owned()]),
+            ["pt-BR", "de", "ja"]
+        );
+
+        let single = settings("en-US", &[]);
+        assert!(single.localized_languages(&["en-US".to_owned()]).is_empty());
+        assert_eq!(single.image_languages(), ["en"]);
+
+        let explicit = TmdbSettings {
+            image_languages: vec!["ja".to_owned()],
+            ..single
+        };
+        assert_eq!(explicit.image_languages(), ["ja"]);
+    }
+
+    #[test]
+    fn translations_only_check_the_region_when_one_is_asked_for() {
+        let translation: TmdbTranslation = serde_json::from_value(json!({
+            "iso_3166_1": "BR",
+            "iso_639_1": "pt",
+            "data": { "name": "Nome" }
+        }))
+        .unwrap();
+
+        assert!(translation.matches("pt"));
+        assert!(translation.matches("pt-br"));
+        assert!(!translation.matches("pt-PT"));
+        assert!(!translation.matches("es"));
+    }
+
+    #[test]
+    fn images_rank_preferred_languages_then_textless_and_logos_prefer_text() {
+        let languages = vec!["de".to_owned(), "en".to_owned()];
+        let mut posters = [
+            image(Some("fr"), 9.0, ".jpg"),
+            image(None, 8.0, ".jpg"),
+            image(Some("en"), 7.0, ".jpg"),
+            image(Some("de"), 5.0, ".jpg"),
+        ];
+        posters.sort_by_key(|row| image_language_rank(&languages, row));
+        assert_eq!(
+            posters
+                .iter()
+                .map(|row| row.iso_639_1.as_deref())
+                .collect::<Vec<_>>(),
+            [Some("de"), Some("en"), None, Some("fr")]
+        );
+
+        let mut logos = [
+            image(None, 9.0, ".png"),
+            image(Some("fr"), 6.0, ".png"),
+            image(Some("en"), 5.0, ".png"),
+            image(Some("en"), 5.0, ".svg"),
+            image(Some("en"), 6.0, ".png"),
+        ];
+        logos.sort_by_key(|row| logo_sort_key(&languages, row));
+        assert_eq!(
+            logos
+                .iter()
+                .map(|row| row.file_path.as_deref().unwrap())
+                .collect::<Vec<_>>(),
+            [
+                "/en-6.png",
+                "/en-5.svg",
+                "/en-5.png",
+                "/fr-6.png",
+                "/none-9.png"
+            ]
+        );
+    }
+
+    #[test]
+    fn only_aired_episodes_leave_season_gaps() {
+        let mut season: TvSeasonDetails = serde_json::from_value(json!({
+            "name": "Season 1",
+            "overview": "A season",
+            "episodes": [
+                { "episode_number": 1, "name": "Pilot", "overview": "It starts", "air_date": "2020-01-01" },
+                { "episode_number": 2, "name": "Episode 2", "overview": "", "air_date": "2999-01-01" },
+                { "episode_number": 3, "name": "Episode 3" }
+            ]
+        }))
+        .unwrap();
+        assert!(!season.has_gaps());
+
+        season.episodes[0].overview = None;
+        assert!(season.has_gaps());
+        let fallback: TvSeasonDetails = serde_json::from_value(json!({
+            "overview": "Eine Staffel",
+            "episodes": [{ "episode_number": 1, "name": "Pilot", "overview": "Es beginnt" }]
+        }))
+        .unwrap();
+        season.fill_gaps(fallback);
+        assert!(!season.has_gaps());
+        assert_eq!(season.overview.as_deref(), Some("A season"));
+        assert_eq!(season.episodes[0].overview.as_deref(), Some("Es beginnt"));
+    }
+
+    fn crew_summary(crew: &[CastCredit]) -> Vec<(String, String)> {
+        crew.iter()
+            .map(|credit| (credit.name.clone(), credit.job.clone().unwrap_or_default()))
+            .collect()
+    }
+
+    #[test]
+    fn movie_crew_keeps_wanted_jobs_in_order_once_per_person() {
+        let rows: Vec<TmdbCrew> = serde_json::from_value(json!([
+            { "id": 1, "name": "Ann", "department": "Writing", "job": "Writer" },
+            { "id": 2, "name": "Ben", "department": "Directing", "job": "Director" },
+            { "id": 3, "name": "Cid", "department": "Lighting", "job": "Gaffer" },
+            { "id": 2, "name": "Ben", "department": "Directing", "job": "Director" },
+            { "id": 4, "name": "Dee", "department": "Directing", "job": "Director" },
+            { "id": 5, "name": "Eve", "department": "Writing" },
+            { "id": 2, "name": "Ben", "department": "Writing", "job": "Screenplay" }
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            crew_summary(&map_movie_crew(rows)),
+            [
+                ("Ben".to_owned(), "Director".to_owned()),
+                ("Dee".to_owned(), "Director".to_owned()),
+                ("Ben".to_owned(), "Screenplay".to_owned()),
+                ("Ann".to_owned(), "Writer".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn tv_crew_leads_with_creators_and_keeps_the_busiest_directors() {
+        let created_by: Vec<TmdbCreator> =
+            serde_json::from_value(json!([{ "id": 100, "name": "Creator" }])).unwrap();
+        let mut rows = (1..=7)
+            .map(|episode_count| {
+                json!({
+                    "id": episode_count,
+                    "name": format!("Director {episode_count}"),
+                    "department": "Directing",
+                    "jobs": [{ "job": "Director", "episode_count": episode_count }]
+                })
+            })
+            .collect::<Vec<_>>();
+        rows.push(json!({
+            "id": 100,
+            "name": "Creator",
+            "department": "Writing",
+            "jobs": [
+                { "job": "Writer", "episode_count": 2 },
+                { "job": "Executive Producer", "episode_count": 10 }
+            ]
+        }));
+        let rows: Vec<TmdbAggregateCrew> = serde_json::from_value(json!(rows)).unwrap();
+
+        let crew = map_tv_crew(created_by, rows);
+        assert_eq!(
+            crew_summary(&crew),
+            [
+                ("Creator".to_owned(), "Creator".to_owned()),
+                ("Director 7".to_owned(), "Director".to_owned()),
+                ("Director 6".to_owned(), "Director".to_owned()),
+                ("Director 5".to_owned(), "Director".to_owned()),
+                ("Director 4".to_owned(), "Director".to_owned()),
+                ("Director 3".to_owned(), "Director".to_owned()),
+                ("Creator".to_owned(), "Writer".to_owned()),
+            ]
+        );
+        assert_eq!(crew[0].department, None);
+        assert_eq!(crew[1].department.as_deref(), Some("Directing"));
+    }
+}
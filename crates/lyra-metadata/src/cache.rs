@@ -0,0 +1,444 @@
+use anyhow::{Context, Result};
+use reqwest::{
+    RequestBuilder, StatusCode,
+    header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED},
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    future::Future,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+// temp files older than this belong to writes that never finished
+const TEMP_FILE_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+static NEXT_TEMP_ID: AtomicU64 = AtomicU64::new(0);
+
+tokio::task_local! {
+    static REVALIDATE_BEFORE: u64;
+}
+
+// runs `f` with the ttl ignored for entries fetched before the scope started, so scheduled and
+// manual refreshes see upstream changes without re-downloading bodies that still match. entries
+// fetched inside the scope stay fresh so repeated lookups don't revalidate twice.
+pub async fn revalidating<F: Future>(f: F) -> F::Output {
+    REVALIDATE_BEFORE.scope(unix_now(), f).await
+}
+
+// returned when offline mode needs a response that was never cached
+#[derive(Debug, thiserror::Error)]
+#[error("no cached response for {key} while metadata providers are offline")]
+pub struct OfflineCacheMiss {
+    pub key: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    key: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    fetched_at: u64,
+}
+
+// on-disk response cache shared by every provider. each entry is a body file plus a small json
+// sidecar holding the validators, so expired entries are revalidated with a conditional request
+// instead of downloaded again. in offline mode nothing leaves the box and stale entries are
+// served as-is.
+pub struct ResponseCache {
+    dir: PathBuf,
+    offline: bool,
+}
+
+impl ResponseCache {
+    pub fn new(dir: PathBuf, offline: bool) -> Self {
+        Self { dir, offline }
+    }
+
+    // `throttle` only runs when a request is actually sent, so rate limits aren't spent on hits
+    pub async fn fetch(
+        &self,
+        key: &str,
+        ttl: Duration,
+        request: RequestBuilder,
+        throttle: impl Future<Output = ()>,
+    ) -> Result<Vec<u8>> {
+        let (body_path, entry_path) = self.entry_paths(key);
+        let cached = read_entry(&entry_path, &body_path).await;
+        let now = unix_now();
+        let revalidate_before = REVALIDATE_BEFORE.try_with(|before| *before).unwrap_or(0);
+
+        if let Some((entry, body)) = &cached
+            && (self.offline
+                || (entry.fetched_at >= revalidate_before
+                    && now.saturating_sub(entry.fetched_at) < ttl.as_secs()))
+        {
+            return Ok(body.clone());
+        }
+        if self.offline {
+            return Err(OfflineCacheMiss {
+                key: key.to_owned(),
+            }
+            .into());
+        }
+
+        let mut request = request;
+        if let Some((entry, _)) = &cached {
+            if let Some(etag) = &entry.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &entry.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        throttle.await;
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(error) => {
+                let Some((_, body)) = cached else {
+                    return Err(error).context("failed to send metadata request");
+                };
+                tracing::warn!(key, "serving stale cached response: {error:#}");
+                return Ok(body);
+            }
+        };
+
+        if response.status() == StatusCode::NOT_MODIFIED
+            && let Some((mut entry, body)) = cached
+        {
+            entry.fetched_at = now;
+            write_entry(&entry_path, &entry).await?;
+            return Ok(body);
+        }
+
+        if let Err(error) = response.error_for_status_ref() {
+            match cached {
+                // a 404 means the resource is gone. rate limits, bans and outages are worth
+                // riding out on the last good copy.
+                Some((_, body)) if error.status() != Some(StatusCode::NOT_FOUND) => {
+                    tracing::warn!(key, "serving stale cached response: {error:#}");
+                    return Ok(body);
+                }
+                _ => return Err(error).context("metadata provider returned an error response"),
+            }
+        }
+        let etag = header_value(&response, ETAG);
+        let last_modified = header_value(&response, LAST_MODIFIED);
+        let body = response
+            .bytes()
+            .await
+            .context("failed to read metadata response body")?
+            .to_vec();
+
+        tokio::fs::create_dir_all(&self.dir).await?;
+        write_atomic(&body_path, &body).await?;
+        write_entry(
+            &entry_path,
+            &CacheEntry {
+                key: key.to_owned(),
+                etag,
+                last_modified,
+                fetched_at: now,
+            },
+        )
+        .await?;
+
+        Ok(body)
+    }
+
+    // drops entries that haven't been fetched or revalidated within `max_age`, along with bodies
+    // and temp files left behind by interrupted writes. offline mode can't fetch anything again,
+    // so it keeps everything. returns how many entries were removed.
+    pub async fn sweep(&self, max_age: Duration) -> Result<usize> {
+        if self.offline {
+            return Ok(0);
+        }
+        let mut dir = match tokio::fs::read_dir(&self.dir).await {
+            Ok(dir) => dir,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(error) => return Err(error.into()),
+        };
+
+        let cutoff = unix_now().saturating_sub(max_age.as_secs());
+        let mut removed = 0;
+        while let Some(item) = dir.next_entry().await? {
+            let path = item.path();
+            match path.extension().and_then(|extension| extension.to_str()) {
+                Some("json") => {
+                    let fresh = tokio::fs::read(&path)
+                        .await
+                        .ok()
+                        .and_then(|bytes| serde_json::from_slice::<CacheEntry>(&bytes).ok())
+                        .is_some_and(|entry| entry.fetched_at >= cutoff);
+                    if !fresh {
+                        remove_if_exists(&path).await?;
+                        remove_if_exists(&path.with_extension("body")).await?;
+                        removed += 1;
+                    }
+                }
+                Some("body") if !tokio::fs::try_exists(path.with_extension("json")).await? => {
+                    remove_if_exists(&path).await?;
+                }
+                Some("tmp") => {
+                    let abandoned = item
+                        .metadata()
+                        .await?
+                        .modified()?
+                        .elapsed()
+                        .is_ok_and(|age| age > TEMP_FILE_MAX_AGE);
+                    if abandoned {
+                        remove_if_exists(&path).await?;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(removed)
+    }
+
+    fn entry_paths(&self, key: &str) -> (PathBuf, PathBuf) {
+        let hash = hex::encode(Sha256::digest(key.as_bytes()));
+        (
+            self.dir.join(format!("{hash}.body")),
+            self.dir.join(format!("{hash}.json")),
+        )
+    }
+}
+
+async fn read_entry(entry_path: &Path, body_path: &Path) -> Option<(CacheEntry, Vec<u8>)> {
+    let entry = tokio::fs::read(entry_path).await.ok()?;
+    let entry = serde_json::from_slice(&entry).ok()?;
+    let body = tokio::fs::read(body_path).await.ok()?;
+    Some((entry, body))
+}
+
+async fn write_entry(path: &Path, entry: &CacheEntry) -> Result<()> {
+    write_atomic(path, &serde_json::to_vec(entry)?).await
+}
+
+// concurrent writes of the same key each get their own temp file and the last rename wins
+async fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        NEXT_TEMP_ID.fetch_add(1, Ordering::Relaxed)
+    ));
+    tokio::fs::write(&temp_path, contents).await?;
+    tokio::fs::rename(&temp_path, path).await?;
+    Ok(())
+}
+
+// a concurrent sweep or fetch may have removed the file first
+async fn remove_if_exists(path: &Path) -> Result<()> {
+    match tokio::fs::remove_file(path).await {
+        Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error.into()),
+        _ => Ok(()),
+    }
+}
+
+fn header_value(response: &reqwest::Response, name: reqwest::header::HeaderName) -> Option<String> {
+    response
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn offline_mode_serves_stale_entries_and_reports_misses() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let cache = ResponseCache::new(dir.path().to_path_buf(), true);
+        let (body_path, entry_path) = cache.entry_paths("/tv/1");
+        write_atomic(&body_path, b"{\"id\":1}").await?;
+        write_entry(
+            &entry_path,
+            &CacheEntry {
+                key: "/tv/1".to_owned(),
+                etag: Some("\"abc\"".to_owned()),
+                last_modified: None,
+                fetched_at: 0,
+            },
+        )
+        .await?;
+
+        let client = reqwest::Client::new();
+        let body = cache
+            .fetch(
+                "/tv/1",
+                Duration::from_secs(60),
+                client.get("http://127.0.0.1:9/tv/1"),
+                async { panic!("offline mode must not send requests") },
+            )
+            .await?;
+        assert_eq!(body, b"{\"id\":1}");
+
+        let missing = cache
+            .fetch(
+                "/tv/2",
+                Duration::from_secs(60),
+                client.get("http://127.0.0.1:9/tv/2"),
+                async { panic!("offline mode must not send requests") },
+            )
+            .await
+            .unwrap_err();
+        assert!(missing.downcast_ref::<OfflineCacheMiss>().is_some());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn revalidating_scope_skips_fresh_entries() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let cache = ResponseCache::new(dir.path().to_path_buf(), false);
+        let (body_path, entry_path) = cache.entry_paths("/tv/1");
+        write_atomic(&body_path, b"{\"id\":1}").await?;
+        write_entry(
+            &entry_path,
+            &CacheEntry {
+                key: "/tv/1".to_owned(),
+                etag: None,
+                last_modified: None,
+                fetched_at: unix_now() - 10,
+            },
+        )
+        .await?;
+
+        let client = reqwest::Client::new();
+        let body = cache
+            .fetch(
+                "/tv/1",
+                Duration::from_secs(60),
+                client.get("http://127.0.0.1:9/tv/1"),
+                async { panic!("fresh entries must not send requests") },
+            )
+            .await?;
+        assert_eq!(body, b"{\"id\":1}");
+
+        // the unreachable host makes the revalidation fail, which falls back to the stale body
+        let sent = std::sync::atomic::AtomicBool::new(false);
+        let body = revalidating(cache.fetch(
+            "/tv/1",
+            Duration::from_secs(60),
+            client.get("http://127.0.0.1:9/tv/1"),
+            async { sent.store(true, std::sync::atomic::Ordering::Relaxed) },
+        ))
+        .await?;
+        assert_eq!(body, b"{\"id\":1}");
+        assert!(sent.load(std::sync::atomic::Ordering::Relaxed));
+
+        Ok(())
+    }
+
+    async fn store(cache: &ResponseCache, key: &str, body: &[u8], fetched_at: u64) -> Result<()> {
+        let (body_path, entry_path) = cache.entry_paths(key);
+        write_atomic(&body_path, body).await?;
+        write_entry(
+            &entry_path,
+            &CacheEntry {
+                key: key.to_owned(),
+                etag: None,
+                last_modified: None,
+                fetched_at,
+            },
+        )
+        .await
+    }
+
+    // answers one request per status, then closes
+    async fn serve_statuses(statuses: &[&'static str]) -> Result<String> {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let address = format!("http://{}", listener.local_addr()?);
+        let statuses = statuses.to_vec();
+        tokio::spawn(async move {
+            for status in statuses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = [0; 1024];
+                let _ = stream.read(&mut request).await.unwrap();
+                let response =
+                    format!("HTTP/1.1 {status}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n");
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        Ok(address)
+    }
+
+    #[tokio::test]
+    async fn error_statuses_fall_back_to_the_stale_copy_unless_it_is_gone() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let cache = ResponseCache::new(dir.path().to_path_buf(), false);
+        store(&cache, "/dump", b"old dump", 0).await?;
+        let address = serve_statuses(&["503 Service Unavailable", "404 Not Found"]).await?;
+
+        let client = reqwest::Client::new();
+        let url = format!("{address}/dump");
+        let body = cache
+            .fetch(
+                "/dump",
+                Duration::from_secs(60),
+                client.get(&url),
+                std::future::ready(()),
+            )
+            .await?;
+        assert_eq!(body, b"old dump");
+
+        let gone = cache
+            .fetch(
+                "/dump",
+                Duration::from_secs(60),
+                client.get(&url),
+                std::future::ready(()),
+            )
+            .await;
+        assert!(gone.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn sweep_drops_old_entries_and_leftovers() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let cache = ResponseCache::new(dir.path().to_path_buf(), false);
+        store(&cache, "/old", b"old", unix_now() - 120).await?;
+        store(&cache, "/new", b"new", unix_now()).await?;
+        let (orphan_body, _) = cache.entry_paths("/orphan");
+        tokio::fs::write(&orphan_body, b"orphan").await?;
+        let pending = dir.path().join("pending.body.1.tmp");
+        tokio::fs::write(&pending, b"pending").await?;
+
+        assert_eq!(cache.sweep(Duration::from_secs(60)).await?, 1);
+
+        let (old_body, old_entry) = cache.entry_paths("/old");
+        let (new_body, new_entry) = cache.entry_paths("/new");
+        assert!(!tokio::fs::try_exists(&old_body).await?);
+        assert!(!tokio::fs::try_exists(&old_entry).await?);
+        assert!(!tokio::fs::try_exists(&orphan_body).await?);
+        assert!(tokio::fs::try_exists(&new_body).await?);
+        assert!(tokio::fs::try_exists(&new_entry).await?);
+        // temp files may belong to a write still in flight
+        assert!(tokio::fs::try_exists(&pending).await?);
+
+        let offline = ResponseCache::new(dir.path().to_path_buf(), true);
+        assert_eq!(offline.sweep(Duration::ZERO).await?, 0);
+        assert!(tokio::fs::try_exists(&new_entry).await?);
+
+        Ok(())
+    }
+}
@@ -1,6 +1,11 @@
 use anyhow::Result;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+mod cache;
+
+pub use cache::{OfflineCacheMiss, ResponseCache, revalidating};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Scored<T> {
@@ -15,6 +20,18 @@ pub struct RootMatchHint {
     pub end_year: Option<i32>,
     pub imdb_id: Option<String>,
     pub tmdb_id: Option<u64>,
+    #[serde(default)]
+    pub anidb_id: Option<u64>,
+    // set for anime libraries and for roots whose episodes use absolute numbering, so anime
+    // providers only claim roots they are meant for
+    #[serde(default)]
+    pub anime: bool,
+    // provider-specific id an admin pinned the root to. providers should return exactly that
+    // candidate instead of searching.
+    pub pinned_id: Option<String>,
+    // media files under the root, for providers that read metadata stored next to them
+    #[serde(default)]
+    pub file_paths: Vec<PathBuf>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,16 +46,27 @@ pub struct MovieRootMatchRequest {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SeriesCandidate {
-    pub tmdb_id: u64,
+    pub tmdb_id: Option<u64>,
     pub name: String,
     pub first_air_year: Option<i32>,
+    #[serde(default)]
+    pub poster_url: Option<String>,
+    // the local file a candidate was read from, for providers that don't have remote ids
+    #[serde(default)]
+    pub source_path: Option<PathBuf>,
+    #[serde(default)]
+    pub anidb_id: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MovieCandidate {
-    pub tmdb_id: u64,
+    pub tmdb_id: Option<u64>,
     pub name: String,
     pub release_year: Option<i32>,
+    #[serde(default)]
+    pub poster_url: Option<String>,
+    #[serde(default)]
+    pub source_path: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -93,12 +121,31 @@ pub struct MetadataGenre {
     pub name: String,
 }
 
+// production companies (studios) and tv networks share a shape
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetadataCompany {
+    pub provider_id: String,
+    pub external_id: Option<String>,
+    pub name: String,
+    pub origin_country: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetadataKeyword {
+    pub provider_id: String,
+    pub external_id: Option<String>,
+    pub name: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContentRating {
     pub country_code: String,
     pub rating: String,
     pub release_date: Option<i64>,
     pub release_type: Option<i64>,
+    // reasons given for the rating, like "Violence" or "Strong Language"
+    #[serde(default)]
+    pub descriptors: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -107,6 +154,9 @@ pub struct CastCredit {
     pub name: String,
     pub character_name: Option<String>,
     pub department: Option<String>,
+    // set on crew credits only, like "Director" or "Original Music Composer"
+    #[serde(default)]
+    pub job: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -127,10 +177,33 @@ pub struct Recommendation {
     pub first_aired: Option<i64>,
 }
 
+// text in one extra language, next to the provider's primary-language fields. `language` is a
+// bcp 47 tag like "de" or "pt-BR" and empty fields fall back to other languages when displayed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalizedText {
+    pub language: String,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub tagline: Option<String>,
+}
+
+// a franchise a movie belongs to, like tmdb's "The Lord of the Rings Collection"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MovieCollection {
+    pub provider_id: String,
+    pub external_id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub poster_url: Option<String>,
+    pub backdrop_url: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SeriesMetadata {
     pub imdb_id: Option<String>,
     pub tmdb_id: Option<u64>,
+    #[serde(default)]
+    pub anidb_id: Option<u64>,
     pub name: String,
     pub description: Option<String>,
     pub score_display: Option<String>,
@@ -140,11 +213,28 @@ pub struct SeriesMetadata {
     pub status: Option<MetadataStatus>,
     pub tagline: Option<String>,
     pub next_aired: Option<i64>,
+    #[serde(default)]
+    pub original_title: Option<String>,
+    #[serde(default)]
+    pub original_language: Option<String>,
+    // typical episode length
+    #[serde(default)]
+    pub runtime_minutes: Option<i64>,
     pub genres: Vec<MetadataGenre>,
     pub content_ratings: Vec<ContentRating>,
+    #[serde(default)]
+    pub studios: Vec<MetadataCompany>,
+    #[serde(default)]
+    pub networks: Vec<MetadataCompany>,
+    #[serde(default)]
+    pub keywords: Vec<MetadataKeyword>,
     pub cast: Vec<CastCredit>,
+    #[serde(default)]
+    pub crew: Vec<CastCredit>,
     pub recommendations: Vec<Recommendation>,
     pub images: ImageSet,
+    #[serde(default)]
+    pub localizations: Vec<LocalizedText>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -159,11 +249,30 @@ pub struct MovieMetadata {
     pub last_aired: Option<i64>,
     pub status: Option<MetadataStatus>,
     pub tagline: Option<String>,
+    #[serde(default)]
+    pub original_title: Option<String>,
+    #[serde(default)]
+    pub original_language: Option<String>,
+    #[serde(default)]
+    pub runtime_minutes: Option<i64>,
+    // in us dollars
+    #[serde(default)]
+    pub budget: Option<i64>,
     pub genres: Vec<MetadataGenre>,
     pub content_ratings: Vec<ContentRating>,
+    #[serde(default)]
+    pub studios: Vec<MetadataCompany>,
+    #[serde(default)]
+    pub keywords: Vec<MetadataKeyword>,
     pub cast: Vec<CastCredit>,
+    #[serde(default)]
+    pub crew: Vec<CastCredit>,
     pub recommendations: Vec<Recommendation>,
     pub images: ImageSet,
+    #[serde(default)]
+    pub localizations: Vec<LocalizedText>,
+    #[serde(default)]
+    pub collection: Option<MovieCollection>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -172,6 +281,8 @@ pub struct SeriesItem {
     pub season_number: Option<i32>,
     pub episode_number: Option<i32>,
     pub name: String,
+    #[serde(default)]
+    pub file_paths: Vec<PathBuf>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -179,6 +290,29 @@ pub struct SeriesItemsRequest {
     pub root_id: String,
     pub candidate: SeriesCandidate,
     pub items: Vec<SeriesItem>,
+    // an alternate episode ordering picked for the root. local season and episode numbers are
+    // read in that ordering instead of the provider's default one.
+    #[serde(default)]
+    pub ordering_id: Option<String>,
+}
+
+// an alternate way to number a series' episodes, like dvd order or story arcs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EpisodeOrdering {
+    pub id: String,
+    pub name: String,
+    pub kind: String,
+    pub description: Option<String>,
+    pub group_count: i32,
+    pub episode_count: i32,
+}
+
+// where an item sits in the selected ordering, for items whose local numbering doesn't say
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemPosition {
+    pub item_id: String,
+    pub season_number: i32,
+    pub episode_number: i32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -198,6 +332,8 @@ pub struct SeasonMetadata {
     pub content_ratings: Vec<ContentRating>,
     pub recommendations: Vec<Recommendation>,
     pub images: ImageSet,
+    #[serde(default)]
+    pub localizations: Vec<LocalizedText>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -216,18 +352,215 @@ pub struct EpisodeMetadata {
     pub content_ratings: Vec<ContentRating>,
     pub recommendations: Vec<Recommendation>,
     pub images: ImageSet,
+    #[serde(default)]
+    pub localizations: Vec<LocalizedText>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeasonEpisodeCount {
+    pub season_number: i32,
+    pub episode_count: i32,
+}
+
+// an episode the provider lists that no local item covers, numbered in the requested ordering
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MissingEpisode {
+    pub season_number: i32,
+    pub episode_number: i32,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub first_aired: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SeriesItemsResult {
     pub seasons: Vec<SeasonMetadata>,
     pub episodes: Vec<EpisodeMetadata>,
+    // only filled when an ordering was requested. positions come from the primary provider
+    // alone, so gap filling leaves them untouched.
+    #[serde(default)]
+    pub positions: Vec<ItemPosition>,
+    #[serde(default)]
+    pub missing: Vec<MissingEpisode>,
+}
+
+// providers rate out of ten, shown with one decimal and stored normalised to 0-100
+pub fn score_display(score: Option<f64>) -> Option<String> {
+    score.map(|score| format!("{score:.1}/10"))
+}
+
+pub fn score_normalized(score: Option<f64>) -> Option<i64> {
+    score.map(|score| (score * 10.0).round() as i64)
+}
+
+// gap filling lets a higher priority provider (local nfo files) win on every field it has
+// while a lower priority one supplies the rest. cast is left alone because person ids are
+// provider specific.
+fn fill<T>(target: &mut Option<T>, other: Option<T>) {
+    if target.is_none() {
+        *target = other;
+    }
+}
+
+fn fill_vec<T>(target: &mut Vec<T>, other: Vec<T>) {
+    if target.is_empty() {
+        *target = other;
+    }
+}
+
+// languages only one side knows are added, and shared languages are filled field by field
+fn fill_localizations(target: &mut Vec<LocalizedText>, other: Vec<LocalizedText>) {
+    for localized in other {
+        match target
+            .iter_mut()
+            .find(|existing| existing.language.eq_ignore_ascii_case(&localized.language))
+        {
+            Some(existing) => {
+                fill(&mut existing.name, localized.name);
+                fill(&mut existing.description, localized.description);
+                fill(&mut existing.tagline, localized.tagline);
+            }
+            None => target.push(localized),
+        }
+    }
+}
+
+impl ImageSet {
+    pub fn fill_gaps(&mut self, other: ImageSet) {
+        fill_vec(&mut self.posters, other.posters);
+        fill_vec(&mut self.thumbnails, other.thumbnails);
+        fill_vec(&mut self.backdrops, other.backdrops);
+        fill_vec(&mut self.logos, other.logos);
+    }
+}
+
+impl SeriesMetadata {
+    pub fn fill_gaps(&mut self, other: SeriesMetadata) {
+        fill(&mut self.imdb_id, other.imdb_id);
+        fill(&mut self.tmdb_id, other.tmdb_id);
+        fill(&mut self.anidb_id, other.anidb_id);
+        fill(&mut self.description, other.description);
+        fill(&mut self.score_display, other.score_display);
+        fill(&mut self.score_normalized, other.score_normalized);
+        fill(&mut self.first_aired, other.first_aired);
+        fill(&mut self.last_aired, other.last_aired);
+        fill(&mut self.status, other.status);
+        fill(&mut self.tagline, other.tagline);
+        fill(&mut self.next_aired, other.next_aired);
+        fill(&mut self.original_title, other.original_title);
+        fill(&mut self.original_language, other.original_language);
+        fill(&mut self.runtime_minutes, other.runtime_minutes);
+        fill_vec(&mut self.genres, other.genres);
+        fill_vec(&mut self.content_ratings, other.content_ratings);
+        fill_vec(&mut self.studios, other.studios);
+        fill_vec(&mut self.networks, other.networks);
+        fill_vec(&mut self.keywords, other.keywords);
+        fill_vec(&mut self.recommendations, other.recommendations);
+        self.images.fill_gaps(other.images);
+        fill_localizations(&mut self.localizations, other.localizations);
+    }
+}
+
+impl MovieMetadata {
+    pub fn fill_gaps(&mut self, other: MovieMetadata) {
+        fill(&mut self.imdb_id, other.imdb_id);
+        fill(&mut self.tmdb_id, other.tmdb_id);
+        fill(&mut self.description, other.description);
+        fill(&mut self.score_display, other.score_display);
+        fill(&mut self.score_normalized, other.score_normalized);
+        fill(&mut self.first_aired, other.first_aired);
+        fill(&mut self.last_aired, other.last_aired);
+        fill(&mut self.status, other.status);
+        fill(&mut self.tagline, other.tagline);
+        fill(&mut self.original_title, other.original_title);
+        fill(&mut self.original_language, other.original_language);
+        fill(&mut self.runtime_minutes, other.runtime_minutes);
+        fill(&mut self.budget, other.budget);
+        fill_vec(&mut self.genres, other.genres);
+        fill_vec(&mut self.content_ratings, other.content_ratings);
+        fill_vec(&mut self.studios, other.studios);
+        fill_vec(&mut self.keywords, other.keywords);
+        fill_vec(&mut self.recommendations, other.recommendations);
+        self.images.fill_gaps(other.images);
+        fill_localizations(&mut self.localizations, other.localizations);
+        fill(&mut self.collection, other.collection);
+    }
+}
+
+impl SeasonMetadata {
+    pub fn fill_gaps(&mut self, other: SeasonMetadata) {
+        fill(&mut self.description, other.description);
+        fill(&mut self.score_display, other.score_display);
+        fill(&mut self.score_normalized, other.score_normalized);
+        fill(&mut self.first_aired, other.first_aired);
+        fill(&mut self.last_aired, other.last_aired);
+        fill(&mut self.status, other.status);
+        fill(&mut self.tagline, other.tagline);
+        fill(&mut self.next_aired, other.next_aired);
+        fill_vec(&mut self.genres, other.genres);
+        fill_vec(&mut self.content_ratings, other.content_ratings);
+        fill_vec(&mut self.recommendations, other.recommendations);
+        self.images.fill_gaps(other.images);
+        fill_localizations(&mut self.localizations, other.localizations);
+    }
+}
+
+impl EpisodeMetadata {
+    pub fn fill_gaps(&mut self, other: EpisodeMetadata) {
+        fill(&mut self.description, other.description);
+        fill(&mut self.score_display, other.score_display);
+        fill(&mut self.score_normalized, other.score_normalized);
+        fill(&mut self.first_aired, other.first_aired);
+        fill(&mut self.last_aired, other.last_aired);
+        fill(&mut self.status, other.status);
+        fill(&mut self.tagline, other.tagline);
+        fill(&mut self.next_aired, other.next_aired);
+        fill_vec(&mut self.genres, other.genres);
+        fill_vec(&mut self.content_ratings, other.content_ratings);
+        fill_vec(&mut self.recommendations, other.recommendations);
+        self.images.fill_gaps(other.images);
+        fill_localizations(&mut self.localizations, other.localizations);
+    }
+}
+
+impl SeriesItemsResult {
+    // seasons are matched by number and episodes by item id. anything only the other
+    // provider knows about is added as-is.
+    pub fn fill_gaps(&mut self, other: SeriesItemsResult) {
+        for season in other.seasons {
+            match self
+                .seasons
+                .iter_mut()
+                .find(|existing| existing.season_number == season.season_number)
+            {
+                Some(existing) => existing.fill_gaps(season),
+                None => self.seasons.push(season),
+            }
+        }
+
+        for episode in other.episodes {
+            match self
+                .episodes
+                .iter_mut()
+                .find(|existing| existing.item_id == episode.item_id)
+            {
+                Some(existing) => existing.fill_gaps(episode),
+                None => self.episodes.push(episode),
+            }
+        }
+
+        fill_vec(&mut self.missing, other.missing);
+    }
 }
 
 #[async_trait]
 pub trait MetadataProvider: Send + Sync {
     fn id(&self) -> &'static str;
 
+    // the languages viewers read metadata in, most common first. providers that return
+    // localized text store it for each of them, others ignore it.
+    fn set_metadata_languages(&self, _languages: &[String]) {}
+
     async fn match_series_root(
         &self,
         req: SeriesRootMatchRequest,
@@ -248,4 +581,130 @@ pub trait MetadataProvider: Send + Sync {
     ) -> Result<Vec<Scored<MovieCandidate>>>;
 
     async fn lookup_movie_metadata(&self, candidate: &MovieCandidate) -> Result<MovieMetadata>;
+
+    // episode counts per season in the provider's own ordering, used to turn absolute episode
+    // numbers into season numbers. providers without a season model return nothing.
+    async fn lookup_season_episode_counts(
+        &self,
+        _candidate: &SeriesCandidate,
+    ) -> Result<Vec<SeasonEpisodeCount>> {
+        Ok(Vec::new())
+    }
+
+    async fn lookup_episode_orderings(
+        &self,
+        _candidate: &SeriesCandidate,
+    ) -> Result<Vec<EpisodeOrdering>> {
+        Ok(Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn season(number: i32, description: Option<&str>, poster: Option<&str>) -> SeasonMetadata {
+        SeasonMetadata {
+            root_id: "root".to_owned(),
+            season_number: number,
+            name: format!("Season {number}"),
+            description: description.map(str::to_owned),
+            score_display: None,
+            score_normalized: None,
+            first_aired: None,
+            last_aired: None,
+            status: None,
+            tagline: None,
+            next_aired: None,
+            genres: Vec::new(),
+            content_ratings: Vec::new(),
+            recommendations: Vec::new(),
+            images: ImageSet {
+                posters: poster
+                    .map(|url| MetadataImage {
+                        kind: MetadataImageKind::Poster,
+                        url: url.to_owned(),
+                        language: None,
+                        vote_average: None,
+                        vote_count: None,
+                        width: None,
+                        height: None,
+                        file_type: None,
+                    })
+                    .into_iter()
+                    .collect(),
+                ..Default::default()
+            },
+            localizations: Vec::new(),
+        }
+    }
+
+    fn localized(language: &str, name: Option<&str>, description: Option<&str>) -> LocalizedText {
+        LocalizedText {
+            language: language.to_owned(),
+            name: name.map(str::to_owned),
+            description: description.map(str::to_owned),
+            tagline: None,
+        }
+    }
+
+    #[test]
+    fn fill_gaps_keeps_existing_fields_and_takes_the_rest() {
+        let mut local = season(1, Some("local plot"), None);
+        local.localizations = vec![localized("de", Some("Staffel 1"), None)];
+        let mut remote = season(
+            1,
+            Some("remote plot"),
+            Some("https://example.com/poster.jpg"),
+        );
+        remote.score_display = score_display(Some(7.36));
+        remote.score_normalized = score_normalized(Some(7.36));
+        remote.localizations = vec![
+            localized("DE", Some("Erste Staffel"), Some("Beschreibung")),
+            localized("fr", Some("Saison 1"), None),
+        ];
+
+        local.fill_gaps(remote);
+
+        assert_eq!(local.description.as_deref(), Some("local plot"));
+        assert_eq!(local.score_display.as_deref(), Some("7.4/10"));
+        assert_eq!(local.score_normalized, Some(74));
+        assert_eq!(local.images.posters.len(), 1);
+        assert_eq!(local.localizations.len(), 2);
+        assert_eq!(local.localizations[0].name.as_deref(), Some("Staffel 1"));
+        assert_eq!(
+            local.localizations[0].description.as_deref(),
+            Some("Beschreibung")
+        );
+        assert_eq!(local.localizations[1].language, "fr");
+    }
+
+    #[test]
+    fn series_items_are_merged_by_season_number() {
+        let mut local = SeriesItemsResult {
+            seasons: vec![season(1, None, Some("https://example.com/local.jpg"))],
+            episodes: Vec::new(),
+            positions: Vec::new(),
+            missing: Vec::new(),
+        };
+        let remote = SeriesItemsResult {
+            seasons: vec![
+                season(1, Some("first"), Some("https://example.com/remote.jpg")),
+                season(2, Some("second"), None),
+            ],
+            episodes: Vec::new(),
+            positions: Vec::new(),
+            missing: Vec::new(),
+        };
+
+        local.fill_gaps(remote);
+
+        assert_eq!(local.seasons.len(), 2);
+        assert_eq!(local.seasons[0].description.as_deref(), Some("first"));
+        assert_eq!(
+            local.seasons[0].images.posters[0].url,
+            "https://example.com/local.jpg"
+        );
+        assert_eq!(local.seasons[1].season_number, 2);
+    }
 }
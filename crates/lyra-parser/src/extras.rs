@@ -0,0 +1,160 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ExtraType {
+    Trailer,
+    Featurette,
+    BehindTheScenes,
+    DeletedScene,
+    Interview,
+    Scene,
+    Short,
+    Clip,
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParsedExtra {
+    pub extra_type: ExtraType,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DetectedExtra {
+    pub extra: ParsedExtra,
+    // the path of the movie or series the extra belongs to, parsed in place of the extra's own
+    pub owner_path: String,
+}
+
+// folder names and file suffixes follow the conventions plex, jellyfin and kodi share
+lazy_static! {
+    static ref EXTRA_FOLDERS: Vec<(Regex, ExtraType)> = vec![
+        (Regex::new(r"^trailers?$").unwrap(), ExtraType::Trailer),
+        (
+            Regex::new(r"^featurettes?$").unwrap(),
+            ExtraType::Featurette
+        ),
+        (
+            Regex::new(r"^behind.the.scenes$").unwrap(),
+            ExtraType::BehindTheScenes
+        ),
+        (
+            Regex::new(r"^deleted.(and.extended.)?scenes$").unwrap(),
+            ExtraType::DeletedScene
+        ),
+        (Regex::new(r"^interviews?$").unwrap(), ExtraType::Interview),
+        (Regex::new(r"^scenes$").unwrap(), ExtraType::Scene),
+        (Regex::new(r"^shorts$").unwrap(), ExtraType::Short),
+        (Regex::new(r"^clips$").unwrap(), ExtraType::Clip),
+        (Regex::new(r"^(extras?|other)$").unwrap(), ExtraType::Other),
+    ];
+}
+
+const EXTRA_SUFFIXES: [(&str, ExtraType); 11] = [
+    ("-trailer", ExtraType::Trailer),
+    ("-featurette", ExtraType::Featurette),
+    ("-behindthescenes", ExtraType::BehindTheScenes),
+    ("-deletedscene", ExtraType::DeletedScene),
+    ("-deleted", ExtraType::DeletedScene),
+    ("-interview", ExtraType::Interview),
+    ("-scene", ExtraType::Scene),
+    ("-short", ExtraType::Short),
+    ("-clip", ExtraType::Clip),
+    ("-other", ExtraType::Other),
+    ("-extra", ExtraType::Other),
+];
+
+pub(crate) fn detect_extra(path: &str) -> Option<DetectedExtra> {
+    let parts = path.split('/').collect::<Vec<_>>();
+    let (file_name, dirs) = parts.split_last()?;
+    let (stem, extension) = match file_name.rfind('.') {
+        Some(index) => file_name.split_at(index),
+        None => (*file_name, ""),
+    };
+
+    // "Movie (2010)/Trailers/Teaser.mkv" belongs to "Movie (2010)"
+    for (index, dir) in dirs.iter().enumerate() {
+        let Some(extra_type) = extra_folder_type(dir) else {
+            continue;
+        };
+        if index == 0 {
+            // an extras folder at the top of the library has nothing to belong to
+            return None;
+        }
+
+        return Some(DetectedExtra {
+            extra: ParsedExtra {
+                extra_type,
+                title: stem.trim().to_owned(),
+            },
+            owner_path: dirs[..index].join("/"),
+        });
+    }
+
+    // "Movie (2010)/Movie (2010)-trailer.mkv" belongs to "Movie (2010)/Movie (2010).mkv"
+    let lower_stem = stem.to_lowercase();
+    let (suffix, extra_type) = EXTRA_SUFFIXES
+        .iter()
+        .find(|(suffix, _)| lower_stem.ends_with(suffix))?;
+    let owner_stem = stem[..stem.len() - suffix.len()].trim();
+    if owner_stem.is_empty() {
+        return None;
+    }
+
+    let owner_file = format!("{owner_stem}{extension}");
+    let owner_path = if dirs.is_empty() {
+        owner_file
+    } else {
+        format!("{}/{owner_file}", dirs.join("/"))
+    };
+    Some(DetectedExtra {
+        extra: ParsedExtra {
+            extra_type: *extra_type,
+            title: stem.trim().to_owned(),
+        },
+        owner_path,
+    })
+}
+
+// "Trailers/Movie (2010).mkv" sits in an extras folder but has nothing to belong to
+pub(crate) fn is_unattributable_extra(path: &str) -> bool {
+    let mut dirs = path.split('/').rev().skip(1);
+    dirs.any(|dir| extra_folder_type(dir).is_some()) && detect_extra(path).is_none()
+}
+
+fn extra_folder_type(dir: &str) -> Option<ExtraType> {
+    let dir = dir.to_lowercase();
+    EXTRA_FOLDERS
+        .iter()
+        .find(|(regex, _)| regex.is_match(&dir))
+        .map(|(_, extra_type)| *extra_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_extras_by_folder_and_suffix() {
+        let folder = detect_extra("Movie (2010)/Behind The Scenes/Making Of.mkv").unwrap();
+        assert_eq!(folder.owner_path, "Movie (2010)");
+        assert_eq!(folder.extra.extra_type, ExtraType::BehindTheScenes);
+        assert_eq!(folder.extra.title, "Making Of");
+
+        let suffix = detect_extra("Movie (2010)/Movie (2010)-trailer.mkv").unwrap();
+        assert_eq!(suffix.owner_path, "Movie (2010)/Movie (2010).mkv");
+        assert_eq!(suffix.extra.extra_type, ExtraType::Trailer);
+
+        let season = detect_extra("Show/Season 1/Extras/Bloopers.mkv").unwrap();
+        assert_eq!(season.owner_path, "Show/Season 1");
+        assert_eq!(season.extra.extra_type, ExtraType::Other);
+
+        assert_eq!(detect_extra("Trailers/Movie (2010).mkv"), None);
+        assert_eq!(
+            detect_extra("Trailer Park Boys/Season 1/Trailer Park Boys S01E01.mkv"),
+            None
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::extras::detect_extra;
 use crate::model::run_batched_inference;
 use crate::parser::edition::parse_edition;
 use crate::parser::ids::parse_ids;
@@ -6,6 +7,9 @@ use parser::season_episode::parse_season_episode;
 use parser::year::parse_year;
 use serde::{Deserialize, Serialize};
 
+pub use extras::{ExtraType, ParsedExtra};
+
+mod extras;
 mod infer_numbers;
 mod model;
 mod parser;
@@ -50,6 +54,9 @@ pub struct ParsedFile {
     pub tvdb_id: Option<u64>,
     pub anidb_id: Option<u64>,
     pub trakt_id: Option<u64>,
+    // set for trailers, featurettes and other bonus material. the rest of the fields then
+    // describe the movie or series the extra belongs to.
+    pub extra: Option<ParsedExtra>,
     // todo: for things like audio channels, 10bit, etc
     // pub tags: Vec<String>,
 }
@@ -58,6 +65,20 @@ pub async fn parse_files(file_paths: Vec<String>) -> Vec<ParsedFile> {
     tokio::task::spawn_blocking(move || {
         const BATCH_SIZE: usize = 100;
 
+        let detected_extras = file_paths
+            .iter()
+            .map(|path| detect_extra(path))
+            .collect::<Vec<_>>();
+        let file_paths = file_paths
+            .into_iter()
+            .zip(&detected_extras)
+            .map(|(path, extra)| match extra {
+                Some(extra) => extra.owner_path.clone(),
+                None => path,
+            })
+            .collect::<Vec<_>>();
+        let mut detected_extras = detected_extras.into_iter();
+
         let inferred_episode_numbers = infer_numbers::infer_additional_episode_numbers(&file_paths);
         let mut results = Vec::with_capacity(file_paths.len());
 
@@ -80,7 +101,13 @@ pub async fn parse_files(file_paths: Vec<String>) -> Vec<ParsedFile> {
                 let edition = parse_edition(&file_name, &mut c);
                 let (imdb_id, tmdb_id, tvdb_id, anidb_id, trakt_id) = parse_ids(&file_name, &mut c);
 
-                if let Some(extra_numbers) = inferred_episode_numbers.get(global_idx) {
+                let extra = detected_extras
+                    .next()
+                    .flatten()
+                    .map(|detected| detected.extra);
+                if extra.is_none()
+                    && let Some(extra_numbers) = inferred_episode_numbers.get(global_idx)
+                {
                     for &episode in extra_numbers {
                         if !episode_numbers.contains(&episode) {
                             episode_numbers.push(episode);
@@ -101,6 +128,7 @@ pub async fn parse_files(file_paths: Vec<String>) -> Vec<ParsedFile> {
                     tvdb_id,
                     anidb_id,
                     trakt_id,
+                    extra,
                 });
             }
         }
@@ -1,3 +1,4 @@
+use crate::extras::is_unattributable_extra;
 use lazy_static::lazy_static;
 use regex::Regex;
 
@@ -10,17 +11,9 @@ lazy_static! {
         Regex::new(r"^lore$").unwrap(),
         Regex::new(r"^histories(( and| &) lore)?$").unwrap(),
         Regex::new(r"sample").unwrap(),
-        Regex::new(r"^behind.the.scenes$").unwrap(),
-        Regex::new(r"^deleted.and.extended.scenes$").unwrap(),
-        Regex::new(r"^deleted.scenes$").unwrap(),
-        Regex::new(r"^extras?$").unwrap(),
-        Regex::new(r"^featurettes$").unwrap(),
-        Regex::new(r"^other$").unwrap(),
-        Regex::new(r"^interviews$").unwrap(),
-        Regex::new(r"^scenes$").unwrap(),
-        Regex::new(r"^shorts$").unwrap(),
+        // trailers, featurettes and other extras folders are picked up by `extras` instead, unless
+        // they have nothing to belong to
         Regex::new(r"^specials?$").unwrap(),
-        Regex::new(r"^trailers?$").unwrap(),
         Regex::new(r"^soundtracks?$").unwrap()
     ];
 }
@@ -39,6 +32,10 @@ pub fn should_ignore_path(input: &str) -> bool {
 }
 
 pub fn path_is_ignored(input: &str) -> bool {
+    if is_unattributable_extra(input) {
+        return true;
+    }
+
     for path_part in input.split('/') {
         if path_part.is_empty() {
             continue;
@@ -65,4 +62,11 @@ mod tests {
             "trailer park boys/season 1/episode 1.mk4"
         ));
     }
+
+    #[test]
+    fn ignores_extras_with_nothing_to_belong_to() {
+        assert!(should_ignore_path("Trailers/Movie (2010).mkv"));
+        assert!(!should_ignore_path("Movie (2010)/Trailers/Teaser.mkv"));
+        assert!(!should_ignore_path("Movie (2010)/Movie (2010)-trailer.mkv"));
+    }
 }
@@ -24,6 +24,7 @@ pub enum ActivityKind {
     NodeSyncMetadataRoot,
     FileExtractSubtitles,
     FileProcessSubtitle,
+    FileDetectCommercials,
 }
 
 impl ActivityKind {
@@ -40,6 +41,7 @@ impl ActivityKind {
             ActivityKind::NodeSyncMetadataRoot => "Metadata Sync",
             ActivityKind::FileExtractSubtitles => "Subtitle Extraction",
             ActivityKind::FileProcessSubtitle => "Subtitle Processing",
+            ActivityKind::FileDetectCommercials => "Commercial Detection",
         }
     }
 
@@ -56,6 +58,7 @@ impl ActivityKind {
             ActivityKind::NodeSyncMetadataRoot => "metadata_sync",
             ActivityKind::FileExtractSubtitles => "subtitle_extract",
             ActivityKind::FileProcessSubtitle => "subtitle_process",
+            ActivityKind::FileDetectCommercials => "commercial_detect",
         }
     }
 }
@@ -73,6 +76,7 @@ impl From<JobKind> for ActivityKind {
             JobKind::NodeSyncMetadataRoot => ActivityKind::NodeSyncMetadataRoot,
             JobKind::FileExtractSubtitles => ActivityKind::FileExtractSubtitles,
             JobKind::FileProcessSubtitle => ActivityKind::FileProcessSubtitle,
+            JobKind::FileDetectCommercials => ActivityKind::FileDetectCommercials,
         }
     }
 }
@@ -5,7 +5,7 @@ mod proxy;
 mod service;
 mod storage;
 
-use crate::signer::sign;
+use crate::signer::{TokenPurpose, sign};
 use sea_orm::DatabaseConnection;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
@@ -18,7 +18,7 @@ pub(crate) use service::{
     create_local_asset_from_bytes, create_local_file_asset_from_bytes, download_asset_to_local,
 };
 use std::time::Duration;
-pub(crate) use storage::get_asset_output_path_from_mime_and_encoding;
+pub(crate) use storage::{get_asset_output_path_from_mime_and_encoding, hash_bytes_sha256_hex};
 
 const ASSET_SIGNATURE_TTL: Duration = Duration::from_hours(24);
 
@@ -26,7 +26,8 @@ pub(crate) fn sign_asset_url(asset_id: &str) -> String {
     let payload = AssetPayload {
         asset_id: asset_id.to_string(),
     };
-    let token = sign(payload, ASSET_SIGNATURE_TTL).expect("failed to sign asset URL");
+    let token =
+        sign(TokenPurpose::Asset, payload, ASSET_SIGNATURE_TTL).expect("failed to sign asset URL");
     format!("/api/assets/{asset_id}/{token}")
 }
 
@@ -5,7 +5,7 @@ use crate::{
     entities::assets::{self as assets_entity, AssetType},
     error::AppError,
     jobs,
-    signer::verify,
+    signer::{TokenPurpose, verify},
 };
 use axum::{
     Router,
@@ -48,7 +48,7 @@ async fn get_asset(
     Path((_unchecked_asset_id, signature)): Path<(String, String)>,
     Query(params): Query<TranscodeParams>,
 ) -> Result<Response, AppError> {
-    let (_expires_in, payload) = verify::<AssetPayload>(&signature)?;
+    let (_expires_in, payload) = verify::<AssetPayload>(TokenPurpose::Asset, &signature)?;
     let mut asset = assets_entity::Entity::find_by_id(&payload.asset_id)
         .one(&state.pool)
         .await?
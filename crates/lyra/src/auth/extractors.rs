@@ -10,7 +10,7 @@ use crate::{
         user_sessions,
         users::{self, UserPerms},
     },
-    signer,
+    signer::{self, TokenPurpose},
 };
 use axum::{
     extract::{FromRef, FromRequestParts, OptionalFromRequestParts},
@@ -104,8 +104,9 @@ where
             return Err(AuthError::Unauthenticated);
         };
 
-        let (expires_in, payload) = signer::verify::<SessionTokenPayload>(session_token)
-            .map_err(|_| AuthError::Unauthenticated)?;
+        let (expires_in, payload) =
+            signer::verify::<SessionTokenPayload>(TokenPurpose::Session, session_token)
+                .map_err(|_| AuthError::Unauthenticated)?;
 
         let Some((session, Some(user))) = user_sessions::Entity::find()
             .filter(user_sessions::Column::Id.eq(payload.session_id))
@@ -175,7 +176,7 @@ where
             return Err(AuthError::Unauthenticated);
         };
 
-        signer::verify::<SessionTokenPayload>(session_token)
+        signer::verify::<SessionTokenPayload>(TokenPurpose::Session, session_token)
             .map_err(|_| AuthError::Unauthenticated)?;
 
         Ok(Self)
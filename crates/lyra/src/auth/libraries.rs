@@ -1,6 +1,6 @@
 use crate::{
     auth::{AuthError, RequestAuth, extractors::get_user_or_auth_error},
-    entities::{library_users, users::UserPerms},
+    entities::{library_users, users, users::UserPerms},
 };
 use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QuerySelect};
 
@@ -14,16 +14,34 @@ pub async fn accessible_library_ids(
     }
 
     let user = get_user_or_auth_error(auth)?;
-    let library_ids = library_users::Entity::find()
-        .filter(library_users::Column::UserId.eq(user.id.clone()))
+    explicit_library_ids(pool, &user.id).await.map(Some)
+}
+
+// same as accessible_library_ids, but for a user other than the one making the request.
+pub async fn accessible_library_ids_for_user(
+    pool: &DatabaseConnection,
+    user: &users::Model,
+) -> Result<Option<Vec<String>>, AuthError> {
+    let permissions = UserPerms::from_bits_truncate(user.permissions as u32);
+    if permissions.intersects(UserPerms::ADMIN | UserPerms::VIEW_ALL_LIBRARIES) {
+        return Ok(None);
+    }
+
+    explicit_library_ids(pool, &user.id).await.map(Some)
+}
+
+async fn explicit_library_ids(
+    pool: &DatabaseConnection,
+    user_id: &str,
+) -> Result<Vec<String>, AuthError> {
+    library_users::Entity::find()
+        .filter(library_users::Column::UserId.eq(user_id))
         .select_only()
         .column(library_users::Column::LibraryId)
         .into_tuple::<String>()
         .all(pool)
         .await
-        .map_err(|_| AuthError::InternalError)?;
-
-    Ok(Some(library_ids))
+        .map_err(|_| AuthError::InternalError)
 }
 
 pub async fn ensure_library_access(
@@ -1,7 +1,8 @@
 use crate::{
     auth::{AuthError, LAST_SEEN_UPDATE_INTERVAL_SECONDS, TOKEN_EXPIRY_DAYS},
     entities::user_sessions,
-    ids, signer,
+    ids,
+    signer::{self, TokenPurpose},
 };
 use chrono::Utc;
 use cookie::{Cookie, SameSite};
@@ -41,7 +42,8 @@ pub fn get_set_cookie_headers_for_session(
         session_id,
     };
 
-    let session_token = signer::sign(payload, expiry).map_err(|_| AuthError::InternalError)?;
+    let session_token = signer::sign(TokenPurpose::Session, payload, expiry)
+        .map_err(|_| AuthError::InternalError)?;
     let cookie = Cookie::build(("session", session_token))
         .path("/api")
         .http_only(true)
@@ -80,3 +82,54 @@ pub(super) struct SessionTokenPayload {
     pub(super) user_id: String,
     pub(super) session_id: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calendar::CalendarTokenPayload;
+    use ed25519_dalek::SigningKey;
+
+    #[test]
+    fn calendar_tokens_are_not_session_tokens() {
+        let signing_key = SigningKey::from_bytes(&[7; 32]);
+        let payload = || CalendarTokenPayload {
+            user_id: "user".to_owned(),
+            watchlist_only: true,
+            version: 0,
+        };
+        let expires_in = Duration::from_hours(1);
+
+        // the two payloads decode from the same bytes, only the purpose tells them apart
+        let untagged =
+            signer::sign_with_key(&signing_key, TokenPurpose::Session, payload(), expires_in)
+                .unwrap();
+        assert!(
+            signer::verify_with_key::<SessionTokenPayload>(
+                &signing_key,
+                TokenPurpose::Session,
+                &untagged
+            )
+            .is_ok()
+        );
+
+        let calendar =
+            signer::sign_with_key(&signing_key, TokenPurpose::Calendar, payload(), expires_in)
+                .unwrap();
+        assert!(
+            signer::verify_with_key::<SessionTokenPayload>(
+                &signing_key,
+                TokenPurpose::Session,
+                &calendar
+            )
+            .is_err()
+        );
+        assert!(
+            signer::verify_with_key::<CalendarTokenPayload>(
+                &signing_key,
+                TokenPurpose::Calendar,
+                &calendar
+            )
+            .is_ok()
+        );
+    }
+}
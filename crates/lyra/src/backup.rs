@@ -1,7 +1,10 @@
 use crate::config::get_config;
 use anyhow::Context;
 use chrono::{Local, TimeZone};
-use sqlx::{SqlitePool, migrate::Migrate};
+use sqlx::{
+    SqliteConnection, SqlitePool,
+    migrate::{Migrate, Migrator},
+};
 use std::borrow::Cow;
 use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
@@ -391,7 +394,38 @@ pub async fn run_migrations_with_backups(
         }
     }
 
-    migrator.run(pool).await?;
+    run_migrations(pool, &migrator).await
+}
+
+// table rebuilds drop tables other tables reference, which cascades into them while foreign keys
+// are on. sqlx runs every sqlite migration in a transaction, where `PRAGMA foreign_keys` is a
+// no-op, so keys are turned off on the connection before the run and checked once it's done.
+async fn run_migrations(pool: &SqlitePool, migrator: &Migrator) -> anyhow::Result<()> {
+    let mut connection = pool.acquire().await?;
+    sqlx::query("PRAGMA foreign_keys = OFF")
+        .execute(&mut *connection)
+        .await?;
+    let result = run_migrations_on(&mut connection, migrator).await;
+    // the connection goes back to the pool, so keys come back on even when a migration failed
+    sqlx::query("PRAGMA foreign_keys = ON")
+        .execute(&mut *connection)
+        .await?;
+    result
+}
+
+async fn run_migrations_on(
+    connection: &mut SqliteConnection,
+    migrator: &Migrator,
+) -> anyhow::Result<()> {
+    migrator.run(&mut *connection).await?;
+    let violations = sqlx::query("PRAGMA foreign_key_check")
+        .fetch_all(&mut *connection)
+        .await?;
+    anyhow::ensure!(
+        violations.is_empty(),
+        "migrations left {} foreign key violations",
+        violations.len()
+    );
     Ok(())
 }
 
@@ -417,3 +451,59 @@ pub enum BackupError {
     #[error("database error: {0}")]
     Sqlx(#[from] sqlx::Error),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+    use std::str::FromStr;
+
+    const EXTRA_NODES_VERSION: i64 = 20260912120000;
+
+    #[tokio::test]
+    async fn rebuilding_nodes_keeps_the_rows_that_reference_them() -> anyhow::Result<()> {
+        // one connection so every query sees the same in-memory database
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(SqliteConnectOptions::from_str("sqlite::memory:")?.foreign_keys(true))
+            .await?;
+        let mut migrator = sqlx::migrate!("../../migrations");
+        let before_rebuild = migrator
+            .iter()
+            .filter(|migration| migration.version < EXTRA_NODES_VERSION)
+            .cloned()
+            .collect::<Vec<_>>();
+        let all_migrations = std::mem::replace(&mut migrator.migrations, before_rebuild.into());
+        run_migrations(&pool, &migrator).await?;
+
+        sqlx::query(
+            "INSERT INTO libraries (id, path, name) VALUES ('lib', '/lib', 'Library');
+             INSERT INTO nodes (id, library_id, root_id, kind, name, \"order\", last_added_at)
+                 VALUES ('movie', 'lib', 'movie', 0, 'Movie', 0, 0);
+             INSERT INTO node_metadata (id, node_id, source, provider_id, name)
+                 VALUES ('metadata', 'movie', 0, 'local', 'Movie');",
+        )
+        .execute(&pool)
+        .await?;
+
+        migrator.migrations = all_migrations;
+        run_migrations(&pool, &migrator).await?;
+
+        let metadata_rows: i64 = sqlx::query_scalar("SELECT count(*) FROM node_metadata")
+            .fetch_one(&pool)
+            .await?;
+        assert_eq!(metadata_rows, 1);
+        sqlx::query(
+            "INSERT INTO nodes (id, library_id, root_id, parent_id, kind, extra_type, name, \"order\", last_added_at)
+                 VALUES ('trailer', 'lib', 'movie', 'movie', 4, 0, 'Trailer', 1, 0)",
+        )
+        .execute(&pool)
+        .await?;
+        let foreign_keys: i64 = sqlx::query_scalar("PRAGMA foreign_keys")
+            .fetch_one(&pool)
+            .await?;
+        assert_eq!(foreign_keys, 1);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,407 @@
+use crate::{
+    AppState,
+    auth::accessible_library_ids_for_user,
+    entities::{collection_items, node_missing_episodes, nodes, users},
+    graphql::types::missing_episode::missing_episodes_query,
+    signer::{TokenPurpose, sign, verify},
+};
+use axum::{
+    Router,
+    extract::{Path, State},
+    http::{HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use chrono::{DateTime, Days, Utc};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder,
+    sea_query::{Expr, Query},
+};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+// calendar apps subscribe once and keep polling for months, so feed urls outlive asset urls
+const CALENDAR_TOKEN_TTL: Duration = Duration::from_hours(365 * 24);
+// the feed keeps recently aired episodes that are still missing so they don't vanish on air day
+const CALENDAR_PAST_SECONDS: i64 = 7 * 24 * 60 * 60;
+const CALENDAR_FUTURE_SECONDS: i64 = 90 * 24 * 60 * 60;
+// rfc 5545 lines are folded at 75 octets
+const ICS_LINE_LIMIT: usize = 75;
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct CalendarTokenPayload {
+    pub(crate) user_id: String,
+    pub(crate) watchlist_only: bool,
+    pub(crate) version: i64,
+}
+
+pub fn sign_calendar_url(user: &users::Model, watchlist_only: bool) -> anyhow::Result<String> {
+    let token = sign(
+        TokenPurpose::Calendar,
+        CalendarTokenPayload {
+            user_id: user.id.clone(),
+            watchlist_only,
+            version: user.calendar_token_version,
+        },
+        CALENDAR_TOKEN_TTL,
+    )?;
+    Ok(format!("/api/calendar/{token}.ics"))
+}
+
+pub fn get_calendar_router() -> Router<AppState> {
+    Router::new().route("/{file_name}", get(get_calendar))
+}
+
+// announced episodes come from the provider's season listings, which only keep episodes that
+// aren't in the library yet. `library_ids` of None means every library.
+pub async fn upcoming_episodes(
+    pool: &DatabaseConnection,
+    library_ids: Option<&[String]>,
+    watchlist_user_id: Option<&str>,
+    from: i64,
+    to: i64,
+) -> Result<Vec<(node_missing_episodes::Model, nodes::Model)>, sea_orm::DbErr> {
+    let mut query = missing_episodes_query(None, true)
+        .filter(node_missing_episodes::Column::FirstAired.gte(from))
+        .filter(node_missing_episodes::Column::FirstAired.lt(to))
+        .find_also_related(nodes::Entity)
+        .filter(nodes::Column::UnavailableAt.is_null());
+    if let Some(library_ids) = library_ids {
+        query = query.filter(nodes::Column::LibraryId.is_in(library_ids.iter().cloned()));
+    }
+    // anything saved from a series, down to a single episode, follows the whole series
+    if let Some(user_id) = watchlist_user_id {
+        query = query.filter(
+            nodes::Column::Id.in_subquery(
+                Query::select()
+                    .column((nodes::Entity, nodes::Column::RootId))
+                    .from(collection_items::Entity)
+                    .inner_join(
+                        nodes::Entity,
+                        Expr::col((nodes::Entity, nodes::Column::Id))
+                            .equals((collection_items::Entity, collection_items::Column::NodeId)),
+                    )
+                    .and_where(
+                        Expr::col((
+                            collection_items::Entity,
+                            collection_items::Column::CollectionId,
+                        ))
+                        .eq(user_id),
+                    )
+                    .to_owned(),
+            ),
+        );
+    }
+
+    Ok(query
+        .order_by_asc(node_missing_episodes::Column::FirstAired)
+        .order_by_asc(nodes::Column::Name)
+        .order_by_asc(node_missing_episodes::Column::SeasonNumber)
+        .order_by_asc(node_missing_episodes::Column::EpisodeNumber)
+        .all(pool)
+        .await?
+        .into_iter()
+        .filter_map(|(episode, series)| Some((episode, series?)))
+        .collect())
+}
+
+// this route has no session auth because calendar apps can't log in, the signed token stands in
+// for the user and their library access is checked again on every fetch. links signed before the
+// user last regenerated theirs are treated as unknown.
+async fn get_calendar(
+    State(state): State<AppState>,
+    Path(file_name): Path<String>,
+) -> Result<Response, (StatusCode, &'static str)> {
+    let not_found = (StatusCode::NOT_FOUND, "calendar not found");
+    let token = file_name.strip_suffix(".ics").ok_or(not_found)?;
+    let (_expires_in, payload) =
+        verify::<CalendarTokenPayload>(TokenPurpose::Calendar, token).map_err(|_| not_found)?;
+
+    let internal_error = (StatusCode::INTERNAL_SERVER_ERROR, "calendar unavailable");
+    let user = users::Entity::find_by_id(payload.user_id)
+        .one(&state.pool)
+        .await
+        .map_err(|_| internal_error)?
+        .ok_or(not_found)?;
+    if user.calendar_token_version != payload.version {
+        return Err(not_found);
+    }
+    let library_ids = accessible_library_ids_for_user(&state.pool, &user)
+        .await
+        .map_err(|_| internal_error)?;
+
+    let now = Utc::now().timestamp();
+    let episodes = upcoming_episodes(
+        &state.pool,
+        library_ids.as_deref(),
+        payload.watchlist_only.then_some(user.id.as_str()),
+        now - CALENDAR_PAST_SECONDS,
+        now + CALENDAR_FUTURE_SECONDS,
+    )
+    .await
+    .map_err(|_| internal_error)?;
+
+    let mut response = render_calendar(&episodes, now).into_response();
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("text/calendar; charset=utf-8"),
+    );
+    Ok(response)
+}
+
+fn render_calendar(episodes: &[(node_missing_episodes::Model, nodes::Model)], now: i64) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//lyra//upcoming episodes//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "X-WR-CALNAME:Lyra".to_string(),
+    ];
+    let stamp = DateTime::from_timestamp(now, 0)
+        .unwrap_or_default()
+        .format("%Y%m%dT%H%M%SZ");
+
+    for (episode, series) in episodes {
+        let Some(aired) = episode
+            .first_aired
+            .and_then(|ts| DateTime::from_timestamp(ts, 0))
+        else {
+            continue;
+        };
+        let next_day = aired.checked_add_days(Days::new(1)).unwrap_or(aired);
+        let mut summary = format!(
+            "{} S{:02}E{:02}",
+            series.name, episode.season_number, episode.episode_number
+        );
+        if let Some(name) = &episode.name {
+            summary.push_str(" - ");
+            summary.push_str(name);
+        }
+
+        // air dates are day precision, so episodes become all-day events
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!(
+            "UID:{}-{}-{}@lyra",
+            episode.root_id, episode.season_number, episode.episode_number
+        ));
+        lines.push(format!("DTSTAMP:{stamp}"));
+        lines.push(format!("DTSTART;VALUE=DATE:{}", aired.format("%Y%m%d")));
+        lines.push(format!("DTEND;VALUE=DATE:{}", next_day.format("%Y%m%d")));
+        lines.push(format!("SUMMARY:{}", escape_text(&summary)));
+        if let Some(description) = &episode.description {
+            lines.push(format!("DESCRIPTION:{}", escape_text(description)));
+        }
+        lines.push("END:VEVENT".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut calendar = String::new();
+    for line in lines {
+        fold_line(&mut calendar, &line);
+    }
+    calendar
+}
+
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for char in text.chars() {
+        match char {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            char => escaped.push(char),
+        }
+    }
+    escaped
+}
+
+// continuation lines start with a space, which counts toward their own limit
+fn fold_line(output: &mut String, line: &str) {
+    let mut width = 0;
+    for char in line.chars() {
+        if width + char.len_utf8() > ICS_LINE_LIMIT {
+            output.push_str("\r\n ");
+            width = 1;
+        }
+        output.push(char);
+        width += char.len_utf8();
+    }
+    output.push_str("\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::{
+        collections::{self, CollectionResolverKind, CollectionVisibility},
+        libraries,
+    };
+    use sea_orm::{ActiveValue::Set, Database};
+
+    async fn setup_test_db() -> anyhow::Result<DatabaseConnection> {
+        let pool = Database::connect("sqlite::memory:").await?;
+        sqlx::migrate!("../../migrations")
+            .run(pool.get_sqlite_connection_pool())
+            .await?;
+
+        for library_id in ["lib", "other"] {
+            libraries::Entity::insert(libraries::ActiveModel {
+                id: Set(library_id.to_owned()),
+                path: Set(format!("/{library_id}")),
+                name: Set(library_id.to_owned()),
+                pinned: Set(false),
+                recordings: Set(false),
+                anime: Set(false),
+                last_scanned_at: Set(None),
+                unavailable_at: Set(None),
+                created_at: Set(0),
+            })
+            .exec(&pool)
+            .await?;
+        }
+        Ok(pool)
+    }
+
+    async fn insert_node(
+        pool: &DatabaseConnection,
+        id: &str,
+        library_id: &str,
+        root_id: &str,
+    ) -> anyhow::Result<()> {
+        let is_root = id == root_id;
+        nodes::Entity::insert(nodes::ActiveModel {
+            id: Set(id.to_owned()),
+            library_id: Set(library_id.to_owned()),
+            root_id: Set(root_id.to_owned()),
+            parent_id: Set((!is_root).then(|| root_id.to_owned())),
+            kind: Set(if is_root {
+                nodes::NodeKind::Series
+            } else {
+                nodes::NodeKind::Episode
+            }),
+            name: Set(id.to_owned()),
+            order: Set(i64::from(!is_root)),
+            season_number: Set((!is_root).then_some(1)),
+            episode_number: Set((!is_root).then_some(1)),
+            extra_type: Set(None),
+            last_added_at: Set(0),
+            last_fingerprint_version: Set(None),
+            unavailable_at: Set(None),
+            created_at: Set(0),
+            updated_at: Set(0),
+        })
+        .exec(pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn insert_announced_episode(
+        pool: &DatabaseConnection,
+        root_id: &str,
+        episode_number: i64,
+        first_aired: i64,
+    ) -> anyhow::Result<()> {
+        node_missing_episodes::Entity::insert(node_missing_episodes::ActiveModel {
+            root_id: Set(root_id.to_owned()),
+            season_number: Set(1),
+            episode_number: Set(episode_number),
+            name: Set(None),
+            description: Set(None),
+            first_aired: Set(Some(first_aired)),
+            created_at: Set(0),
+        })
+        .exec(pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn upcoming_root_ids(
+        pool: &DatabaseConnection,
+        library_ids: Option<&[String]>,
+        watchlist_user_id: Option<&str>,
+    ) -> anyhow::Result<Vec<String>> {
+        Ok(
+            upcoming_episodes(pool, library_ids, watchlist_user_id, 0, 1_000)
+                .await?
+                .into_iter()
+                .map(|(episode, _)| episode.root_id)
+                .collect(),
+        )
+    }
+
+    #[tokio::test]
+    async fn upcoming_episodes_respect_library_access_and_the_watchlist() -> anyhow::Result<()> {
+        let pool = setup_test_db().await?;
+        for (root_id, library_id, first_aired) in [
+            ("show-a", "lib", 100),
+            ("show-b", "lib", 200),
+            ("show-c", "other", 300),
+        ] {
+            insert_node(&pool, root_id, library_id, root_id).await?;
+            insert_announced_episode(&pool, root_id, 2, first_aired).await?;
+        }
+        // outside the requested range
+        insert_announced_episode(&pool, "show-a", 3, 5_000).await?;
+        // saving a single episode follows its whole series
+        insert_node(&pool, "show-b-e1", "lib", "show-b").await?;
+        collections::Entity::insert(collections::ActiveModel {
+            id: Set("user".to_owned()),
+            name: Set("Watchlist".to_owned()),
+            visibility: Set(CollectionVisibility::Private),
+            resolver_kind: Set(CollectionResolverKind::Manual),
+            ..Default::default()
+        })
+        .exec(&pool)
+        .await?;
+        collection_items::Entity::insert(collection_items::ActiveModel {
+            collection_id: Set("user".to_owned()),
+            node_id: Set("show-b-e1".to_owned()),
+            position: Set(0),
+            created_at: Set(0),
+            updated_at: Set(0),
+        })
+        .exec(&pool)
+        .await?;
+        let visible = ["lib".to_owned()];
+
+        assert_eq!(
+            upcoming_root_ids(&pool, Some(&visible), None).await?,
+            ["show-a", "show-b"]
+        );
+        assert_eq!(
+            upcoming_root_ids(&pool, None, None).await?,
+            ["show-a", "show-b", "show-c"]
+        );
+        assert_eq!(
+            upcoming_root_ids(&pool, Some(&visible), Some("user")).await?,
+            ["show-b"]
+        );
+        assert!(
+            upcoming_root_ids(&pool, Some(&visible), Some("someone-else"))
+                .await?
+                .is_empty()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn calendar_lines_are_escaped_and_folded() {
+        let mut output = String::new();
+        fold_line(
+            &mut output,
+            &format!("SUMMARY:{}", escape_text(&"a, b; c".repeat(10))),
+        );
+        let lines = output.split("\r\n").collect::<Vec<_>>();
+
+        assert!(lines[0].starts_with("SUMMARY:a\\, b\\; c"));
+        assert!(lines.iter().all(|line| line.len() <= ICS_LINE_LIMIT));
+        assert!(lines[1].starts_with(' '));
+        assert_eq!(
+            output.replace("\r\n ", ""),
+            format!("SUMMARY:{}\r\n", "a\\, b\\; c".repeat(10))
+        );
+    }
+}
@@ -16,6 +16,9 @@ pub async fn start_cleanup_worker(
 
     loop {
         prune_stale_unavailable(&pool).await?;
+        if let Err(error) = crate::metadata::sweep_response_cache().await {
+            tracing::warn!("failed to sweep the metadata response cache: {error:#}");
+        }
         sleep(CLEANUP_INTERVAL).await;
     }
 }
@@ -1,17 +1,24 @@
 use crate::entities::collections::{
     self, CollectionKind, CollectionResolverKind, CollectionVisibility,
 };
+use crate::entities::{collection_items, node_metadata, node_metadata_collections};
 use crate::graphql::query::{NodeFilter, OrderBy, OrderDirection};
 use crate::ids;
 use chrono::{Duration, Utc};
+use sea_orm::sea_query::OnConflict;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter,
-    Set,
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, EntityTrait,
+    IntoActiveModel, JoinType, QueryFilter, QuerySelect, RelationTrait, Set,
 };
+use std::collections::{BTreeMap, HashSet};
 
 const CONTINUE_WATCHING_CARD_NAME: &str = "continue-watching";
 const RECENTLY_RELEASED_CARD_NAME: &str = "recently-released";
 const RECENTLY_ADDED_CARD_NAME: &str = "recently-added";
+const FRANCHISE_MIN_MEMBERS: usize = 2;
+
+// the provider id and external id of a franchise
+pub type FranchiseKey = (String, String);
 
 fn continue_watching_id() -> String {
     ids::generate_prefixed_hashid("hs", [CONTINUE_WATCHING_CARD_NAME])
@@ -25,6 +32,10 @@ pub fn recently_added_id() -> String {
     ids::generate_prefixed_hashid("hs", [RECENTLY_ADDED_CARD_NAME])
 }
 
+fn franchise_id(provider_id: &str, external_id: &str) -> String {
+    ids::generate_prefixed_hashid("fr", [provider_id, external_id])
+}
+
 fn continue_watching_filter() -> NodeFilter {
     NodeFilter {
         order_by: Some(OrderBy::WatchProgressUpdatedAt),
@@ -80,6 +91,8 @@ fn synthetic_collection(
         home_position: 0,
         pinned: false,
         pinned_position: 0,
+        provider_id: None,
+        external_id: None,
         created_at: 0,
         updated_at: 0,
     }
@@ -173,3 +186,358 @@ pub async fn reconcile_system_collections(pool: &DatabaseConnection) -> anyhow::
 
     Ok(())
 }
+
+struct FranchiseMember {
+    node_id: String,
+    first_aired: Option<i64>,
+}
+
+struct Franchise {
+    name: String,
+    description: Option<String>,
+    members: Vec<FranchiseMember>,
+}
+
+// how many items a viewer has to be able to see before the collection is shown to them. a
+// franchise of one movie isn't a franchise, whichever libraries or missing files got it there.
+pub fn min_visible_items(collection: &collections::Model) -> i64 {
+    if collection.kind == Some(CollectionKind::Franchise.as_db()) {
+        FRANCHISE_MIN_MEMBERS as i64
+    } else {
+        1
+    }
+}
+
+// the franchises a root's synced metadata puts it in
+pub async fn root_franchise_keys(
+    pool: &impl ConnectionTrait,
+    root_id: &str,
+) -> anyhow::Result<Vec<FranchiseKey>> {
+    Ok(node_metadata_collections::Entity::find()
+        .join(
+            JoinType::InnerJoin,
+            node_metadata_collections::Relation::NodeMetadata.def(),
+        )
+        .filter(node_metadata::Column::NodeId.eq(root_id))
+        .select_only()
+        .column(node_metadata_collections::Column::ProviderId)
+        .column(node_metadata_collections::Column::ExternalId)
+        .distinct()
+        .into_tuple::<FranchiseKey>()
+        .all(pool)
+        .await?)
+}
+
+// franchise collections mirror the provider collections that movies in the library belong to,
+// so a franchise goes away as soon as it drops below two movies. this rebuilds all of them.
+pub async fn reconcile_franchise_collections(
+    pool: &impl ConnectionTrait,
+    now: i64,
+) -> anyhow::Result<()> {
+    reconcile_franchises(pool, None, now).await
+}
+
+// rebuilds only the given franchises, what a single root's sync can have changed
+pub async fn reconcile_franchise_collections_for(
+    pool: &impl ConnectionTrait,
+    keys: &[FranchiseKey],
+    now: i64,
+) -> anyhow::Result<()> {
+    if keys.is_empty() {
+        return Ok(());
+    }
+
+    reconcile_franchises(pool, Some(keys), now).await
+}
+
+async fn reconcile_franchises(
+    pool: &impl ConnectionTrait,
+    keys: Option<&[FranchiseKey]>,
+    now: i64,
+) -> anyhow::Result<()> {
+    let mut query =
+        node_metadata_collections::Entity::find().find_also_related(node_metadata::Entity);
+    if let Some(keys) = keys {
+        let mut condition = Condition::any();
+        for (provider_id, external_id) in keys {
+            condition = condition.add(
+                Condition::all()
+                    .add(node_metadata_collections::Column::ProviderId.eq(provider_id.clone()))
+                    .add(node_metadata_collections::Column::ExternalId.eq(external_id.clone())),
+            );
+        }
+        query = query.filter(condition);
+    }
+    let rows = query.all(pool).await?;
+
+    let mut franchises: BTreeMap<(String, String), Franchise> = BTreeMap::new();
+    for (collection, metadata) in rows {
+        let Some(metadata) = metadata else {
+            continue;
+        };
+        let franchise = franchises
+            .entry((collection.provider_id, collection.external_id))
+            .or_insert_with(|| Franchise {
+                name: collection.name,
+                description: collection.description,
+                members: Vec::new(),
+            });
+        if franchise
+            .members
+            .iter()
+            .any(|member| member.node_id == metadata.node_id)
+        {
+            continue;
+        }
+        franchise.members.push(FranchiseMember {
+            node_id: metadata.node_id,
+            first_aired: metadata.first_aired,
+        });
+    }
+
+    let mut kept_ids = HashSet::new();
+    for ((provider_id, external_id), mut franchise) in franchises {
+        if franchise.members.len() < FRANCHISE_MIN_MEMBERS {
+            continue;
+        }
+
+        let id = franchise_id(&provider_id, &external_id);
+        kept_ids.insert(id.clone());
+        collections::Entity::insert(collections::ActiveModel {
+            id: Set(id.clone()),
+            name: Set(franchise.name),
+            description: Set(franchise.description),
+            created_by_id: Set(None),
+            visibility: Set(CollectionVisibility::Public),
+            resolver_kind: Set(CollectionResolverKind::Manual),
+            kind: Set(Some(CollectionKind::Franchise.as_db())),
+            filter_json: Set(None),
+            provider_id: Set(Some(provider_id)),
+            external_id: Set(Some(external_id)),
+            created_at: Set(now),
+            updated_at: Set(now),
+            ..Default::default()
+        })
+        .on_conflict(
+            OnConflict::column(collections::Column::Id)
+                .update_columns([
+                    collections::Column::Name,
+                    collections::Column::Description,
+                    collections::Column::UpdatedAt,
+                ])
+                .to_owned(),
+        )
+        .exec(pool)
+        .await?;
+
+        // release order, movies without a date go last
+        franchise.members.sort_by(|a, b| {
+            a.first_aired
+                .is_none()
+                .cmp(&b.first_aired.is_none())
+                .then(a.first_aired.cmp(&b.first_aired))
+                .then_with(|| a.node_id.cmp(&b.node_id))
+        });
+        collection_items::Entity::delete_many()
+            .filter(collection_items::Column::CollectionId.eq(id.clone()))
+            .exec(pool)
+            .await?;
+        collection_items::Entity::insert_many(franchise.members.into_iter().enumerate().map(
+            |(position, member)| collection_items::ActiveModel {
+                collection_id: Set(id.clone()),
+                node_id: Set(member.node_id),
+                position: Set(position as i64),
+                created_at: Set(now),
+                updated_at: Set(now),
+            },
+        ))
+        .exec(pool)
+        .await?;
+    }
+
+    let mut stale = collections::Entity::delete_many()
+        .filter(collections::Column::Kind.eq(CollectionKind::Franchise.as_db()));
+    if let Some(keys) = keys {
+        stale = stale.filter(
+            collections::Column::Id.is_in(
+                keys.iter()
+                    .map(|(provider_id, external_id)| franchise_id(provider_id, external_id)),
+            ),
+        );
+    }
+    if !kept_ids.is_empty() {
+        stale = stale.filter(collections::Column::Id.is_not_in(kept_ids));
+    }
+    stale.exec(pool).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::{libraries, metadata_source::MetadataSource, nodes};
+    use sea_orm::{Database, QueryOrder};
+
+    async fn setup_test_db() -> anyhow::Result<DatabaseConnection> {
+        let pool = Database::connect("sqlite::memory:").await?;
+        sqlx::migrate!("../../migrations")
+            .run(pool.get_sqlite_connection_pool())
+            .await?;
+
+        libraries::Entity::insert(libraries::ActiveModel {
+            id: Set("lib".to_owned()),
+            path: Set("/library".to_owned()),
+            name: Set("Library".to_owned()),
+            pinned: Set(false),
+            recordings: Set(false),
+            anime: Set(false),
+            last_scanned_at: Set(None),
+            unavailable_at: Set(None),
+            created_at: Set(0),
+        })
+        .exec(&pool)
+        .await?;
+        Ok(pool)
+    }
+
+    async fn insert_movie(
+        pool: &DatabaseConnection,
+        id: &str,
+        first_aired: i64,
+        collection_id: Option<&str>,
+    ) -> anyhow::Result<()> {
+        nodes::Entity::insert(nodes::ActiveModel {
+            id: Set(id.to_owned()),
+            library_id: Set("lib".to_owned()),
+            root_id: Set(id.to_owned()),
+            parent_id: Set(None),
+            kind: Set(nodes::NodeKind::Movie),
+            name: Set(id.to_owned()),
+            order: Set(0),
+            season_number: Set(None),
+            episode_number: Set(None),
+            extra_type: Set(None),
+            last_added_at: Set(0),
+            last_fingerprint_version: Set(None),
+            unavailable_at: Set(None),
+            created_at: Set(0),
+            updated_at: Set(0),
+        })
+        .exec(pool)
+        .await?;
+        node_metadata::Entity::insert(node_metadata::ActiveModel {
+            id: Set(format!("remote-{id}")),
+            node_id: Set(id.to_owned()),
+            source: Set(MetadataSource::Remote),
+            provider_id: Set("tmdb".to_owned()),
+            name: Set(id.to_owned()),
+            first_aired: Set(Some(first_aired)),
+            created_at: Set(0),
+            updated_at: Set(0),
+            ..Default::default()
+        })
+        .exec(pool)
+        .await?;
+
+        if let Some(collection_id) = collection_id {
+            node_metadata_collections::Entity::insert(node_metadata_collections::ActiveModel {
+                node_metadata_id: Set(format!("remote-{id}")),
+                provider_id: Set("tmdb".to_owned()),
+                external_id: Set(collection_id.to_owned()),
+                name: Set("The Collection".to_owned()),
+                description: Set(None),
+                poster_asset_id: Set(None),
+                backdrop_asset_id: Set(None),
+                created_at: Set(0),
+            })
+            .exec(pool)
+            .await?;
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn franchise_collections_follow_owned_movies() -> anyhow::Result<()> {
+        let pool = setup_test_db().await?;
+        insert_movie(&pool, "return", 300, Some("119")).await?;
+        insert_movie(&pool, "fellowship", 100, Some("119")).await?;
+        insert_movie(&pool, "towers", 200, Some("119")).await?;
+        insert_movie(&pool, "lonely", 100, Some("120")).await?;
+        insert_movie(&pool, "standalone", 100, None).await?;
+
+        reconcile_franchise_collections(&pool, 1).await?;
+
+        let franchises = collections::Entity::find()
+            .filter(collections::Column::Kind.eq(CollectionKind::Franchise.as_db()))
+            .all(&pool)
+            .await?;
+        assert_eq!(franchises.len(), 1);
+        assert_eq!(franchises[0].external_id.as_deref(), Some("119"));
+        let items = collection_items::Entity::find()
+            .filter(collection_items::Column::CollectionId.eq(franchises[0].id.clone()))
+            .order_by_asc(collection_items::Column::Position)
+            .all(&pool)
+            .await?;
+        assert_eq!(
+            items
+                .iter()
+                .map(|item| item.node_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["fellowship", "towers", "return"]
+        );
+
+        nodes::Entity::delete_many()
+            .filter(nodes::Column::Id.is_in(["towers", "return"]))
+            .exec(&pool)
+            .await?;
+        reconcile_franchise_collections(&pool, 2).await?;
+
+        assert!(
+            collections::Entity::find()
+                .filter(collections::Column::Kind.eq(CollectionKind::Franchise.as_db()))
+                .all(&pool)
+                .await?
+                .is_empty()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn scoped_reconcile_only_rebuilds_the_given_franchises() -> anyhow::Result<()> {
+        let pool = setup_test_db().await?;
+        insert_movie(&pool, "fellowship", 100, Some("119")).await?;
+        insert_movie(&pool, "towers", 200, Some("119")).await?;
+        insert_movie(&pool, "alien", 100, Some("8091")).await?;
+        insert_movie(&pool, "aliens", 200, Some("8091")).await?;
+        reconcile_franchise_collections(&pool, 1).await?;
+
+        assert_eq!(
+            root_franchise_keys(&pool, "towers").await?,
+            vec![("tmdb".to_owned(), "119".to_owned())]
+        );
+
+        nodes::Entity::delete_many()
+            .filter(nodes::Column::Id.is_in(["towers", "aliens"]))
+            .exec(&pool)
+            .await?;
+        reconcile_franchise_collections_for(&pool, &[("tmdb".to_owned(), "119".to_owned())], 2)
+            .await?;
+
+        // only the synced franchise noticed its missing movie
+        let remaining = collections::Entity::find()
+            .filter(collections::Column::Kind.eq(CollectionKind::Franchise.as_db()))
+            .all(&pool)
+            .await?;
+        assert_eq!(
+            remaining
+                .iter()
+                .map(|collection| collection.external_id.as_deref())
+                .collect::<Vec<_>>(),
+            vec![Some("8091")]
+        );
+
+        Ok(())
+    }
+}
@@ -22,6 +22,20 @@ impl BackupConfig {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TmdbConfig {
+    pub api_key: Option<String>,
+    // v4 read access token, used instead of the api key when set
+    pub access_token: Option<String>,
+    pub api_base_url: String,
+    pub image_base_url: String,
+    pub language: String,
+    #[serde(default)]
+    pub fallback_languages: Vec<String>,
+    #[serde(default)]
+    pub image_languages: Vec<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Config {
     pub data_dir: PathBuf,
@@ -36,6 +50,9 @@ pub struct Config {
     pub watch_progress_minimum_threshold: f32,
     pub watch_progress_completed_threshold: f32,
     pub metadata_content_rating_country: String,
+    // serve metadata only from the response cache, for boxes without internet access
+    pub metadata_offline: bool,
+    pub tmdb: TmdbConfig,
 }
 
 impl Config {
@@ -67,6 +84,10 @@ impl Config {
         self.data_dir.join("tmp")
     }
 
+    pub fn get_metadata_cache_dir(&self) -> PathBuf {
+        self.data_dir.join("metadata_cache")
+    }
+
     pub fn get_signing_key_path(&self) -> PathBuf {
         self.data_dir.join(SIGNING_KEY_FILENAME)
     }
@@ -101,6 +122,11 @@ fn init() -> Result<(Config, SigningKey), Box<dyn std::error::Error>> {
         .set_default("watch_progress_minimum_threshold", 0.05)?
         .set_default("watch_progress_completed_threshold", 0.8)?
         .set_default("metadata_content_rating_country", "AU")?
+        .set_default("metadata_offline", false)?
+        .set_default("tmdb.api_key", "f81a38fe9eba82e5dc3695a7406068bd")?
+        .set_default("tmdb.api_base_url", "https://api.themoviedb.org/3")?
+        .set_default("tmdb.image_base_url", "https://image.tmdb.org/t/p")?
+        .set_default("tmdb.language", "en-US")?
         .build()
         .unwrap();
 
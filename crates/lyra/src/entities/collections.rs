@@ -21,6 +21,10 @@ pub struct Model {
     pub home_position: i64,
     pub pinned: bool,
     pub pinned_position: i64,
+    #[graphql(skip)]
+    pub provider_id: Option<String>,
+    #[graphql(skip)]
+    pub external_id: Option<String>,
     pub created_at: i64,
     pub updated_at: i64,
 }
@@ -107,6 +111,7 @@ pub enum CollectionKind {
     ContinueWatching = 0,
     RecentlyReleased = 1,
     RecentlyAdded = 2,
+    Franchise = 3,
 }
 
 impl CollectionKind {
@@ -115,6 +120,7 @@ impl CollectionKind {
             0 => Some(Self::ContinueWatching),
             1 => Some(Self::RecentlyReleased),
             2 => Some(Self::RecentlyAdded),
+            3 => Some(Self::Franchise),
             _ => None,
         }
     }
@@ -19,10 +19,17 @@ pub struct Model {
     #[graphql(skip)]
     pub audio_fingerprint: Option<Vec<u8>>,
     #[graphql(skip)]
+    pub audio_tail_fingerprint: Option<Vec<u8>>,
+    #[graphql(skip)]
+    pub audio_body_fingerprint: Option<Vec<u8>>,
+    #[graphql(skip)]
     pub segments_json: Option<Vec<u8>>,
     #[graphql(skip)]
+    pub segments_sidecar_hash: Option<String>,
+    #[graphql(skip)]
     pub keyframes_json: Option<Vec<u8>>,
     pub subtitles_extracted_at: Option<i64>,
+    pub commercials_scanned_at: Option<i64>,
     pub unavailable_at: Option<i64>,
     pub scanned_at: Option<i64>,
     pub discovered_at: i64,
@@ -46,6 +53,8 @@ pub enum Relation {
     WatchProgress,
     #[sea_orm(has_many = "super::file_subtitles::Entity")]
     FileSubtitles,
+    #[sea_orm(has_many = "super::intro_fingerprints::Entity")]
+    IntroFingerprints,
 }
 
 impl Related<super::file_probe::Entity> for Entity {
@@ -78,6 +87,12 @@ impl Related<super::file_subtitles::Entity> for Entity {
     }
 }
 
+impl Related<super::intro_fingerprints::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::IntroFingerprints.def()
+    }
+}
+
 impl Related<super::nodes::Entity> for Entity {
     fn to() -> RelationDef {
         super::node_files::Relation::Nodes.def()
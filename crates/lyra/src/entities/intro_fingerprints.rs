@@ -0,0 +1,52 @@
+use async_graphql::SimpleObject;
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, SimpleObject)]
+#[sea_orm(table_name = "intro_fingerprints")]
+#[graphql(name = "IntroFingerprint")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false, column_type = "Text")]
+    pub id: String,
+    pub root_id: String,
+    pub shared: bool,
+    pub label: Option<String>,
+    #[graphql(skip)]
+    pub fingerprint: Vec<u8>,
+    pub duration_ms: i64,
+    pub source_file_id: Option<String>,
+    pub created_at: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::nodes::Entity",
+        from = "Column::RootId",
+        to = "super::nodes::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Nodes,
+    #[sea_orm(
+        belongs_to = "super::files::Entity",
+        from = "Column::SourceFileId",
+        to = "super::files::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    Files,
+}
+
+impl Related<super::nodes::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Nodes.def()
+    }
+}
+
+impl Related<super::files::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Files.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
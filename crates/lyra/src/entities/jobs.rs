@@ -47,6 +47,8 @@ pub enum JobKind {
     FileProcessSubtitle,
     #[sea_orm(num_value = 10)]
     AssetCleanup,
+    #[sea_orm(num_value = 11)]
+    FileDetectCommercials,
 }
 
 impl JobKind {
@@ -62,6 +64,7 @@ impl JobKind {
             JobKind::FileExtractSubtitles => 8,
             JobKind::FileProcessSubtitle => 9,
             JobKind::AssetCleanup => 10,
+            JobKind::FileDetectCommercials => 11,
         }
     }
 }
@@ -11,6 +11,10 @@ pub struct Model {
     #[sea_orm(column_type = "Text", unique)]
     pub path: String,
     pub pinned: bool,
+    // recorded tv libraries get commercial detection
+    pub recordings: bool,
+    // anime libraries match through anidb and use absolute episode numbering
+    pub anime: bool,
     pub last_scanned_at: Option<i64>,
     pub unavailable_at: Option<i64>,
     pub created_at: i64,
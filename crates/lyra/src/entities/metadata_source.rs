@@ -9,4 +9,6 @@ use serde::{Deserialize, Serialize};
 pub enum MetadataSource {
     Local = 0,
     Remote = 1,
+    // hand edits, only the fields in `locked_fields` are read from these rows
+    User = 2,
 }
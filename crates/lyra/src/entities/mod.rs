@@ -5,21 +5,34 @@ pub mod file_assets;
 pub mod file_probe;
 pub mod file_subtitles;
 pub mod files;
+pub mod intro_fingerprints;
 pub mod jobs;
 pub mod libraries;
 pub mod library_users;
 pub mod metadata_source;
 pub mod node_closure;
 pub mod node_files;
+pub mod node_image_selections;
+pub mod node_local_images;
 pub mod node_metadata;
 pub mod node_metadata_cast;
+pub mod node_metadata_collections;
+pub mod node_metadata_companies;
 pub mod node_metadata_content_ratings;
+pub mod node_metadata_details;
 pub mod node_metadata_genres;
 pub mod node_metadata_images;
+pub mod node_metadata_keywords;
+pub mod node_metadata_localizations;
 pub mod node_metadata_recommendations;
+pub mod node_missing_episodes;
+pub mod node_ordering_positions;
 pub mod nodes;
 pub mod people;
+pub mod root_episode_orderings;
+pub mod root_matches;
 pub mod root_node_cast;
+pub mod user_ratings;
 pub mod user_sessions;
 pub mod users;
 pub mod watch_progress;
@@ -0,0 +1,47 @@
+use crate::entities::node_metadata_images::NodeMetadataImageKind;
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "node_image_selections")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false, column_type = "Text")]
+    pub node_id: String,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub kind: NodeMetadataImageKind,
+    pub asset_id: String,
+    pub created_at: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::assets::Entity",
+        from = "Column::AssetId",
+        to = "super::assets::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Assets,
+    #[sea_orm(
+        belongs_to = "super::nodes::Entity",
+        from = "Column::NodeId",
+        to = "super::nodes::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Nodes,
+}
+
+impl Related<super::assets::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Assets.def()
+    }
+}
+
+impl Related<super::nodes::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Nodes.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,4 +1,5 @@
 use crate::entities::metadata_source::MetadataSource;
+use bitflags::bitflags;
 use sea_orm::entity::prelude::*;
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
@@ -12,6 +13,7 @@ pub struct Model {
     #[sea_orm(column_type = "Text", nullable)]
     pub imdb_id: Option<String>,
     pub tmdb_id: Option<i64>,
+    pub anidb_id: Option<i64>,
     pub name: String,
     #[sea_orm(column_type = "Text", nullable)]
     pub description: Option<String>,
@@ -24,6 +26,7 @@ pub struct Model {
     #[sea_orm(column_type = "Text", nullable)]
     pub tagline: Option<String>,
     pub next_aired: Option<i64>,
+    pub locked_fields: i64,
     pub created_at: i64,
     pub updated_at: i64,
 }
@@ -32,12 +35,18 @@ pub struct Model {
 pub enum Relation {
     #[sea_orm(has_many = "super::node_metadata_cast::Entity")]
     NodeMetadataCast,
+    #[sea_orm(has_many = "super::node_metadata_companies::Entity")]
+    NodeMetadataCompanies,
     #[sea_orm(has_many = "super::node_metadata_content_ratings::Entity")]
     NodeMetadataContentRatings,
     #[sea_orm(has_many = "super::node_metadata_genres::Entity")]
     NodeMetadataGenres,
     #[sea_orm(has_many = "super::node_metadata_images::Entity")]
     NodeMetadataImages,
+    #[sea_orm(has_many = "super::node_metadata_keywords::Entity")]
+    NodeMetadataKeywords,
+    #[sea_orm(has_many = "super::node_metadata_localizations::Entity")]
+    NodeMetadataLocalizations,
     #[sea_orm(has_many = "super::node_metadata_recommendations::Entity")]
     NodeMetadataRecommendations,
     #[sea_orm(
@@ -58,6 +67,25 @@ impl Related<super::nodes::Entity> for Entity {
 
 impl ActiveModelBehavior for ActiveModel {}
 
+impl Model {
+    pub fn locked(&self) -> LockedFields {
+        LockedFields::from_bits_truncate(self.locked_fields)
+    }
+}
+
+bitflags! {
+    // fields a user row overrides. provider rows leave this empty.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub struct LockedFields: i64 {
+        const NAME = 1 << 0;
+        const DESCRIPTION = 1 << 1;
+        const TAGLINE = 1 << 2;
+        const FIRST_AIRED = 1 << 3;
+        const LAST_AIRED = 1 << 4;
+        const GENRES = 1 << 5;
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
 #[sea_orm(rs_type = "i64", db_type = "Integer")]
 pub enum MetadataStatus {
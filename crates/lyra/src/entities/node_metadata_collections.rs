@@ -0,0 +1,35 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "node_metadata_collections")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false, column_type = "Text")]
+    pub node_metadata_id: String,
+    pub provider_id: String,
+    pub external_id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub poster_asset_id: Option<String>,
+    pub backdrop_asset_id: Option<String>,
+    pub created_at: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::node_metadata::Entity",
+        from = "Column::NodeMetadataId",
+        to = "super::node_metadata::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    NodeMetadata,
+}
+
+impl Related<super::node_metadata::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::NodeMetadata.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -10,6 +10,7 @@ pub struct Model {
     pub rating: String,
     pub release_date: Option<i64>,
     pub release_type: Option<i64>,
+    pub descriptors: String,
     pub position: i64,
     pub created_at: i64,
 }
@@ -33,3 +34,10 @@ impl Related<super::node_metadata::Entity> for Entity {
 }
 
 impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    // stored as a json array, like "Violence" or "Strong Language"
+    pub fn descriptors(&self) -> Vec<String> {
+        serde_json::from_str(&self.descriptors).unwrap_or_default()
+    }
+}
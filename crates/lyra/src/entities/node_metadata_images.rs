@@ -1,3 +1,4 @@
+use crate::entities::assets::AssetKind;
 use sea_orm::entity::prelude::*;
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
@@ -55,7 +56,7 @@ impl Related<super::node_metadata::Entity> for Entity {
 
 impl ActiveModelBehavior for ActiveModel {}
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum)]
 #[sea_orm(rs_type = "i64", db_type = "Integer")]
 pub enum NodeMetadataImageKind {
     Poster = 0,
@@ -63,3 +64,14 @@ pub enum NodeMetadataImageKind {
     Backdrop = 2,
     Logo = 3,
 }
+
+impl From<NodeMetadataImageKind> for AssetKind {
+    fn from(value: NodeMetadataImageKind) -> Self {
+        match value {
+            NodeMetadataImageKind::Poster => Self::Poster,
+            NodeMetadataImageKind::Thumbnail => Self::Thumbnail,
+            NodeMetadataImageKind::Backdrop => Self::Backdrop,
+            NodeMetadataImageKind::Logo => Self::Logo,
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "node_metadata_localizations")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false, column_type = "Text")]
+    pub node_metadata_id: String,
+    #[sea_orm(primary_key, auto_increment = false, column_type = "Text")]
+    pub language: String,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub name: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub description: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub tagline: Option<String>,
+    pub created_at: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::node_metadata::Entity",
+        from = "Column::NodeMetadataId",
+        to = "super::node_metadata::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    NodeMetadata,
+}
+
+impl Related<super::node_metadata::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::NodeMetadata.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,39 @@
+use async_graphql::SimpleObject;
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, SimpleObject)]
+#[sea_orm(table_name = "node_missing_episodes")]
+#[graphql(name = "MissingEpisode", complex)]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false, column_type = "Text")]
+    pub root_id: String,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub season_number: i64,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub episode_number: i64,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub first_aired: Option<i64>,
+    #[graphql(skip)]
+    pub created_at: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::nodes::Entity",
+        from = "Column::RootId",
+        to = "super::nodes::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Nodes,
+}
+
+impl Related<super::nodes::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Nodes.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
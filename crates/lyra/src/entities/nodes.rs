@@ -17,6 +17,7 @@ pub struct Model {
     pub order: i64,
     pub season_number: Option<i64>,
     pub episode_number: Option<i64>,
+    pub extra_type: Option<ExtraType>,
     pub last_added_at: i64,
     pub last_fingerprint_version: Option<i64>,
     pub unavailable_at: Option<i64>,
@@ -60,6 +61,10 @@ pub enum Relation {
     WatchProgress,
     #[sea_orm(has_many = "super::root_node_cast::Entity")]
     RootNodeCast,
+    #[sea_orm(has_many = "super::user_ratings::Entity")]
+    UserRatings,
+    #[sea_orm(has_many = "super::intro_fingerprints::Entity")]
+    IntroFingerprints,
 }
 
 impl Related<super::libraries::Entity> for Entity {
@@ -98,6 +103,18 @@ impl Related<super::root_node_cast::Entity> for Entity {
     }
 }
 
+impl Related<super::intro_fingerprints::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::IntroFingerprints.def()
+    }
+}
+
+impl Related<super::user_ratings::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserRatings.def()
+    }
+}
+
 impl Related<super::files::Entity> for Entity {
     fn to() -> RelationDef {
         super::node_files::Relation::Files.def()
@@ -138,4 +155,49 @@ pub enum NodeKind {
     Series = 1,
     Season = 2,
     Episode = 3,
+    Extra = 4,
+}
+
+#[derive(
+    Debug,
+    Enum,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    EnumIter,
+    DeriveActiveEnum,
+)]
+#[sea_orm(rs_type = "i64", db_type = "Integer")]
+pub enum ExtraType {
+    Trailer = 0,
+    Featurette = 1,
+    BehindTheScenes = 2,
+    DeletedScene = 3,
+    Interview = 4,
+    Scene = 5,
+    Short = 6,
+    Clip = 7,
+    Other = 8,
+}
+
+impl From<lyra_parser::ExtraType> for ExtraType {
+    fn from(value: lyra_parser::ExtraType) -> Self {
+        match value {
+            lyra_parser::ExtraType::Trailer => Self::Trailer,
+            lyra_parser::ExtraType::Featurette => Self::Featurette,
+            lyra_parser::ExtraType::BehindTheScenes => Self::BehindTheScenes,
+            lyra_parser::ExtraType::DeletedScene => Self::DeletedScene,
+            lyra_parser::ExtraType::Interview => Self::Interview,
+            lyra_parser::ExtraType::Scene => Self::Scene,
+            lyra_parser::ExtraType::Short => Self::Short,
+            lyra_parser::ExtraType::Clip => Self::Clip,
+            lyra_parser::ExtraType::Other => Self::Other,
+        }
+    }
 }
@@ -11,6 +11,8 @@ pub struct Model {
     pub character_name: Option<String>,
     #[sea_orm(column_type = "Text", nullable)]
     pub department: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub job: Option<String>,
     pub position: i64,
     pub created_at: i64,
 }
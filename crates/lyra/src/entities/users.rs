@@ -22,6 +22,10 @@ pub struct Model {
     pub preferred_subtitle_languages: String,
     #[graphql(skip)]
     pub subtitle_variant_preference: SubtitleVariantPreference,
+    #[graphql(skip)]
+    pub preferred_metadata_languages: String,
+    #[graphql(skip)]
+    pub calendar_token_version: i64,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -34,6 +38,8 @@ pub enum Relation {
     UserSessions,
     #[sea_orm(has_many = "super::watch_progress::Entity")]
     WatchProgress,
+    #[sea_orm(has_many = "super::user_ratings::Entity")]
+    UserRatings,
 }
 
 impl Related<super::library_users::Entity> for Entity {
@@ -60,6 +66,12 @@ impl Related<super::watch_progress::Entity> for Entity {
     }
 }
 
+impl Related<super::user_ratings::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserRatings.def()
+    }
+}
+
 impl Related<super::libraries::Entity> for Entity {
     fn to() -> RelationDef {
         super::library_users::Relation::Libraries.def()
@@ -71,6 +83,13 @@ impl Related<super::libraries::Entity> for Entity {
 
 impl ActiveModelBehavior for ActiveModel {}
 
+impl Model {
+    // stored as a json array of bcp 47 tags, most preferred first
+    pub fn metadata_languages(&self) -> Vec<String> {
+        serde_json::from_str(&self.preferred_metadata_languages).unwrap_or_default()
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
 #[sea_orm(rs_type = "i64", db_type = "Integer")]
 pub enum SubtitleMode {
@@ -1,2 +1,4 @@
 pub mod node_counts;
 pub mod node_metadata;
+pub mod node_metadata_details;
+pub mod node_ratings;
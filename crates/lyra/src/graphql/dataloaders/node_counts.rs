@@ -71,7 +71,7 @@ impl Loader<String> for NodeCountsLoader {
             match row.kind {
                 nodes::NodeKind::Season => entry.season_count = row.count,
                 nodes::NodeKind::Episode => entry.episode_count = row.count,
-                nodes::NodeKind::Movie | nodes::NodeKind::Series => {}
+                nodes::NodeKind::Movie | nodes::NodeKind::Series | nodes::NodeKind::Extra => {}
             }
         }
 
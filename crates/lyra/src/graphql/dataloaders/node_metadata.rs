@@ -1,21 +1,68 @@
-use crate::entities::{node_metadata, nodes};
+use crate::entities::{
+    metadata_source::MetadataSource,
+    node_metadata::{self, LockedFields},
+    node_metadata_localizations, nodes,
+};
 use async_graphql::dataloader::Loader;
 use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder};
 use std::collections::HashMap;
 
 #[derive(Clone, Debug)]
 pub struct PreferredNodeMetadata {
+    // the provider row with locked user fields already swapped in
     pub metadata: Option<node_metadata::Model>,
+    pub localizations: Vec<node_metadata_localizations::Model>,
     pub node_name: String,
+    pub user_metadata_id: Option<String>,
+    pub locked: LockedFields,
 }
 
 impl PreferredNodeMetadata {
-    pub fn display_name(&self) -> &str {
-        self.metadata
+    pub fn display_name(&self, languages: &[String]) -> &str {
+        let name = self
+            .metadata
             .as_ref()
             .map(|metadata| metadata.name.as_str())
+            .filter(|name| !name.is_empty());
+        if self.locked.contains(LockedFields::NAME)
+            && let Some(name) = name
+        {
+            return name;
+        }
+
+        self.localized(languages, |row| row.name.as_deref())
+            .or(name)
             .unwrap_or(self.node_name.as_str())
     }
+
+    // walks the viewer's languages in order and returns the first one with the field set. an
+    // exact tag wins, otherwise any region of the same language will do, so "pt-BR" still gets
+    // portuguese text before falling through to the next language.
+    pub fn localized<'a>(
+        &'a self,
+        languages: &[String],
+        field: impl Fn(&'a node_metadata_localizations::Model) -> Option<&'a str>,
+    ) -> Option<&'a str> {
+        languages.iter().find_map(|language| {
+            let base = language.split('-').next().unwrap_or_default();
+            let exact = self
+                .localizations
+                .iter()
+                .filter(|row| row.language.eq_ignore_ascii_case(language))
+                .find_map(&field);
+            exact.or_else(|| {
+                self.localizations
+                    .iter()
+                    .filter(|row| {
+                        row.language
+                            .split('-')
+                            .next()
+                            .is_some_and(|code| code.eq_ignore_ascii_case(base))
+                    })
+                    .find_map(&field)
+            })
+        })
+    }
 }
 
 #[derive(Clone)]
@@ -54,21 +101,61 @@ impl Loader<String> for NodeMetadataLoader {
             .map_err(|error| error.to_string())?;
 
         let mut preferred_by_node_id = HashMap::new();
+        let mut user_by_node_id = HashMap::new();
         for metadata in metadata_rows {
+            if metadata.source == MetadataSource::User {
+                user_by_node_id.insert(metadata.node_id.clone(), metadata);
+                continue;
+            }
             preferred_by_node_id
                 .entry(metadata.node_id.clone())
                 .or_insert(metadata);
         }
 
+        let mut localizations_by_metadata_id = HashMap::<String, Vec<_>>::new();
+        if !preferred_by_node_id.is_empty() {
+            let localizations = node_metadata_localizations::Entity::find()
+                .filter(
+                    node_metadata_localizations::Column::NodeMetadataId.is_in(
+                        preferred_by_node_id
+                            .values()
+                            .map(|metadata| metadata.id.clone()),
+                    ),
+                )
+                .all(&self.pool)
+                .await
+                .map_err(|error| error.to_string())?;
+            for localization in localizations {
+                localizations_by_metadata_id
+                    .entry(localization.node_metadata_id.clone())
+                    .or_default()
+                    .push(localization);
+            }
+        }
+
         Ok(nodes
             .into_iter()
             .map(|node| {
                 let metadata = preferred_by_node_id.remove(&node.id);
+                let localizations = metadata
+                    .as_ref()
+                    .and_then(|metadata| localizations_by_metadata_id.remove(&metadata.id))
+                    .unwrap_or_default();
+                let user = user_by_node_id.remove(&node.id);
+                let locked = user.as_ref().map(node_metadata::Model::locked);
+                let user_metadata_id = user.as_ref().map(|user| user.id.clone());
+                let metadata = match (metadata, user) {
+                    (Some(metadata), Some(user)) => Some(apply_locked_fields(metadata, user)),
+                    (metadata, user) => metadata.or(user),
+                };
                 (
                     node.id.clone(),
                     PreferredNodeMetadata {
                         metadata,
+                        localizations,
                         node_name: node.name,
+                        user_metadata_id,
+                        locked: locked.unwrap_or_default(),
                     },
                 )
             })
@@ -76,10 +163,36 @@ impl Loader<String> for NodeMetadataLoader {
     }
 }
 
+fn apply_locked_fields(
+    mut metadata: node_metadata::Model,
+    user: node_metadata::Model,
+) -> node_metadata::Model {
+    let locked = user.locked();
+    if locked.contains(LockedFields::NAME) && !user.name.is_empty() {
+        metadata.name = user.name;
+    }
+    if locked.contains(LockedFields::DESCRIPTION) {
+        metadata.description = user.description;
+    }
+    if locked.contains(LockedFields::TAGLINE) {
+        metadata.tagline = user.tagline;
+    }
+    if locked.contains(LockedFields::FIRST_AIRED) {
+        metadata.first_aired = user.first_aired;
+    }
+    if locked.contains(LockedFields::LAST_AIRED) {
+        metadata.last_aired = user.last_aired;
+    }
+    metadata
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::entities::{libraries, metadata_source::MetadataSource, node_metadata, nodes};
+    use crate::entities::{
+        libraries, metadata_source::MetadataSource, node_metadata, node_metadata_localizations,
+        nodes,
+    };
     use sea_orm::{ActiveValue::Set, Database};
 
     async fn setup_test_db() -> anyhow::Result<DatabaseConnection> {
@@ -96,6 +209,8 @@ mod tests {
             path: Set("/library".to_owned()),
             name: Set("Library".to_owned()),
             pinned: Set(false),
+            recordings: Set(false),
+            anime: Set(false),
             last_scanned_at: Set(None),
             unavailable_at: Set(None),
             created_at: Set(0),
@@ -116,6 +231,7 @@ mod tests {
             order: Set(0),
             season_number: Set(None),
             episode_number: Set(None),
+            extra_type: Set(None),
             last_added_at: Set(0),
             last_fingerprint_version: Set(None),
             unavailable_at: Set(None),
@@ -170,4 +286,145 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn localized_text_follows_viewer_languages() -> anyhow::Result<()> {
+        let pool = setup_test_db().await?;
+        insert_library(&pool).await?;
+        insert_node(&pool, "movie").await?;
+
+        node_metadata::Entity::insert(node_metadata::ActiveModel {
+            id: Set("remote".to_owned()),
+            node_id: Set("movie".to_owned()),
+            source: Set(MetadataSource::Remote),
+            provider_id: Set("tmdb".to_owned()),
+            name: Set("Spirited Away".to_owned()),
+            created_at: Set(1),
+            updated_at: Set(1),
+            ..Default::default()
+        })
+        .exec(&pool)
+        .await?;
+        node_metadata_localizations::Entity::insert_many([
+            node_metadata_localizations::ActiveModel {
+                node_metadata_id: Set("remote".to_owned()),
+                language: Set("de-DE".to_owned()),
+                name: Set(Some("Chihiros Reise ins Zauberland".to_owned())),
+                description: Set(None),
+                tagline: Set(None),
+                created_at: Set(1),
+            },
+            node_metadata_localizations::ActiveModel {
+                node_metadata_id: Set("remote".to_owned()),
+                language: Set("ja-JP".to_owned()),
+                name: Set(Some("千と千尋の神隠し".to_owned())),
+                description: Set(Some("10歳の少女千尋".to_owned())),
+                tagline: Set(None),
+                created_at: Set(1),
+            },
+        ])
+        .exec(&pool)
+        .await?;
+
+        let loaded = NodeMetadataLoader::new(pool.clone())
+            .load(&["movie".to_owned()])
+            .await
+            .map_err(anyhow::Error::msg)?;
+        let metadata = &loaded["movie"];
+        let languages = ["de".to_owned(), "ja-JP".to_owned()];
+
+        assert_eq!(
+            metadata.display_name(&languages),
+            "Chihiros Reise ins Zauberland"
+        );
+        assert_eq!(
+            metadata.localized(&languages, |row| row.description.as_deref()),
+            Some("10歳の少女千尋")
+        );
+        assert_eq!(metadata.display_name(&[]), "Spirited Away");
+
+        let matches: Vec<String> = sqlx::query_scalar(
+            "SELECT node_id FROM node_localization_search_fts WHERE node_localization_search_fts MATCH 'zauberland'",
+        )
+        .fetch_all(pool.get_sqlite_connection_pool())
+        .await?;
+        assert_eq!(matches, vec!["movie".to_owned()]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn locked_fields_survive_provider_refresh() -> anyhow::Result<()> {
+        let pool = setup_test_db().await?;
+        insert_library(&pool).await?;
+        insert_node(&pool, "movie").await?;
+
+        node_metadata::Entity::insert(node_metadata::ActiveModel {
+            id: Set("remote".to_owned()),
+            node_id: Set("movie".to_owned()),
+            source: Set(MetadataSource::Remote),
+            provider_id: Set("tmdb".to_owned()),
+            name: Set("Remote".to_owned()),
+            description: Set(Some("Remote description".to_owned())),
+            created_at: Set(1),
+            updated_at: Set(1),
+            ..Default::default()
+        })
+        .exec(&pool)
+        .await?;
+        crate::metadata::apply_metadata_overrides(
+            &pool,
+            "movie",
+            crate::metadata::MetadataOverrides {
+                name: Some("Edited".to_owned()),
+                ..Default::default()
+            },
+            2,
+        )
+        .await?;
+        crate::metadata::lock_metadata_fields(&pool, "movie", LockedFields::DESCRIPTION, 2).await?;
+
+        // a refresh rewrites the provider row underneath the edits
+        node_metadata::Entity::update(node_metadata::ActiveModel {
+            id: Set("remote".to_owned()),
+            name: Set("Refreshed".to_owned()),
+            description: Set(Some("Refreshed description".to_owned())),
+            updated_at: Set(3),
+            ..Default::default()
+        })
+        .exec(&pool)
+        .await?;
+
+        let loader = NodeMetadataLoader::new(pool.clone());
+        let loaded = loader
+            .load(&["movie".to_owned()])
+            .await
+            .map_err(anyhow::Error::msg)?;
+        let metadata = &loaded["movie"];
+        assert_eq!(metadata.display_name(&[]), "Edited");
+        assert_eq!(
+            metadata
+                .metadata
+                .as_ref()
+                .and_then(|row| row.description.as_deref()),
+            Some("Remote description")
+        );
+
+        crate::metadata::unlock_metadata_fields(
+            &pool,
+            "movie",
+            LockedFields::NAME | LockedFields::DESCRIPTION,
+            4,
+        )
+        .await?;
+        let loaded = loader
+            .load(&["movie".to_owned()])
+            .await
+            .map_err(anyhow::Error::msg)?;
+        let metadata = &loaded["movie"];
+        assert_eq!(metadata.display_name(&[]), "Refreshed");
+        assert_eq!(metadata.user_metadata_id, None);
+
+        Ok(())
+    }
 }
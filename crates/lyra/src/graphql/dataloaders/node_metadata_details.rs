@@ -0,0 +1,80 @@
+use crate::entities::{node_metadata_companies, node_metadata_details, node_metadata_keywords};
+use async_graphql::dataloader::Loader;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder};
+use std::collections::HashMap;
+
+// everything a node page shows beyond the core metadata row, keyed by metadata id
+#[derive(Clone, Debug, Default)]
+pub struct NodeMetadataDetails {
+    pub details: Option<node_metadata_details::Model>,
+    pub companies: Vec<node_metadata_companies::Model>,
+    pub keywords: Vec<node_metadata_keywords::Model>,
+}
+
+#[derive(Clone)]
+pub struct NodeMetadataDetailsLoader {
+    pool: DatabaseConnection,
+}
+
+impl NodeMetadataDetailsLoader {
+    pub fn new(pool: DatabaseConnection) -> Self {
+        Self { pool }
+    }
+}
+
+impl Loader<String> for NodeMetadataDetailsLoader {
+    type Value = NodeMetadataDetails;
+    type Error = String;
+
+    async fn load(&self, keys: &[String]) -> Result<HashMap<String, Self::Value>, Self::Error> {
+        if keys.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let mut loaded = keys
+            .iter()
+            .cloned()
+            .map(|metadata_id| (metadata_id, NodeMetadataDetails::default()))
+            .collect::<HashMap<_, _>>();
+
+        let details = node_metadata_details::Entity::find()
+            .filter(node_metadata_details::Column::NodeMetadataId.is_in(keys.to_vec()))
+            .all(&self.pool)
+            .await
+            .map_err(|error| error.to_string())?;
+        for row in details {
+            let metadata_id = row.node_metadata_id.clone();
+            loaded.entry(metadata_id).or_default().details = Some(row);
+        }
+
+        let companies = node_metadata_companies::Entity::find()
+            .filter(node_metadata_companies::Column::NodeMetadataId.is_in(keys.to_vec()))
+            .order_by_asc(node_metadata_companies::Column::Position)
+            .all(&self.pool)
+            .await
+            .map_err(|error| error.to_string())?;
+        for row in companies {
+            loaded
+                .entry(row.node_metadata_id.clone())
+                .or_default()
+                .companies
+                .push(row);
+        }
+
+        let keywords = node_metadata_keywords::Entity::find()
+            .filter(node_metadata_keywords::Column::NodeMetadataId.is_in(keys.to_vec()))
+            .order_by_asc(node_metadata_keywords::Column::Position)
+            .all(&self.pool)
+            .await
+            .map_err(|error| error.to_string())?;
+        for row in keywords {
+            loaded
+                .entry(row.node_metadata_id.clone())
+                .or_default()
+                .keywords
+                .push(row);
+        }
+
+        Ok(loaded)
+    }
+}
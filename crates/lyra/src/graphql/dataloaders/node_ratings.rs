@@ -0,0 +1,197 @@
+use crate::entities::user_ratings;
+use async_graphql::dataloader::Loader;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QuerySelect,
+    sea_query::{Expr, Func},
+};
+use std::collections::{HashMap, HashSet};
+
+// the viewer is only needed for their own rating, the household average is the same for everyone
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NodeRatingKey {
+    pub node_id: String,
+    pub user_id: Option<String>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct NodeRatings {
+    pub user_rating: Option<user_ratings::Model>,
+    pub average_household_rating: Option<f64>,
+}
+
+#[derive(Clone)]
+pub struct NodeRatingLoader {
+    pool: DatabaseConnection,
+}
+
+impl NodeRatingLoader {
+    pub fn new(pool: DatabaseConnection) -> Self {
+        Self { pool }
+    }
+}
+
+impl Loader<NodeRatingKey> for NodeRatingLoader {
+    type Value = NodeRatings;
+    type Error = String;
+
+    async fn load(
+        &self,
+        keys: &[NodeRatingKey],
+    ) -> Result<HashMap<NodeRatingKey, Self::Value>, Self::Error> {
+        if keys.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let node_ids = keys
+            .iter()
+            .map(|key| key.node_id.clone())
+            .collect::<HashSet<_>>();
+        let user_ids = keys
+            .iter()
+            .filter_map(|key| key.user_id.clone())
+            .collect::<HashSet<_>>();
+
+        let averages = user_ratings::Entity::find()
+            .filter(user_ratings::Column::NodeId.is_in(node_ids.iter().cloned()))
+            .select_only()
+            .column(user_ratings::Column::NodeId)
+            .expr(Func::avg(Expr::col(user_ratings::Column::Rating)))
+            .group_by(user_ratings::Column::NodeId)
+            .into_tuple::<(String, Option<f64>)>()
+            .all(&self.pool)
+            .await
+            .map_err(|error| error.to_string())?
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+
+        let mut user_ratings_by_key = HashMap::new();
+        if !user_ids.is_empty() {
+            let rows = user_ratings::Entity::find()
+                .filter(user_ratings::Column::NodeId.is_in(node_ids))
+                .filter(user_ratings::Column::UserId.is_in(user_ids))
+                .all(&self.pool)
+                .await
+                .map_err(|error| error.to_string())?;
+            for row in rows {
+                user_ratings_by_key.insert((row.user_id.clone(), row.node_id.clone()), row);
+            }
+        }
+
+        Ok(keys
+            .iter()
+            .map(|key| {
+                let user_rating = key.user_id.as_ref().and_then(|user_id| {
+                    user_ratings_by_key
+                        .get(&(user_id.clone(), key.node_id.clone()))
+                        .cloned()
+                });
+                let ratings = NodeRatings {
+                    user_rating,
+                    average_household_rating: averages.get(&key.node_id).copied().flatten(),
+                };
+                (key.clone(), ratings)
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::{libraries, nodes, users};
+    use sea_orm::{ActiveValue::Set, Database};
+
+    async fn setup_test_db() -> anyhow::Result<DatabaseConnection> {
+        let pool = Database::connect("sqlite::memory:").await?;
+        sqlx::migrate!("../../migrations")
+            .run(pool.get_sqlite_connection_pool())
+            .await?;
+        Ok(pool)
+    }
+
+    #[tokio::test]
+    async fn ratings_load_per_viewer_with_a_shared_average() -> anyhow::Result<()> {
+        let pool = setup_test_db().await?;
+        libraries::Entity::insert(libraries::ActiveModel {
+            id: Set("lib".to_owned()),
+            path: Set("/library".to_owned()),
+            name: Set("Library".to_owned()),
+            pinned: Set(false),
+            recordings: Set(false),
+            anime: Set(false),
+            last_scanned_at: Set(None),
+            unavailable_at: Set(None),
+            created_at: Set(0),
+        })
+        .exec(&pool)
+        .await?;
+        for (order, id) in ["rated", "unrated"].into_iter().enumerate() {
+            nodes::Entity::insert(nodes::ActiveModel {
+                id: Set(id.to_owned()),
+                library_id: Set("lib".to_owned()),
+                root_id: Set(id.to_owned()),
+                parent_id: Set(None),
+                kind: Set(nodes::NodeKind::Movie),
+                name: Set(id.to_owned()),
+                order: Set(order as i64),
+                season_number: Set(None),
+                episode_number: Set(None),
+                extra_type: Set(None),
+                last_added_at: Set(0),
+                last_fingerprint_version: Set(None),
+                unavailable_at: Set(None),
+                created_at: Set(0),
+                updated_at: Set(0),
+            })
+            .exec(&pool)
+            .await?;
+        }
+        for (id, rating) in [("alice", 4), ("bob", 7)] {
+            users::Entity::insert(users::ActiveModel {
+                id: Set(id.to_owned()),
+                username: Set(id.to_owned()),
+                password_hash: Set(Some("hash".to_owned())),
+                permissions: Set(0),
+                ..Default::default()
+            })
+            .exec(&pool)
+            .await?;
+            user_ratings::Entity::insert(user_ratings::ActiveModel {
+                id: Set(format!("{id}-rating")),
+                user_id: Set(id.to_owned()),
+                node_id: Set("rated".to_owned()),
+                rating: Set(rating),
+                review: Set(None),
+                created_at: Set(0),
+                updated_at: Set(0),
+            })
+            .exec(&pool)
+            .await?;
+        }
+
+        let key = |node_id: &str, user_id: Option<&str>| NodeRatingKey {
+            node_id: node_id.to_owned(),
+            user_id: user_id.map(str::to_owned),
+        };
+        let loaded = NodeRatingLoader::new(pool)
+            .load(&[
+                key("rated", Some("alice")),
+                key("rated", None),
+                key("unrated", Some("alice")),
+            ])
+            .await
+            .map_err(anyhow::Error::msg)?;
+
+        let alice = &loaded[&key("rated", Some("alice"))];
+        assert_eq!(alice.user_rating.as_ref().map(|row| row.rating), Some(4));
+        assert_eq!(alice.average_household_rating, Some(5.5));
+        let anonymous = &loaded[&key("rated", None)];
+        assert!(anonymous.user_rating.is_none());
+        assert_eq!(anonymous.average_household_rating, Some(5.5));
+        let unrated = &loaded[&key("unrated", Some("alice"))];
+        assert!(unrated.user_rating.is_none());
+        assert_eq!(unrated.average_household_rating, None);
+
+        Ok(())
+    }
+}
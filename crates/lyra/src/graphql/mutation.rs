@@ -1,38 +1,60 @@
+use crate::assets::create_local_asset_from_bytes;
 use crate::auth::{
-    AuthenticatedGuard, PermissionGuard, accessible_library_ids, create_session_for_user,
-    ensure_library_access, find_pending_invite_user, get_set_cookie_headers_for_session,
+    AuthenticatedGuard, PermissionGuard, accessible_library_ids, accessible_library_ids_for_user,
+    create_session_for_user, ensure_library_access, find_pending_invite_user,
+    get_set_cookie_headers_for_session,
 };
+use crate::calendar;
 use crate::content_update::CONTENT_UPDATE;
 use crate::entities::collections::{CollectionResolverKind, CollectionVisibility};
+use crate::entities::node_metadata::LockedFields;
+use crate::entities::node_metadata_images::NodeMetadataImageKind;
 use crate::entities::users::SubtitleMode;
 use crate::entities::users::UserPerms;
 use crate::entities::{
-    collection_items, collections, files, libraries, library_users, node_files, nodes,
-    user_sessions, users, watch_progress,
+    collection_items, collections, files, intro_fingerprints, libraries, library_users,
+    node_closure, node_files, node_image_selections, node_local_images, node_metadata,
+    node_metadata_images, nodes, root_episode_orderings, root_matches, user_ratings, user_sessions,
+    users, watch_progress,
+};
+use crate::graphql::properties::{
+    FileSegmentKind, ImageKind, MetadataField, TrackDispositionPreference,
+};
+use crate::graphql::query::{
+    NodeFilter, build_node_query, collection_editable_by_user, collection_visible_to_user,
+    is_watchlist_collection,
 };
-use crate::graphql::properties::TrackDispositionPreference;
-use crate::graphql::query::{NodeFilter, collection_editable_by_user, is_watchlist_collection};
 use crate::graphql::types::file::parse_source_track_id;
 use crate::hls;
 use crate::ids::{self, new_invite_code};
-use crate::import::watch_state_import;
+use crate::import::{jellyfin_import, watch_state_import};
+use crate::metadata;
+use crate::segment_markers::{self, StoredFileSegment, sidecar};
 use crate::subtitles::language::SubtitleTrackVariant;
 use crate::{RequestAuth, UserAgent};
 use argon2::{
     Argon2,
     password_hash::{PasswordHasher, SaltString, rand_core::OsRng},
 };
-use async_graphql::{Context, InputObject, Object, SimpleObject};
+use async_graphql::{Context, Enum, InputObject, MaybeUndefined, Object, SimpleObject, Upload};
 use chrono::Utc;
 use reqwest::header::SET_COOKIE;
 use sea_orm::Set;
-use sea_orm::sea_query::OnConflict;
+use sea_orm::sea_query::{Expr, OnConflict, Query};
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel,
-    PaginatorTrait, QueryFilter, QuerySelect, TransactionTrait,
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, EntityTrait, IntoActiveModel,
+    JoinType, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, RelationTrait,
+    TransactionTrait,
 };
+use std::path::PathBuf;
+use tokio::io::AsyncReadExt;
 pub struct Mutation;
 
+const WATCH_STATE_WRITE_CHUNK_SIZE: usize = 500;
+const MAX_REVIEW_LENGTH: usize = 2000;
+// well past any real poster or backdrop, uploads are held in memory while they're decoded
+const MAX_IMAGE_UPLOAD_BYTES: u64 = 20 * 1024 * 1024;
+
 fn normalize_username(username: String) -> Result<String, async_graphql::Error> {
     let username = username.trim();
     if username.is_empty() {
@@ -116,6 +138,251 @@ async fn ensure_node_accessible(
         .ok_or_else(|| async_graphql::Error::new("Node not found"))
 }
 
+// requests for another user's watch state need EDIT_OTHERS_WATCH_STATE, otherwise the caller is the target.
+fn resolve_watch_state_user_id(
+    auth: &RequestAuth,
+    user_id: Option<String>,
+) -> Result<String, async_graphql::Error> {
+    if let Some(user_id) = user_id {
+        if !auth.has_permission(UserPerms::EDIT_OTHERS_WATCH_STATE) {
+            return Err(async_graphql::Error::new(
+                "Lacking permission to edit watch state for other users".to_string(),
+            ));
+        }
+
+        return Ok(user_id);
+    }
+
+    let user = auth
+        .get_user()
+        .ok_or_else(|| async_graphql::Error::new("No user in context".to_string()))?;
+    Ok(user.id.clone())
+}
+
+// libraries whose nodes a watch state edit may touch, which are the target user's rather than the
+// caller's when an admin edits someone else's watch state.
+async fn watch_state_library_ids(
+    pool: &DatabaseConnection,
+    auth: &RequestAuth,
+    user_id: &str,
+) -> Result<Option<Vec<String>>, async_graphql::Error> {
+    if auth.get_user().is_some_and(|user| user.id == user_id) {
+        return accessible_library_ids(pool, auth)
+            .await
+            .map_err(async_graphql::Error::from);
+    }
+
+    target_user_library_ids(pool, user_id).await
+}
+
+async fn target_user_library_ids(
+    pool: &DatabaseConnection,
+    user_id: &str,
+) -> Result<Option<Vec<String>>, async_graphql::Error> {
+    let user = users::Entity::find_by_id(user_id.to_owned())
+        .one(pool)
+        .await?
+        .ok_or_else(|| async_graphql::Error::new("User not found"))?;
+    accessible_library_ids_for_user(pool, &user)
+        .await
+        .map_err(async_graphql::Error::from)
+}
+
+// expands the given nodes to every available playable node beneath them (including themselves).
+async fn load_playable_descendant_ids(
+    pool: &DatabaseConnection,
+    visible_library_ids: Option<&[String]>,
+    ancestor_ids: Vec<String>,
+) -> Result<Vec<String>, async_graphql::Error> {
+    if ancestor_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut query = nodes::Entity::find()
+        .filter(
+            nodes::Column::Id.in_subquery(
+                Query::select()
+                    .column(node_closure::Column::DescendantId)
+                    .from(node_closure::Entity)
+                    .and_where(Expr::col(node_closure::Column::AncestorId).is_in(ancestor_ids))
+                    .to_owned(),
+            ),
+        )
+        .filter(nodes::Column::Kind.is_in([nodes::NodeKind::Movie, nodes::NodeKind::Episode]))
+        .filter(nodes::Column::UnavailableAt.is_null());
+    if let Some(visible_library_ids) = visible_library_ids {
+        query = query.filter(nodes::Column::LibraryId.is_in(visible_library_ids.to_vec()));
+    }
+
+    Ok(query
+        .select_only()
+        .column(nodes::Column::Id)
+        .distinct()
+        .order_by_asc(nodes::Column::Id)
+        .into_tuple::<String>()
+        .all(pool)
+        .await?)
+}
+
+// playable nodes in the same root that come before `node` in playback order.
+async fn load_watched_before_ids(
+    pool: &DatabaseConnection,
+    visible_library_ids: Option<&[String]>,
+    node: &nodes::Model,
+) -> Result<Vec<String>, async_graphql::Error> {
+    let node_ids =
+        load_playable_descendant_ids(pool, visible_library_ids, vec![node.root_id.clone()]).await?;
+    Ok(nodes::Entity::find()
+        .filter(nodes::Column::Id.is_in(node_ids))
+        .filter(
+            Condition::any()
+                .add(nodes::Column::Order.lt(node.order))
+                .add(
+                    Condition::all()
+                        .add(nodes::Column::Order.eq(node.order))
+                        .add(nodes::Column::Id.lt(node.id.clone())),
+                ),
+        )
+        .select_only()
+        .column(nodes::Column::Id)
+        .into_tuple::<String>()
+        .all(pool)
+        .await?)
+}
+
+// marks every node in one transaction so bulk updates are all-or-nothing.
+async fn set_nodes_watched(
+    pool: &DatabaseConnection,
+    user_id: &str,
+    node_ids: Vec<String>,
+    watched: bool,
+) -> Result<i32, async_graphql::Error> {
+    if node_ids.is_empty() {
+        return Ok(0);
+    }
+
+    let txn = pool.begin().await?;
+    let mut updated = 0_i32;
+    for chunk in node_ids.chunks(WATCH_STATE_WRITE_CHUNK_SIZE) {
+        if !watched {
+            let result = watch_progress::Entity::delete_many()
+                .filter(watch_progress::Column::UserId.eq(user_id.to_string()))
+                .filter(watch_progress::Column::NodeId.is_in(chunk.iter().cloned()))
+                .exec(&txn)
+                .await?;
+            updated += result.rows_affected as i32;
+            continue;
+        }
+
+        let links = node_files::Entity::find()
+            .join(JoinType::InnerJoin, node_files::Relation::Files.def())
+            .filter(node_files::Column::NodeId.is_in(chunk.iter().cloned()))
+            .filter(files::Column::UnavailableAt.is_null())
+            .order_by_asc(node_files::Column::Order)
+            .order_by_asc(node_files::Column::FileId)
+            .all(&txn)
+            .await?;
+        let mut file_id_by_node_id = std::collections::HashMap::new();
+        for link in links {
+            file_id_by_node_id
+                .entry(link.node_id)
+                .or_insert(link.file_id);
+        }
+        if file_id_by_node_id.is_empty() {
+            continue;
+        }
+
+        let now = Utc::now().timestamp();
+        updated += file_id_by_node_id.len() as i32;
+        watch_progress::Entity::insert_many(file_id_by_node_id.into_iter().map(
+            |(node_id, file_id)| watch_progress::ActiveModel {
+                id: Set(ids::generate_ulid()),
+                user_id: Set(user_id.to_string()),
+                node_id: Set(node_id),
+                file_id: Set(file_id),
+                progress_percent: Set(1.0),
+                created_at: Set(now),
+                updated_at: Set(now),
+            },
+        ))
+        .on_conflict(
+            OnConflict::columns([
+                watch_progress::Column::UserId,
+                watch_progress::Column::NodeId,
+            ])
+            .update_columns([
+                watch_progress::Column::FileId,
+                watch_progress::Column::ProgressPercent,
+                watch_progress::Column::UpdatedAt,
+            ])
+            .to_owned(),
+        )
+        .exec(&txn)
+        .await?;
+    }
+    txn.commit().await?;
+
+    Ok(updated)
+}
+
+// ratings run 1 to 10, reviews are trimmed and blank ones dropped.
+async fn save_node_rating(
+    pool: &DatabaseConnection,
+    user_id: &str,
+    node_id: &str,
+    rating: Option<i32>,
+    review: Option<String>,
+) -> Result<Option<user_ratings::Model>, async_graphql::Error> {
+    let Some(rating) = rating else {
+        user_ratings::Entity::delete_many()
+            .filter(user_ratings::Column::UserId.eq(user_id.to_string()))
+            .filter(user_ratings::Column::NodeId.eq(node_id.to_string()))
+            .exec(pool)
+            .await?;
+        return Ok(None);
+    };
+
+    if !(1..=10).contains(&rating) {
+        return Err(async_graphql::Error::new("Rating must be between 1 and 10"));
+    }
+
+    let review = review
+        .map(|review| review.trim().to_string())
+        .filter(|review| !review.is_empty());
+    if review
+        .as_ref()
+        .is_some_and(|review| review.chars().count() > MAX_REVIEW_LENGTH)
+    {
+        return Err(async_graphql::Error::new(format!(
+            "Review must be at most {MAX_REVIEW_LENGTH} characters"
+        )));
+    }
+
+    let now = Utc::now().timestamp();
+    let row = user_ratings::Entity::insert(user_ratings::ActiveModel {
+        id: Set(ids::generate_ulid()),
+        user_id: Set(user_id.to_string()),
+        node_id: Set(node_id.to_string()),
+        rating: Set(rating as i64),
+        review: Set(review),
+        created_at: Set(now),
+        updated_at: Set(now),
+    })
+    .on_conflict(
+        OnConflict::columns([user_ratings::Column::UserId, user_ratings::Column::NodeId])
+            .update_columns([
+                user_ratings::Column::Rating,
+                user_ratings::Column::Review,
+                user_ratings::Column::UpdatedAt,
+            ])
+            .to_owned(),
+    )
+    .exec_with_returning(pool)
+    .await?;
+
+    Ok(Some(row))
+}
+
 async fn ensure_watchlist_collection(
     pool: &DatabaseConnection,
     user_id: &str,
@@ -158,6 +425,159 @@ async fn ensure_watchlist_collection(
     .await?)
 }
 
+// edits read and write the segment list in one transaction, the same way the detection jobs
+// do, so segments a job stores in between aren't overwritten
+async fn load_file_segments_for_edit(
+    db: &impl sea_orm::ConnectionTrait,
+    file_id: &str,
+) -> Result<(files::Model, Vec<StoredFileSegment>), async_graphql::Error> {
+    let file = files::Entity::find_by_id(file_id.to_string())
+        .one(db)
+        .await?
+        .ok_or_else(|| async_graphql::Error::new("File not found"))?;
+    let segments = segment_markers::load_file_segments(&file)
+        .map_err(|error| async_graphql::Error::new(error.to_string()))?;
+
+    Ok((file, segments))
+}
+
+async fn save_file_segments(
+    db: &impl sea_orm::ConnectionTrait,
+    file_id: &str,
+    mut segments: Vec<StoredFileSegment>,
+) -> Result<files::Model, async_graphql::Error> {
+    segments.sort_by_key(|segment| (segment.start_ms, segment.end_ms));
+    segment_markers::store_file_segments(db, file_id, &segments)
+        .await
+        .map_err(|error| async_graphql::Error::new(error.to_string()))?;
+
+    files::Entity::find_by_id(file_id.to_string())
+        .one(db)
+        .await?
+        .ok_or_else(|| async_graphql::Error::new("File not found"))
+}
+
+fn segment_from_input(input: FileSegmentInput) -> Result<StoredFileSegment, async_graphql::Error> {
+    if input.start_ms < 0 || input.end_ms <= input.start_ms {
+        return Err(async_graphql::Error::new(
+            "Segment end must be after its start",
+        ));
+    }
+
+    Ok(StoredFileSegment {
+        kind: input.kind.into(),
+        start_ms: input.start_ms,
+        end_ms: input.end_ms,
+        locked: input.locked.unwrap_or(true),
+    })
+}
+
+// detection jobs rewrite and re-sort the list in the background, so segments are addressed by
+// their kind and start instead of their position in it
+fn segment_position(
+    segments: &[StoredFileSegment],
+    kind: FileSegmentKind,
+    start_ms: i64,
+) -> Result<usize, async_graphql::Error> {
+    let kind = kind.into();
+    segments
+        .iter()
+        .position(|segment| segment.kind == kind && segment.start_ms == start_ms)
+        .ok_or_else(|| async_graphql::Error::new("Segment not found"))
+}
+
+fn ensure_segment_key_free(
+    segments: &[StoredFileSegment],
+    segment: &StoredFileSegment,
+) -> Result<(), async_graphql::Error> {
+    if segments
+        .iter()
+        .any(|other| other.kind == segment.kind && other.start_ms == segment.start_ms)
+    {
+        return Err(async_graphql::Error::new(
+            "A segment of this kind already starts there",
+        ));
+    }
+    Ok(())
+}
+
+async fn load_file_media_path(
+    pool: &DatabaseConnection,
+    file: &files::Model,
+) -> Result<PathBuf, async_graphql::Error> {
+    let library = libraries::Entity::find_by_id(file.library_id.clone())
+        .one(pool)
+        .await?
+        .ok_or_else(|| async_graphql::Error::new("Library not found"))?;
+
+    Ok(PathBuf::from(library.path).join(&file.relative_path))
+}
+
+async fn load_matchable_root(
+    pool: &DatabaseConnection,
+    node_id: &str,
+) -> Result<nodes::Model, async_graphql::Error> {
+    let root = nodes::Entity::find_by_id(node_id.to_string())
+        .one(pool)
+        .await?
+        .ok_or_else(|| async_graphql::Error::new("Node not found"))?;
+    if root.parent_id.is_some() {
+        return Err(async_graphql::Error::new("Only root nodes can be matched"));
+    }
+    if !matches!(root.kind, nodes::NodeKind::Movie | nodes::NodeKind::Series) {
+        return Err(async_graphql::Error::new(
+            "Only movies and series can be matched",
+        ));
+    }
+
+    Ok(root)
+}
+
+// only images already offered for the node can be picked, including the current pick
+async fn is_node_image_candidate(
+    pool: &DatabaseConnection,
+    node_id: &str,
+    kind: NodeMetadataImageKind,
+    asset_id: &str,
+) -> Result<bool, async_graphql::Error> {
+    let provider_image = node_metadata_images::Entity::find()
+        .join(
+            JoinType::InnerJoin,
+            node_metadata_images::Relation::NodeMetadata.def(),
+        )
+        .filter(node_metadata::Column::NodeId.eq(node_id.to_string()))
+        .filter(node_metadata_images::Column::Kind.eq(kind))
+        .filter(node_metadata_images::Column::AssetId.eq(asset_id.to_string()))
+        .one(pool)
+        .await?;
+    if provider_image.is_some() {
+        return Ok(true);
+    }
+
+    let key = (node_id.to_string(), kind);
+    let local = node_local_images::Entity::find_by_id(key.clone())
+        .one(pool)
+        .await?;
+    let selection = node_image_selections::Entity::find_by_id(key)
+        .one(pool)
+        .await?;
+    Ok(local.is_some_and(|local| local.asset_id == asset_id)
+        || selection.is_some_and(|selection| selection.asset_id == asset_id))
+}
+
+// bumping updated_at marks the remote metadata stale so the sync job picks the root up again
+async fn resync_root_metadata(
+    db: &impl sea_orm::ConnectionTrait,
+    root: nodes::Model,
+) -> Result<nodes::Model, async_graphql::Error> {
+    metadata::mark_root_dirty(db, &root.id)
+        .await
+        .map_err(|error| async_graphql::Error::new(error.to_string()))?;
+    let mut root = root.into_active_model();
+    root.updated_at = Set(Utc::now().timestamp());
+    Ok(root.update(db).await?)
+}
+
 // keep user updates atomic so permission flips and explicit library assignments can't drift apart.
 async fn sync_user_library_access<C>(
     db: &C,
@@ -295,6 +715,104 @@ pub struct ImportWatchStatesResult {
     pub unmatched: Vec<ImportWatchStateUnmatched>,
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Enum)]
+pub enum ExternalWatchStateSource {
+    Jellyfin,
+    Emby,
+}
+
+#[derive(Debug, Clone, InputObject)]
+pub struct ImportExternalWatchStatesInput {
+    pub source: ExternalWatchStateSource,
+    /// Server-side path to a Jellyfin `jellyfin.db`/`library.db` or an Emby items export.
+    pub path: String,
+    /// Jellyfin `jellyfin.db` holding the users table, for servers still on `library.db`.
+    pub users_database_path: Option<String>,
+    pub dry_run: bool,
+    pub overwrite_conflicts: bool,
+    pub user_mappings: Vec<ExternalWatchStateUserMappingInput>,
+}
+
+#[derive(Debug, Clone, InputObject)]
+pub struct ExternalWatchStateUserMappingInput {
+    pub source_username: String,
+    pub user_id: String,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub struct ImportExternalWatchStatesUserResult {
+    pub source_username: String,
+    pub user_id: Option<String>,
+    pub result: Option<ImportWatchStatesResult>,
+}
+
+#[derive(Debug, Clone, InputObject)]
+pub struct FileSegmentInput {
+    pub kind: FileSegmentKind,
+    pub start_ms: i64,
+    pub end_ms: i64,
+    /// Locked segments are kept when detection reruns. Defaults to true.
+    pub locked: Option<bool>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Enum)]
+pub enum SegmentSidecarFormat {
+    /// Kodi/MPlayer edit decision list (`.edl`).
+    Edl,
+    /// Matroska chapters XML (`.chapters.xml`).
+    ChaptersXml,
+}
+
+// omitted fields are left alone, nullable fields can be set to null to override them as empty
+#[derive(Debug, Clone, InputObject)]
+pub struct NodeMetadataOverrideInput {
+    pub name: Option<String>,
+    pub description: MaybeUndefined<String>,
+    pub tagline: MaybeUndefined<String>,
+    pub first_aired: MaybeUndefined<i64>,
+    pub last_aired: MaybeUndefined<i64>,
+    pub genres: Option<Vec<String>>,
+}
+
+impl NodeMetadataOverrideInput {
+    fn into_overrides(self) -> Result<metadata::MetadataOverrides, async_graphql::Error> {
+        let name = match self.name {
+            Some(name) => {
+                let name = name.trim().to_string();
+                if name.is_empty() {
+                    return Err(async_graphql::Error::new("Name cannot be empty"));
+                }
+                Some(name)
+            }
+            None => None,
+        };
+        let genres = self.genres.map(|genres| {
+            genres
+                .into_iter()
+                .map(|genre| genre.trim().to_string())
+                .filter(|genre| !genre.is_empty())
+                .collect()
+        });
+
+        Ok(metadata::MetadataOverrides {
+            name,
+            description: nullable_override(self.description),
+            tagline: nullable_override(self.tagline),
+            first_aired: nullable_override(self.first_aired),
+            last_aired: nullable_override(self.last_aired),
+            genres,
+        })
+    }
+}
+
+fn nullable_override<T>(value: MaybeUndefined<T>) -> Option<Option<T>> {
+    match value {
+        MaybeUndefined::Undefined => None,
+        MaybeUndefined::Null => Some(None),
+        MaybeUndefined::Value(value) => Some(Some(value)),
+    }
+}
+
 #[derive(Debug, Clone, InputObject)]
 pub struct DisabledSubtitlesHintInput {
     pub file_id: String,
@@ -641,21 +1159,7 @@ impl Mutation {
         let pool = ctx.data::<DatabaseConnection>()?;
         let auth = ctx.data::<RequestAuth>()?;
 
-        let user_id = if let Some(user_id) = user_id {
-            if !auth.has_permission(UserPerms::EDIT_OTHERS_WATCH_STATE) {
-                return Err(async_graphql::Error::new(
-                    "Lacking permission to edit watch state for other users".to_string(),
-                ));
-            }
-
-            user_id
-        } else {
-            let user = auth
-                .get_user()
-                .ok_or_else(|| async_graphql::Error::new("No user in context".to_string()))?;
-
-            user.id.clone()
-        };
+        let user_id = resolve_watch_state_user_id(auth, user_id)?;
 
         let file = files::Entity::find_by_id(file_id)
             .filter(files::Column::UnavailableAt.is_null())
@@ -722,6 +1226,127 @@ impl Mutation {
         Ok(updated_rows)
     }
 
+    // covers every playable node under a series, season, movie or episode.
+    #[graphql(guard = AuthenticatedGuard::new())]
+    pub async fn mark_node_watched(
+        &self,
+        ctx: &Context<'_>,
+        node_id: String,
+        watched: bool,
+        user_id: Option<String>,
+    ) -> Result<i32, async_graphql::Error> {
+        let pool = ctx.data::<DatabaseConnection>()?;
+        let auth = ctx.data::<RequestAuth>()?;
+        let user_id = resolve_watch_state_user_id(auth, user_id)?;
+        let node = ensure_node_accessible(pool, auth, &node_id).await?;
+
+        let visible_library_ids = watch_state_library_ids(pool, auth, &user_id).await?;
+        let node_ids =
+            load_playable_descendant_ids(pool, visible_library_ids.as_deref(), vec![node.id])
+                .await?;
+        let updated = set_nodes_watched(pool, &user_id, node_ids, watched).await?;
+
+        CONTENT_UPDATE.emit();
+        Ok(updated)
+    }
+
+    // "mark everything before this episode", ordered the same way as next/previous playable.
+    #[graphql(guard = AuthenticatedGuard::new())]
+    pub async fn mark_watched_before(
+        &self,
+        ctx: &Context<'_>,
+        node_id: String,
+        user_id: Option<String>,
+    ) -> Result<i32, async_graphql::Error> {
+        let pool = ctx.data::<DatabaseConnection>()?;
+        let auth = ctx.data::<RequestAuth>()?;
+        let user_id = resolve_watch_state_user_id(auth, user_id)?;
+        let node = ensure_node_accessible(pool, auth, &node_id).await?;
+
+        let visible_library_ids = watch_state_library_ids(pool, auth, &user_id).await?;
+        let node_ids = load_watched_before_ids(pool, visible_library_ids.as_deref(), &node).await?;
+        let updated = set_nodes_watched(pool, &user_id, node_ids, true).await?;
+
+        CONTENT_UPDATE.emit();
+        Ok(updated)
+    }
+
+    // collection items can be whole series, so expand them through node_closure as well.
+    #[graphql(guard = AuthenticatedGuard::new())]
+    pub async fn mark_collection_watched(
+        &self,
+        ctx: &Context<'_>,
+        collection_id: String,
+        watched: bool,
+        user_id: Option<String>,
+    ) -> Result<i32, async_graphql::Error> {
+        let pool = ctx.data::<DatabaseConnection>()?;
+        let auth = ctx.data::<RequestAuth>()?;
+        let viewer_id = auth.get_user_or_err()?.id.clone();
+        let user_id = resolve_watch_state_user_id(auth, user_id)?;
+
+        let collection = collections::Entity::find_by_id(collection_id)
+            .one(pool)
+            .await?
+            .filter(|collection| collection_visible_to_user(collection, &viewer_id))
+            .ok_or_else(|| async_graphql::Error::new("Collection not found"))?;
+
+        let item_ids = match collection.resolver_kind {
+            CollectionResolverKind::Manual => {
+                collection_items::Entity::find()
+                    .filter(collection_items::Column::CollectionId.eq(collection.id.clone()))
+                    .select_only()
+                    .column(collection_items::Column::NodeId)
+                    .into_tuple::<String>()
+                    .all(pool)
+                    .await?
+            }
+            CollectionResolverKind::Filter => {
+                let filter: NodeFilter = collection
+                    .filter_json
+                    .as_deref()
+                    .map(serde_json::from_slice)
+                    .transpose()?
+                    .unwrap_or_default();
+                build_node_query(pool, auth, &filter)
+                    .await?
+                    .select_only()
+                    .column(nodes::Column::Id)
+                    .into_tuple::<String>()
+                    .all(pool)
+                    .await?
+            }
+        };
+
+        let visible_library_ids = watch_state_library_ids(pool, auth, &user_id).await?;
+        let node_ids =
+            load_playable_descendant_ids(pool, visible_library_ids.as_deref(), item_ids).await?;
+        let updated = set_nodes_watched(pool, &user_id, node_ids, watched).await?;
+
+        CONTENT_UPDATE.emit();
+        Ok(updated)
+    }
+
+    // passing no rating clears both the rating and the review.
+    #[graphql(guard = AuthenticatedGuard::new())]
+    pub async fn set_node_rating(
+        &self,
+        ctx: &Context<'_>,
+        node_id: String,
+        rating: Option<i32>,
+        review: Option<String>,
+    ) -> Result<Option<user_ratings::Model>, async_graphql::Error> {
+        let pool = ctx.data::<DatabaseConnection>()?;
+        let auth = ctx.data::<RequestAuth>()?;
+        let user = auth.get_user_or_err()?;
+        let node = ensure_node_accessible(pool, auth, &node_id).await?;
+
+        let row = save_node_rating(pool, &user.id, &node.id, rating, review).await?;
+
+        CONTENT_UPDATE.emit();
+        Ok(row)
+    }
+
     pub async fn import_watch_states(
         &self,
         ctx: &Context<'_>,
@@ -772,16 +1397,121 @@ impl Mutation {
         Ok(result.into())
     }
 
-    pub async fn create_library(
+    #[graphql(guard = PermissionGuard::new(UserPerms::ADMIN))]
+    pub async fn import_external_watch_states(
         &self,
         ctx: &Context<'_>,
-        name: String,
-        path: String,
-        pinned: Option<bool>,
-    ) -> Result<libraries::Model, async_graphql::Error> {
+        input: ImportExternalWatchStatesInput,
+    ) -> Result<Vec<ImportExternalWatchStatesUserResult>, async_graphql::Error> {
         let pool = ctx.data::<DatabaseConnection>()?;
-        let auth = ctx.data::<RequestAuth>()?;
-
+        let path = std::path::Path::new(&input.path);
+
+        let source_users = match input.source {
+            ExternalWatchStateSource::Jellyfin => {
+                jellyfin_import::read_jellyfin_database(
+                    path,
+                    input
+                        .users_database_path
+                        .as_deref()
+                        .map(std::path::Path::new),
+                )
+                .await
+            }
+            ExternalWatchStateSource::Emby => {
+                // emby exports are per-user, so the single mapping names the user it belongs to.
+                let [mapping] = input.user_mappings.as_slice() else {
+                    return Err(async_graphql::Error::new(
+                        "Emby exports require exactly one user mapping",
+                    ));
+                };
+                let bytes = tokio::fs::read(path)
+                    .await
+                    .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+                jellyfin_import::read_emby_export(&bytes, &mapping.source_username)
+            }
+        }
+        .map_err(|error| async_graphql::Error::new(format!("{error:#}")))?;
+
+        let lyra_users = users::Entity::find()
+            .all(pool)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        let mut results = Vec::with_capacity(source_users.len());
+        let mut imported_any = false;
+        for source_user in source_users {
+            // explicit mappings win, otherwise fall back to a lyra user with the same name.
+            let user = match input
+                .user_mappings
+                .iter()
+                .find(|mapping| mapping.source_username == source_user.source_username)
+            {
+                Some(mapping) => Some(
+                    lyra_users
+                        .iter()
+                        .find(|user| user.id == mapping.user_id)
+                        .ok_or_else(|| {
+                            async_graphql::Error::new(format!("User {} not found", mapping.user_id))
+                        })?,
+                ),
+                None => lyra_users.iter().find(|user| {
+                    user.username
+                        .eq_ignore_ascii_case(&source_user.source_username)
+                }),
+            };
+
+            let Some(user) = user else {
+                results.push(ImportExternalWatchStatesUserResult {
+                    source_username: source_user.source_username,
+                    user_id: None,
+                    result: None,
+                });
+                continue;
+            };
+
+            let request = watch_state_import::ImportWatchStatesRequest {
+                user_id: user.id.clone(),
+                accessible_library_ids: accessible_library_ids_for_user(pool, user)
+                    .await
+                    .map_err(|error| -> async_graphql::Error { error.into() })?,
+                overwrite_conflicts: input.overwrite_conflicts,
+                rows: source_user.rows,
+            };
+
+            let result = if input.dry_run {
+                watch_state_import::dry_run(pool, request).await
+            } else {
+                watch_state_import::commit(pool, request).await
+            }
+            .map_err(|error| async_graphql::Error::new(error.to_string()))?;
+
+            imported_any |= result.imported > 0;
+            results.push(ImportExternalWatchStatesUserResult {
+                source_username: source_user.source_username,
+                user_id: Some(user.id.clone()),
+                result: Some(result.into()),
+            });
+        }
+
+        if imported_any {
+            CONTENT_UPDATE.emit();
+        }
+
+        Ok(results)
+    }
+
+    pub async fn create_library(
+        &self,
+        ctx: &Context<'_>,
+        name: String,
+        path: String,
+        pinned: Option<bool>,
+        recordings: Option<bool>,
+        anime: Option<bool>,
+    ) -> Result<libraries::Model, async_graphql::Error> {
+        let pool = ctx.data::<DatabaseConnection>()?;
+        let auth = ctx.data::<RequestAuth>()?;
+
         if !auth.has_permission(UserPerms::ADMIN) {
             return Err(async_graphql::Error::new(
                 "Lacking permission to create libraries".to_string(),
@@ -793,6 +1523,8 @@ impl Mutation {
             name: Set(name),
             path: Set(path),
             pinned: Set(pinned.unwrap_or(true)),
+            recordings: Set(recordings.unwrap_or(false)),
+            anime: Set(anime.unwrap_or(false)),
             ..Default::default()
         })
         .exec_with_returning(pool)
@@ -803,6 +1535,7 @@ impl Mutation {
         Ok(library)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn update_library(
         &self,
         ctx: &Context<'_>,
@@ -810,6 +1543,8 @@ impl Mutation {
         name: String,
         path: String,
         pinned: bool,
+        recordings: Option<bool>,
+        anime: Option<bool>,
     ) -> Result<libraries::Model, async_graphql::Error> {
         let pool = ctx.data::<DatabaseConnection>()?;
         let auth = ctx.data::<RequestAuth>()?;
@@ -826,10 +1561,17 @@ impl Mutation {
             .map_err(|e| async_graphql::Error::new(e.to_string()))?
             .ok_or_else(|| async_graphql::Error::new("Library not found".to_string()))?;
         let path_changed = existing_library.path != path;
+        let anime_changed = anime.is_some_and(|anime| anime != existing_library.anime);
         let mut library = existing_library.into_active_model();
         library.name = Set(name);
         library.path = Set(path);
         library.pinned = Set(pinned);
+        if let Some(recordings) = recordings {
+            library.recordings = Set(recordings);
+        }
+        if let Some(anime) = anime {
+            library.anime = Set(anime);
+        }
 
         // force the scheduler to rescan quickly when the root changes
         // instead of leaving the moved library on the previous scan cadence.
@@ -842,6 +1584,24 @@ impl Mutation {
             .await
             .map_err(|e| async_graphql::Error::new(e.to_string()))?;
 
+        // roots matched before the switch need another pass through the providers
+        if anime_changed {
+            let root_ids = nodes::Entity::find()
+                .select_only()
+                .column(nodes::Column::Id)
+                .filter(nodes::Column::LibraryId.eq(library.id.clone()))
+                .filter(nodes::Column::ParentId.is_null())
+                .into_tuple::<String>()
+                .all(pool)
+                .await
+                .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+            for root_id in root_ids {
+                metadata::mark_root_dirty(pool, &root_id)
+                    .await
+                    .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+            }
+        }
+
         CONTENT_UPDATE.emit();
         Ok(library)
     }
@@ -1107,6 +1867,77 @@ impl Mutation {
         Ok(updated)
     }
 
+    pub async fn set_preferred_metadata_languages(
+        &self,
+        ctx: &Context<'_>,
+        languages: Vec<String>,
+    ) -> Result<users::Model, async_graphql::Error> {
+        let pool = ctx.data::<DatabaseConnection>()?;
+        let auth = ctx.data::<RequestAuth>()?;
+        let user = auth.get_user_or_err()?;
+
+        let mut normalized: Vec<String> = Vec::new();
+        for language in languages {
+            let language = language.trim();
+            if language.is_empty() {
+                continue;
+            }
+            if language.len() > 35
+                || !language
+                    .chars()
+                    .all(|ch| ch.is_ascii_alphanumeric() || ch == '-')
+            {
+                return Err(async_graphql::Error::new(format!(
+                    "Invalid language tag '{language}'"
+                )));
+            }
+            if !normalized
+                .iter()
+                .any(|existing| existing.eq_ignore_ascii_case(language))
+            {
+                normalized.push(language.to_owned());
+            }
+        }
+
+        let existing = users::Entity::find_by_id(user.id.clone())
+            .one(pool)
+            .await?
+            .ok_or_else(|| async_graphql::Error::new("User not found"))?;
+        let mut active = existing.into_active_model();
+        active.preferred_metadata_languages = Set(serde_json::to_string(&normalized)?);
+        let updated = active.update(pool).await?;
+
+        CONTENT_UPDATE.emit();
+        Ok(updated)
+    }
+
+    /// Revoke every calendar link handed out so far and return a new one.
+    #[graphql(guard = AuthenticatedGuard::new())]
+    pub async fn regenerate_calendar_url(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = false)] watchlist_only: bool,
+    ) -> Result<String, async_graphql::Error> {
+        let pool = ctx.data::<DatabaseConnection>()?;
+        let auth = ctx.data::<RequestAuth>()?;
+        let user = auth.get_user_or_err()?;
+
+        let user = users::Entity::update_many()
+            .col_expr(
+                users::Column::CalendarTokenVersion,
+                Expr::col(users::Column::CalendarTokenVersion).add(1),
+            )
+            .filter(users::Column::Id.eq(user.id.clone()))
+            .exec_with_returning(pool)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| async_graphql::Error::new("User not found"))?;
+
+        calendar::sign_calendar_url(&user, watchlist_only)
+            .map_err(|error| async_graphql::Error::new(error.to_string()))
+    }
+
     pub async fn delete_library(
         &self,
         ctx: &Context<'_>,
@@ -1136,6 +1967,522 @@ impl Mutation {
         Ok(true)
     }
 
+    #[graphql(guard = PermissionGuard::new(UserPerms::ADMIN))]
+    pub async fn create_file_segment(
+        &self,
+        ctx: &Context<'_>,
+        file_id: String,
+        input: FileSegmentInput,
+    ) -> Result<files::Model, async_graphql::Error> {
+        let pool = ctx.data::<DatabaseConnection>()?;
+        let segment = segment_from_input(input)?;
+        let txn = pool.begin().await?;
+        let (_, mut segments) = load_file_segments_for_edit(&txn, &file_id).await?;
+        ensure_segment_key_free(&segments, &segment)?;
+        segments.push(segment);
+        let file = save_file_segments(&txn, &file_id, segments).await?;
+        txn.commit().await?;
+
+        CONTENT_UPDATE.emit();
+        Ok(file)
+    }
+
+    /// Replaces the segment of `kind` that starts at `startMs`.
+    #[graphql(guard = PermissionGuard::new(UserPerms::ADMIN))]
+    pub async fn update_file_segment(
+        &self,
+        ctx: &Context<'_>,
+        file_id: String,
+        kind: FileSegmentKind,
+        start_ms: i64,
+        input: FileSegmentInput,
+    ) -> Result<files::Model, async_graphql::Error> {
+        let pool = ctx.data::<DatabaseConnection>()?;
+        let segment = segment_from_input(input)?;
+        let txn = pool.begin().await?;
+        let (_, mut segments) = load_file_segments_for_edit(&txn, &file_id).await?;
+        let position = segment_position(&segments, kind, start_ms)?;
+        segments.remove(position);
+        ensure_segment_key_free(&segments, &segment)?;
+        segments.push(segment);
+        let file = save_file_segments(&txn, &file_id, segments).await?;
+        txn.commit().await?;
+
+        CONTENT_UPDATE.emit();
+        Ok(file)
+    }
+
+    /// Deletes the segment of `kind` that starts at `startMs`.
+    #[graphql(guard = PermissionGuard::new(UserPerms::ADMIN))]
+    pub async fn delete_file_segment(
+        &self,
+        ctx: &Context<'_>,
+        file_id: String,
+        kind: FileSegmentKind,
+        start_ms: i64,
+    ) -> Result<files::Model, async_graphql::Error> {
+        let pool = ctx.data::<DatabaseConnection>()?;
+        let txn = pool.begin().await?;
+        let (_, mut segments) = load_file_segments_for_edit(&txn, &file_id).await?;
+        let position = segment_position(&segments, kind, start_ms)?;
+        segments.remove(position);
+        let file = save_file_segments(&txn, &file_id, segments).await?;
+        txn.commit().await?;
+
+        CONTENT_UPDATE.emit();
+        Ok(file)
+    }
+
+    /// Imports segments from a `.chapters.xml` or `.edl` file next to the media file.
+    /// Imported segments are locked and replace existing segments of the same kind.
+    #[graphql(guard = PermissionGuard::new(UserPerms::ADMIN))]
+    pub async fn import_file_segment_sidecars(
+        &self,
+        ctx: &Context<'_>,
+        file_id: String,
+    ) -> Result<files::Model, async_graphql::Error> {
+        let pool = ctx.data::<DatabaseConnection>()?;
+        let file = files::Entity::find_by_id(file_id.clone())
+            .one(pool)
+            .await?
+            .ok_or_else(|| async_graphql::Error::new("File not found"))?;
+        let media_path = load_file_media_path(pool, &file).await?;
+        let duration_ms = crate::media::load_cached_probe(pool, &file.id)
+            .await
+            .map_err(|error| async_graphql::Error::new(error.to_string()))?
+            .and_then(|probe| probe.duration_secs)
+            .map(|duration| (duration * 1000.0) as i64);
+
+        let imported = sidecar::read_sidecar_segments(&media_path, duration_ms)
+            .await
+            .map_err(|error| async_graphql::Error::new(error.to_string()))?
+            .ok_or_else(|| async_graphql::Error::new("No segment sidecar found"))?;
+
+        // the sidecar is read before the transaction so it isn't held open over file io
+        let txn = pool.begin().await?;
+        let (_, segments) = load_file_segments_for_edit(&txn, &file_id).await?;
+        let segments = segment_markers::merge_locked_segments(imported.segments, segments);
+        let file = save_file_segments(&txn, &file_id, segments).await?;
+        segment_markers::store_sidecar_hash(&txn, &file_id, &imported.hash)
+            .await
+            .map_err(|error| async_graphql::Error::new(error.to_string()))?;
+        txn.commit().await?;
+
+        CONTENT_UPDATE.emit();
+        Ok(file)
+    }
+
+    /// Returns the file's segments in the given sidecar format, optionally writing it next
+    /// to the media file so other players pick it up.
+    #[graphql(guard = PermissionGuard::new(UserPerms::ADMIN))]
+    pub async fn export_file_segments(
+        &self,
+        ctx: &Context<'_>,
+        file_id: String,
+        format: SegmentSidecarFormat,
+        write_sidecar: Option<bool>,
+    ) -> Result<String, async_graphql::Error> {
+        let pool = ctx.data::<DatabaseConnection>()?;
+        let (file, segments) = load_file_segments_for_edit(pool, &file_id).await?;
+        let content = match format {
+            SegmentSidecarFormat::Edl => sidecar::format_edl(&segments),
+            SegmentSidecarFormat::ChaptersXml => sidecar::format_chapters_xml(&segments),
+        };
+
+        if write_sidecar.unwrap_or(false) {
+            let media_path = load_file_media_path(pool, &file).await?;
+            let sidecar_path = match format {
+                SegmentSidecarFormat::Edl => sidecar::edl_path(&media_path),
+                SegmentSidecarFormat::ChaptersXml => sidecar::chapters_path(&media_path),
+            };
+            tokio::fs::write(&sidecar_path, &content)
+                .await
+                .map_err(|error| {
+                    async_graphql::Error::new(format!(
+                        "Failed to write '{}': {error}",
+                        sidecar_path.display()
+                    ))
+                })?;
+            // the written sidecar already matches the stored segments, so detection
+            // shouldn't import it back over later edits
+            segment_markers::store_sidecar_hash(pool, &file_id, &sidecar::sidecar_hash(&content))
+                .await
+                .map_err(|error| async_graphql::Error::new(error.to_string()))?;
+        }
+
+        Ok(content)
+    }
+
+    /// Shared intros (studio logos, idents) are matched against every root. Sharing one
+    /// queues roots with files that have no intro yet for segment detection again so they
+    /// pick it up.
+    #[graphql(guard = PermissionGuard::new(UserPerms::ADMIN))]
+    pub async fn update_intro_fingerprint(
+        &self,
+        ctx: &Context<'_>,
+        id: String,
+        shared: bool,
+        label: Option<String>,
+    ) -> Result<intro_fingerprints::Model, async_graphql::Error> {
+        let pool = ctx.data::<DatabaseConnection>()?;
+        let existing = intro_fingerprints::Entity::find_by_id(id)
+            .one(pool)
+            .await?
+            .ok_or_else(|| async_graphql::Error::new("Intro fingerprint not found"))?;
+        let newly_shared = shared && !existing.shared;
+
+        let txn = pool.begin().await?;
+        let mut active = existing.into_active_model();
+        active.shared = Set(shared);
+        active.label = Set(label
+            .map(|label| label.trim().to_string())
+            .filter(|label| !label.is_empty()));
+        let updated = active.update(&txn).await?;
+
+        if newly_shared {
+            let root_ids = segment_markers::roots_missing_intros(&txn)
+                .await
+                .map_err(|error| async_graphql::Error::new(error.to_string()))?;
+            nodes::Entity::update_many()
+                .col_expr(
+                    nodes::Column::LastFingerprintVersion,
+                    Expr::value(Option::<i64>::None),
+                )
+                .filter(nodes::Column::Id.is_in(root_ids))
+                .exec(&txn)
+                .await?;
+        }
+        txn.commit().await?;
+
+        Ok(updated)
+    }
+
+    #[graphql(guard = PermissionGuard::new(UserPerms::ADMIN))]
+    pub async fn delete_intro_fingerprint(
+        &self,
+        ctx: &Context<'_>,
+        id: String,
+    ) -> Result<bool, async_graphql::Error> {
+        let pool = ctx.data::<DatabaseConnection>()?;
+        let result = intro_fingerprints::Entity::delete_by_id(id)
+            .exec(pool)
+            .await?;
+        if result.rows_affected == 0 {
+            return Err(async_graphql::Error::new("Intro fingerprint not found"));
+        }
+
+        Ok(true)
+    }
+
+    /// Pin a movie or series to a provider result so metadata syncs stop searching for it.
+    #[graphql(guard = PermissionGuard::new(UserPerms::ADMIN))]
+    pub async fn set_root_match(
+        &self,
+        ctx: &Context<'_>,
+        node_id: String,
+        provider_id: String,
+        external_id: String,
+    ) -> Result<nodes::Model, async_graphql::Error> {
+        let pool = ctx.data::<DatabaseConnection>()?;
+        let root = load_matchable_root(pool, &node_id).await?;
+        if !metadata::metadata_providers()
+            .iter()
+            .any(|provider| provider.id() == provider_id)
+        {
+            return Err(async_graphql::Error::new("Unknown metadata provider"));
+        }
+        let external_id = external_id.trim().to_string();
+        if external_id.is_empty() {
+            return Err(async_graphql::Error::new("External id cannot be empty"));
+        }
+
+        let txn = pool.begin().await?;
+        root_matches::Entity::insert(root_matches::ActiveModel {
+            root_id: Set(root.id.clone()),
+            provider_id: Set(provider_id),
+            external_id: Set(external_id),
+            created_at: Set(Utc::now().timestamp()),
+        })
+        .on_conflict(
+            OnConflict::column(root_matches::Column::RootId)
+                .update_columns([
+                    root_matches::Column::ProviderId,
+                    root_matches::Column::ExternalId,
+                    root_matches::Column::CreatedAt,
+                ])
+                .to_owned(),
+        )
+        .exec(&txn)
+        .await?;
+        let root = resync_root_metadata(&txn, root).await?;
+        txn.commit().await?;
+
+        CONTENT_UPDATE.emit();
+        Ok(root)
+    }
+
+    #[graphql(guard = PermissionGuard::new(UserPerms::ADMIN))]
+    pub async fn clear_root_match(
+        &self,
+        ctx: &Context<'_>,
+        node_id: String,
+    ) -> Result<nodes::Model, async_graphql::Error> {
+        let pool = ctx.data::<DatabaseConnection>()?;
+        let root = load_matchable_root(pool, &node_id).await?;
+
+        let txn = pool.begin().await?;
+        let result = root_matches::Entity::delete_by_id(root.id.clone())
+            .exec(&txn)
+            .await?;
+        let root = if result.rows_affected > 0 {
+            resync_root_metadata(&txn, root).await?
+        } else {
+            root
+        };
+        txn.commit().await?;
+
+        CONTENT_UPDATE.emit();
+        Ok(root)
+    }
+
+    /// Fetch fresh metadata for a node's movie or series from the providers, skipping the
+    /// refresh schedule and any retry backoff.
+    #[graphql(guard = PermissionGuard::new(UserPerms::ADMIN))]
+    pub async fn refresh_node_metadata(
+        &self,
+        ctx: &Context<'_>,
+        node_id: String,
+    ) -> Result<nodes::Model, async_graphql::Error> {
+        let pool = ctx.data::<DatabaseConnection>()?;
+        let node = nodes::Entity::find_by_id(node_id)
+            .one(pool)
+            .await?
+            .ok_or_else(|| async_graphql::Error::new("Node not found"))?;
+        let root = load_matchable_root(pool, &node.root_id).await?;
+
+        // forced before the root is marked dirty so the job can't pick it up without the flag
+        metadata::force_metadata_refresh(&root.id);
+        let txn = pool.begin().await?;
+        let root = resync_root_metadata(&txn, root).await?;
+        txn.commit().await?;
+
+        CONTENT_UPDATE.emit();
+        Ok(root)
+    }
+
+    /// Read a series root's episode numbers in an alternate ordering, or go back to the
+    /// provider's default ordering when `ordering_id` is null.
+    #[graphql(guard = PermissionGuard::new(UserPerms::ADMIN))]
+    pub async fn set_root_episode_ordering(
+        &self,
+        ctx: &Context<'_>,
+        node_id: String,
+        ordering_id: Option<String>,
+    ) -> Result<nodes::Model, async_graphql::Error> {
+        let pool = ctx.data::<DatabaseConnection>()?;
+        let root = load_matchable_root(pool, &node_id).await?;
+        if root.kind != nodes::NodeKind::Series {
+            return Err(async_graphql::Error::new(
+                "Only series have episode orderings",
+            ));
+        }
+
+        // the ordering list comes from the provider, so check it before opening a transaction
+        let selected = match ordering_id {
+            Some(ordering_id) => {
+                let orderings = metadata::lookup_root_episode_orderings(
+                    pool,
+                    &metadata::metadata_providers(),
+                    &root,
+                )
+                .await
+                .map_err(|error| async_graphql::Error::new(format!("{error:#}")))?;
+                let Some((provider_id, _)) = orderings
+                    .into_iter()
+                    .find(|(_, ordering)| ordering.id == ordering_id)
+                else {
+                    return Err(async_graphql::Error::new("Unknown episode ordering"));
+                };
+                Some((provider_id, ordering_id))
+            }
+            None => None,
+        };
+
+        let txn = pool.begin().await?;
+        match selected {
+            Some((provider_id, ordering_id)) => {
+                root_episode_orderings::Entity::insert(root_episode_orderings::ActiveModel {
+                    root_id: Set(root.id.clone()),
+                    provider_id: Set(provider_id),
+                    ordering_id: Set(ordering_id),
+                    created_at: Set(Utc::now().timestamp()),
+                })
+                .on_conflict(
+                    OnConflict::column(root_episode_orderings::Column::RootId)
+                        .update_columns([
+                            root_episode_orderings::Column::ProviderId,
+                            root_episode_orderings::Column::OrderingId,
+                            root_episode_orderings::Column::CreatedAt,
+                        ])
+                        .to_owned(),
+                )
+                .exec(&txn)
+                .await?;
+            }
+            None => {
+                root_episode_orderings::Entity::delete_by_id(root.id.clone())
+                    .exec(&txn)
+                    .await?;
+            }
+        }
+        let root = resync_root_metadata(&txn, root).await?;
+        txn.commit().await?;
+
+        CONTENT_UPDATE.emit();
+        Ok(root)
+    }
+
+    /// Override metadata fields on a node. Every field that is set gets locked, so metadata
+    /// refreshes and rescans keep the edited value until it is unlocked.
+    #[graphql(guard = PermissionGuard::new(UserPerms::ADMIN))]
+    pub async fn update_node_metadata(
+        &self,
+        ctx: &Context<'_>,
+        node_id: String,
+        input: NodeMetadataOverrideInput,
+    ) -> Result<nodes::Model, async_graphql::Error> {
+        let pool = ctx.data::<DatabaseConnection>()?;
+        let node = nodes::Entity::find_by_id(node_id)
+            .one(pool)
+            .await?
+            .ok_or_else(|| async_graphql::Error::new("Node not found"))?;
+        let overrides = input.into_overrides()?;
+
+        let txn = pool.begin().await?;
+        metadata::apply_metadata_overrides(&txn, &node.id, overrides, Utc::now().timestamp())
+            .await
+            .map_err(|error| async_graphql::Error::new(format!("{error:#}")))?;
+        txn.commit().await?;
+
+        CONTENT_UPDATE.emit();
+        Ok(node)
+    }
+
+    /// Lock fields to the values currently shown, or unlock them to go back to provider
+    /// metadata.
+    #[graphql(guard = PermissionGuard::new(UserPerms::ADMIN))]
+    pub async fn set_node_metadata_locks(
+        &self,
+        ctx: &Context<'_>,
+        node_id: String,
+        fields: Vec<MetadataField>,
+        locked: bool,
+    ) -> Result<nodes::Model, async_graphql::Error> {
+        let pool = ctx.data::<DatabaseConnection>()?;
+        let node = nodes::Entity::find_by_id(node_id)
+            .one(pool)
+            .await?
+            .ok_or_else(|| async_graphql::Error::new("Node not found"))?;
+        let fields = fields
+            .into_iter()
+            .fold(LockedFields::empty(), |fields, field| fields | field.into());
+
+        let now = Utc::now().timestamp();
+        let txn = pool.begin().await?;
+        let result = if locked {
+            metadata::lock_metadata_fields(&txn, &node.id, fields, now).await
+        } else {
+            metadata::unlock_metadata_fields(&txn, &node.id, fields, now).await
+        };
+        result.map_err(|error| async_graphql::Error::new(format!("{error:#}")))?;
+        txn.commit().await?;
+
+        CONTENT_UPDATE.emit();
+        Ok(node)
+    }
+
+    /// Pick the artwork shown for a node from `availableImages`, or upload a custom image. With
+    /// neither set the pick is cleared and artwork is chosen automatically again.
+    #[graphql(guard = PermissionGuard::new(UserPerms::ADMIN))]
+    pub async fn set_node_image(
+        &self,
+        ctx: &Context<'_>,
+        node_id: String,
+        kind: ImageKind,
+        asset_id: Option<String>,
+        upload: Option<Upload>,
+    ) -> Result<nodes::Model, async_graphql::Error> {
+        let pool = ctx.data::<DatabaseConnection>()?;
+        let node = nodes::Entity::find_by_id(node_id)
+            .one(pool)
+            .await?
+            .ok_or_else(|| async_graphql::Error::new("Node not found"))?;
+        let kind = NodeMetadataImageKind::from(kind);
+
+        let asset_id = match (asset_id, upload) {
+            (Some(_), Some(_)) => {
+                return Err(async_graphql::Error::new(
+                    "Pick an existing image or upload one, not both",
+                ));
+            }
+            (Some(asset_id), None) => {
+                if !is_node_image_candidate(pool, &node.id, kind, &asset_id).await? {
+                    return Err(async_graphql::Error::new("Image not found"));
+                }
+                Some(asset_id)
+            }
+            (None, Some(upload)) => {
+                let mut bytes = Vec::new();
+                tokio::fs::File::from_std(upload.value(ctx)?.content)
+                    .take(MAX_IMAGE_UPLOAD_BYTES + 1)
+                    .read_to_end(&mut bytes)
+                    .await?;
+                if bytes.len() as u64 > MAX_IMAGE_UPLOAD_BYTES {
+                    return Err(async_graphql::Error::new("Image upload is too large"));
+                }
+                let asset = create_local_asset_from_bytes(pool, &bytes, kind.into())
+                    .await
+                    .map_err(|error| {
+                        async_graphql::Error::new(format!("Invalid image upload: {error:#}"))
+                    })?;
+                Some(asset.id)
+            }
+            (None, None) => None,
+        };
+
+        match asset_id {
+            Some(asset_id) => {
+                node_image_selections::Entity::insert(node_image_selections::ActiveModel {
+                    node_id: Set(node.id.clone()),
+                    kind: Set(kind),
+                    asset_id: Set(asset_id),
+                    created_at: Set(Utc::now().timestamp()),
+                })
+                .on_conflict(
+                    OnConflict::columns([
+                        node_image_selections::Column::NodeId,
+                        node_image_selections::Column::Kind,
+                    ])
+                    .update_columns([
+                        node_image_selections::Column::AssetId,
+                        node_image_selections::Column::CreatedAt,
+                    ])
+                    .to_owned(),
+                )
+                .exec(pool)
+                .await?;
+            }
+            None => {
+                node_image_selections::Entity::delete_by_id((node.id.clone(), kind))
+                    .exec(pool)
+                    .await?;
+            }
+        }
+
+        CONTENT_UPDATE.emit();
+        Ok(node)
+    }
+
     #[graphql(guard = AuthenticatedGuard::new())]
     pub async fn disabled_subtitles_hint(
         &self,
@@ -1168,3 +2515,449 @@ impl Mutation {
         Ok(true)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphql::query::{OrderBy, OrderDirection, build_node_query_for_viewer};
+    use sea_orm::Database;
+
+    async fn setup_test_db() -> anyhow::Result<DatabaseConnection> {
+        let pool = Database::connect("sqlite::memory:").await?;
+        sqlx::migrate!("../../migrations")
+            .run(pool.get_sqlite_connection_pool())
+            .await?;
+
+        for library_id in ["lib", "other"] {
+            libraries::Entity::insert(libraries::ActiveModel {
+                id: Set(library_id.to_owned()),
+                path: Set(format!("/{library_id}")),
+                name: Set(library_id.to_owned()),
+                pinned: Set(false),
+                recordings: Set(false),
+                anime: Set(false),
+                last_scanned_at: Set(None),
+                unavailable_at: Set(None),
+                created_at: Set(0),
+            })
+            .exec(&pool)
+            .await?;
+        }
+        for user_id in ["user", "housemate"] {
+            users::Entity::insert(users::ActiveModel {
+                id: Set(user_id.to_owned()),
+                username: Set(user_id.to_owned()),
+                password_hash: Set(Some("hash".to_owned())),
+                permissions: Set(0),
+                created_at: Set(0),
+                ..Default::default()
+            })
+            .exec(&pool)
+            .await?;
+        }
+        Ok(pool)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn insert_node(
+        pool: &DatabaseConnection,
+        id: &str,
+        library_id: &str,
+        root_id: &str,
+        ancestor_ids: &[&str],
+        kind: nodes::NodeKind,
+        order: i64,
+        file_available: Option<bool>,
+    ) -> anyhow::Result<()> {
+        nodes::Entity::insert(nodes::ActiveModel {
+            id: Set(id.to_owned()),
+            library_id: Set(library_id.to_owned()),
+            root_id: Set(root_id.to_owned()),
+            parent_id: Set(ancestor_ids.last().map(|id| (*id).to_owned())),
+            kind: Set(kind),
+            name: Set(id.to_owned()),
+            order: Set(order),
+            // every test series has a single season, numbered by order
+            season_number: Set(
+                matches!(kind, nodes::NodeKind::Season | nodes::NodeKind::Episode).then_some(1),
+            ),
+            episode_number: Set((kind == nodes::NodeKind::Episode).then_some(order)),
+            extra_type: Set(None),
+            last_added_at: Set(0),
+            last_fingerprint_version: Set(None),
+            unavailable_at: Set(None),
+            created_at: Set(0),
+            updated_at: Set(0),
+        })
+        .exec(pool)
+        .await?;
+
+        let closure = std::iter::once((id, 0))
+            .chain(
+                ancestor_ids
+                    .iter()
+                    .rev()
+                    .enumerate()
+                    .map(|(index, ancestor_id)| (*ancestor_id, index as i64 + 1)),
+            )
+            .map(|(ancestor_id, depth)| node_closure::ActiveModel {
+                ancestor_id: Set(ancestor_id.to_owned()),
+                descendant_id: Set(id.to_owned()),
+                depth: Set(depth),
+            });
+        node_closure::Entity::insert_many(closure)
+            .exec(pool)
+            .await?;
+
+        if let Some(available) = file_available {
+            files::Entity::insert(files::ActiveModel {
+                id: Set(format!("file-{id}")),
+                library_id: Set(library_id.to_owned()),
+                relative_path: Set(format!("{id}.mkv")),
+                size_bytes: Set(0),
+                unavailable_at: Set((!available).then_some(0)),
+                discovered_at: Set(0),
+                ..Default::default()
+            })
+            .exec(pool)
+            .await?;
+            node_files::Entity::insert(node_files::ActiveModel {
+                node_id: Set(id.to_owned()),
+                file_id: Set(format!("file-{id}")),
+                order: Set(0),
+                created_at: Set(0),
+                updated_at: Set(0),
+            })
+            .exec(pool)
+            .await?;
+        }
+        Ok(())
+    }
+
+    // a series with three episodes, the second of which lost its file
+    async fn insert_series(pool: &DatabaseConnection) -> anyhow::Result<()> {
+        use nodes::NodeKind::{Episode, Season, Series};
+        insert_node(pool, "show", "lib", "show", &[], Series, 0, None).await?;
+        insert_node(pool, "s1", "lib", "show", &["show"], Season, 1, None).await?;
+        insert_node(
+            pool,
+            "e1",
+            "lib",
+            "show",
+            &["show", "s1"],
+            Episode,
+            2,
+            Some(true),
+        )
+        .await?;
+        insert_node(
+            pool,
+            "e2",
+            "lib",
+            "show",
+            &["show", "s1"],
+            Episode,
+            3,
+            Some(false),
+        )
+        .await?;
+        insert_node(
+            pool,
+            "e3",
+            "lib",
+            "show",
+            &["show", "s1"],
+            Episode,
+            4,
+            Some(true),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn watched_node_ids(pool: &DatabaseConnection) -> anyhow::Result<Vec<String>> {
+        Ok(watch_progress::Entity::find()
+            .filter(watch_progress::Column::UserId.eq("user"))
+            .order_by_asc(watch_progress::Column::NodeId)
+            .all(pool)
+            .await?
+            .into_iter()
+            .map(|row| row.node_id)
+            .collect())
+    }
+
+    #[tokio::test]
+    async fn marking_a_series_covers_episodes_with_available_files() -> anyhow::Result<()> {
+        let pool = setup_test_db().await?;
+        insert_series(&pool).await?;
+
+        let node_ids = load_playable_descendant_ids(&pool, None, vec!["show".to_owned()])
+            .await
+            .map_err(|error| anyhow::anyhow!(error.message))?;
+        assert_eq!(node_ids, ["e1", "e2", "e3"]);
+
+        let updated = set_nodes_watched(&pool, "user", node_ids.clone(), true)
+            .await
+            .map_err(|error| anyhow::anyhow!(error.message))?;
+        assert_eq!(updated, 2);
+        assert_eq!(watched_node_ids(&pool).await?, ["e1", "e3"]);
+
+        let updated = set_nodes_watched(&pool, "user", node_ids, false)
+            .await
+            .map_err(|error| anyhow::anyhow!(error.message))?;
+        assert_eq!(updated, 2);
+        assert!(watched_node_ids(&pool).await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn playable_descendants_respect_library_access() -> anyhow::Result<()> {
+        let pool = setup_test_db().await?;
+        insert_series(&pool).await?;
+        insert_node(
+            &pool,
+            "movie",
+            "other",
+            "movie",
+            &[],
+            nodes::NodeKind::Movie,
+            0,
+            Some(true),
+        )
+        .await?;
+
+        let visible = ["lib".to_owned()];
+        let node_ids = load_playable_descendant_ids(
+            &pool,
+            Some(&visible),
+            vec!["show".to_owned(), "movie".to_owned()],
+        )
+        .await
+        .map_err(|error| anyhow::anyhow!(error.message))?;
+        assert_eq!(node_ids, ["e1", "e2", "e3"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn watch_state_for_another_user_uses_their_libraries() -> anyhow::Result<()> {
+        let pool = setup_test_db().await?;
+        insert_series(&pool).await?;
+        insert_node(
+            &pool,
+            "movie",
+            "other",
+            "movie",
+            &[],
+            nodes::NodeKind::Movie,
+            0,
+            Some(true),
+        )
+        .await?;
+        library_users::Entity::insert(library_users::ActiveModel {
+            library_id: Set("other".to_owned()),
+            user_id: Set("housemate".to_owned()),
+        })
+        .exec(&pool)
+        .await?;
+
+        let visible = target_user_library_ids(&pool, "housemate")
+            .await
+            .map_err(|error| anyhow::anyhow!(error.message))?;
+        assert_eq!(visible.as_deref(), Some(&["other".to_owned()][..]));
+        let node_ids = load_playable_descendant_ids(
+            &pool,
+            visible.as_deref(),
+            vec!["show".to_owned(), "movie".to_owned()],
+        )
+        .await
+        .map_err(|error| anyhow::anyhow!(error.message))?;
+        assert_eq!(node_ids, ["movie"]);
+
+        assert!(target_user_library_ids(&pool, "missing").await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn watched_before_stops_at_the_given_episode() -> anyhow::Result<()> {
+        let pool = setup_test_db().await?;
+        insert_series(&pool).await?;
+        let episode = nodes::Entity::find_by_id("e3")
+            .one(&pool)
+            .await?
+            .expect("episode exists");
+
+        let mut node_ids = load_watched_before_ids(&pool, None, &episode)
+            .await
+            .map_err(|error| anyhow::anyhow!(error.message))?;
+        node_ids.sort();
+        assert_eq!(node_ids, ["e1", "e2"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn ratings_are_validated_updated_and_cleared() -> anyhow::Result<()> {
+        let pool = setup_test_db().await?;
+        insert_node(
+            &pool,
+            "movie",
+            "lib",
+            "movie",
+            &[],
+            nodes::NodeKind::Movie,
+            0,
+            None,
+        )
+        .await?;
+
+        assert!(
+            save_node_rating(&pool, "user", "movie", Some(11), None)
+                .await
+                .is_err()
+        );
+        assert!(
+            save_node_rating(&pool, "user", "movie", Some(5), Some("x".repeat(2001)))
+                .await
+                .is_err()
+        );
+
+        let first = save_node_rating(&pool, "user", "movie", Some(8), Some("  great  ".into()))
+            .await
+            .map_err(|error| anyhow::anyhow!(error.message))?
+            .expect("rating saved");
+        assert_eq!((first.rating, first.review.as_deref()), (8, Some("great")));
+
+        let second = save_node_rating(&pool, "user", "movie", Some(6), Some("   ".into()))
+            .await
+            .map_err(|error| anyhow::anyhow!(error.message))?
+            .expect("rating saved");
+        assert_eq!(second.id, first.id);
+        assert_eq!((second.rating, second.review), (6, None));
+
+        let cleared = save_node_rating(&pool, "user", "movie", None, None)
+            .await
+            .map_err(|error| anyhow::anyhow!(error.message))?;
+        assert!(cleared.is_none());
+        assert_eq!(user_ratings::Entity::find().count(&pool).await?, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn node_filters_use_viewer_and_household_ratings() -> anyhow::Result<()> {
+        let pool = setup_test_db().await?;
+        for (order, id) in ["loved", "mixed", "unrated"].into_iter().enumerate() {
+            insert_node(
+                &pool,
+                id,
+                "lib",
+                id,
+                &[],
+                nodes::NodeKind::Movie,
+                order as i64,
+                None,
+            )
+            .await?;
+        }
+        for (user_id, node_id, rating) in [
+            ("user", "loved", 9),
+            ("user", "mixed", 4),
+            ("housemate", "mixed", 10),
+        ] {
+            save_node_rating(&pool, user_id, node_id, Some(rating), None)
+                .await
+                .map_err(|error| anyhow::anyhow!(error.message))?;
+        }
+
+        let query_ids = |filter: NodeFilter| {
+            let pool = pool.clone();
+            async move {
+                let nodes = build_node_query_for_viewer(&pool, None, "user", &filter)
+                    .await
+                    .map_err(|error| anyhow::anyhow!(error.message))?
+                    .all(&pool)
+                    .await?;
+                anyhow::Ok(nodes.into_iter().map(|node| node.id).collect::<Vec<_>>())
+            }
+        };
+
+        assert_eq!(
+            query_ids(NodeFilter {
+                rated: Some(true),
+                ..Default::default()
+            })
+            .await?,
+            ["loved", "mixed"]
+        );
+        assert_eq!(
+            query_ids(NodeFilter {
+                rated: Some(false),
+                ..Default::default()
+            })
+            .await?,
+            ["unrated"]
+        );
+        assert_eq!(
+            query_ids(NodeFilter {
+                min_user_rating: Some(5),
+                ..Default::default()
+            })
+            .await?,
+            ["loved"]
+        );
+        // the housemate's 10 lifts "mixed" to a household average of 7
+        assert_eq!(
+            query_ids(NodeFilter {
+                min_household_rating: Some(7.0),
+                order_by: Some(OrderBy::HouseholdRating),
+                ..Default::default()
+            })
+            .await?,
+            ["loved", "mixed"]
+        );
+        assert_eq!(
+            query_ids(NodeFilter {
+                rated: Some(true),
+                order_by: Some(OrderBy::UserRating),
+                order_direction: Some(OrderDirection::Asc),
+                ..Default::default()
+            })
+            .await?,
+            ["mixed", "loved"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn segments_are_found_by_kind_and_start() {
+        let segment = |kind, start_ms| StoredFileSegment {
+            kind,
+            start_ms,
+            end_ms: start_ms + 1_000,
+            locked: true,
+        };
+        let segments = vec![
+            segment(segment_markers::StoredFileSegmentKind::Commercial, 0),
+            segment(segment_markers::StoredFileSegmentKind::Intro, 0),
+            segment(segment_markers::StoredFileSegmentKind::Credits, 50_000),
+        ];
+
+        assert_eq!(
+            segment_position(&segments, FileSegmentKind::Intro, 0).unwrap(),
+            1
+        );
+        assert!(segment_position(&segments, FileSegmentKind::Intro, 50_000).is_err());
+        assert!(
+            ensure_segment_key_free(
+                &segments,
+                &segment(segment_markers::StoredFileSegmentKind::Credits, 50_000)
+            )
+            .is_err()
+        );
+        assert!(
+            ensure_segment_key_free(
+                &segments,
+                &segment(segment_markers::StoredFileSegmentKind::Recap, 50_000)
+            )
+            .is_ok()
+        );
+    }
+}
@@ -1,4 +1,8 @@
-use crate::entities::nodes;
+use crate::entities::{
+    node_metadata::LockedFields, node_metadata_images::NodeMetadataImageKind, nodes,
+    nodes::ExtraType, people,
+};
+use crate::segment_markers::StoredFileSegmentKind;
 use async_graphql::{Enum, SimpleObject};
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Enum)]
@@ -167,6 +171,60 @@ pub enum MetadataStatus {
     Released,
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Enum)]
+pub enum ImageKind {
+    Poster,
+    Thumbnail,
+    Backdrop,
+    Logo,
+}
+
+impl From<ImageKind> for NodeMetadataImageKind {
+    fn from(value: ImageKind) -> Self {
+        match value {
+            ImageKind::Poster => Self::Poster,
+            ImageKind::Thumbnail => Self::Thumbnail,
+            ImageKind::Backdrop => Self::Backdrop,
+            ImageKind::Logo => Self::Logo,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Enum)]
+pub enum NodeImageSource {
+    Provider,
+    Local,
+    Upload,
+}
+
+// one artwork candidate for a node, `selected` marks the one currently shown
+#[derive(Clone, Debug, SimpleObject)]
+pub struct NodeImage {
+    pub asset: Asset,
+    pub source: NodeImageSource,
+    pub language: Option<String>,
+    pub vote_average: Option<f64>,
+    pub vote_count: Option<i64>,
+    pub width: Option<i64>,
+    pub height: Option<i64>,
+    pub selected: bool,
+}
+
+#[derive(Clone, Debug, SimpleObject)]
+pub struct NodeExtraGroup {
+    pub extra_type: ExtraType,
+    pub extras: Vec<nodes::Model>,
+}
+
+#[derive(Clone, Debug, SimpleObject)]
+pub struct UpcomingEpisode {
+    pub series: nodes::Model,
+    pub season_number: i64,
+    pub episode_number: i64,
+    pub name: Option<String>,
+    pub airs_at: i64,
+}
+
 #[derive(Clone, Debug, SimpleObject)]
 pub struct MetadataGenre {
     pub provider_id: String,
@@ -174,10 +232,26 @@ pub struct MetadataGenre {
     pub name: String,
 }
 
+#[derive(Clone, Debug, SimpleObject)]
+pub struct MetadataCompany {
+    pub provider_id: String,
+    pub external_id: Option<String>,
+    pub name: String,
+    pub origin_country: Option<String>,
+}
+
+#[derive(Clone, Debug, SimpleObject)]
+pub struct MetadataKeyword {
+    pub provider_id: String,
+    pub external_id: Option<String>,
+    pub name: String,
+}
+
 #[derive(Clone, Debug, SimpleObject)]
 pub struct ContentRating {
     pub country_code: String,
     pub rating: String,
+    pub descriptors: Vec<String>,
 }
 
 #[derive(Clone, Debug, SimpleObject)]
@@ -186,20 +260,77 @@ pub struct Person {
     pub id: String,
     pub name: String,
     pub birthday: Option<String>,
+    pub description: Option<String>,
     #[graphql(skip)]
     pub profile_asset_id: Option<String>,
 }
 
+impl From<people::Model> for Person {
+    fn from(person: people::Model) -> Self {
+        Self {
+            id: person.id,
+            name: person.name,
+            birthday: person.birthday,
+            description: person.description,
+            profile_asset_id: person.profile_asset_id,
+        }
+    }
+}
+
 #[derive(Clone, Debug, SimpleObject)]
 pub struct CastMember {
     pub character_name: Option<String>,
     pub department: Option<String>,
+    // set for crew credits, like "Director" or "Screenplay"
+    pub job: Option<String>,
     pub person: Person,
 }
 
+#[derive(Clone, Debug, SimpleObject)]
+pub struct CreditRole {
+    pub character_name: Option<String>,
+    pub department: Option<String>,
+    pub job: Option<String>,
+}
+
+// a movie or series in the library that a person is credited on
+#[derive(Clone, Debug, SimpleObject)]
+pub struct PersonCredit {
+    pub node: nodes::Model,
+    pub roles: Vec<CreditRole>,
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Enum)]
 pub enum FileSegmentKind {
     Intro,
+    Credits,
+    Recap,
+    Preview,
+    Commercial,
+}
+
+impl From<StoredFileSegmentKind> for FileSegmentKind {
+    fn from(kind: StoredFileSegmentKind) -> Self {
+        match kind {
+            StoredFileSegmentKind::Intro => Self::Intro,
+            StoredFileSegmentKind::Credits => Self::Credits,
+            StoredFileSegmentKind::Recap => Self::Recap,
+            StoredFileSegmentKind::Preview => Self::Preview,
+            StoredFileSegmentKind::Commercial => Self::Commercial,
+        }
+    }
+}
+
+impl From<FileSegmentKind> for StoredFileSegmentKind {
+    fn from(kind: FileSegmentKind) -> Self {
+        match kind {
+            FileSegmentKind::Intro => Self::Intro,
+            FileSegmentKind::Credits => Self::Credits,
+            FileSegmentKind::Recap => Self::Recap,
+            FileSegmentKind::Preview => Self::Preview,
+            FileSegmentKind::Commercial => Self::Commercial,
+        }
+    }
 }
 
 #[derive(Clone, Debug, SimpleObject)]
@@ -207,6 +338,42 @@ pub struct FileSegment {
     pub kind: FileSegmentKind,
     pub start_ms: i64,
     pub end_ms: i64,
+    pub locked: bool,
+}
+
+// metadata fields a user can override and lock against provider refreshes
+#[derive(Enum, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MetadataField {
+    Name,
+    Description,
+    Tagline,
+    FirstAired,
+    LastAired,
+    Genres,
+}
+
+impl MetadataField {
+    pub const ALL: [Self; 6] = [
+        Self::Name,
+        Self::Description,
+        Self::Tagline,
+        Self::FirstAired,
+        Self::LastAired,
+        Self::Genres,
+    ];
+}
+
+impl From<MetadataField> for LockedFields {
+    fn from(value: MetadataField) -> Self {
+        match value {
+            MetadataField::Name => Self::NAME,
+            MetadataField::Description => Self::DESCRIPTION,
+            MetadataField::Tagline => Self::TAGLINE,
+            MetadataField::FirstAired => Self::FIRST_AIRED,
+            MetadataField::LastAired => Self::LAST_AIRED,
+            MetadataField::Genres => Self::GENRES,
+        }
+    }
 }
 
 #[derive(Clone, Debug, SimpleObject)]
@@ -225,6 +392,11 @@ pub struct NodeProperties {
     pub updated_at: Option<i64>,
     #[graphql(skip)]
     pub metadata_id: Option<String>,
+    // the node's user row, which holds the hand edited genres when they are locked
+    #[graphql(skip)]
+    pub user_metadata_id: Option<String>,
+    #[graphql(skip)]
+    pub locked: LockedFields,
     #[graphql(skip)]
     pub node_id: String,
     #[graphql(skip)]
@@ -233,6 +405,9 @@ pub struct NodeProperties {
     pub parent_id: Option<String>,
     #[graphql(skip)]
     pub kind: nodes::NodeKind,
+    // the viewer's preferred metadata languages, for resolving localized fields and images
+    #[graphql(skip)]
+    pub languages: Vec<String>,
 }
 
 #[derive(Clone, Debug, SimpleObject)]
@@ -1,13 +1,25 @@
 use crate::{
     activity::ACTIVITY_REGISTRY,
     auth::{AuthenticatedGuard, PermissionGuard, RequestAuth, accessible_library_ids},
+    calendar,
     collections::{
-        recently_added_collection, recently_added_id, recently_released_collection,
-        recently_released_id,
+        min_visible_items, recently_added_collection, recently_added_id,
+        recently_released_collection, recently_released_id,
     },
     entities::root_node_cast,
-    entities::{collections, libraries, node_metadata, nodes, users, watch_progress},
-    graphql::types::collection::collection_item_count,
+    entities::{
+        collections, intro_fingerprints, libraries,
+        node_metadata::{self, LockedFields},
+        node_metadata_companies::{self, NodeMetadataCompanyKind},
+        node_metadata_details, node_metadata_keywords, node_missing_episodes, nodes, people,
+        user_ratings, users, watch_progress,
+    },
+    graphql::{
+        properties::{Person, UpcomingEpisode},
+        types::{
+            collection::collection_item_count, missing_episode::library_missing_episodes_query,
+        },
+    },
     metadata,
 };
 use async_graphql::{
@@ -20,10 +32,14 @@ use sea_orm::{
     ColumnTrait, DatabaseConnection, EntityTrait, JoinType, Order, PaginatorTrait, QueryFilter,
     QueryOrder, QuerySelect, QueryTrait, RelationTrait,
     prelude::Expr,
-    sea_query::{Alias, Func, Query as SeaQuery},
+    sea_query::{Alias, Func, LikeExpr, Query as SeaQuery, SimpleExpr, UnionType},
 };
 use tokio::task::spawn_blocking;
 
+const PEOPLE_SEARCH_LIMIT: u64 = 50;
+// wide enough for a year view, narrow enough that one request can't list every episode ever aired
+const UPCOMING_MAX_RANGE_SECONDS: i64 = 366 * 24 * 60 * 60;
+
 const DIRECTORY_PRIORITY_HINTS: &[&str] = &[
     "mnt",
     "media",
@@ -79,6 +95,15 @@ pub struct NodeFilter {
     pub watched: Option<bool>,
     pub continue_watching: Option<bool>,
     pub released_after: Option<i64>,
+    pub rated: Option<bool>,
+    pub min_user_rating: Option<i32>,
+    pub min_household_rating: Option<f64>,
+    // matched case insensitively against the provider's names, like "A24" or "HBO"
+    pub studio: Option<String>,
+    pub network: Option<String>,
+    pub keyword: Option<String>,
+    // iso 639-1, like "ja"
+    pub original_language: Option<String>,
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Enum, serde::Deserialize, serde::Serialize)]
@@ -115,6 +140,8 @@ pub enum OrderBy {
     Rating,
     Order,
     WatchProgressUpdatedAt,
+    UserRating,
+    HouseholdRating,
 }
 
 impl OrderBy {
@@ -125,7 +152,9 @@ impl OrderBy {
             | OrderBy::FirstAired
             | OrderBy::LastAired
             | OrderBy::ReleasedAt
-            | OrderBy::Rating => OrderDirection::Desc,
+            | OrderBy::Rating
+            | OrderBy::UserRating
+            | OrderBy::HouseholdRating => OrderDirection::Desc,
             OrderBy::Alphabetical | OrderBy::Order => OrderDirection::Asc,
             OrderBy::WatchProgressUpdatedAt => OrderDirection::Desc,
         }
@@ -141,6 +170,17 @@ pub struct Activity {
     pub progress_percent: Option<f64>,
 }
 
+#[derive(Debug, Clone, SimpleObject)]
+pub struct EpisodeOrdering {
+    pub provider_id: String,
+    pub id: String,
+    pub name: String,
+    pub kind: String,
+    pub description: Option<String>,
+    pub group_count: i32,
+    pub episode_count: i32,
+}
+
 #[derive(Debug, Clone, SimpleObject)]
 pub struct HomeView {
     pub sections: Vec<collections::Model>,
@@ -152,6 +192,13 @@ pub fn current_user_id(ctx: &Context<'_>) -> Option<String> {
     Some(user.id.clone())
 }
 
+pub fn current_user_metadata_languages(ctx: &Context<'_>) -> Vec<String> {
+    ctx.data_opt::<RequestAuth>()
+        .and_then(RequestAuth::get_user)
+        .map(users::Model::metadata_languages)
+        .unwrap_or_default()
+}
+
 pub fn collection_visible_to_user(collection: &collections::Model, user_id: &str) -> bool {
     match collection.visibility {
         collections::CollectionVisibility::Public => true,
@@ -209,6 +256,24 @@ pub async fn build_node_query(
     build_node_query_for_viewer(pool, visible_library_ids.as_deref(), &viewer_id, filter).await
 }
 
+// studios, networks and keywords hang off the preferred metadata row the node query joins
+fn metadata_company_condition(kind: NodeMetadataCompanyKind, name: &str) -> SimpleExpr {
+    let company_metadata_ids = node_metadata_companies::Entity::find()
+        .filter(node_metadata_companies::Column::Kind.eq(kind))
+        .filter(nocase_eq(node_metadata_companies::Column::Name, name))
+        .select_only()
+        .column(node_metadata_companies::Column::NodeMetadataId);
+    node_metadata::Column::Id.in_subquery(company_metadata_ids.into_query())
+}
+
+// compared with nocase rather than lower() so the lookup can use the nocase name indexes
+fn nocase_eq(column: impl ColumnTrait, value: &str) -> SimpleExpr {
+    Expr::cust_with_exprs(
+        "? = ? COLLATE NOCASE",
+        [Expr::col(column).into(), Expr::val(value.trim()).into()],
+    )
+}
+
 pub async fn build_node_query_for_viewer(
     pool: &DatabaseConnection,
     visible_library_ids: Option<&[String]>,
@@ -257,9 +322,13 @@ pub async fn build_node_query_for_viewer(
         qb = qb.filter(nodes::Column::SeasonNumber.is_in(season_numbers.clone()));
     }
     if let Some(person_id) = &filter.person_id {
-        qb = qb
-            .join(JoinType::InnerJoin, nodes::Relation::RootNodeCast.def())
+        // a subquery rather than a join, someone who directed and wrote a film has two rows
+        let credited_root_ids = root_node_cast::Entity::find()
             .filter(root_node_cast::Column::PersonId.eq(person_id.clone()))
+            .select_only()
+            .column(root_node_cast::Column::RootNodeId);
+        qb = qb
+            .filter(nodes::Column::Id.in_subquery(credited_root_ids.into_query()))
             .filter(
                 Expr::col((nodes::Entity, nodes::Column::Id))
                     .equals((nodes::Entity, nodes::Column::RootId)),
@@ -272,6 +341,9 @@ pub async fn build_node_query_for_viewer(
         }
 
         qb = qb.filter(nodes::Column::Kind.is_in(kinds.clone()));
+    } else {
+        // extras only show up under their movie or series unless asked for
+        qb = qb.filter(nodes::Column::Kind.ne(nodes::NodeKind::Extra));
     }
 
     if search_term.is_some() && fts_query.is_none() {
@@ -347,13 +419,63 @@ pub async fn build_node_query_for_viewer(
     if let Some(released_after) = filter.released_after {
         qb = qb.filter(
             Expr::expr(Func::coalesce([
-                Expr::col(node_metadata::Column::LastAired).into(),
-                Expr::col(node_metadata::Column::FirstAired).into(),
+                preferred_last_aired(),
+                preferred_first_aired(),
             ]))
             .gte(released_after),
         );
     }
 
+    if let Some(rated) = filter.rated {
+        let rated_node_ids = user_ratings::Entity::find()
+            .filter(user_ratings::Column::UserId.eq(viewer_id.to_string()))
+            .select_only()
+            .column(user_ratings::Column::NodeId);
+        qb = if rated {
+            qb.filter(nodes::Column::Id.in_subquery(rated_node_ids.into_query()))
+        } else {
+            qb.filter(nodes::Column::Id.not_in_subquery(rated_node_ids.into_query()))
+        };
+    }
+
+    if let Some(min_user_rating) = filter.min_user_rating {
+        qb = qb.filter(Expr::expr(user_rating_expr(viewer_id)).gte(min_user_rating));
+    }
+
+    if let Some(min_household_rating) = filter.min_household_rating {
+        qb = qb.filter(Expr::expr(household_rating_expr()).gte(min_household_rating));
+    }
+
+    if let Some(studio) = &filter.studio {
+        qb = qb.filter(metadata_company_condition(
+            NodeMetadataCompanyKind::Studio,
+            studio,
+        ));
+    }
+    if let Some(network) = &filter.network {
+        qb = qb.filter(metadata_company_condition(
+            NodeMetadataCompanyKind::Network,
+            network,
+        ));
+    }
+    if let Some(keyword) = &filter.keyword {
+        let keyword_metadata_ids = node_metadata_keywords::Entity::find()
+            .filter(nocase_eq(node_metadata_keywords::Column::Name, keyword))
+            .select_only()
+            .column(node_metadata_keywords::Column::NodeMetadataId);
+        qb = qb.filter(node_metadata::Column::Id.in_subquery(keyword_metadata_ids.into_query()));
+    }
+    if let Some(original_language) = &filter.original_language {
+        let language_metadata_ids = node_metadata_details::Entity::find()
+            .filter(nocase_eq(
+                node_metadata_details::Column::OriginalLanguage,
+                original_language,
+            ))
+            .select_only()
+            .column(node_metadata_details::Column::NodeMetadataId);
+        qb = qb.filter(node_metadata::Column::Id.in_subquery(language_metadata_ids.into_query()));
+    }
+
     if fts_query.is_some() {
         let search_matches = Alias::new("search_matches");
         qb = qb
@@ -376,30 +498,34 @@ pub async fn build_node_query_for_viewer(
             OrderBy::AddedAt => {
                 qb = qb
                     .order_by(nodes::Column::LastAddedAt, order_direction.clone())
-                    .order_by(node_metadata::Column::LastAired, order_direction.clone())
-                    .order_by(node_metadata::Column::FirstAired, order_direction)
+                    .order_by(preferred_last_aired(), order_direction.clone())
+                    .order_by(preferred_first_aired(), order_direction)
             }
             OrderBy::LastAddedAt => {
                 qb = qb
                     .order_by(nodes::Column::LastAddedAt, order_direction.clone())
-                    .order_by(node_metadata::Column::LastAired, order_direction)
-            }
-            OrderBy::FirstAired => {
-                qb = qb.order_by(node_metadata::Column::FirstAired, order_direction)
-            }
-            OrderBy::LastAired => {
-                qb = qb.order_by(node_metadata::Column::LastAired, order_direction)
+                    .order_by(preferred_last_aired(), order_direction)
             }
+            OrderBy::FirstAired => qb = qb.order_by(preferred_first_aired(), order_direction),
+            OrderBy::LastAired => qb = qb.order_by(preferred_last_aired(), order_direction),
             OrderBy::ReleasedAt => {
                 qb = qb.order_by(
                     Expr::expr(Func::coalesce([
-                        Expr::col(node_metadata::Column::LastAired).into(),
-                        Expr::col(node_metadata::Column::FirstAired).into(),
+                        preferred_last_aired(),
+                        preferred_first_aired(),
                     ])),
                     order_direction,
                 )
             }
-            OrderBy::Alphabetical => qb = qb.order_by(node_metadata::Column::Name, order_direction),
+            OrderBy::Alphabetical => {
+                qb = qb.order_by(
+                    metadata::preferred_metadata_column(
+                        node_metadata::Column::Name,
+                        LockedFields::NAME,
+                    ),
+                    order_direction,
+                )
+            }
             OrderBy::Rating => {
                 qb = qb.order_by(node_metadata::Column::ScoreNormalized, order_direction)
             }
@@ -410,6 +536,8 @@ pub async fn build_node_query_for_viewer(
                     .filter(watch_progress::Column::UserId.eq(viewer_id.to_string()))
                     .order_by(watch_progress::Column::UpdatedAt, order_direction)
             }
+            OrderBy::UserRating => qb = qb.order_by(user_rating_expr(viewer_id), order_direction),
+            OrderBy::HouseholdRating => qb = qb.order_by(household_rating_expr(), order_direction),
         }
     }
 
@@ -417,6 +545,58 @@ pub async fn build_node_query_for_viewer(
     Ok(qb)
 }
 
+fn preferred_first_aired() -> SimpleExpr {
+    metadata::preferred_metadata_column(
+        node_metadata::Column::FirstAired,
+        LockedFields::FIRST_AIRED,
+    )
+}
+
+fn preferred_last_aired() -> SimpleExpr {
+    metadata::preferred_metadata_column(node_metadata::Column::LastAired, LockedFields::LAST_AIRED)
+}
+
+fn user_rating_expr(viewer_id: &str) -> SimpleExpr {
+    SimpleExpr::SubQuery(
+        None,
+        Box::new(
+            SeaQuery::select()
+                .column(user_ratings::Column::Rating)
+                .from(user_ratings::Entity)
+                .and_where(
+                    Expr::col((user_ratings::Entity, user_ratings::Column::NodeId))
+                        .equals((nodes::Entity, nodes::Column::Id)),
+                )
+                .and_where(
+                    Expr::col((user_ratings::Entity, user_ratings::Column::UserId)).eq(viewer_id),
+                )
+                .to_owned()
+                .into_sub_query_statement(),
+        ),
+    )
+}
+
+// household ratings are the mean across every user on the server, not just the viewer.
+fn household_rating_expr() -> SimpleExpr {
+    SimpleExpr::SubQuery(
+        None,
+        Box::new(
+            SeaQuery::select()
+                .expr(Func::avg(Expr::col((
+                    user_ratings::Entity,
+                    user_ratings::Column::Rating,
+                ))))
+                .from(user_ratings::Entity)
+                .and_where(
+                    Expr::col((user_ratings::Entity, user_ratings::Column::NodeId))
+                        .equals((nodes::Entity, nodes::Column::Id)),
+                )
+                .to_owned()
+                .into_sub_query_statement(),
+        ),
+    )
+}
+
 pub async fn paginate_node_query(
     pool: &DatabaseConnection,
     qb: sea_orm::Select<nodes::Entity>,
@@ -490,18 +670,47 @@ fn join_node_search(
     let search_node_id = Alias::new("node_id");
     let search_rank = Alias::new("search_rank");
     let search_rowid = Alias::new("search_rowid");
+    // bm25 depends on the term statistics of the index it ran against, so each index scales its
+    // ranks by its own best match (1.0, stored negated to keep ascending order) before the two
+    // are compared
+    let ranked_matches = |table: &str| {
+        let matches = SeaQuery::select()
+            .expr_as(Expr::col(Alias::new("node_id")), search_node_id.clone())
+            .expr_as(
+                Expr::cust(format!("bm25({table}, 8.0, 1.0)")),
+                search_rank.clone(),
+            )
+            .expr_as(Expr::cust("rowid"), search_rowid.clone())
+            .from(Alias::new(table))
+            .and_where(Expr::cust_with_values(
+                format!("{table} MATCH ?"),
+                [fts_query.to_owned()],
+            ))
+            .to_owned();
+        SeaQuery::select()
+            .column(search_node_id.clone())
+            .expr_as(
+                Expr::cust("-COALESCE(search_rank / NULLIF(MIN(search_rank) OVER (), 0), 1.0)"),
+                search_rank.clone(),
+            )
+            .column(search_rowid.clone())
+            .from_subquery(matches, Alias::new(format!("{table}_matches")))
+            .to_owned()
+    };
+
+    // localized titles live in their own index. a node matching several rows keeps its best
+    // rank so it only shows up once.
+    let mut all_matches = ranked_matches("node_search_fts");
+    all_matches.union(
+        UnionType::All,
+        ranked_matches("node_localization_search_fts"),
+    );
     let search_query = SeaQuery::select()
-        .expr_as(Expr::col(Alias::new("node_id")), search_node_id.clone())
-        .expr_as(
-            Expr::cust("bm25(node_search_fts, 8.0, 1.0)"),
-            search_rank.clone(),
-        )
-        .expr_as(Expr::cust("rowid"), search_rowid.clone())
-        .from(Alias::new("node_search_fts"))
-        .and_where(Expr::cust_with_values(
-            "node_search_fts MATCH ?",
-            [fts_query.to_owned()],
-        ))
+        .column(search_node_id.clone())
+        .expr_as(Expr::cust("MIN(search_rank)"), search_rank.clone())
+        .expr_as(Expr::cust("MIN(search_rowid)"), search_rowid.clone())
+        .from_subquery(all_matches, Alias::new("all_matches"))
+        .group_by_col(search_node_id.clone())
         .to_owned();
 
     QueryTrait::query(&mut qb).join_subquery(
@@ -514,6 +723,53 @@ fn join_node_search(
     qb
 }
 
+// people are shared between libraries, so they're only visible through something the viewer
+// can see them in
+async fn visible_people_query(
+    pool: &DatabaseConnection,
+    auth: &RequestAuth,
+) -> Result<sea_orm::Select<people::Entity>, async_graphql::Error> {
+    let visible_library_ids = accessible_library_ids(pool, auth)
+        .await
+        .map_err(async_graphql::Error::from)?;
+    Ok(visible_people_query_for_viewer(
+        visible_library_ids.as_deref(),
+    ))
+}
+
+fn visible_people_query_for_viewer(
+    visible_library_ids: Option<&[String]>,
+) -> sea_orm::Select<people::Entity> {
+    let mut credited_person_ids = root_node_cast::Entity::find()
+        .select_only()
+        .column(root_node_cast::Column::PersonId)
+        .join(JoinType::InnerJoin, root_node_cast::Relation::Nodes.def());
+    if let Some(visible_library_ids) = visible_library_ids {
+        credited_person_ids = credited_person_ids
+            .filter(nodes::Column::LibraryId.is_in(visible_library_ids.iter().cloned()));
+    }
+
+    people::Entity::find().filter(people::Column::Id.in_subquery(credited_person_ids.into_query()))
+}
+
+// the search is matched literally, so wildcards typed into it don't widen the match
+fn search_people_query(
+    qb: sea_orm::Select<people::Entity>,
+    search: &str,
+) -> sea_orm::Select<people::Entity> {
+    let escaped = search
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    qb.filter(
+        Expr::col((people::Entity, people::Column::Name))
+            .like(LikeExpr::new(format!("%{escaped}%")).escape('\\')),
+    )
+    .order_by_asc(people::Column::Name)
+    .order_by_asc(people::Column::Id)
+    .limit(PEOPLE_SEARCH_LIMIT)
+}
+
 pub struct Query;
 
 #[Object]
@@ -561,6 +817,46 @@ impl Query {
             .ok_or_else(|| async_graphql::Error::new("Node not found"))
     }
 
+    #[graphql(guard = AuthenticatedGuard::new())]
+    async fn person(
+        &self,
+        ctx: &Context<'_>,
+        person_id: String,
+    ) -> Result<Person, async_graphql::Error> {
+        let pool = ctx.data::<DatabaseConnection>()?;
+        let auth = ctx.data::<RequestAuth>()?;
+        visible_people_query(pool, auth)
+            .await?
+            .filter(people::Column::Id.eq(person_id))
+            .one(pool)
+            .await?
+            .map(Person::from)
+            .ok_or_else(|| async_graphql::Error::new("Person not found"))
+    }
+
+    #[graphql(guard = AuthenticatedGuard::new())]
+    async fn people(
+        &self,
+        ctx: &Context<'_>,
+        search: String,
+    ) -> Result<Vec<Person>, async_graphql::Error> {
+        let search = search.trim();
+        if search.is_empty() {
+            return Err(async_graphql::Error::new("search should not be empty"));
+        }
+
+        let pool = ctx.data::<DatabaseConnection>()?;
+        let auth = ctx.data::<RequestAuth>()?;
+        Ok(
+            search_people_query(visible_people_query(pool, auth).await?, search)
+                .all(pool)
+                .await?
+                .into_iter()
+                .map(Person::from)
+                .collect(),
+        )
+    }
+
     #[graphql(guard = PermissionGuard::new(users::UserPerms::ADMIN))]
     async fn list_files(&self, path: String) -> Result<Vec<String>, async_graphql::Error> {
         if !path.starts_with('/') || path.contains("..") || path.contains("/.") {
@@ -624,6 +920,96 @@ impl Query {
             .ok_or_else(|| async_graphql::Error::new("Library not found"))
     }
 
+    /// Announced episodes of series in visible libraries that air between `from` and `to`, which
+    /// can be at most a year apart.
+    #[graphql(guard = AuthenticatedGuard::new())]
+    async fn upcoming(
+        &self,
+        ctx: &Context<'_>,
+        from: i64,
+        to: i64,
+        #[graphql(default = false)] watchlist_only: bool,
+    ) -> Result<Vec<UpcomingEpisode>, async_graphql::Error> {
+        if to <= from {
+            return Err(async_graphql::Error::new("to must be after from"));
+        }
+        if to.saturating_sub(from) > UPCOMING_MAX_RANGE_SECONDS {
+            return Err(async_graphql::Error::new(
+                "from and to can be at most a year apart",
+            ));
+        }
+
+        let pool = ctx.data::<DatabaseConnection>()?;
+        let auth = ctx.data::<RequestAuth>()?;
+        let user_id = auth.get_user_or_err()?.id.clone();
+        let library_ids = accessible_library_ids(pool, auth)
+            .await
+            .map_err(async_graphql::Error::from)?;
+        let episodes = calendar::upcoming_episodes(
+            pool,
+            library_ids.as_deref(),
+            watchlist_only.then_some(user_id.as_str()),
+            from,
+            to,
+        )
+        .await?;
+
+        Ok(episodes
+            .into_iter()
+            .filter_map(|(episode, series)| {
+                Some(UpcomingEpisode {
+                    series,
+                    season_number: episode.season_number,
+                    episode_number: episode.episode_number,
+                    name: episode.name,
+                    airs_at: episode.first_aired?,
+                })
+            })
+            .collect())
+    }
+
+    /// A private iCal feed of upcoming episodes for calendar apps. Anyone with the link can read
+    /// the feed, so treat it like a password. `regenerateCalendarUrl` revokes it.
+    #[graphql(guard = AuthenticatedGuard::new())]
+    async fn calendar_url(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = false)] watchlist_only: bool,
+    ) -> Result<String, async_graphql::Error> {
+        let auth = ctx.data::<RequestAuth>()?;
+        let user = auth.get_user_or_err()?;
+        calendar::sign_calendar_url(user, watchlist_only)
+            .map_err(|error| async_graphql::Error::new(error.to_string()))
+    }
+
+    /// Episodes the metadata providers list for series in a library that have no file yet.
+    #[graphql(guard = AuthenticatedGuard::new())]
+    async fn missing_episodes(
+        &self,
+        ctx: &Context<'_>,
+        library_id: String,
+        #[graphql(default = true)] aired_only: bool,
+        #[graphql(default = false)] include_specials: bool,
+    ) -> Result<Vec<node_missing_episodes::Model>, async_graphql::Error> {
+        let pool = ctx.data::<DatabaseConnection>()?;
+        let auth = ctx.data::<RequestAuth>()?;
+        if let Some(visible_library_ids) = accessible_library_ids(pool, auth)
+            .await
+            .map_err(async_graphql::Error::from)?
+            && !visible_library_ids.contains(&library_id)
+        {
+            return Err(async_graphql::Error::new("Library not found"));
+        }
+
+        Ok(library_missing_episodes_query(
+            &library_id,
+            aired_only.then(|| chrono::Utc::now().timestamp()),
+            include_specials,
+        )
+        .all(pool)
+        .await?)
+    }
+
     #[graphql(guard = AuthenticatedGuard::new())]
     async fn libraries(
         &self,
@@ -668,7 +1054,7 @@ impl Query {
                 continue;
             }
 
-            if collection_item_count(ctx, &collection).await? > 0 {
+            if collection_item_count(ctx, &collection).await? >= min_visible_items(&collection) {
                 visible_sections.push(collection);
             }
         }
@@ -727,7 +1113,7 @@ impl Query {
                 continue;
             }
 
-            if collection_item_count(ctx, &collection).await? > 0 {
+            if collection_item_count(ctx, &collection).await? >= min_visible_items(&collection) {
                 visible_collections.push(collection);
             }
         }
@@ -777,7 +1163,7 @@ impl Query {
             return Ok(None);
         }
 
-        if collection_item_count(ctx, &collection).await? == 0 {
+        if collection_item_count(ctx, &collection).await? < min_visible_items(&collection) {
             return Ok(None);
         }
 
@@ -804,6 +1190,120 @@ impl Query {
             .await?)
     }
 
+    #[graphql(guard = PermissionGuard::new(users::UserPerms::ADMIN))]
+    async fn intro_fingerprints(
+        &self,
+        ctx: &Context<'_>,
+        root_id: Option<String>,
+        shared: Option<bool>,
+    ) -> Result<Vec<intro_fingerprints::Model>, async_graphql::Error> {
+        let pool = ctx.data::<DatabaseConnection>()?;
+        let mut query = intro_fingerprints::Entity::find();
+        if let Some(root_id) = root_id {
+            query = query.filter(intro_fingerprints::Column::RootId.eq(root_id));
+        }
+        if let Some(shared) = shared {
+            query = query.filter(intro_fingerprints::Column::Shared.eq(shared));
+        }
+
+        Ok(query
+            .order_by_asc(intro_fingerprints::Column::RootId)
+            .order_by_asc(intro_fingerprints::Column::CreatedAt)
+            .all(pool)
+            .await?)
+    }
+
+    /// Search every metadata provider for candidates to fix a root's match. `query` defaults to
+    /// the root's parsed title.
+    #[graphql(guard = PermissionGuard::new(users::UserPerms::ADMIN))]
+    async fn search_metadata_candidates(
+        &self,
+        ctx: &Context<'_>,
+        node_id: String,
+        query: Option<String>,
+        year: Option<i32>,
+    ) -> Result<Vec<metadata::MetadataCandidate>, async_graphql::Error> {
+        let pool = ctx.data::<DatabaseConnection>()?;
+        let node = nodes::Entity::find_by_id(node_id)
+            .one(pool)
+            .await?
+            .ok_or_else(|| async_graphql::Error::new("Node not found"))?;
+        let root = nodes::Entity::find_by_id(node.root_id)
+            .one(pool)
+            .await?
+            .ok_or_else(|| async_graphql::Error::new("Root node not found"))?;
+        if !matches!(root.kind, nodes::NodeKind::Movie | nodes::NodeKind::Series) {
+            return Err(async_graphql::Error::new(
+                "Only movies and series can be matched",
+            ));
+        }
+
+        // anime-only providers only answer searches for roots that would match them
+        let anime = metadata::root_is_anime(pool, &root).await?;
+        let query = query
+            .map(|query| query.trim().to_string())
+            .filter(|query| !query.is_empty())
+            .unwrap_or(root.name);
+
+        let mut candidates = Vec::new();
+        let mut errors = Vec::new();
+        let providers = metadata::metadata_providers();
+        for provider in &providers {
+            match metadata::search_candidates(provider.as_ref(), root.kind, &query, year, anime)
+                .await
+            {
+                Ok(found) => candidates.extend(found),
+                Err(error) => errors.push(format!("{}: {error:#}", provider.id())),
+            }
+        }
+
+        if candidates.is_empty() && !errors.is_empty() {
+            return Err(async_graphql::Error::new(format!(
+                "Metadata search failed: {}",
+                errors.join("; ")
+            )));
+        }
+
+        candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+        Ok(candidates)
+    }
+
+    /// Alternate episode orderings (DVD, absolute, story arcs) offered by any metadata provider
+    /// for a series root.
+    #[graphql(guard = PermissionGuard::new(users::UserPerms::ADMIN))]
+    async fn episode_orderings(
+        &self,
+        ctx: &Context<'_>,
+        node_id: String,
+    ) -> Result<Vec<EpisodeOrdering>, async_graphql::Error> {
+        let pool = ctx.data::<DatabaseConnection>()?;
+        let node = nodes::Entity::find_by_id(node_id)
+            .one(pool)
+            .await?
+            .ok_or_else(|| async_graphql::Error::new("Node not found"))?;
+        let root = nodes::Entity::find_by_id(node.root_id)
+            .one(pool)
+            .await?
+            .ok_or_else(|| async_graphql::Error::new("Root node not found"))?;
+
+        let orderings =
+            metadata::lookup_root_episode_orderings(pool, &metadata::metadata_providers(), &root)
+                .await
+                .map_err(|error| async_graphql::Error::new(format!("{error:#}")))?;
+        Ok(orderings
+            .into_iter()
+            .map(|(provider_id, ordering)| EpisodeOrdering {
+                provider_id,
+                id: ordering.id,
+                name: ordering.name,
+                kind: ordering.kind,
+                description: ordering.description,
+                group_count: ordering.group_count,
+                episode_count: ordering.episode_count,
+            })
+            .collect())
+    }
+
     #[graphql(guard = PermissionGuard::new(users::UserPerms::ADMIN))]
     async fn activities(&self, _ctx: &Context<'_>) -> Result<Vec<Activity>, async_graphql::Error> {
         Ok(ACTIVITY_REGISTRY
@@ -819,3 +1319,363 @@ impl Query {
             .collect())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::{
+        metadata_source::MetadataSource, node_ordering_positions, root_episode_orderings,
+    };
+    use crate::graphql::types::node_properties::person_credits_for_viewer;
+    use sea_orm::{ActiveValue::Set, Database};
+
+    async fn setup_test_db() -> anyhow::Result<DatabaseConnection> {
+        let pool = Database::connect("sqlite::memory:").await?;
+        sqlx::migrate!("../../migrations")
+            .run(pool.get_sqlite_connection_pool())
+            .await?;
+
+        for library_id in ["lib", "other"] {
+            libraries::Entity::insert(libraries::ActiveModel {
+                id: Set(library_id.to_owned()),
+                path: Set(format!("/{library_id}")),
+                name: Set(library_id.to_owned()),
+                pinned: Set(false),
+                recordings: Set(false),
+                anime: Set(false),
+                last_scanned_at: Set(None),
+                unavailable_at: Set(None),
+                created_at: Set(0),
+            })
+            .exec(&pool)
+            .await?;
+        }
+        Ok(pool)
+    }
+
+    async fn insert_movie(
+        pool: &DatabaseConnection,
+        id: &str,
+        library_id: &str,
+        first_aired: i64,
+    ) -> anyhow::Result<()> {
+        nodes::Entity::insert(nodes::ActiveModel {
+            id: Set(id.to_owned()),
+            library_id: Set(library_id.to_owned()),
+            root_id: Set(id.to_owned()),
+            parent_id: Set(None),
+            kind: Set(nodes::NodeKind::Movie),
+            name: Set(id.to_owned()),
+            order: Set(0),
+            season_number: Set(None),
+            episode_number: Set(None),
+            extra_type: Set(None),
+            last_added_at: Set(0),
+            last_fingerprint_version: Set(None),
+            unavailable_at: Set(None),
+            created_at: Set(0),
+            updated_at: Set(0),
+        })
+        .exec(pool)
+        .await?;
+        node_metadata::Entity::insert(node_metadata::ActiveModel {
+            id: Set(format!("remote-{id}")),
+            node_id: Set(id.to_owned()),
+            source: Set(MetadataSource::Remote),
+            provider_id: Set("tmdb".to_owned()),
+            name: Set(id.to_owned()),
+            first_aired: Set(Some(first_aired)),
+            created_at: Set(0),
+            updated_at: Set(0),
+            ..Default::default()
+        })
+        .exec(pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn insert_series_node(
+        pool: &DatabaseConnection,
+        id: &str,
+        library_id: &str,
+        root_id: &str,
+        numbers: Option<(i64, i64)>,
+        unavailable_at: Option<i64>,
+    ) -> anyhow::Result<()> {
+        let is_root = id == root_id;
+        nodes::Entity::insert(nodes::ActiveModel {
+            id: Set(id.to_owned()),
+            library_id: Set(library_id.to_owned()),
+            root_id: Set(root_id.to_owned()),
+            parent_id: Set((!is_root).then(|| root_id.to_owned())),
+            kind: Set(if is_root {
+                nodes::NodeKind::Series
+            } else {
+                nodes::NodeKind::Episode
+            }),
+            name: Set(id.to_owned()),
+            order: Set(numbers.map_or(0, |(_, episode)| episode)),
+            season_number: Set(numbers.map(|(season, _)| season)),
+            episode_number: Set(numbers.map(|(_, episode)| episode)),
+            extra_type: Set(None),
+            last_added_at: Set(0),
+            last_fingerprint_version: Set(None),
+            unavailable_at: Set(unavailable_at),
+            created_at: Set(0),
+            updated_at: Set(0),
+        })
+        .exec(pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn insert_missing_episode(
+        pool: &DatabaseConnection,
+        root_id: &str,
+        season_number: i64,
+        episode_number: i64,
+        first_aired: Option<i64>,
+    ) -> anyhow::Result<()> {
+        node_missing_episodes::Entity::insert(node_missing_episodes::ActiveModel {
+            root_id: Set(root_id.to_owned()),
+            season_number: Set(season_number),
+            episode_number: Set(episode_number),
+            name: Set(None),
+            description: Set(None),
+            first_aired: Set(first_aired),
+            created_at: Set(0),
+        })
+        .exec(pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn library_missing_numbers(
+        pool: &DatabaseConnection,
+        aired_before: Option<i64>,
+        include_specials: bool,
+    ) -> anyhow::Result<Vec<(String, i64, i64)>> {
+        Ok(
+            library_missing_episodes_query("lib", aired_before, include_specials)
+                .all(pool)
+                .await?
+                .into_iter()
+                .map(|episode| {
+                    (
+                        episode.root_id,
+                        episode.season_number,
+                        episode.episode_number,
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    async fn insert_person(pool: &DatabaseConnection, id: &str, name: &str) -> anyhow::Result<()> {
+        people::Entity::insert(people::ActiveModel {
+            id: Set(id.to_owned()),
+            provider_id: Set("tmdb".to_owned()),
+            provider_person_id: Set(id.to_owned()),
+            name: Set(name.to_owned()),
+            birthday: Set(None),
+            description: Set(None),
+            profile_asset_id: Set(None),
+            created_at: Set(0),
+            updated_at: Set(0),
+        })
+        .exec(pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn insert_credit(
+        pool: &DatabaseConnection,
+        root_id: &str,
+        person_id: &str,
+        character_name: Option<&str>,
+        job: Option<&str>,
+        position: i64,
+    ) -> anyhow::Result<()> {
+        root_node_cast::Entity::insert(root_node_cast::ActiveModel {
+            id: Set(format!("{root_id}-{person_id}-{position}")),
+            root_node_id: Set(root_id.to_owned()),
+            person_id: Set(person_id.to_owned()),
+            character_name: Set(character_name.map(str::to_owned)),
+            department: Set(job.map(|_| "Directing".to_owned())),
+            job: Set(job.map(str::to_owned)),
+            position: Set(position),
+            created_at: Set(0),
+        })
+        .exec(pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn search_names(
+        pool: &DatabaseConnection,
+        visible_library_ids: Option<&[String]>,
+        search: &str,
+    ) -> anyhow::Result<Vec<String>> {
+        Ok(
+            search_people_query(visible_people_query_for_viewer(visible_library_ids), search)
+                .all(pool)
+                .await?
+                .into_iter()
+                .map(|person| person.name)
+                .collect(),
+        )
+    }
+
+    #[tokio::test]
+    async fn people_search_matches_wildcards_literally_within_visible_libraries()
+    -> anyhow::Result<()> {
+        let pool = setup_test_db().await?;
+        insert_movie(&pool, "movie", "lib", 0).await?;
+        insert_movie(&pool, "hidden", "other", 0).await?;
+        for (position, (id, name, root_id)) in [
+            ("percent", "Ann 50% Smith", "movie"),
+            ("digits", "Ann 500 Smith", "movie"),
+            ("underscore", "Under_Score", "movie"),
+            ("letter", "UnderXScore", "movie"),
+            ("elsewhere", "Ann 50% Jones", "hidden"),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            insert_person(&pool, id, name).await?;
+            insert_credit(&pool, root_id, id, Some("Someone"), None, position as i64).await?;
+        }
+        let visible = ["lib".to_owned()];
+
+        assert_eq!(
+            search_names(&pool, Some(&visible), "50%").await?,
+            ["Ann 50% Smith"]
+        );
+        assert_eq!(
+            search_names(&pool, Some(&visible), "r_s").await?,
+            ["Under_Score"]
+        );
+        assert_eq!(
+            search_names(&pool, Some(&visible), "ann").await?,
+            ["Ann 50% Smith", "Ann 500 Smith"]
+        );
+        assert_eq!(
+            search_names(&pool, None, "50%").await?,
+            ["Ann 50% Jones", "Ann 50% Smith"]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn person_credits_are_newest_first_with_every_role() -> anyhow::Result<()> {
+        let pool = setup_test_db().await?;
+        insert_movie(&pool, "old", "lib", 100).await?;
+        insert_movie(&pool, "new", "lib", 300).await?;
+        insert_movie(&pool, "hidden", "other", 200).await?;
+        insert_movie(&pool, "uncredited", "lib", 400).await?;
+        insert_person(&pool, "person", "Ann Smith").await?;
+        insert_credit(&pool, "old", "person", Some("Hero"), None, 0).await?;
+        insert_credit(&pool, "new", "person", None, Some("Director"), 5).await?;
+        insert_credit(&pool, "new", "person", Some("Villain"), None, 1).await?;
+        insert_credit(&pool, "hidden", "person", Some("Cameo"), None, 0).await?;
+
+        let visible = ["lib".to_owned()];
+        let credits =
+            person_credits_for_viewer(&pool, Some(&visible), "viewer", "person", None, None)
+                .await
+                .map_err(|error| anyhow::anyhow!(error.message))?;
+
+        let credits = credits
+            .edges
+            .iter()
+            .map(|edge| {
+                (
+                    edge.node.node.id.as_str(),
+                    edge.node
+                        .roles
+                        .iter()
+                        .map(|role| {
+                            role.character_name
+                                .as_deref()
+                                .or(role.job.as_deref())
+                                .unwrap_or_default()
+                        })
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            credits,
+            [("new", vec!["Villain", "Director"]), ("old", vec!["Hero"])]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn library_missing_episodes_apply_the_aired_and_special_filters() -> anyhow::Result<()> {
+        let pool = setup_test_db().await?;
+        insert_series_node(&pool, "show", "lib", "show", None, None).await?;
+        insert_series_node(&pool, "show-e1", "lib", "show", Some((1, 1)), None).await?;
+        insert_series_node(&pool, "gone", "lib", "gone", None, Some(50)).await?;
+        insert_series_node(&pool, "elsewhere", "other", "elsewhere", None, None).await?;
+        insert_missing_episode(&pool, "show", 1, 1, Some(100)).await?;
+        insert_missing_episode(&pool, "show", 1, 2, Some(100)).await?;
+        insert_missing_episode(&pool, "show", 1, 3, Some(2_000)).await?;
+        insert_missing_episode(&pool, "show", 1, 4, None).await?;
+        insert_missing_episode(&pool, "show", 0, 1, Some(100)).await?;
+        insert_missing_episode(&pool, "gone", 1, 1, Some(100)).await?;
+        insert_missing_episode(&pool, "elsewhere", 1, 1, Some(100)).await?;
+
+        let show = |season, episode| ("show".to_owned(), season, episode);
+        assert_eq!(
+            library_missing_numbers(&pool, Some(1_000), false).await?,
+            [show(1, 2)]
+        );
+        assert_eq!(
+            library_missing_numbers(&pool, Some(1_000), true).await?,
+            [show(0, 1), show(1, 2)]
+        );
+        assert_eq!(
+            library_missing_numbers(&pool, None, false).await?,
+            [show(1, 2), show(1, 3), show(1, 4)]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn missing_episodes_compare_against_the_selected_ordering() -> anyhow::Result<()> {
+        let pool = setup_test_db().await?;
+        insert_series_node(&pool, "show", "lib", "show", None, None).await?;
+        insert_series_node(&pool, "show-e5", "lib", "show", Some((1, 5)), None).await?;
+        root_episode_orderings::Entity::insert(root_episode_orderings::ActiveModel {
+            root_id: Set("show".to_owned()),
+            provider_id: Set("tmdb".to_owned()),
+            ordering_id: Set("dvd".to_owned()),
+            created_at: Set(0),
+        })
+        .exec(&pool)
+        .await?;
+        node_ordering_positions::Entity::insert(node_ordering_positions::ActiveModel {
+            node_id: Set("show-e5".to_owned()),
+            root_id: Set("show".to_owned()),
+            season_number: Set(2),
+            episode_number: Set(1),
+            created_at: Set(0),
+        })
+        .exec(&pool)
+        .await?;
+        // in the dvd ordering s01e05 is some other episode, the file is s02e01 there
+        insert_missing_episode(&pool, "show", 1, 5, Some(100)).await?;
+        insert_missing_episode(&pool, "show", 2, 1, Some(100)).await?;
+
+        assert_eq!(
+            library_missing_numbers(&pool, None, false).await?,
+            [("show".to_owned(), 1, 5)]
+        );
+
+        Ok(())
+    }
+}
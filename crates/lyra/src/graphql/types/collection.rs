@@ -1,5 +1,8 @@
 use crate::auth::{RequestAuth, accessible_library_ids};
-use crate::entities::{collection_items, collections, nodes, users};
+use crate::entities::{
+    assets, collection_items, collections, node_metadata_collections, nodes, users,
+};
+use crate::graphql::properties::Asset;
 use crate::graphql::query::{
     build_node_query_for_viewer, collection_editable_by_user, current_user_id,
     is_watchlist_collection, paginate_node_query,
@@ -136,6 +139,17 @@ impl collections::Model {
             .await
     }
 
+    /// Provider artwork for franchise collections.
+    pub async fn poster(&self, ctx: &Context<'_>) -> Result<Option<Asset>, sea_orm::DbErr> {
+        self.franchise_asset(ctx, node_metadata_collections::Column::PosterAssetId)
+            .await
+    }
+
+    pub async fn backdrop(&self, ctx: &Context<'_>) -> Result<Option<Asset>, sea_orm::DbErr> {
+        self.franchise_asset(ctx, node_metadata_collections::Column::BackdropAssetId)
+            .await
+    }
+
     pub async fn can_edit(&self, ctx: &Context<'_>) -> Result<bool, async_graphql::Error> {
         let auth = ctx.data::<RequestAuth>()?;
         let user = auth.get_user_or_err()?;
@@ -249,3 +263,35 @@ impl collections::Model {
         }
     }
 }
+
+impl collections::Model {
+    // every member movie carries a copy of the franchise artwork, any of them will do
+    async fn franchise_asset(
+        &self,
+        ctx: &Context<'_>,
+        column: node_metadata_collections::Column,
+    ) -> Result<Option<Asset>, sea_orm::DbErr> {
+        let (Some(provider_id), Some(external_id)) = (&self.provider_id, &self.external_id) else {
+            return Ok(None);
+        };
+
+        let pool = ctx.data_unchecked::<DatabaseConnection>();
+        let asset_id = node_metadata_collections::Entity::find()
+            .filter(node_metadata_collections::Column::ProviderId.eq(provider_id.clone()))
+            .filter(node_metadata_collections::Column::ExternalId.eq(external_id.clone()))
+            .filter(column.is_not_null())
+            .select_only()
+            .column(column)
+            .into_tuple::<String>()
+            .one(pool)
+            .await?;
+        let Some(asset_id) = asset_id else {
+            return Ok(None);
+        };
+
+        Ok(assets::Entity::find_by_id(asset_id)
+            .one(pool)
+            .await?
+            .map(Asset::from))
+    }
+}
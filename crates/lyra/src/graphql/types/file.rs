@@ -6,7 +6,7 @@ use crate::entities::{
     file_probe, file_subtitles, files, node_files, nodes, users, watch_progress,
 };
 use crate::graphql::properties::{
-    FileProbe, FileSegment, FileSegmentKind, Playback, PlaybackAudioCodec, PlaybackAudioProfileId,
+    FileProbe, FileSegment, Playback, PlaybackAudioCodec, PlaybackAudioProfileId,
     PlaybackAudioRendition, PlaybackAudioTrack, PlaybackSubtitleCodec, PlaybackSubtitleKind,
     PlaybackSubtitleRendition, PlaybackSubtitleTrack, PlaybackVideoCodec, PlaybackVideoProfileId,
     PlaybackVideoRendition, PlaybackVideoTrack, TimelinePreviewSheet, TrackDispositionPreference,
@@ -14,7 +14,7 @@ use crate::graphql::properties::{
 use crate::graphql::query::current_user_id;
 use crate::hls;
 use crate::jobs;
-use crate::segment_markers::StoredFileSegmentKind;
+use crate::segment_markers::{StoredFileSegmentKind, load_file_segments};
 use crate::subtitles::job_extract::FileSubtitleExtractJob;
 use crate::subtitles::job_process::FileSubtitleProcessJob;
 use crate::subtitles::language::{
@@ -50,7 +50,7 @@ impl files::Model {
         Ok(probe
             .as_ref()
             .and_then(|probe| probe.get_probe().ok())
-            .map(|probe| summarize_probe(&probe)))
+            .map(|probe| summarize_probe(&probe, commercial_seconds(self))))
     }
 
     pub async fn resume_hint(
@@ -293,14 +293,11 @@ impl files::Model {
                     return None;
                 }
 
-                let kind = match segment.kind {
-                    StoredFileSegmentKind::Intro => FileSegmentKind::Intro,
-                };
-
                 Some(FileSegment {
-                    kind,
+                    kind: segment.kind.into(),
                     start_ms: segment.start_ms,
                     end_ms: segment.end_ms,
+                    locked: segment.locked,
                 })
             })
             .collect())
@@ -722,7 +719,19 @@ fn subtitle_kind_label(kind: file_subtitles::SubtitleKind) -> &'static str {
     }
 }
 
-fn summarize_probe(probe: &lyra_probe::ProbeData) -> FileProbe {
+// commercial breaks in recordings aren't part of the programme, so they're left out of the
+// runtime shown to users. duration_seconds stays the real file length for the player.
+fn commercial_seconds(file: &files::Model) -> i64 {
+    load_file_segments(file)
+        .unwrap_or_default()
+        .iter()
+        .filter(|segment| segment.kind == StoredFileSegmentKind::Commercial)
+        .map(|segment| (segment.end_ms - segment.start_ms).max(0))
+        .sum::<i64>()
+        / 1000
+}
+
+fn summarize_probe(probe: &lyra_probe::ProbeData, commercial_seconds: i64) -> FileProbe {
     let video = probe.get_video_stream();
     let audio = probe.get_audio_stream();
     let duration_seconds = probe
@@ -731,7 +740,8 @@ fn summarize_probe(probe: &lyra_probe::ProbeData) -> FileProbe {
         .filter(|seconds| *seconds > 0);
 
     FileProbe {
-        runtime_minutes: duration_seconds.map(minutes_from_seconds_ceil),
+        runtime_minutes: duration_seconds
+            .map(|seconds| minutes_from_seconds_ceil((seconds - commercial_seconds).max(1))),
         duration_seconds,
         width: video.and_then(|stream| stream.width()).map(i64::from),
         height: video.and_then(|stream| stream.height()).map(i64::from),
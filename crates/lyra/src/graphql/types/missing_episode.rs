@@ -0,0 +1,145 @@
+use crate::entities::{
+    node_missing_episodes, node_ordering_positions, nodes, root_episode_orderings,
+};
+use async_graphql::{ComplexObject, Context};
+use sea_orm::{
+    ColumnTrait, Condition, DatabaseConnection, EntityTrait, JoinType, QueryFilter, QueryOrder,
+    QuerySelect, RelationTrait, Select,
+    sea_query::{Expr, Query},
+};
+
+// rows are only rebuilt when the root syncs again, so episodes picked up by a scan in the
+// meantime are hidden here instead of waiting for the next sync. with an ordering selected the
+// provider numbers missing episodes in that ordering, so they're compared against where each
+// episode landed in it rather than against the numbers parsed from the file name.
+pub fn missing_episodes_query(
+    aired_before: Option<i64>,
+    include_specials: bool,
+) -> Select<node_missing_episodes::Entity> {
+    let ordering_selected = Expr::exists(
+        Query::select()
+            .expr(Expr::val(1))
+            .from(root_episode_orderings::Entity)
+            .and_where(
+                Expr::col((
+                    root_episode_orderings::Entity,
+                    root_episode_orderings::Column::RootId,
+                ))
+                .equals((
+                    node_missing_episodes::Entity,
+                    node_missing_episodes::Column::RootId,
+                )),
+            )
+            .to_owned(),
+    );
+    let in_library = Expr::exists(
+        Query::select()
+            .expr(Expr::val(1))
+            .from(nodes::Entity)
+            .and_where(Expr::col((nodes::Entity, nodes::Column::RootId)).equals((
+                node_missing_episodes::Entity,
+                node_missing_episodes::Column::RootId,
+            )))
+            .and_where(Expr::col((nodes::Entity, nodes::Column::Kind)).eq(nodes::NodeKind::Episode))
+            .and_where(
+                Expr::col((nodes::Entity, nodes::Column::SeasonNumber)).equals((
+                    node_missing_episodes::Entity,
+                    node_missing_episodes::Column::SeasonNumber,
+                )),
+            )
+            .and_where(
+                Expr::col((nodes::Entity, nodes::Column::EpisodeNumber)).equals((
+                    node_missing_episodes::Entity,
+                    node_missing_episodes::Column::EpisodeNumber,
+                )),
+            )
+            .to_owned(),
+    );
+    let in_ordering = Expr::exists(
+        Query::select()
+            .expr(Expr::val(1))
+            .from(node_ordering_positions::Entity)
+            .and_where(
+                Expr::col((
+                    node_ordering_positions::Entity,
+                    node_ordering_positions::Column::RootId,
+                ))
+                .equals((
+                    node_missing_episodes::Entity,
+                    node_missing_episodes::Column::RootId,
+                )),
+            )
+            .and_where(
+                Expr::col((
+                    node_ordering_positions::Entity,
+                    node_ordering_positions::Column::SeasonNumber,
+                ))
+                .equals((
+                    node_missing_episodes::Entity,
+                    node_missing_episodes::Column::SeasonNumber,
+                )),
+            )
+            .and_where(
+                Expr::col((
+                    node_ordering_positions::Entity,
+                    node_ordering_positions::Column::EpisodeNumber,
+                ))
+                .equals((
+                    node_missing_episodes::Entity,
+                    node_missing_episodes::Column::EpisodeNumber,
+                )),
+            )
+            .to_owned(),
+    );
+
+    let mut query = node_missing_episodes::Entity::find().filter(
+        Condition::any()
+            .add(
+                Condition::all()
+                    .add(ordering_selected.clone())
+                    .add(Condition::all().not().add(in_ordering)),
+            )
+            .add(
+                Condition::all()
+                    .add(Condition::all().not().add(ordering_selected))
+                    .add(Condition::all().not().add(in_library)),
+            ),
+    );
+    if let Some(aired_before) = aired_before {
+        query = query.filter(node_missing_episodes::Column::FirstAired.lte(aired_before));
+    }
+    if !include_specials {
+        query = query.filter(node_missing_episodes::Column::SeasonNumber.ne(0));
+    }
+    query
+}
+
+// missing episodes for every available series in a library, grouped by series
+pub fn library_missing_episodes_query(
+    library_id: &str,
+    aired_before: Option<i64>,
+    include_specials: bool,
+) -> Select<node_missing_episodes::Entity> {
+    missing_episodes_query(aired_before, include_specials)
+        .join(
+            JoinType::InnerJoin,
+            node_missing_episodes::Relation::Nodes.def(),
+        )
+        .filter(nodes::Column::LibraryId.eq(library_id))
+        .filter(nodes::Column::UnavailableAt.is_null())
+        .order_by_asc(nodes::Column::Name)
+        .order_by_asc(node_missing_episodes::Column::RootId)
+        .order_by_asc(node_missing_episodes::Column::SeasonNumber)
+        .order_by_asc(node_missing_episodes::Column::EpisodeNumber)
+}
+
+#[ComplexObject]
+impl node_missing_episodes::Model {
+    pub async fn series(&self, ctx: &Context<'_>) -> Result<nodes::Model, async_graphql::Error> {
+        let pool = ctx.data_unchecked::<DatabaseConnection>();
+        nodes::Entity::find_by_id(self.root_id.clone())
+            .one(pool)
+            .await?
+            .ok_or_else(|| async_graphql::Error::new("Series not found"))
+    }
+}
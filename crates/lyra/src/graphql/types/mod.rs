@@ -1,6 +1,7 @@
 pub mod asset;
 pub mod collection;
 pub mod file;
+pub mod missing_episode;
 pub mod node;
 pub mod node_properties;
 pub mod user;
@@ -1,17 +1,22 @@
+use crate::collections::min_visible_items;
 use crate::entities::{
-    collection_items, metadata_source::MetadataSource, node_closure, node_files, node_metadata,
-    node_metadata_recommendations, node_metadata_recommendations::RecommendationMediaKind, nodes,
-    watch_progress,
+    collection_items, collections, metadata_source::MetadataSource, node_closure, node_files,
+    node_metadata, node_metadata_recommendations,
+    node_metadata_recommendations::RecommendationMediaKind, node_missing_episodes, nodes,
+    root_episode_orderings, root_matches, user_ratings, watch_progress,
 };
 use crate::graphql::dataloaders::node_counts::{NodeCounts, NodeCountsLoader};
 use crate::graphql::dataloaders::node_metadata::NodeMetadataLoader;
-use crate::graphql::properties::NodeProperties;
-use crate::graphql::query::current_user_id;
+use crate::graphql::dataloaders::node_ratings::{NodeRatingKey, NodeRatingLoader, NodeRatings};
+use crate::graphql::properties::{ImageKind, NodeExtraGroup, NodeImage, NodeProperties};
+use crate::graphql::query::{current_user_id, current_user_metadata_languages};
+use crate::graphql::types::collection::collection_item_count;
+use crate::graphql::types::missing_episode::missing_episodes_query;
 use async_graphql::dataloader::DataLoader;
 use async_graphql::{ComplexObject, Context};
 use sea_orm::{
-    ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
-    RelationTrait,
+    ColumnTrait, Condition, DatabaseConnection, EntityTrait, JoinType, QueryFilter, QueryOrder,
+    QuerySelect, RelationTrait,
 };
 
 async fn previous_or_next_playable(
@@ -86,7 +91,10 @@ async fn previous_or_next_playable(
 }
 
 fn is_playable_node(node: &nodes::Model) -> bool {
-    matches!(node.kind, nodes::NodeKind::Movie | nodes::NodeKind::Episode)
+    matches!(
+        node.kind,
+        nodes::NodeKind::Movie | nodes::NodeKind::Episode | nodes::NodeKind::Extra
+    )
 }
 
 async fn load_node_counts(ctx: &Context<'_>, node_id: &str) -> Result<NodeCounts, sea_orm::DbErr> {
@@ -98,8 +106,46 @@ async fn load_node_counts(ctx: &Context<'_>, node_id: &str) -> Result<NodeCounts
         .unwrap_or_default())
 }
 
+async fn load_node_ratings(
+    ctx: &Context<'_>,
+    node_id: &str,
+    user_id: Option<String>,
+) -> Result<NodeRatings, sea_orm::DbErr> {
+    let loader = ctx.data_unchecked::<DataLoader<NodeRatingLoader>>();
+    Ok(loader
+        .load_one(NodeRatingKey {
+            node_id: node_id.to_owned(),
+            user_id,
+        })
+        .await
+        .map_err(sea_orm::DbErr::Custom)?
+        .unwrap_or_default())
+}
+
 #[ComplexObject]
 impl nodes::Model {
+    /// The provider match an admin pinned this node's root to, if any.
+    pub async fn root_match(
+        &self,
+        ctx: &Context<'_>,
+    ) -> Result<Option<root_matches::Model>, sea_orm::DbErr> {
+        let pool = ctx.data_unchecked::<DatabaseConnection>();
+        root_matches::Entity::find_by_id(self.root_id.clone())
+            .one(pool)
+            .await
+    }
+
+    /// The alternate episode ordering picked for this node's root, if any.
+    pub async fn episode_ordering(
+        &self,
+        ctx: &Context<'_>,
+    ) -> Result<Option<root_episode_orderings::Model>, sea_orm::DbErr> {
+        let pool = ctx.data_unchecked::<DatabaseConnection>();
+        root_episode_orderings::Entity::find_by_id(self.root_id.clone())
+            .one(pool)
+            .await
+    }
+
     pub async fn root(&self, ctx: &Context<'_>) -> Result<Option<nodes::Model>, sea_orm::DbErr> {
         let pool = ctx.data_unchecked::<DatabaseConnection>();
         nodes::Entity::find_by_id(self.root_id.clone())
@@ -120,6 +166,8 @@ impl nodes::Model {
         let pool = ctx.data_unchecked::<DatabaseConnection>();
         nodes::Entity::find()
             .filter(nodes::Column::ParentId.eq(self.id.clone()))
+            // extras are listed separately by `extras`
+            .filter(nodes::Column::Kind.ne(nodes::NodeKind::Extra))
             .order_by_asc(nodes::Column::Order)
             .order_by_asc(nodes::Column::Id)
             .all(pool)
@@ -133,7 +181,117 @@ impl nodes::Model {
             .load_one(self.id.clone())
             .await
             .map_err(sea_orm::DbErr::Custom)?;
-        NodeProperties::from_node(pool, self, metadata).await
+        NodeProperties::from_node(pool, self, metadata, current_user_metadata_languages(ctx)).await
+    }
+
+    /// Artwork that can be picked for this node with `setNodeImage`.
+    pub async fn available_images(
+        &self,
+        ctx: &Context<'_>,
+        kind: ImageKind,
+    ) -> Result<Vec<NodeImage>, sea_orm::DbErr> {
+        let pool = ctx.data_unchecked::<DatabaseConnection>();
+        self.properties(ctx)
+            .await?
+            .available_images(pool, kind.into())
+            .await
+    }
+
+    /// The franchise collection this movie is part of, like "The Lord of the Rings Collection".
+    pub async fn franchise(
+        &self,
+        ctx: &Context<'_>,
+    ) -> Result<Option<collections::Model>, async_graphql::Error> {
+        if self.kind != nodes::NodeKind::Movie {
+            return Ok(None);
+        }
+
+        let pool = ctx.data_unchecked::<DatabaseConnection>();
+        let Some(franchise) = collections::Entity::find()
+            .join(
+                JoinType::InnerJoin,
+                collections::Relation::CollectionItems.def(),
+            )
+            .filter(collection_items::Column::NodeId.eq(self.id.clone()))
+            .filter(collections::Column::Kind.eq(collections::CollectionKind::Franchise.as_db()))
+            .one(pool)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        // the other movies may be missing or in libraries the viewer can't see
+        if collection_item_count(ctx, &franchise).await? < min_visible_items(&franchise) {
+            return Ok(None);
+        }
+
+        Ok(Some(franchise))
+    }
+
+    /// Trailers, featurettes and other extras found next to a movie or series, grouped by type.
+    /// Empty for seasons and episodes.
+    pub async fn extras(&self, ctx: &Context<'_>) -> Result<Vec<NodeExtraGroup>, sea_orm::DbErr> {
+        if self.id != self.root_id {
+            return Ok(Vec::new());
+        }
+
+        let pool = ctx.data_unchecked::<DatabaseConnection>();
+        let rows = nodes::Entity::find()
+            .filter(nodes::Column::ParentId.eq(self.id.clone()))
+            .filter(nodes::Column::Kind.eq(nodes::NodeKind::Extra))
+            .filter(nodes::Column::UnavailableAt.is_null())
+            .order_by_asc(nodes::Column::ExtraType)
+            .order_by_asc(nodes::Column::Name)
+            .all(pool)
+            .await?;
+
+        let mut groups: Vec<NodeExtraGroup> = Vec::new();
+        for extra in rows {
+            let Some(extra_type) = extra.extra_type else {
+                continue;
+            };
+            match groups.last_mut() {
+                Some(group) if group.extra_type == extra_type => group.extras.push(extra),
+                _ => groups.push(NodeExtraGroup {
+                    extra_type,
+                    extras: vec![extra],
+                }),
+            }
+        }
+
+        Ok(groups)
+    }
+
+    /// Episodes the metadata provider lists for a series or season that aren't in the library.
+    /// Empty for movies and episodes.
+    pub async fn missing_episodes(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = true)] aired_only: bool,
+        #[graphql(default = false)] include_specials: bool,
+    ) -> Result<Vec<node_missing_episodes::Model>, sea_orm::DbErr> {
+        let mut query = missing_episodes_query(
+            aired_only.then(|| chrono::Utc::now().timestamp()),
+            include_specials,
+        )
+        .filter(node_missing_episodes::Column::RootId.eq(self.root_id.clone()));
+        match self.kind {
+            nodes::NodeKind::Series => {}
+            nodes::NodeKind::Season => {
+                let Some(season_number) = self.season_number else {
+                    return Ok(Vec::new());
+                };
+                query = query.filter(node_missing_episodes::Column::SeasonNumber.eq(season_number));
+            }
+            _ => return Ok(Vec::new()),
+        }
+
+        let pool = ctx.data_unchecked::<DatabaseConnection>();
+        query
+            .order_by_asc(node_missing_episodes::Column::SeasonNumber)
+            .order_by_asc(node_missing_episodes::Column::EpisodeNumber)
+            .all(pool)
+            .await
     }
 
     pub async fn default_file(
@@ -183,6 +341,29 @@ impl nodes::Model {
         )
     }
 
+    pub async fn user_rating(
+        &self,
+        ctx: &Context<'_>,
+    ) -> Result<Option<user_ratings::Model>, async_graphql::Error> {
+        let Some(user_id) = current_user_id(ctx) else {
+            return Ok(None);
+        };
+
+        Ok(load_node_ratings(ctx, &self.id, Some(user_id))
+            .await?
+            .user_rating)
+    }
+
+    pub async fn average_household_rating(
+        &self,
+        ctx: &Context<'_>,
+    ) -> Result<Option<f64>, sea_orm::DbErr> {
+        // keyed with the viewer so it shares the batch with user_rating on the same node
+        Ok(load_node_ratings(ctx, &self.id, current_user_id(ctx))
+            .await?
+            .average_household_rating)
+    }
+
     pub async fn current_playable(
         &self,
         ctx: &Context<'_>,
@@ -1,8 +1,12 @@
+use crate::auth::{RequestAuth, accessible_library_ids};
 use crate::config::get_config;
 use crate::entities::{
     assets,
     file_assets::{self, FileAssetRole},
-    files, node_files, node_metadata, node_metadata_content_ratings, node_metadata_genres,
+    files, node_files, node_image_selections, node_local_images,
+    node_metadata::{self, LockedFields},
+    node_metadata_companies::NodeMetadataCompanyKind,
+    node_metadata_content_ratings, node_metadata_details, node_metadata_genres,
     node_metadata_images,
     node_metadata_images::NodeMetadataImageKind,
     nodes, people, root_node_cast,
@@ -10,17 +14,26 @@ use crate::entities::{
 use crate::graphql::dataloaders::{
     node_counts::NodeCountsLoader,
     node_metadata::{NodeMetadataLoader, PreferredNodeMetadata},
+    node_metadata_details::{NodeMetadataDetails, NodeMetadataDetailsLoader},
 };
 use crate::graphql::properties::{
-    Asset, CastMember, ContentRating, MetadataGenre, NodeProperties, Person,
+    Asset, CastMember, ContentRating, CreditRole, MetadataCompany, MetadataField, MetadataGenre,
+    MetadataKeyword, NodeImage, NodeImageSource, NodeProperties, Person, PersonCredit,
+};
+use crate::graphql::query::{
+    NodeFilter, OrderBy, build_node_query_for_viewer, paginate_node_query,
 };
 use async_graphql::dataloader::DataLoader;
-use async_graphql::{ComplexObject, Context};
+use async_graphql::{
+    ComplexObject, Context,
+    connection::{self, EmptyFields},
+};
 use chrono::{DateTime, Datelike, Utc};
 use sea_orm::{
-    ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+    ActiveEnum, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
     RelationTrait,
 };
+use std::collections::HashMap;
 
 #[ComplexObject]
 impl NodeProperties {
@@ -34,6 +47,10 @@ impl NodeProperties {
 
     pub async fn logo_image(&self, ctx: &Context<'_>) -> Result<Option<Asset>, sea_orm::DbErr> {
         let pool = ctx.data_unchecked::<DatabaseConnection>();
+        if let Some(asset_id) = self.localized_logo_asset_id(pool).await? {
+            return find_asset(pool, Some(asset_id)).await;
+        }
+
         let asset_id = self
             .active_image_asset_id(pool, NodeMetadataImageKind::Logo)
             .await?;
@@ -42,11 +59,15 @@ impl NodeProperties {
 
     pub async fn poster_image(&self, ctx: &Context<'_>) -> Result<Option<Asset>, sea_orm::DbErr> {
         let pool = ctx.data_unchecked::<DatabaseConnection>();
-        if self.kind != nodes::NodeKind::Episode
-            && let Some(asset_id) = self
-                .active_image_asset_id(pool, NodeMetadataImageKind::Poster)
+        // episodes borrow their parents' posters unless one was picked for them
+        let own_poster = if self.kind == nodes::NodeKind::Episode {
+            self.selected_image_asset_id(pool, NodeMetadataImageKind::Poster)
                 .await?
-        {
+        } else {
+            self.active_image_asset_id(pool, NodeMetadataImageKind::Poster)
+                .await?
+        };
+        if let Some(asset_id) = own_poster {
             return find_asset(pool, Some(asset_id)).await;
         }
 
@@ -83,7 +104,12 @@ impl NodeProperties {
 
     pub async fn genres(&self, ctx: &Context<'_>) -> Result<Vec<MetadataGenre>, sea_orm::DbErr> {
         let pool = ctx.data_unchecked::<DatabaseConnection>();
-        let Some(metadata_id) = self.metadata_id.clone() else {
+        let metadata_id = if self.locked.contains(LockedFields::GENRES) {
+            self.user_metadata_id.clone()
+        } else {
+            self.metadata_id.clone()
+        };
+        let Some(metadata_id) = metadata_id else {
             return Ok(Vec::new());
         };
 
@@ -102,35 +128,85 @@ impl NodeProperties {
             .collect())
     }
 
-    pub async fn cast(&self, ctx: &Context<'_>) -> Result<Vec<CastMember>, sea_orm::DbErr> {
-        if self.node_id != self.root_id {
-            return Ok(Vec::new());
-        }
+    pub async fn studios(&self, ctx: &Context<'_>) -> Result<Vec<MetadataCompany>, sea_orm::DbErr> {
+        self.load_companies(ctx, NodeMetadataCompanyKind::Studio)
+            .await
+    }
 
-        let pool = ctx.data_unchecked::<DatabaseConnection>();
-        let rows = root_node_cast::Entity::find()
-            .find_also_related(people::Entity)
-            .filter(root_node_cast::Column::RootNodeId.eq(self.root_id.clone()))
-            .order_by_asc(root_node_cast::Column::Position)
-            .all(pool)
-            .await?;
-        Ok(rows
+    pub async fn networks(
+        &self,
+        ctx: &Context<'_>,
+    ) -> Result<Vec<MetadataCompany>, sea_orm::DbErr> {
+        self.load_companies(ctx, NodeMetadataCompanyKind::Network)
+            .await
+    }
+
+    pub async fn keywords(
+        &self,
+        ctx: &Context<'_>,
+    ) -> Result<Vec<MetadataKeyword>, sea_orm::DbErr> {
+        Ok(self
+            .load_metadata_details(ctx)
+            .await?
+            .keywords
             .into_iter()
-            .filter_map(|(cast, person)| {
-                person.map(|person| CastMember {
-                    character_name: cast.character_name,
-                    department: cast.department,
-                    person: Person {
-                        id: person.id,
-                        name: person.name,
-                        birthday: person.birthday,
-                        profile_asset_id: person.profile_asset_id,
-                    },
-                })
+            .map(|row| MetadataKeyword {
+                provider_id: row.provider_id,
+                external_id: row.external_id,
+                name: row.name,
             })
             .collect())
     }
 
+    pub async fn original_title(
+        &self,
+        ctx: &Context<'_>,
+    ) -> Result<Option<String>, sea_orm::DbErr> {
+        Ok(self
+            .load_details(ctx)
+            .await?
+            .and_then(|details| details.original_title))
+    }
+
+    pub async fn original_language(
+        &self,
+        ctx: &Context<'_>,
+    ) -> Result<Option<String>, sea_orm::DbErr> {
+        Ok(self
+            .load_details(ctx)
+            .await?
+            .and_then(|details| details.original_language))
+    }
+
+    pub async fn runtime_minutes(&self, ctx: &Context<'_>) -> Result<Option<i64>, sea_orm::DbErr> {
+        Ok(self
+            .load_details(ctx)
+            .await?
+            .and_then(|details| details.runtime_minutes))
+    }
+
+    pub async fn budget(&self, ctx: &Context<'_>) -> Result<Option<i64>, sea_orm::DbErr> {
+        Ok(self
+            .load_details(ctx)
+            .await?
+            .and_then(|details| details.budget))
+    }
+
+    pub async fn locked_fields(&self) -> Vec<MetadataField> {
+        MetadataField::ALL
+            .into_iter()
+            .filter(|field| self.locked.contains(LockedFields::from(*field)))
+            .collect()
+    }
+
+    pub async fn cast(&self, ctx: &Context<'_>) -> Result<Vec<CastMember>, sea_orm::DbErr> {
+        self.load_credits(ctx, false).await
+    }
+
+    pub async fn crew(&self, ctx: &Context<'_>) -> Result<Vec<CastMember>, sea_orm::DbErr> {
+        self.load_credits(ctx, true).await
+    }
+
     pub async fn content_rating(
         &self,
         ctx: &Context<'_>,
@@ -148,6 +224,7 @@ impl NodeProperties {
         Ok(select_content_rating(&rows).map(|row| ContentRating {
             country_code: row.country_code.clone(),
             rating: row.rating.clone(),
+            descriptors: row.descriptors(),
         }))
     }
 
@@ -174,13 +251,13 @@ impl NodeProperties {
                     .unwrap_or_default();
                 Ok(format_count_detail(counts.episode_count, "episode"))
             }
-            nodes::NodeKind::Episode => {
+            nodes::NodeKind::Episode | nodes::NodeKind::Extra => {
                 let loader = ctx.data_unchecked::<DataLoader<NodeMetadataLoader>>();
                 Ok(loader
                     .load_one(self.root_id.clone())
                     .await
                     .map_err(sea_orm::DbErr::Custom)?
-                    .map(|metadata| metadata.display_name().to_owned()))
+                    .map(|metadata| metadata.display_name(&self.languages).to_owned()))
             }
             nodes::NodeKind::Movie => Ok(self.release_year()),
         }
@@ -188,37 +265,107 @@ impl NodeProperties {
 }
 
 impl NodeProperties {
+    async fn load_companies(
+        &self,
+        ctx: &Context<'_>,
+        kind: NodeMetadataCompanyKind,
+    ) -> Result<Vec<MetadataCompany>, sea_orm::DbErr> {
+        Ok(self
+            .load_metadata_details(ctx)
+            .await?
+            .companies
+            .into_iter()
+            .filter(|row| row.kind == kind)
+            .map(|row| MetadataCompany {
+                provider_id: row.provider_id,
+                external_id: row.external_id,
+                name: row.name,
+                origin_country: row.origin_country,
+            })
+            .collect())
+    }
+
+    async fn load_details(
+        &self,
+        ctx: &Context<'_>,
+    ) -> Result<Option<node_metadata_details::Model>, sea_orm::DbErr> {
+        Ok(self.load_metadata_details(ctx).await?.details)
+    }
+
+    async fn load_metadata_details(
+        &self,
+        ctx: &Context<'_>,
+    ) -> Result<NodeMetadataDetails, sea_orm::DbErr> {
+        let Some(metadata_id) = self.metadata_id.clone() else {
+            return Ok(NodeMetadataDetails::default());
+        };
+
+        let loader = ctx.data_unchecked::<DataLoader<NodeMetadataDetailsLoader>>();
+        Ok(loader
+            .load_one(metadata_id)
+            .await
+            .map_err(sea_orm::DbErr::Custom)?
+            .unwrap_or_default())
+    }
+
     pub async fn from_node(
         _pool: &DatabaseConnection,
         node: &nodes::Model,
         metadata: Option<PreferredNodeMetadata>,
+        languages: Vec<String>,
     ) -> Result<Self, sea_orm::DbErr> {
-        let metadata = metadata.and_then(|metadata| metadata.metadata);
         let display_name = metadata
             .as_ref()
-            .map(|metadata| metadata.name.clone())
+            .map(|metadata| metadata.display_name(&languages).to_owned())
             .unwrap_or_else(|| node.name.clone());
-
-        Ok(match metadata {
+        let locked = metadata
+            .as_ref()
+            .map(|metadata| metadata.locked)
+            .unwrap_or_default();
+        let user_metadata_id = metadata
+            .as_ref()
+            .and_then(|metadata| metadata.user_metadata_id.clone());
+        // locked text is shown as the user wrote it, in every language
+        let description = metadata
+            .as_ref()
+            .filter(|_| !locked.contains(LockedFields::DESCRIPTION))
+            .and_then(|metadata| {
+                metadata
+                    .localized(&languages, |row| row.description.as_deref())
+                    .map(str::to_owned)
+            });
+        let tagline = metadata
+            .as_ref()
+            .filter(|_| !locked.contains(LockedFields::TAGLINE))
+            .and_then(|metadata| {
+                metadata
+                    .localized(&languages, |row| row.tagline.as_deref())
+                    .map(str::to_owned)
+            });
+
+        Ok(match metadata.and_then(|metadata| metadata.metadata) {
             Some(metadata) => {
                 let status = derive_display_status(&metadata, node.kind);
                 Self {
-                    display_name: display_name.clone(),
-                    description: metadata.description.clone(),
+                    display_name,
+                    description: description.or_else(|| metadata.description.clone()),
                     rating: metadata.score_normalized.map(|score| score as f64 / 10.0),
                     season_number: node.season_number,
                     episode_number: node.episode_number,
                     first_aired: metadata.first_aired,
                     last_aired: metadata.last_aired,
                     status,
-                    tagline: metadata.tagline.clone(),
+                    tagline: tagline.or_else(|| metadata.tagline.clone()),
                     created_at: Some(metadata.created_at),
                     updated_at: Some(metadata.updated_at),
                     metadata_id: Some(metadata.id),
+                    user_metadata_id,
+                    locked,
                     node_id: node.id.clone(),
                     root_id: node.root_id.clone(),
                     parent_id: node.parent_id.clone(),
                     kind: node.kind,
+                    languages,
                 }
             }
             None => Self {
@@ -234,10 +381,13 @@ impl NodeProperties {
                 created_at: None,
                 updated_at: None,
                 metadata_id: None,
+                user_metadata_id,
+                locked,
                 node_id: node.id.clone(),
                 root_id: node.root_id.clone(),
                 parent_id: node.parent_id.clone(),
                 kind: node.kind,
+                languages,
             },
         })
     }
@@ -264,12 +414,25 @@ impl NodeProperties {
             .column_as(files::Column::Width, "width")
             .column_as(files::Column::EditionName, "edition_name")
             .column_as(files::Column::AudioFingerprint, "audio_fingerprint")
+            .column_as(
+                files::Column::AudioTailFingerprint,
+                "audio_tail_fingerprint",
+            )
+            .column_as(
+                files::Column::AudioBodyFingerprint,
+                "audio_body_fingerprint",
+            )
             .column_as(files::Column::SegmentsJson, "segments_json")
+            .column_as(files::Column::SegmentsSidecarHash, "segments_sidecar_hash")
             .column_as(files::Column::KeyframesJson, "keyframes_json")
             .column_as(
                 files::Column::SubtitlesExtractedAt,
                 "subtitles_extracted_at",
             )
+            .column_as(
+                files::Column::CommercialsScannedAt,
+                "commercials_scanned_at",
+            )
             .column_as(files::Column::UnavailableAt, "unavailable_at")
             .column_as(files::Column::ScannedAt, "scanned_at")
             .column_as(files::Column::DiscoveredAt, "discovered_at")
@@ -310,11 +473,24 @@ impl NodeProperties {
         Ok(None)
     }
 
+    // a hand picked image wins, then artwork sitting next to the media since it was put there on
+    // purpose, then the provider's pick
     async fn active_image_asset_id(
         &self,
         pool: &DatabaseConnection,
         kind: NodeMetadataImageKind,
     ) -> Result<Option<String>, sea_orm::DbErr> {
+        if let Some(asset_id) = self.selected_image_asset_id(pool, kind).await? {
+            return Ok(Some(asset_id));
+        }
+
+        if let Some(local) = node_local_images::Entity::find_by_id((self.node_id.clone(), kind))
+            .one(pool)
+            .await?
+        {
+            return Ok(Some(local.asset_id));
+        }
+
         let Some(metadata_id) = self.metadata_id.clone() else {
             return Ok(None);
         };
@@ -329,8 +505,150 @@ impl NodeProperties {
             .map(|row| row.map(|row| row.asset_id))
     }
 
+    async fn selected_image_asset_id(
+        &self,
+        pool: &DatabaseConnection,
+        kind: NodeMetadataImageKind,
+    ) -> Result<Option<String>, sea_orm::DbErr> {
+        Ok(
+            node_image_selections::Entity::find_by_id((self.node_id.clone(), kind))
+                .one(pool)
+                .await?
+                .map(|selection| selection.asset_id),
+        )
+    }
+
+    // every image that could be shown for `kind`: an uploaded pick, local artwork, then the
+    // provider's images in their ranked order
+    pub async fn available_images(
+        &self,
+        pool: &DatabaseConnection,
+        kind: NodeMetadataImageKind,
+    ) -> Result<Vec<NodeImage>, sea_orm::DbErr> {
+        let selected = if kind == NodeMetadataImageKind::Logo {
+            match self.localized_logo_asset_id(pool).await? {
+                Some(asset_id) => Some(asset_id),
+                None => self.active_image_asset_id(pool, kind).await?,
+            }
+        } else {
+            self.active_image_asset_id(pool, kind).await?
+        };
+        let selection = self.selected_image_asset_id(pool, kind).await?;
+        let local = node_local_images::Entity::find_by_id((self.node_id.clone(), kind))
+            .one(pool)
+            .await?;
+        let provider_images = match self.metadata_id.clone() {
+            Some(metadata_id) => {
+                node_metadata_images::Entity::find()
+                    .filter(node_metadata_images::Column::NodeMetadataId.eq(metadata_id))
+                    .filter(node_metadata_images::Column::Kind.eq(kind))
+                    .order_by_asc(node_metadata_images::Column::Position)
+                    .all(pool)
+                    .await?
+            }
+            None => Vec::new(),
+        };
+
+        let mut candidates = Vec::new();
+        if let Some(asset_id) = selection.filter(|asset_id| {
+            local
+                .as_ref()
+                .is_none_or(|local| &local.asset_id != asset_id)
+                && provider_images
+                    .iter()
+                    .all(|image| &image.asset_id != asset_id)
+        }) {
+            candidates.push((asset_id, NodeImageSource::Upload, None));
+        }
+        if let Some(local) = local {
+            candidates.push((local.asset_id, NodeImageSource::Local, None));
+        }
+        for image in provider_images {
+            candidates.push((
+                image.asset_id.clone(),
+                NodeImageSource::Provider,
+                Some(image),
+            ));
+        }
+
+        let mut assets_by_id = assets::Entity::find()
+            .filter(
+                assets::Column::Id.is_in(
+                    candidates
+                        .iter()
+                        .map(|(asset_id, _, _)| asset_id.clone())
+                        .collect::<Vec<_>>(),
+                ),
+            )
+            .all(pool)
+            .await?
+            .into_iter()
+            .map(|asset| (asset.id.clone(), asset))
+            .collect::<HashMap<_, _>>();
+        Ok(candidates
+            .into_iter()
+            .filter_map(|(asset_id, source, image)| {
+                let asset = assets_by_id.remove(&asset_id)?;
+                let image = image.as_ref();
+                Some(NodeImage {
+                    selected: selected.as_deref() == Some(asset.id.as_str()),
+                    source,
+                    language: image.and_then(|image| image.language.clone()),
+                    vote_average: image.and_then(|image| image.vote_average),
+                    vote_count: image.and_then(|image| image.vote_count),
+                    width: image.and_then(|image| image.width).or(asset.width),
+                    height: image.and_then(|image| image.height).or(asset.height),
+                    asset: asset.into(),
+                })
+            })
+            .collect())
+    }
+
+    // logos carry their text's language, so one matching the viewer beats the provider's pick.
+    // a picked or local logo still overrides both.
+    async fn localized_logo_asset_id(
+        &self,
+        pool: &DatabaseConnection,
+    ) -> Result<Option<String>, sea_orm::DbErr> {
+        let Some(metadata_id) = self.metadata_id.clone() else {
+            return Ok(None);
+        };
+        let key = (self.node_id.clone(), NodeMetadataImageKind::Logo);
+        if self.languages.is_empty()
+            || node_image_selections::Entity::find_by_id(key.clone())
+                .one(pool)
+                .await?
+                .is_some()
+            || node_local_images::Entity::find_by_id(key)
+                .one(pool)
+                .await?
+                .is_some()
+        {
+            return Ok(None);
+        }
+
+        let logos = node_metadata_images::Entity::find()
+            .filter(node_metadata_images::Column::NodeMetadataId.eq(metadata_id))
+            .filter(node_metadata_images::Column::Kind.eq(NodeMetadataImageKind::Logo))
+            .filter(node_metadata_images::Column::Language.is_not_null())
+            .order_by_asc(node_metadata_images::Column::Position)
+            .all(pool)
+            .await?;
+        Ok(self.languages.iter().find_map(|language| {
+            let code = language.split('-').next().unwrap_or_default();
+            logos
+                .iter()
+                .find(|row| {
+                    row.language
+                        .as_deref()
+                        .is_some_and(|logo_language| logo_language.eq_ignore_ascii_case(code))
+                })
+                .map(|row| row.asset_id.clone())
+        }))
+    }
+
     // Rank metadata per ancestor by the existing preference order, then return the nearest
-    // ancestor whose preferred row includes a poster.
+    // ancestor whose preferred row includes a poster. Local artwork wins at the same depth.
     async fn poster_fallback_asset_id(
         &self,
         pool: &DatabaseConnection,
@@ -342,6 +660,7 @@ impl NodeProperties {
             return Ok(None);
         }
 
+        let poster = NodeMetadataImageKind::Poster.to_value();
         Ok(sqlx::query_scalar::<_, String>(
             r#"
             WITH ranked_ancestor_metadata AS (
@@ -357,25 +676,86 @@ impl NodeProperties {
                 INNER JOIN node_metadata nm ON nm.node_id = nc.ancestor_id
                 INNER JOIN node_metadata_images nmi
                     ON nmi.node_metadata_id = nm.id
-                   AND nmi.kind = 0
+                   AND nmi.kind = ?
                    AND nmi.is_active = 1
                 WHERE nc.descendant_id = ?
                 AND nc.depth > 0
+            ),
+            ancestor_posters AS (
+                SELECT nc.depth, nis.asset_id AS poster_asset_id, 0 AS source_rank
+                FROM node_closure nc
+                INNER JOIN node_image_selections nis
+                    ON nis.node_id = nc.ancestor_id
+                   AND nis.kind = ?
+                WHERE nc.descendant_id = ?
+                AND nc.depth > 0
+                UNION ALL
+                SELECT nc.depth, nli.asset_id AS poster_asset_id, 1 AS source_rank
+                FROM node_closure nc
+                INNER JOIN node_local_images nli
+                    ON nli.node_id = nc.ancestor_id
+                   AND nli.kind = ?
+                WHERE nc.descendant_id = ?
+                AND nc.depth > 0
+                UNION ALL
+                SELECT depth, poster_asset_id, 2 AS source_rank
+                FROM ranked_ancestor_metadata
+                WHERE metadata_rank = 1
+                AND poster_asset_id IS NOT NULL
             )
             SELECT poster_asset_id AS "poster_asset_id?: String"
-            FROM ranked_ancestor_metadata
-            WHERE metadata_rank = 1
-            AND poster_asset_id IS NOT NULL
-            ORDER BY depth ASC
+            FROM ancestor_posters
+            ORDER BY depth ASC, source_rank ASC
             LIMIT 1
             "#,
         )
+        .bind(poster)
+        .bind(self.node_id.clone())
+        .bind(poster)
+        .bind(self.node_id.clone())
+        .bind(poster)
         .bind(self.node_id.clone())
         .fetch_optional(pool.get_sqlite_connection_pool())
         .await
         .map_err(|error| sea_orm::DbErr::Custom(error.to_string()))?)
     }
 
+    // cast and crew share root_node_cast, crew rows are the ones with a job
+    async fn load_credits(
+        &self,
+        ctx: &Context<'_>,
+        crew: bool,
+    ) -> Result<Vec<CastMember>, sea_orm::DbErr> {
+        if self.node_id != self.root_id {
+            return Ok(Vec::new());
+        }
+
+        let pool = ctx.data_unchecked::<DatabaseConnection>();
+        let job_filter = if crew {
+            root_node_cast::Column::Job.is_not_null()
+        } else {
+            root_node_cast::Column::Job.is_null()
+        };
+        let rows = root_node_cast::Entity::find()
+            .find_also_related(people::Entity)
+            .filter(root_node_cast::Column::RootNodeId.eq(self.root_id.clone()))
+            .filter(job_filter)
+            .order_by_asc(root_node_cast::Column::Position)
+            .all(pool)
+            .await?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|(credit, person)| {
+                person.map(|person| CastMember {
+                    character_name: credit.character_name,
+                    department: credit.department,
+                    job: credit.job,
+                    person: person.into(),
+                })
+            })
+            .collect())
+    }
+
     fn release_year(&self) -> Option<String> {
         year_from_unix_timestamp(self.first_aired.or(self.last_aired)?).map(|year| year.to_string())
     }
@@ -387,6 +767,87 @@ impl Person {
         let pool = ctx.data_unchecked::<DatabaseConnection>();
         find_asset(pool, self.profile_asset_id.clone()).await
     }
+
+    // everything in the viewer's libraries this person is credited on, newest first
+    pub async fn credits(
+        &self,
+        ctx: &Context<'_>,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> Result<
+        connection::Connection<u64, PersonCredit, EmptyFields, EmptyFields>,
+        async_graphql::Error,
+    > {
+        let pool = ctx.data::<DatabaseConnection>()?;
+        let auth = ctx.data::<RequestAuth>()?;
+        let visible_library_ids = accessible_library_ids(pool, auth)
+            .await
+            .map_err(async_graphql::Error::from)?;
+        let viewer_id = auth.get_user_or_err()?.id.clone();
+        person_credits_for_viewer(
+            pool,
+            visible_library_ids.as_deref(),
+            &viewer_id,
+            &self.id,
+            after,
+            first,
+        )
+        .await
+    }
+}
+
+pub async fn person_credits_for_viewer(
+    pool: &DatabaseConnection,
+    visible_library_ids: Option<&[String]>,
+    viewer_id: &str,
+    person_id: &str,
+    after: Option<String>,
+    first: Option<i32>,
+) -> Result<connection::Connection<u64, PersonCredit, EmptyFields, EmptyFields>, async_graphql::Error>
+{
+    let filter = NodeFilter {
+        person_id: Some(person_id.to_owned()),
+        order_by: Some(OrderBy::ReleasedAt),
+        ..Default::default()
+    };
+    let qb = build_node_query_for_viewer(pool, visible_library_ids, viewer_id, &filter).await?;
+    let page = paginate_node_query(pool, qb, after, first).await?;
+
+    let root_ids = page
+        .edges
+        .iter()
+        .map(|edge| edge.node.id.clone())
+        .collect::<Vec<_>>();
+    let mut roles_by_root_id = HashMap::<String, Vec<CreditRole>>::new();
+    for credit in root_node_cast::Entity::find()
+        .filter(root_node_cast::Column::PersonId.eq(person_id))
+        .filter(root_node_cast::Column::RootNodeId.is_in(root_ids))
+        .order_by_asc(root_node_cast::Column::Position)
+        .all(pool)
+        .await?
+    {
+        roles_by_root_id
+            .entry(credit.root_node_id)
+            .or_default()
+            .push(CreditRole {
+                character_name: credit.character_name,
+                department: credit.department,
+                job: credit.job,
+            });
+    }
+
+    let mut credits = connection::Connection::new(page.has_previous_page, page.has_next_page);
+    credits.edges.extend(page.edges.into_iter().map(|edge| {
+        let roles = roles_by_root_id.remove(&edge.node.id).unwrap_or_default();
+        connection::Edge::new(
+            edge.cursor,
+            PersonCredit {
+                node: edge.node,
+                roles,
+            },
+        )
+    }));
+    Ok(credits)
 }
 
 fn select_content_rating<'a>(
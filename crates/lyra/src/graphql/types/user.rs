@@ -16,6 +16,11 @@ impl users::Model {
         Ok(session.map(|s| s.last_seen_at))
     }
 
+    /// Languages used to pick localized titles, descriptions and logos, most preferred first.
+    pub async fn preferred_metadata_languages(&self) -> Vec<String> {
+        self.metadata_languages()
+    }
+
     pub async fn libraries(
         &self,
         ctx: &Context<'_>,
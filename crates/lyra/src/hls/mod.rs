@@ -4,7 +4,7 @@ use crate::{
     entities::{files, libraries},
     jobs,
     media::{self, FileProbeJob},
-    signer::{sign, verify},
+    signer::{TokenPurpose, sign, verify},
 };
 use anyhow::Context;
 use axum::{
@@ -71,6 +71,7 @@ pub fn get_hls_router() -> Router<AppState> {
 
 pub(crate) fn sign_playback_url_template(file_id: &str) -> anyhow::Result<String> {
     let token = sign(
+        TokenPurpose::Playback,
         PlaybackTokenPayload {
             file_id: file_id.to_string(),
         },
@@ -205,7 +206,7 @@ async fn get_or_create_session_for_selection(
 }
 
 fn verify_playback_token(token: &str, file_id: &str) -> anyhow::Result<()> {
-    let (_expires_in, payload) = verify::<PlaybackTokenPayload>(token)?;
+    let (_expires_in, payload) = verify::<PlaybackTokenPayload>(TokenPurpose::Playback, token)?;
     anyhow::ensure!(payload.file_id == file_id, "stream not found");
     Ok(())
 }
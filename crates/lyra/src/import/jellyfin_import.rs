@@ -0,0 +1,524 @@
+use crate::import::watch_state_import::ImportWatchStateRow;
+use anyhow::Context;
+use chrono::{DateTime, NaiveDateTime};
+use serde::Deserialize;
+use sqlx::Row;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use std::collections::HashMap;
+use std::path::Path;
+
+pub const JELLYFIN_SOURCE: &str = "jellyfin";
+pub const EMBY_SOURCE: &str = "emby";
+
+#[derive(Debug, Clone)]
+pub struct ExternalUserWatchStates {
+    pub source_username: String,
+    pub rows: Vec<ImportWatchStateRow>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SourceItemKind {
+    Movie,
+    Series,
+    Episode,
+    Other,
+}
+
+#[derive(Debug, Clone)]
+struct SourceItem {
+    id: String,
+    kind: SourceItemKind,
+    name: Option<String>,
+    path: Option<String>,
+    size_bytes: Option<i64>,
+    run_time_ticks: Option<i64>,
+    season_number: Option<i64>,
+    episode_number: Option<i64>,
+    series_id: Option<String>,
+    provider_ids: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone)]
+struct SourceUserData {
+    username: String,
+    item_id: String,
+    played: bool,
+    playback_position_ticks: i64,
+    last_played_at: Option<i64>,
+}
+
+/// Reads watch state out of a Jellyfin database. Both the EF-based `jellyfin.db` layout
+/// (10.11+) and the older `library.db` layout are supported. Older servers keep users in a
+/// separate `jellyfin.db`, so `users_database_path` is used to resolve usernames when given;
+/// otherwise the internal user id is used as the username.
+pub async fn read_jellyfin_database(
+    path: &Path,
+    users_database_path: Option<&Path>,
+) -> anyhow::Result<Vec<ExternalUserWatchStates>> {
+    let pool = open_read_only(path).await?;
+    let result = if has_table(&pool, "BaseItems").await? {
+        read_current_layout(&pool).await
+    } else if has_table(&pool, "TypedBaseItems").await? {
+        read_legacy_layout(&pool, users_database_path).await
+    } else {
+        Err(anyhow::anyhow!(
+            "{} does not look like a Jellyfin database",
+            path.display()
+        ))
+    };
+    pool.close().await;
+
+    let (items, user_data) = result?;
+    Ok(build_user_watch_states(JELLYFIN_SOURCE, &items, user_data))
+}
+
+/// Reads an Emby user-data export, which is the JSON body of
+/// `/Users/{id}/Items?Recursive=true&Fields=ProviderIds,Path,MediaSources` for a single user.
+pub fn read_emby_export(
+    bytes: &[u8],
+    source_username: &str,
+) -> anyhow::Result<Vec<ExternalUserWatchStates>> {
+    let export: EmbyItemsExport =
+        serde_json::from_slice(bytes).context("failed to parse emby export")?;
+
+    let mut items = HashMap::new();
+    let mut user_data = Vec::new();
+    for item in export.items {
+        let size_bytes = item
+            .media_sources
+            .iter()
+            .find_map(|source| source.size)
+            .or(item.size);
+        let path = item.path.or_else(|| {
+            item.media_sources
+                .iter()
+                .find_map(|source| source.path.clone())
+        });
+
+        if let Some(data) = item.user_data {
+            user_data.push(SourceUserData {
+                username: source_username.to_string(),
+                item_id: item.id.clone(),
+                played: data.played,
+                playback_position_ticks: data.playback_position_ticks.unwrap_or(0),
+                last_played_at: data.last_played_date.as_deref().and_then(parse_timestamp),
+            });
+        }
+
+        items.insert(
+            item.id.clone(),
+            SourceItem {
+                id: item.id,
+                kind: parse_item_kind(item.item_type.as_deref()),
+                name: item.name,
+                path,
+                size_bytes,
+                run_time_ticks: item.run_time_ticks,
+                season_number: item.parent_index_number,
+                episode_number: item.index_number,
+                series_id: item.series_id,
+                provider_ids: item
+                    .provider_ids
+                    .into_iter()
+                    .map(|(key, value)| (key.to_ascii_lowercase(), value))
+                    .collect(),
+            },
+        );
+    }
+
+    Ok(build_user_watch_states(EMBY_SOURCE, &items, user_data))
+}
+
+async fn open_read_only(path: &Path) -> anyhow::Result<SqlitePool> {
+    let options = SqliteConnectOptions::new()
+        .filename(path)
+        .read_only(true)
+        .immutable(true);
+
+    SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(options)
+        .await
+        .with_context(|| format!("failed to open {}", path.display()))
+}
+
+async fn has_table(pool: &SqlitePool, name: &str) -> anyhow::Result<bool> {
+    let count: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?")
+            .bind(name)
+            .fetch_one(pool)
+            .await?;
+    Ok(count > 0)
+}
+
+async fn read_current_layout(
+    pool: &SqlitePool,
+) -> anyhow::Result<(HashMap<String, SourceItem>, Vec<SourceUserData>)> {
+    let mut items = HashMap::new();
+    let rows = sqlx::query(
+        "SELECT lower(Id) AS id, Type, Name, Path, Size, RunTimeTicks, ParentIndexNumber, \
+         IndexNumber, lower(SeriesId) AS series_id FROM BaseItems",
+    )
+    .fetch_all(pool)
+    .await?;
+    for row in rows {
+        let item = SourceItem {
+            id: row.try_get("id")?,
+            kind: parse_item_kind(row.try_get::<Option<String>, _>("Type")?.as_deref()),
+            name: row.try_get("Name")?,
+            path: row.try_get("Path")?,
+            size_bytes: row.try_get("Size")?,
+            run_time_ticks: row.try_get("RunTimeTicks")?,
+            season_number: row.try_get("ParentIndexNumber")?,
+            episode_number: row.try_get("IndexNumber")?,
+            series_id: row.try_get("series_id")?,
+            provider_ids: HashMap::new(),
+        };
+        items.insert(item.id.clone(), item);
+    }
+
+    let provider_rows = sqlx::query(
+        "SELECT lower(ItemId) AS item_id, ProviderId, ProviderValue FROM BaseItemProviders",
+    )
+    .fetch_all(pool)
+    .await?;
+    for row in provider_rows {
+        let item_id: String = row.try_get("item_id")?;
+        let provider_id: String = row.try_get("ProviderId")?;
+        let provider_value: String = row.try_get("ProviderValue")?;
+        if let Some(item) = items.get_mut(&item_id) {
+            item.provider_ids
+                .insert(provider_id.to_ascii_lowercase(), provider_value);
+        }
+    }
+
+    let user_rows = sqlx::query(
+        "SELECT u.Username AS username, lower(d.ItemId) AS item_id, d.Played AS played, \
+         d.PlaybackPositionTicks AS position, d.LastPlayedDate AS last_played \
+         FROM UserData d INNER JOIN Users u ON lower(u.Id) = lower(d.UserId)",
+    )
+    .fetch_all(pool)
+    .await?;
+    let mut user_data = Vec::with_capacity(user_rows.len());
+    for row in user_rows {
+        user_data.push(SourceUserData {
+            username: row.try_get("username")?,
+            item_id: row.try_get("item_id")?,
+            played: row.try_get::<Option<bool>, _>("played")?.unwrap_or(false),
+            playback_position_ticks: row.try_get::<Option<i64>, _>("position")?.unwrap_or(0),
+            last_played_at: row
+                .try_get::<Option<String>, _>("last_played")?
+                .as_deref()
+                .and_then(parse_timestamp),
+        });
+    }
+
+    Ok((items, user_data))
+}
+
+async fn read_legacy_layout(
+    pool: &SqlitePool,
+    users_database_path: Option<&Path>,
+) -> anyhow::Result<(HashMap<String, SourceItem>, Vec<SourceUserData>)> {
+    let usernames = match users_database_path {
+        Some(path) => read_legacy_usernames(path).await?,
+        None => HashMap::new(),
+    };
+
+    let mut items = HashMap::new();
+    let mut item_ids_by_user_data_key = HashMap::new();
+    let rows = sqlx::query(
+        "SELECT lower(hex(guid)) AS id, type, Name, Path, Size, RunTimeTicks, \
+         ParentIndexNumber, IndexNumber, lower(hex(SeriesId)) AS series_id, ProviderIds, \
+         UserDataKey FROM TypedBaseItems",
+    )
+    .fetch_all(pool)
+    .await?;
+    for row in rows {
+        let item = SourceItem {
+            id: row.try_get("id")?,
+            kind: parse_item_kind(row.try_get::<Option<String>, _>("type")?.as_deref()),
+            name: row.try_get("Name")?,
+            path: row.try_get("Path")?,
+            size_bytes: row.try_get("Size")?,
+            run_time_ticks: row.try_get("RunTimeTicks")?,
+            season_number: row.try_get("ParentIndexNumber")?,
+            episode_number: row.try_get("IndexNumber")?,
+            series_id: row
+                .try_get::<Option<String>, _>("series_id")?
+                .filter(|value| !value.is_empty()),
+            provider_ids: parse_legacy_provider_ids(
+                row.try_get::<Option<String>, _>("ProviderIds")?
+                    .as_deref()
+                    .unwrap_or_default(),
+            ),
+        };
+        if let Some(key) = row.try_get::<Option<String>, _>("UserDataKey")? {
+            item_ids_by_user_data_key.insert(key, item.id.clone());
+        }
+        items.insert(item.id.clone(), item);
+    }
+
+    let user_rows = sqlx::query(
+        "SELECT key, userId, played, playbackPositionTicks, lastPlayedDate FROM UserDatas",
+    )
+    .fetch_all(pool)
+    .await?;
+    let mut user_data = Vec::with_capacity(user_rows.len());
+    for row in user_rows {
+        let key: String = row.try_get("key")?;
+        let Some(item_id) = item_ids_by_user_data_key.get(&key) else {
+            continue;
+        };
+        let user_id: i64 = row.try_get("userId")?;
+        user_data.push(SourceUserData {
+            username: usernames
+                .get(&user_id)
+                .cloned()
+                .unwrap_or_else(|| user_id.to_string()),
+            item_id: item_id.clone(),
+            played: row.try_get::<Option<bool>, _>("played")?.unwrap_or(false),
+            playback_position_ticks: row
+                .try_get::<Option<i64>, _>("playbackPositionTicks")?
+                .unwrap_or(0),
+            last_played_at: row
+                .try_get::<Option<String>, _>("lastPlayedDate")?
+                .as_deref()
+                .and_then(parse_timestamp),
+        });
+    }
+
+    Ok((items, user_data))
+}
+
+async fn read_legacy_usernames(path: &Path) -> anyhow::Result<HashMap<i64, String>> {
+    let pool = open_read_only(path).await?;
+    let rows = sqlx::query_as::<_, (i64, String)>("SELECT InternalId, Username FROM Users")
+        .fetch_all(&pool)
+        .await
+        .with_context(|| format!("failed to read users from {}", path.display()));
+    pool.close().await;
+    Ok(rows?.into_iter().collect())
+}
+
+fn build_user_watch_states(
+    source: &str,
+    items: &HashMap<String, SourceItem>,
+    user_data: Vec<SourceUserData>,
+) -> Vec<ExternalUserWatchStates> {
+    let mut rows_by_username: HashMap<String, Vec<ImportWatchStateRow>> = HashMap::new();
+    for data in user_data {
+        let Some(item) = items.get(&data.item_id) else {
+            continue;
+        };
+        if !matches!(item.kind, SourceItemKind::Movie | SourceItemKind::Episode) {
+            continue;
+        }
+        let Some(progress_percent) = progress_percent(&data, item) else {
+            continue;
+        };
+
+        // episodes carry their own provider ids, but matching is done against the series root.
+        let root = match item.kind {
+            SourceItemKind::Episode => item.series_id.as_ref().and_then(|id| items.get(id)),
+            _ => Some(item),
+        };
+        let provider_ids = root.map(|root| &root.provider_ids);
+
+        rows_by_username
+            .entry(data.username)
+            .or_default()
+            .push(ImportWatchStateRow {
+                source: source.to_string(),
+                source_item_id: Some(item.id.clone()),
+                title: item.name.clone(),
+                media_type: Some(
+                    match item.kind {
+                        SourceItemKind::Episode => "episode",
+                        _ => "movie",
+                    }
+                    .to_string(),
+                ),
+                season_number: item.season_number,
+                episode_number: item.episode_number,
+                progress_percent,
+                viewed_at: data.last_played_at,
+                file_path: item.path.clone(),
+                file_basename: None,
+                file_size_bytes: item.size_bytes,
+                imdb_id: provider_ids.and_then(|ids| ids.get("imdb").cloned()),
+                tmdb_id: provider_ids
+                    .and_then(|ids| ids.get("tmdb"))
+                    .and_then(|value| value.trim().parse().ok()),
+            });
+    }
+
+    let mut result = rows_by_username
+        .into_iter()
+        .map(|(source_username, rows)| ExternalUserWatchStates {
+            source_username,
+            rows,
+        })
+        .collect::<Vec<_>>();
+    result.sort_by(|a, b| a.source_username.cmp(&b.source_username));
+    result
+}
+
+fn progress_percent(data: &SourceUserData, item: &SourceItem) -> Option<f32> {
+    if data.played {
+        return Some(1.0);
+    }
+
+    let run_time_ticks = item.run_time_ticks.filter(|ticks| *ticks > 0)?;
+    if data.playback_position_ticks <= 0 {
+        return None;
+    }
+
+    Some((data.playback_position_ticks as f64 / run_time_ticks as f64).clamp(0.0, 1.0) as f32)
+}
+
+fn parse_item_kind(value: Option<&str>) -> SourceItemKind {
+    // jellyfin stores full .NET type names while the emby api returns bare names.
+    match value.and_then(|value| value.rsplit('.').next()) {
+        Some("Movie") => SourceItemKind::Movie,
+        Some("Series") => SourceItemKind::Series,
+        Some("Episode") => SourceItemKind::Episode,
+        _ => SourceItemKind::Other,
+    }
+}
+
+fn parse_legacy_provider_ids(value: &str) -> HashMap<String, String> {
+    value
+        .split('|')
+        .filter_map(|pair| pair.split_once('='))
+        .filter(|(_, value)| !value.trim().is_empty())
+        .map(|(key, value)| (key.trim().to_ascii_lowercase(), value.trim().to_string()))
+        .collect()
+}
+
+fn parse_timestamp(value: &str) -> Option<i64> {
+    let value = value.trim();
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Some(timestamp.timestamp());
+    }
+
+    let value = value.trim_end_matches('Z');
+    ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .map(|timestamp| timestamp.and_utc().timestamp())
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct EmbyItemsExport {
+    items: Vec<EmbyItem>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct EmbyItem {
+    id: String,
+    name: Option<String>,
+    #[serde(rename = "Type")]
+    item_type: Option<String>,
+    path: Option<String>,
+    size: Option<i64>,
+    run_time_ticks: Option<i64>,
+    parent_index_number: Option<i64>,
+    index_number: Option<i64>,
+    series_id: Option<String>,
+    #[serde(default)]
+    provider_ids: HashMap<String, String>,
+    #[serde(default)]
+    media_sources: Vec<EmbyMediaSource>,
+    user_data: Option<EmbyUserData>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct EmbyMediaSource {
+    path: Option<String>,
+    size: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct EmbyUserData {
+    #[serde(default)]
+    played: bool,
+    playback_position_ticks: Option<i64>,
+    last_played_date: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn emby_export_uses_series_provider_ids_for_episodes() {
+        let export = br#"{
+            "Items": [
+                {
+                    "Id": "series",
+                    "Name": "Show",
+                    "Type": "Series",
+                    "ProviderIds": { "Tmdb": "1399", "Imdb": "tt0944947" }
+                },
+                {
+                    "Id": "episode",
+                    "Name": "Pilot",
+                    "Type": "Episode",
+                    "SeriesId": "series",
+                    "ParentIndexNumber": 1,
+                    "IndexNumber": 2,
+                    "RunTimeTicks": 36000000000,
+                    "ProviderIds": { "Tmdb": "63056" },
+                    "MediaSources": [{ "Path": "/tv/Show/S01E02.mkv", "Size": 1234 }],
+                    "UserData": {
+                        "Played": false,
+                        "PlaybackPositionTicks": 9000000000,
+                        "LastPlayedDate": "2024-01-02T03:04:05.0000000Z"
+                    }
+                },
+                {
+                    "Id": "unwatched",
+                    "Name": "Other",
+                    "Type": "Movie",
+                    "RunTimeTicks": 36000000000,
+                    "UserData": { "Played": false, "PlaybackPositionTicks": 0 }
+                }
+            ]
+        }"#;
+
+        let result = read_emby_export(export, "alice").unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].source_username, "alice");
+        assert_eq!(result[0].rows.len(), 1);
+
+        let row = &result[0].rows[0];
+        assert_eq!(row.source, EMBY_SOURCE);
+        assert_eq!(row.tmdb_id, Some(1399));
+        assert_eq!(row.imdb_id.as_deref(), Some("tt0944947"));
+        assert_eq!(row.season_number, Some(1));
+        assert_eq!(row.episode_number, Some(2));
+        assert_eq!(row.file_path.as_deref(), Some("/tv/Show/S01E02.mkv"));
+        assert_eq!(row.file_size_bytes, Some(1234));
+        assert_eq!(row.viewed_at, Some(1_704_164_645));
+        assert!((row.progress_percent - 0.25).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn parses_legacy_provider_ids_and_timestamps() {
+        let ids = parse_legacy_provider_ids("Imdb=tt0133093|Tmdb=603|TmdbCollection=");
+        assert_eq!(ids.get("imdb").map(String::as_str), Some("tt0133093"));
+        assert_eq!(ids.get("tmdb").map(String::as_str), Some("603"));
+        assert!(!ids.contains_key("tmdbcollection"));
+
+        assert_eq!(
+            parse_timestamp("2024-01-02 03:04:05.1234567Z"),
+            Some(1_704_164_645)
+        );
+        assert_eq!(parse_timestamp("not a date"), None);
+    }
+}
@@ -1 +1,2 @@
+pub mod jellyfin_import;
 pub mod watch_state_import;
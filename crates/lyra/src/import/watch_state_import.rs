@@ -10,6 +10,7 @@ use std::collections::{HashMap, HashSet};
 
 const WRITE_CHUNK_SIZE: usize = 100;
 const CONFLICT_EPSILON: f32 = 0.0001;
+const SUPPORTED_SOURCES: &[&str] = &["plex", "jellyfin", "emby"];
 
 #[derive(Debug, Clone)]
 pub struct ImportWatchStatesRequest {
@@ -214,9 +215,9 @@ fn normalize_row(row: ImportWatchStateRow) -> NormalizedImportWatchStateRow {
 
 fn match_row(row: &NormalizedImportWatchStateRow, lookups: &MatchLookups) -> MatchOutcome {
     let source = row.source.trim().to_ascii_lowercase();
-    if source != "plex" {
+    if !SUPPORTED_SOURCES.contains(&source.as_str()) {
         return MatchOutcome::Unmatched {
-            reason: "Unsupported source; expected 'plex'".to_string(),
+            reason: "Unsupported source; expected 'plex', 'jellyfin' or 'emby'".to_string(),
             ambiguous: false,
         };
     }
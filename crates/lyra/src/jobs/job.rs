@@ -84,4 +84,7 @@ pub enum JobScheduling {
 pub enum JobOutcome {
     Complete,
     Cancelled,
+    // try again after this many seconds without counting an attempt, for targets that are
+    // waiting on something outside the job's control
+    Postponed(i64),
 }
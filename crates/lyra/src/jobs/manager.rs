@@ -287,19 +287,27 @@ pub async fn run_job_for_target<J: Job>(
 
             delete_job_row(pool, J::JOB_KIND, &target_id).await?;
         }
+        Ok(JobOutcome::Postponed(delay_seconds)) => {
+            tracing::info!(
+                job_kind = ?J::JOB_KIND,
+                target_id = %target_id,
+                delay_seconds,
+                "job postponed"
+            );
+
+            let job_row = find_job_row(pool, J::JOB_KIND, &target_id).await?;
+            let attempt_count = job_row.attempt_count;
+            persist_job_error(
+                pool,
+                job_row,
+                attempt_count,
+                Some(chrono::Utc::now().timestamp() + delay_seconds),
+                "postponed".to_string(),
+            )
+            .await?;
+        }
         Err(error) => {
-            let job_row = jobs_entity::Entity::find()
-                .filter(jobs_entity::Column::JobKind.eq(J::JOB_KIND.code()))
-                .filter(jobs_entity::Column::TargetId.eq(target_id.clone()))
-                .one(pool)
-                .await?
-                .with_context(|| {
-                    format!(
-                        "missing job row while recording failed execution for {:?} {}",
-                        J::JOB_KIND,
-                        target_id
-                    )
-                })?;
+            let job_row = find_job_row(pool, J::JOB_KIND, &target_id).await?;
             let attempt_count = job_row.attempt_count + 1;
             let retry_after = policy.next_retry_at(chrono::Utc::now().timestamp(), attempt_count);
 
@@ -319,6 +327,22 @@ pub async fn run_job_for_target<J: Job>(
     Ok(())
 }
 
+pub(crate) async fn find_job_row<C>(
+    database: &C,
+    job_kind: jobs_entity::JobKind,
+    target_id: &str,
+) -> anyhow::Result<jobs_entity::Model>
+where
+    C: ConnectionTrait,
+{
+    jobs_entity::Entity::find()
+        .filter(jobs_entity::Column::JobKind.eq(job_kind.code()))
+        .filter(jobs_entity::Column::TargetId.eq(target_id))
+        .one(database)
+        .await?
+        .with_context(|| format!("missing job row for {job_kind:?} {target_id}"))
+}
+
 pub(crate) async fn delete_job_row<C>(
     database: &C,
     job_kind: jobs_entity::JobKind,
@@ -1,5 +1,5 @@
 use crate::entities::jobs as jobs_entity;
-use crate::jobs::manager::{delete_job_row, persist_job_error};
+use crate::jobs::manager::{delete_job_row, find_job_row, persist_job_error};
 use crate::jobs::{JobLease, JobOutcome};
 use crate::{activity::ActivityHandle, jobs::Job};
 use anyhow::Context;
@@ -118,6 +118,23 @@ async fn run_inline<J: Job>(
             delete_job_row(database, J::JOB_KIND, &target_id).await?;
             Ok(())
         }
+        Ok(JobOutcome::Postponed(delay_seconds)) => {
+            let job_row = find_job_row(database, J::JOB_KIND, &target_id).await?;
+            let attempt_count = job_row.attempt_count;
+            persist_job_error(
+                database,
+                job_row,
+                attempt_count,
+                Some(chrono::Utc::now().timestamp() + delay_seconds),
+                "postponed".to_string(),
+            )
+            .await?;
+            anyhow::bail!(
+                "{:?} job for target {} was postponed",
+                J::JOB_KIND,
+                target_id
+            );
+        }
         Err(error) => {
             tracing::error!(
                 "on-demand {:?} job for target {} failed with error: {:?}",
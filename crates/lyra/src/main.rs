@@ -2,7 +2,7 @@ use crate::{
     auth::RequestAuth,
     backup::{BackupManager, run_backup_worker, run_migrations_with_backups},
     cleanup::start_cleanup_worker,
-    collections::reconcile_system_collections,
+    collections::{reconcile_franchise_collections, reconcile_system_collections},
     config::get_config,
     content_update::CONTENT_UPDATE,
     entities::{
@@ -46,6 +46,7 @@ mod activity;
 mod assets;
 mod auth;
 mod backup;
+mod calendar;
 mod cleanup;
 mod collections;
 mod config;
@@ -252,6 +253,9 @@ async fn main() {
     reconcile_system_collections(&pool)
         .await
         .expect("Failed to reconcile system collections");
+    reconcile_franchise_collections(&pool, chrono::Utc::now().timestamp())
+        .await
+        .expect("Failed to reconcile franchise collections");
     let job_wake_signal = Arc::new(Notify::new());
     let heavy_job_controller = Arc::new(HeavyJobController::new());
     let startup_scans_complete = CancellationToken::new();
@@ -344,6 +348,14 @@ async fn main() {
         graphql::dataloaders::node_counts::NodeCountsLoader::new(pool.clone()),
         tokio::spawn,
     ))
+    .data(DataLoader::new(
+        graphql::dataloaders::node_metadata_details::NodeMetadataDetailsLoader::new(pool.clone()),
+        tokio::spawn,
+    ))
+    .data(DataLoader::new(
+        graphql::dataloaders::node_ratings::NodeRatingLoader::new(pool.clone()),
+        tokio::spawn,
+    ))
     .finish();
 
     // write the schema to a file in dev
@@ -368,6 +380,7 @@ async fn main() {
     let mut app = Router::new()
         .nest("/api/hls", hls::get_hls_router())
         .nest("/api/assets", assets::get_assets_router())
+        .nest("/api/calendar", calendar::get_calendar_router())
         .route("/api/graphql", get(get_graphql).post(post_graphql))
         .route("/api/graphql/ws", get(get_graphql_ws))
         .route("/api/init", get(get_init_state))
@@ -1,15 +1,48 @@
 use super::sync;
 use crate::entities::{
-    jobs as jobs_entity, metadata_source::MetadataSource, node_metadata, nodes, nodes::NodeKind,
+    jobs as jobs_entity, metadata_source::MetadataSource, node_metadata,
+    node_metadata::MetadataStatus, nodes, nodes::NodeKind,
 };
 use crate::jobs::{Job, JobExecutionPolicy, JobLease, JobOutcome};
 use crate::metadata::METADATA_RETRY_BACKOFF_SECONDS;
-use lyra_metadata::MetadataProvider;
+use lazy_static::lazy_static;
+use lyra_metadata::{MetadataProvider, OfflineCacheMiss};
 use sea_orm::{
     ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Select,
     sea_query::{Expr, Query},
 };
-use std::sync::Arc;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex, PoisonError};
+
+// how long an offline sync that missed the cache waits before looking again
+const OFFLINE_RETRY_SECONDS: i64 = 6 * 60 * 60;
+
+// providers take a few hours to fill in titles and runtimes after an episode airs
+const AIRED_REFRESH_DELAY_SECONDS: i64 = 6 * 60 * 60;
+// airing shows without a known next date still pick up newly announced episodes
+const AIRING_REFRESH_SECONDS: i64 = 3 * 24 * 60 * 60;
+const RETURNING_REFRESH_SECONDS: i64 = 7 * 24 * 60 * 60;
+const SETTLED_REFRESH_SECONDS: i64 = 90 * 24 * 60 * 60;
+
+lazy_static! {
+    // roots an admin asked to refresh. their next sync revalidates cached provider responses the
+    // way a scheduled refresh does. a restart forgets the request, the dirty root still resyncs.
+    static ref FORCED_REFRESHES: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+}
+
+pub fn force_metadata_refresh(root_id: &str) {
+    FORCED_REFRESHES
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(root_id.to_owned());
+}
+
+fn take_forced_refresh(root_id: &str) -> bool {
+    FORCED_REFRESHES
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .remove(root_id)
+}
 
 pub struct NodeMetadataSyncRootJob {
     providers: Vec<Arc<dyn MetadataProvider>>,
@@ -33,6 +66,7 @@ impl Job for NodeMetadataSyncRootJob {
     }
 
     fn query(&self) -> Select<Self::Entity> {
+        let now = chrono::Utc::now().timestamp();
         nodes::Entity::find()
             .filter(nodes::Column::ParentId.is_null())
             .filter(nodes::Column::Kind.is_in([NodeKind::Movie, NodeKind::Series]))
@@ -44,7 +78,8 @@ impl Job for NodeMetadataSyncRootJob {
                             .not()
                             .add(Expr::exists(remote_metadata_exists_query())),
                     )
-                    .add(Expr::exists(stale_remote_metadata_query())),
+                    .add(Expr::exists(stale_remote_metadata_query()))
+                    .add(Expr::exists(due_remote_metadata_query(now))),
             )
             .order_by_asc(nodes::Column::LastAddedAt)
             .order_by_asc(nodes::Column::Id)
@@ -60,8 +95,32 @@ impl Job for NodeMetadataSyncRootJob {
         root: Self::Model,
         _ctx: &JobLease,
     ) -> anyhow::Result<JobOutcome> {
-        sync::sync_root(db, &self.providers, &root).await?;
-        Ok(JobOutcome::Complete)
+        // forced and scheduled refreshes revalidate cached provider responses, otherwise a sync
+        // shortly after the last one would read the same bodies back out of the cache
+        let refresh_due = take_forced_refresh(&root.id)
+            || nodes::Entity::find_by_id(root.id.clone())
+                .filter(Expr::exists(due_remote_metadata_query(
+                    chrono::Utc::now().timestamp(),
+                )))
+                .one(db)
+                .await?
+                .is_some();
+        let result = if refresh_due {
+            lyra_metadata::revalidating(sync::sync_root(db, &self.providers, &root)).await
+        } else {
+            sync::sync_root(db, &self.providers, &root).await
+        };
+
+        match result {
+            Ok(()) => Ok(JobOutcome::Complete),
+            // retrying a cache miss sooner or more often won't fix it, anything else offline is
+            // a real failure and backs off like one
+            Err(error) if error.downcast_ref::<OfflineCacheMiss>().is_some() => {
+                tracing::debug!(root_id = %root.id, "offline metadata sync postponed: {error:#}");
+                Ok(JobOutcome::Postponed(OFFLINE_RETRY_SECONDS))
+            }
+            Err(error) => Err(error),
+        }
     }
 }
 
@@ -95,6 +154,51 @@ fn remote_metadata_exists_query() -> sea_orm::sea_query::SelectStatement {
         .to_owned()
 }
 
+// remote metadata that is old enough for its air status to warrant another look
+fn due_remote_metadata_query(now: i64) -> sea_orm::sea_query::SelectStatement {
+    let status = (node_metadata::Entity, node_metadata::Column::Status);
+    let updated_at = (node_metadata::Entity, node_metadata::Column::UpdatedAt);
+    let next_aired = (node_metadata::Entity, node_metadata::Column::NextAired);
+    let airing = [MetadataStatus::Upcoming, MetadataStatus::Airing];
+    let returning = [MetadataStatus::Returning, MetadataStatus::InTheaters];
+
+    Query::select()
+        .expr(Expr::val(1))
+        .from(node_metadata::Entity)
+        .and_where(
+            Expr::col((node_metadata::Entity, node_metadata::Column::NodeId))
+                .equals((nodes::Entity, nodes::Column::Id)),
+        )
+        .and_where(
+            Expr::col((node_metadata::Entity, node_metadata::Column::Source))
+                .eq(MetadataSource::Remote),
+        )
+        .cond_where(
+            Condition::any()
+                .add(
+                    Condition::all()
+                        .add(Expr::col(status).is_in(airing))
+                        .add(Expr::col(next_aired).lte(now - AIRED_REFRESH_DELAY_SECONDS))
+                        .add(
+                            Expr::col(updated_at)
+                                .lt(Expr::col(next_aired).add(AIRED_REFRESH_DELAY_SECONDS)),
+                        ),
+                )
+                .add(
+                    Condition::all()
+                        .add(Expr::col(status).is_in(airing))
+                        .add(Expr::col(updated_at).lt(now - AIRING_REFRESH_SECONDS)),
+                )
+                .add(
+                    Condition::all()
+                        .add(Expr::col(status).is_in(returning))
+                        .add(Expr::col(updated_at).lt(now - RETURNING_REFRESH_SECONDS)),
+                )
+                .add(Expr::col(updated_at).lt(now - SETTLED_REFRESH_SECONDS)),
+        )
+        .to_owned()
+}
+
 fn stale_remote_metadata_query() -> sea_orm::sea_query::SelectStatement {
     Query::select()
         .expr(Expr::val(1))
@@ -113,3 +217,162 @@ fn stale_remote_metadata_query() -> sea_orm::sea_query::SelectStatement {
         )
         .to_owned()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::libraries;
+    use sea_orm::{ActiveValue::Set, Database, QueryOrder};
+
+    const DAY: i64 = 24 * 60 * 60;
+    const NOW: i64 = 1_000 * DAY;
+
+    async fn setup_test_db() -> anyhow::Result<DatabaseConnection> {
+        let pool = Database::connect("sqlite::memory:").await?;
+        sqlx::migrate!("../../migrations")
+            .run(pool.get_sqlite_connection_pool())
+            .await?;
+
+        libraries::Entity::insert(libraries::ActiveModel {
+            id: Set("lib".to_owned()),
+            path: Set("/library".to_owned()),
+            name: Set("Library".to_owned()),
+            pinned: Set(false),
+            recordings: Set(false),
+            anime: Set(false),
+            last_scanned_at: Set(None),
+            unavailable_at: Set(None),
+            created_at: Set(0),
+        })
+        .exec(&pool)
+        .await?;
+        Ok(pool)
+    }
+
+    async fn insert_series(
+        pool: &DatabaseConnection,
+        id: &str,
+        status: MetadataStatus,
+        next_aired: Option<i64>,
+        updated_at: i64,
+    ) -> anyhow::Result<()> {
+        nodes::Entity::insert(nodes::ActiveModel {
+            id: Set(id.to_owned()),
+            library_id: Set("lib".to_owned()),
+            root_id: Set(id.to_owned()),
+            parent_id: Set(None),
+            kind: Set(NodeKind::Series),
+            name: Set(id.to_owned()),
+            order: Set(0),
+            season_number: Set(None),
+            episode_number: Set(None),
+            extra_type: Set(None),
+            last_added_at: Set(0),
+            last_fingerprint_version: Set(None),
+            unavailable_at: Set(None),
+            created_at: Set(0),
+            updated_at: Set(0),
+        })
+        .exec(pool)
+        .await?;
+        node_metadata::Entity::insert(node_metadata::ActiveModel {
+            id: Set(format!("remote-{id}")),
+            node_id: Set(id.to_owned()),
+            source: Set(MetadataSource::Remote),
+            provider_id: Set("tmdb".to_owned()),
+            name: Set(id.to_owned()),
+            status: Set(Some(status)),
+            next_aired: Set(next_aired),
+            created_at: Set(0),
+            updated_at: Set(updated_at),
+            ..Default::default()
+        })
+        .exec(pool)
+        .await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn refresh_policy_follows_air_status() -> anyhow::Result<()> {
+        let pool = setup_test_db().await?;
+        // aired a day ago, last synced before it aired
+        insert_series(
+            &pool,
+            "aired",
+            MetadataStatus::Airing,
+            Some(NOW - DAY),
+            NOW - 2 * DAY,
+        )
+        .await?;
+        // aired a day ago and already synced since
+        insert_series(
+            &pool,
+            "caught-up",
+            MetadataStatus::Airing,
+            Some(NOW - DAY),
+            NOW - 1,
+        )
+        .await?;
+        // airs tomorrow, synced yesterday
+        insert_series(
+            &pool,
+            "soon",
+            MetadataStatus::Airing,
+            Some(NOW + DAY),
+            NOW - DAY,
+        )
+        .await?;
+        insert_series(
+            &pool,
+            "returning",
+            MetadataStatus::Returning,
+            None,
+            NOW - 8 * DAY,
+        )
+        .await?;
+        insert_series(
+            &pool,
+            "on-break",
+            MetadataStatus::Returning,
+            None,
+            NOW - 2 * DAY,
+        )
+        .await?;
+        insert_series(
+            &pool,
+            "finished",
+            MetadataStatus::Finished,
+            None,
+            NOW - 30 * DAY,
+        )
+        .await?;
+        insert_series(
+            &pool,
+            "forgotten",
+            MetadataStatus::Finished,
+            None,
+            NOW - 100 * DAY,
+        )
+        .await?;
+
+        let due = nodes::Entity::find()
+            .filter(Expr::exists(due_remote_metadata_query(NOW)))
+            .order_by_asc(nodes::Column::Id)
+            .all(&pool)
+            .await?
+            .into_iter()
+            .map(|node| node.id)
+            .collect::<Vec<_>>();
+        assert_eq!(due, vec!["aired", "forgotten", "returning"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn forced_refresh_applies_to_one_sync() {
+        force_metadata_refresh("forced");
+        assert!(!take_forced_refresh("other"));
+        assert!(take_forced_refresh("forced"));
+        assert!(!take_forced_refresh("forced"));
+    }
+}
@@ -12,6 +12,7 @@ pub struct NodeLocalMetadataInput {
     pub name: String,
     pub imdb_id: Option<String>,
     pub tmdb_id: Option<i64>,
+    pub anidb_id: Option<i64>,
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
@@ -37,6 +38,9 @@ pub fn upsert_node_local_metadata_input(
             if existing.tmdb_id.is_none() {
                 existing.tmdb_id = next.tmdb_id;
             }
+            if existing.anidb_id.is_none() {
+                existing.anidb_id = next.anidb_id;
+            }
         }
     }
 }
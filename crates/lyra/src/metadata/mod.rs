@@ -1,22 +1,34 @@
-use lyra_metadata::MetadataProvider;
-use lyra_metadata_tmdb::TmdbMetadataProvider;
+use crate::config::get_config;
+use lazy_static::lazy_static;
+use lyra_metadata::{MetadataProvider, ResponseCache};
+use lyra_metadata_anime::AnimeMetadataProvider;
+use lyra_metadata_nfo::NfoMetadataProvider;
+use lyra_metadata_tmdb::{TmdbMetadataProvider, TmdbSettings};
 use sea_orm::DatabaseConnection;
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 use tokio::sync::Notify;
 use tokio_util::sync::CancellationToken;
 
 mod job_root_sync;
 mod local;
+mod overrides;
 mod read;
 mod remote;
 mod store;
 mod sync;
 
+pub(crate) use job_root_sync::force_metadata_refresh;
 pub(crate) use local::{
     LocalMetadataPlan, NodeLocalMetadataInput, replace_local_metadata_for_root,
     upsert_node_local_metadata_input,
 };
-pub(crate) use read::join_preferred_node_metadata;
+pub(crate) use overrides::{
+    MetadataOverrides, apply_metadata_overrides, lock_metadata_fields, unlock_metadata_fields,
+};
+pub(crate) use read::{join_preferred_node_metadata, preferred_metadata_column};
+pub(crate) use remote::{
+    MetadataCandidate, lookup_root_episode_orderings, root_is_anime, search_candidates,
+};
 pub(crate) use sync::mark_root_dirty;
 
 pub(crate) const METADATA_RETRY_BACKOFF_SECONDS: &[i64] = &[
@@ -26,8 +38,62 @@ pub(crate) const METADATA_RETRY_BACKOFF_SECONDS: &[i64] = &[
     90 * 24 * 60 * 60,
 ];
 
-fn build_metadata_providers() -> Vec<Arc<dyn MetadataProvider>> {
-    vec![Arc::new(TmdbMetadataProvider::new())]
+// far past every provider ttl. entries still in use are revalidated long before this, so
+// anything older belongs to a show or person nothing looks up anymore.
+const RESPONSE_CACHE_MAX_AGE: Duration = Duration::from_secs(180 * 24 * 60 * 60);
+
+lazy_static! {
+    static ref RESPONSE_CACHE: Arc<ResponseCache> = Arc::new(ResponseCache::new(
+        get_config().get_metadata_cache_dir().join("responses"),
+        get_config().metadata_offline,
+    ));
+
+    // shared between the sync job and graphql candidate searches so both go through the same
+    // rate limiter and response cache. order is priority: local nfo files win and tmdb fills
+    // in whatever they leave out. the anime provider wraps the same tmdb instance so remapped
+    // lookups share its rate limiter.
+    static ref METADATA_PROVIDERS: Vec<Arc<dyn MetadataProvider>> = {
+        let tmdb: Arc<dyn MetadataProvider> = Arc::new(TmdbMetadataProvider::new(
+            tmdb_settings(),
+            RESPONSE_CACHE.clone(),
+        ));
+        vec![
+            Arc::new(NfoMetadataProvider::new()),
+            Arc::new(AnimeMetadataProvider::new(
+                tmdb.clone(),
+                RESPONSE_CACHE.clone(),
+            )),
+            tmdb,
+        ]
+    };
+}
+
+fn tmdb_settings() -> TmdbSettings {
+    let config = &get_config().tmdb;
+    TmdbSettings {
+        api_key: config.api_key.clone().filter(|key| !key.is_empty()),
+        access_token: config
+            .access_token
+            .clone()
+            .filter(|token| !token.is_empty()),
+        api_base_url: config.api_base_url.trim_end_matches('/').to_owned(),
+        image_base_url: config.image_base_url.trim_end_matches('/').to_owned(),
+        language: config.language.clone(),
+        fallback_languages: config.fallback_languages.clone(),
+        image_languages: config.image_languages.clone(),
+    }
+}
+
+pub(crate) fn metadata_providers() -> Vec<Arc<dyn MetadataProvider>> {
+    METADATA_PROVIDERS.clone()
+}
+
+pub(crate) async fn sweep_response_cache() -> anyhow::Result<()> {
+    let removed = RESPONSE_CACHE.sweep(RESPONSE_CACHE_MAX_AGE).await?;
+    if removed > 0 {
+        tracing::info!(removed, "removed old cached metadata responses");
+    }
+    Ok(())
 }
 
 pub(crate) fn register_jobs(
@@ -39,7 +105,7 @@ pub(crate) fn register_jobs(
 ) {
     crate::jobs::register_job(
         Arc::new(job_root_sync::NodeMetadataSyncRootJob::new(
-            build_metadata_providers(),
+            metadata_providers(),
         )),
         jobs,
         heavy_jobs,
@@ -0,0 +1,243 @@
+use crate::entities::{
+    metadata_source::MetadataSource,
+    node_metadata::{self, LockedFields},
+    node_metadata_genres,
+};
+use crate::ids;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, ConnectionTrait, EntityTrait, IntoActiveModel,
+    QueryFilter, QueryOrder,
+};
+
+const USER_METADATA_PROVIDER_ID: &str = "user";
+
+// hand edits for one node. `None` leaves a field alone, for the nullable fields `Some(None)`
+// overrides them to empty.
+#[derive(Debug, Default)]
+pub struct MetadataOverrides {
+    pub name: Option<String>,
+    pub description: Option<Option<String>>,
+    pub tagline: Option<Option<String>>,
+    pub first_aired: Option<Option<i64>>,
+    pub last_aired: Option<Option<i64>>,
+    pub genres: Option<Vec<String>>,
+}
+
+// every overridden field is locked, so later syncs and rescans can't replace it
+pub async fn apply_metadata_overrides(
+    pool: &impl ConnectionTrait,
+    node_id: &str,
+    overrides: MetadataOverrides,
+    now: i64,
+) -> anyhow::Result<()> {
+    let row = load_or_create_user_row(pool, node_id, now).await?;
+    let mut locked = row.locked();
+    if let Some(genres) = overrides.genres {
+        replace_user_genres(pool, &row.id, genres, now).await?;
+        locked |= LockedFields::GENRES;
+    }
+
+    let mut row = row.into_active_model();
+
+    if let Some(name) = overrides.name {
+        row.name = Set(name);
+        locked |= LockedFields::NAME;
+    }
+    if let Some(description) = overrides.description {
+        row.description = Set(description);
+        locked |= LockedFields::DESCRIPTION;
+    }
+    if let Some(tagline) = overrides.tagline {
+        row.tagline = Set(tagline);
+        locked |= LockedFields::TAGLINE;
+    }
+    if let Some(first_aired) = overrides.first_aired {
+        row.first_aired = Set(first_aired);
+        locked |= LockedFields::FIRST_AIRED;
+    }
+    if let Some(last_aired) = overrides.last_aired {
+        row.last_aired = Set(last_aired);
+        locked |= LockedFields::LAST_AIRED;
+    }
+    row.locked_fields = Set(locked.bits());
+    row.updated_at = Set(now);
+    row.update(pool).await?;
+
+    Ok(())
+}
+
+// locking without an edit pins whatever is showing right now
+pub async fn lock_metadata_fields(
+    pool: &impl ConnectionTrait,
+    node_id: &str,
+    fields: LockedFields,
+    now: i64,
+) -> anyhow::Result<()> {
+    let base = node_metadata::Entity::find()
+        .filter(node_metadata::Column::NodeId.eq(node_id.to_string()))
+        .filter(node_metadata::Column::Source.ne(MetadataSource::User))
+        .order_by_desc(node_metadata::Column::Source)
+        .one(pool)
+        .await?;
+    let row = load_or_create_user_row(pool, node_id, now).await?;
+    // fields that are already locked keep their edited values
+    let fields = fields - row.locked();
+    let Some(base) = base else {
+        // nothing to snapshot, the fields are locked as empty
+        let locked = row.locked() | fields;
+        let mut row = row.into_active_model();
+        row.locked_fields = Set(locked.bits());
+        row.updated_at = Set(now);
+        row.update(pool).await?;
+        return Ok(());
+    };
+
+    let mut overrides = MetadataOverrides::default();
+    if fields.contains(LockedFields::NAME) {
+        overrides.name = Some(base.name.clone());
+    }
+    if fields.contains(LockedFields::DESCRIPTION) {
+        overrides.description = Some(base.description.clone());
+    }
+    if fields.contains(LockedFields::TAGLINE) {
+        overrides.tagline = Some(base.tagline.clone());
+    }
+    if fields.contains(LockedFields::FIRST_AIRED) {
+        overrides.first_aired = Some(base.first_aired);
+    }
+    if fields.contains(LockedFields::LAST_AIRED) {
+        overrides.last_aired = Some(base.last_aired);
+    }
+    if fields.contains(LockedFields::GENRES) {
+        let genres = node_metadata_genres::Entity::find()
+            .filter(node_metadata_genres::Column::NodeMetadataId.eq(base.id.clone()))
+            .order_by_asc(node_metadata_genres::Column::Position)
+            .all(pool)
+            .await?;
+        overrides.genres = Some(genres.into_iter().map(|genre| genre.name).collect());
+    }
+
+    apply_metadata_overrides(pool, node_id, overrides, now).await
+}
+
+// unlocked fields go back to provider values. the user row goes away with its last lock.
+pub async fn unlock_metadata_fields(
+    pool: &impl ConnectionTrait,
+    node_id: &str,
+    fields: LockedFields,
+    now: i64,
+) -> anyhow::Result<()> {
+    let Some(row) = find_user_row(pool, node_id).await? else {
+        return Ok(());
+    };
+
+    let locked = row.locked() - fields;
+    if locked.is_empty() {
+        node_metadata::Entity::delete_by_id(row.id)
+            .exec(pool)
+            .await?;
+        return Ok(());
+    }
+
+    if fields.contains(LockedFields::GENRES) {
+        replace_user_genres(pool, &row.id, Vec::new(), now).await?;
+    }
+    let mut row = row.into_active_model();
+    if fields.contains(LockedFields::NAME) {
+        row.name = Set(String::new());
+    }
+    if fields.contains(LockedFields::DESCRIPTION) {
+        row.description = Set(None);
+    }
+    if fields.contains(LockedFields::TAGLINE) {
+        row.tagline = Set(None);
+    }
+    if fields.contains(LockedFields::FIRST_AIRED) {
+        row.first_aired = Set(None);
+    }
+    if fields.contains(LockedFields::LAST_AIRED) {
+        row.last_aired = Set(None);
+    }
+    row.locked_fields = Set(locked.bits());
+    row.updated_at = Set(now);
+    row.update(pool).await?;
+
+    Ok(())
+}
+
+async fn find_user_row(
+    pool: &impl ConnectionTrait,
+    node_id: &str,
+) -> anyhow::Result<Option<node_metadata::Model>> {
+    Ok(node_metadata::Entity::find()
+        .filter(node_metadata::Column::NodeId.eq(node_id.to_string()))
+        .filter(node_metadata::Column::Source.eq(MetadataSource::User))
+        .one(pool)
+        .await?)
+}
+
+// unlocked fields on the user row are left empty, which also keeps its search index entry from
+// matching on stale text
+async fn load_or_create_user_row(
+    pool: &impl ConnectionTrait,
+    node_id: &str,
+    now: i64,
+) -> anyhow::Result<node_metadata::Model> {
+    if let Some(row) = find_user_row(pool, node_id).await? {
+        return Ok(row);
+    }
+
+    Ok(node_metadata::ActiveModel {
+        id: Set(ids::generate_ulid()),
+        node_id: Set(node_id.to_string()),
+        source: Set(MetadataSource::User),
+        provider_id: Set(USER_METADATA_PROVIDER_ID.to_owned()),
+        imdb_id: Set(None),
+        tmdb_id: Set(None),
+        anidb_id: Set(None),
+        name: Set(String::new()),
+        description: Set(None),
+        score_display: Set(None),
+        score_normalized: Set(None),
+        first_aired: Set(None),
+        last_aired: Set(None),
+        status: Set(None),
+        tagline: Set(None),
+        next_aired: Set(None),
+        locked_fields: Set(0),
+        created_at: Set(now),
+        updated_at: Set(now),
+    }
+    .insert(pool)
+    .await?)
+}
+
+async fn replace_user_genres(
+    pool: &impl ConnectionTrait,
+    metadata_id: &str,
+    genres: Vec<String>,
+    now: i64,
+) -> anyhow::Result<()> {
+    node_metadata_genres::Entity::delete_many()
+        .filter(node_metadata_genres::Column::NodeMetadataId.eq(metadata_id.to_string()))
+        .exec(pool)
+        .await?;
+    if genres.is_empty() {
+        return Ok(());
+    }
+
+    node_metadata_genres::Entity::insert_many(genres.into_iter().enumerate().map(
+        |(position, name)| node_metadata_genres::ActiveModel {
+            id: Set(ids::generate_ulid()),
+            node_metadata_id: Set(metadata_id.to_string()),
+            provider_id: Set(USER_METADATA_PROVIDER_ID.to_owned()),
+            external_id: Set(None),
+            name: Set(name),
+            position: Set(position as i64),
+            created_at: Set(now),
+        },
+    ))
+    .exec(pool)
+    .await?;
+    Ok(())
+}
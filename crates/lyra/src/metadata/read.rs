@@ -1,40 +1,86 @@
-use crate::entities::{metadata_source::MetadataSource, node_metadata, nodes};
+use crate::entities::{
+    metadata_source::MetadataSource,
+    node_metadata::{self, LockedFields},
+    nodes,
+};
 use sea_orm::{
-    ColumnTrait, Condition, JoinType, QueryFilter, QuerySelect, RelationTrait, Select,
-    sea_query::{Alias, Expr, Query},
+    ColumnTrait, Condition, JoinType, QueryFilter, QuerySelect, QueryTrait, RelationTrait, Select,
+    sea_query::{Alias, BinOper, Expr, Query, SimpleExpr},
 };
 
+const USER_METADATA_ALIAS: &str = "user_metadata";
+
+// joins the provider row a node displays plus its user row, if any. sort and filter code should
+// go through `preferred_metadata_column` for fields a user can lock.
 pub fn join_preferred_node_metadata(mut query: Select<nodes::Entity>) -> Select<nodes::Entity> {
     query = query
         .join(JoinType::LeftJoin, nodes::Relation::NodeMetadata.def())
         .filter(preferred_node_metadata_condition());
+    let user_metadata = Alias::new(USER_METADATA_ALIAS);
+    QueryTrait::query(&mut query).join_as(
+        JoinType::LeftJoin,
+        node_metadata::Entity,
+        user_metadata.clone(),
+        Condition::all()
+            .add(
+                Expr::col((user_metadata.clone(), node_metadata::Column::NodeId))
+                    .equals((nodes::Entity, nodes::Column::Id)),
+            )
+            .add(
+                Expr::col((user_metadata, node_metadata::Column::Source)).eq(MetadataSource::User),
+            ),
+    );
     query
 }
 
-fn preferred_node_metadata_condition() -> Condition {
-    let remote_rows = Alias::new("preferred_remote_rows");
+// the user's value when they locked the field, the provider's otherwise
+pub fn preferred_metadata_column(column: node_metadata::Column, field: LockedFields) -> SimpleExpr {
+    let user_metadata = Alias::new(USER_METADATA_ALIAS);
+    Expr::case(
+        Expr::expr(
+            Expr::col((user_metadata.clone(), node_metadata::Column::LockedFields))
+                .binary(BinOper::BitAnd, field.bits()),
+        )
+        .ne(0),
+        Expr::col((user_metadata, column)),
+    )
+    .finally(Expr::col((node_metadata::Entity, column)))
+    .into()
+}
 
+fn preferred_node_metadata_condition() -> Condition {
     Condition::any()
         .add(node_metadata::Column::Id.is_null())
         .add(node_metadata::Column::Source.eq(MetadataSource::Remote))
         .add(
             Condition::all()
                 .add(node_metadata::Column::Source.eq(MetadataSource::Local))
-                .add(
-                    Condition::all().not().add(Expr::exists(
-                        Query::select()
-                            .expr(Expr::val(1))
-                            .from_as(node_metadata::Entity, remote_rows.clone())
-                            .and_where(
-                                Expr::col((remote_rows.clone(), node_metadata::Column::NodeId))
-                                    .equals((node_metadata::Entity, node_metadata::Column::NodeId)),
-                            )
-                            .and_where(
-                                Expr::col((remote_rows, node_metadata::Column::Source))
-                                    .eq(MetadataSource::Remote),
-                            )
-                            .to_owned(),
-                    )),
-                ),
+                .add(not_exists_metadata_from(&[MetadataSource::Remote])),
         )
+        // a node that only has hand edits still needs a row, or the join would drop it
+        .add(
+            Condition::all()
+                .add(node_metadata::Column::Source.eq(MetadataSource::User))
+                .add(not_exists_metadata_from(&[
+                    MetadataSource::Local,
+                    MetadataSource::Remote,
+                ])),
+        )
+}
+
+fn not_exists_metadata_from(sources: &[MetadataSource]) -> Condition {
+    let other_rows = Alias::new("preferred_other_rows");
+    Condition::all().not().add(Expr::exists(
+        Query::select()
+            .expr(Expr::val(1))
+            .from_as(node_metadata::Entity, other_rows.clone())
+            .and_where(
+                Expr::col((other_rows.clone(), node_metadata::Column::NodeId))
+                    .equals((node_metadata::Entity, node_metadata::Column::NodeId)),
+            )
+            .and_where(
+                Expr::col((other_rows, node_metadata::Column::Source)).is_in(sources.to_vec()),
+            )
+            .to_owned(),
+    ))
 }
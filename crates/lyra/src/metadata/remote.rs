@@ -1,12 +1,17 @@
 use crate::entities::metadata_source::MetadataSource;
-use crate::entities::{node_metadata, nodes, nodes::NodeKind};
+use crate::entities::{files, libraries, node_files, node_metadata, nodes, nodes::NodeKind};
 use anyhow::Context;
+use async_graphql::SimpleObject;
 use chrono::Datelike;
 use lyra_metadata::{
-    MetadataProvider, MovieMetadata, MovieRootMatchRequest, RootMatchHint, Scored, SeriesCandidate,
-    SeriesItemsRequest, SeriesItemsResult, SeriesMetadata, SeriesRootMatchRequest,
+    EpisodeOrdering, MetadataProvider, MovieMetadata, MovieRootMatchRequest, RootMatchHint, Scored,
+    SeriesCandidate, SeriesItemsRequest, SeriesItemsResult, SeriesMetadata, SeriesRootMatchRequest,
 };
-use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, EntityTrait, FromQueryResult, JoinType, QueryFilter, QueryOrder,
+    QuerySelect, RelationTrait,
+};
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
 
 pub enum MatchedRoot {
     Series {
@@ -18,14 +23,31 @@ pub enum MatchedRoot {
     },
 }
 
+// a candidate from a manual search, flattened so series and movie results can be listed
+// together
+#[derive(Debug, Clone, SimpleObject)]
+pub struct MetadataCandidate {
+    pub provider_id: String,
+    pub external_id: String,
+    pub name: String,
+    pub year: Option<i32>,
+    pub poster_url: Option<String>,
+    pub score: f32,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct NodeFilePathRow {
+    node_id: String,
+    relative_path: String,
+    library_path: String,
+}
+
 pub async fn match_root(
-    db: &impl ConnectionTrait,
     provider: &dyn MetadataProvider,
-    node: &nodes::Model,
+    kind: NodeKind,
+    hint: RootMatchHint,
 ) -> anyhow::Result<Option<MatchedRoot>> {
-    let hint = load_root_match_hint(db, node).await?;
-
-    match node.kind {
+    match kind {
         NodeKind::Series => {
             let candidates = provider
                 .match_series_root(SeriesRootMatchRequest { hint })
@@ -63,11 +85,116 @@ pub async fn match_root(
     }
 }
 
+pub async fn search_candidates(
+    provider: &dyn MetadataProvider,
+    kind: NodeKind,
+    query: &str,
+    year: Option<i32>,
+    anime: bool,
+) -> anyhow::Result<Vec<MetadataCandidate>> {
+    let hint = RootMatchHint {
+        title: query.to_owned(),
+        start_year: year,
+        end_year: year,
+        imdb_id: None,
+        tmdb_id: None,
+        pinned_id: None,
+        file_paths: Vec::new(),
+        anidb_id: None,
+        anime,
+    };
+
+    let candidates = match kind {
+        NodeKind::Series => provider
+            .match_series_root(SeriesRootMatchRequest { hint })
+            .await?
+            .into_iter()
+            .filter_map(|Scored { value, score }| {
+                Some(MetadataCandidate {
+                    provider_id: provider.id().to_owned(),
+                    external_id: value.anidb_id.or(value.tmdb_id)?.to_string(),
+                    name: value.name,
+                    year: value.first_air_year,
+                    poster_url: value.poster_url,
+                    score,
+                })
+            })
+            .collect(),
+        NodeKind::Movie => provider
+            .match_movie_root(MovieRootMatchRequest { hint })
+            .await?
+            .into_iter()
+            .filter_map(|Scored { value, score }| {
+                Some(MetadataCandidate {
+                    provider_id: provider.id().to_owned(),
+                    external_id: value.tmdb_id?.to_string(),
+                    name: value.name,
+                    year: value.release_year,
+                    poster_url: value.poster_url,
+                    score,
+                })
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    Ok(candidates)
+}
+
+// every provider is asked, since the one a root is matched to (an nfo, say) often has no
+// orderings while a gap filler does. the candidate is rebuilt from the ids gap filling stored on
+// the remote metadata rather than matching the root again.
+pub async fn lookup_root_episode_orderings(
+    db: &impl ConnectionTrait,
+    providers: &[Arc<dyn MetadataProvider>],
+    root: &nodes::Model,
+) -> anyhow::Result<Vec<(String, EpisodeOrdering)>> {
+    if root.kind != NodeKind::Series {
+        return Ok(Vec::new());
+    }
+    let Some(remote) = node_metadata::Entity::find()
+        .filter(node_metadata::Column::NodeId.eq(root.id.clone()))
+        .filter(node_metadata::Column::Source.eq(MetadataSource::Remote))
+        .one(db)
+        .await?
+    else {
+        return Ok(Vec::new());
+    };
+
+    let candidate = SeriesCandidate {
+        tmdb_id: remote.tmdb_id.and_then(|value| u64::try_from(value).ok()),
+        name: remote.name,
+        first_air_year: None,
+        poster_url: None,
+        source_path: None,
+        anidb_id: remote.anidb_id.and_then(|value| u64::try_from(value).ok()),
+    };
+    let mut orderings = Vec::new();
+    let mut errors = Vec::new();
+    for provider in providers {
+        match provider.lookup_episode_orderings(&candidate).await {
+            Ok(found) => orderings.extend(
+                found
+                    .into_iter()
+                    .map(|ordering| (provider.id().to_owned(), ordering)),
+            ),
+            Err(error) => errors.push(format!("{}: {error:#}", provider.id())),
+        }
+    }
+
+    if orderings.is_empty() && !errors.is_empty() {
+        anyhow::bail!("failed to look up episode orderings: {}", errors.join("; "));
+    }
+    Ok(orderings)
+}
+
 pub async fn lookup_series_items(
     provider: &dyn MetadataProvider,
     root_id: &str,
     candidate: &SeriesCandidate,
     episode_nodes: &[nodes::Model],
+    file_paths: &HashMap<String, Vec<PathBuf>>,
+    ordering_id: Option<String>,
 ) -> anyhow::Result<SeriesItemsResult> {
     let items = episode_nodes
         .iter()
@@ -80,6 +207,7 @@ pub async fn lookup_series_items(
                 .episode_number
                 .and_then(|value| i32::try_from(value).ok()),
             name: node.name.clone(),
+            file_paths: file_paths.get(&node.id).cloned().unwrap_or_default(),
         })
         .collect::<Vec<_>>();
 
@@ -88,11 +216,12 @@ pub async fn lookup_series_items(
             root_id: root_id.to_owned(),
             candidate: candidate.clone(),
             items,
+            ordering_id,
         })
         .await
 }
 
-async fn load_root_match_hint(
+pub async fn load_root_match_hint(
     db: &impl ConnectionTrait,
     node: &nodes::Model,
 ) -> anyhow::Result<RootMatchHint> {
@@ -108,6 +237,8 @@ async fn load_root_match_hint(
         .and_then(|timestamp| chrono::DateTime::from_timestamp(timestamp, 0))
         .map(|timestamp| timestamp.year());
 
+    let anime = root_is_anime(db, node).await?;
+
     Ok(RootMatchHint {
         title: local_metadata.name,
         start_year: year,
@@ -116,5 +247,72 @@ async fn load_root_match_hint(
         tmdb_id: local_metadata
             .tmdb_id
             .and_then(|value| u64::try_from(value).ok()),
+        pinned_id: None,
+        file_paths: Vec::new(),
+        anidb_id: local_metadata
+            .anidb_id
+            .and_then(|value| u64::try_from(value).ok()),
+        anime,
     })
 }
+
+// anime matching is either switched on for the library or inferred from episodes that were
+// only ever numbered absolutely. one stray episode without a season (a special parsed from an
+// odd file name) isn't enough.
+pub async fn root_is_anime(db: &impl ConnectionTrait, root: &nodes::Model) -> anyhow::Result<bool> {
+    let anime_library = libraries::Entity::find_by_id(root.library_id.clone())
+        .one(db)
+        .await?
+        .is_some_and(|library| library.anime);
+    if anime_library {
+        return Ok(true);
+    }
+
+    let episodes = nodes::Entity::find()
+        .filter(nodes::Column::RootId.eq(root.id.clone()))
+        .filter(nodes::Column::Kind.eq(NodeKind::Episode));
+    let has_absolute = episodes
+        .clone()
+        .filter(nodes::Column::SeasonNumber.is_null())
+        .one(db)
+        .await?
+        .is_some();
+    let has_seasons = episodes
+        .filter(nodes::Column::SeasonNumber.is_not_null())
+        .one(db)
+        .await?
+        .is_some();
+    Ok(has_absolute && !has_seasons)
+}
+
+// available files under a root, keyed by the node they're attached to. local providers read
+// metadata stored next to these.
+pub async fn load_root_file_paths(
+    db: &impl ConnectionTrait,
+    root_id: &str,
+) -> anyhow::Result<HashMap<String, Vec<PathBuf>>> {
+    let rows = node_files::Entity::find()
+        .join(JoinType::InnerJoin, node_files::Relation::Nodes.def())
+        .join(JoinType::InnerJoin, node_files::Relation::Files.def())
+        .join(JoinType::InnerJoin, files::Relation::Libraries.def())
+        .filter(nodes::Column::RootId.eq(root_id.to_string()))
+        .filter(files::Column::UnavailableAt.is_null())
+        .select_only()
+        .column_as(node_files::Column::NodeId, "node_id")
+        .column_as(files::Column::RelativePath, "relative_path")
+        .column_as(libraries::Column::Path, "library_path")
+        .order_by_asc(nodes::Column::Order)
+        .order_by_asc(node_files::Column::Order)
+        .into_model::<NodeFilePathRow>()
+        .all(db)
+        .await?;
+
+    let mut paths = HashMap::<String, Vec<PathBuf>>::new();
+    for row in rows {
+        paths
+            .entry(row.node_id)
+            .or_default()
+            .push(PathBuf::from(row.library_path).join(row.relative_path));
+    }
+    Ok(paths)
+}
@@ -1,18 +1,22 @@
 use crate::entities::{
     assets::{self, AssetKind, AssetType},
     metadata_source::MetadataSource,
-    node_metadata, node_metadata_content_ratings, node_metadata_genres, node_metadata_images,
+    node_metadata, node_metadata_collections, node_metadata_companies,
+    node_metadata_companies::NodeMetadataCompanyKind,
+    node_metadata_content_ratings, node_metadata_details, node_metadata_genres,
+    node_metadata_images,
     node_metadata_images::NodeMetadataImageKind,
-    node_metadata_recommendations,
+    node_metadata_keywords, node_metadata_localizations, node_metadata_recommendations,
     node_metadata_recommendations::RecommendationMediaKind,
-    nodes, people, root_node_cast,
+    node_missing_episodes, node_ordering_positions, nodes, people, root_node_cast,
 };
 use crate::ids;
 use crate::metadata::{NodeLocalMetadataInput, local::LOCAL_METADATA_PROVIDER_ID};
 use lyra_metadata::{
-    CastCredit, ContentRating, EpisodeMetadata, ImageSet, MetadataGenre, MetadataStatus,
-    MovieMetadata, PersonMetadata, Recommendation, RecommendedMediaKind, SeasonMetadata,
-    SeriesMetadata,
+    CastCredit, ContentRating, EpisodeMetadata, ImageSet, ItemPosition, LocalizedText,
+    MetadataCompany, MetadataGenre, MetadataKeyword, MetadataStatus, MissingEpisode,
+    MovieCollection, MovieMetadata, PersonMetadata, Recommendation, RecommendedMediaKind,
+    SeasonMetadata, SeriesMetadata,
 };
 use sea_orm::sea_query::{Expr, OnConflict, Query};
 use sea_orm::{
@@ -21,6 +25,9 @@ use sea_orm::{
 };
 use std::collections::{HashMap, HashSet};
 
+// long running shows list thousands of episodes, more than sqlite takes in one statement
+const MISSING_EPISODE_INSERT_CHUNK_SIZE: usize = 500;
+
 pub async fn upsert_local_node_metadata_rows(
     pool: &impl ConnectionTrait,
     rows: &[NodeLocalMetadataInput],
@@ -38,6 +45,7 @@ pub async fn upsert_local_node_metadata_rows(
             provider_id: Set(LOCAL_METADATA_PROVIDER_ID.to_owned()),
             imdb_id: Set(row.imdb_id),
             tmdb_id: Set(row.tmdb_id),
+            anidb_id: Set(row.anidb_id),
             name: Set(row.name),
             description: Set(None),
             score_display: Set(None),
@@ -47,6 +55,7 @@ pub async fn upsert_local_node_metadata_rows(
             status: Set(None),
             tagline: Set(None),
             next_aired: Set(None),
+            locked_fields: Set(0),
             created_at: Set(now),
             updated_at: Set(now),
         }
@@ -57,6 +66,7 @@ pub async fn upsert_local_node_metadata_rows(
                 node_metadata::Column::ProviderId,
                 node_metadata::Column::ImdbId,
                 node_metadata::Column::TmdbId,
+                node_metadata::Column::AnidbId,
                 node_metadata::Column::Name,
                 node_metadata::Column::Description,
                 node_metadata::Column::ScoreDisplay,
@@ -144,6 +154,7 @@ pub async fn upsert_remote_episode_metadata_for_batch(
             MetadataFields {
                 imdb_id: None,
                 tmdb_id: None,
+                anidb_id: None,
                 name: episode.name.clone(),
                 description: episode.description.clone(),
                 score_display: episode.score_display.clone(),
@@ -157,6 +168,8 @@ pub async fn upsert_remote_episode_metadata_for_batch(
                 content_ratings: episode.content_ratings.clone(),
                 recommendations: episode.recommendations.clone(),
                 images: episode.images.clone(),
+                localizations: episode.localizations.clone(),
+                ..MetadataFields::default()
             },
             now,
         )
@@ -195,6 +208,7 @@ pub async fn upsert_remote_season_metadata_for_batch(
             MetadataFields {
                 imdb_id: None,
                 tmdb_id: None,
+                anidb_id: None,
                 name: season.name.clone(),
                 description: season.description.clone(),
                 score_display: season.score_display.clone(),
@@ -208,6 +222,8 @@ pub async fn upsert_remote_season_metadata_for_batch(
                 content_ratings: season.content_ratings.clone(),
                 recommendations: season.recommendations.clone(),
                 images: season.images.clone(),
+                localizations: season.localizations.clone(),
+                ..MetadataFields::default()
             },
             now,
         )
@@ -232,6 +248,122 @@ pub async fn clear_remote_node_metadata_for_root_except(
     delete_metadata_for_root_except(pool, root_id, MetadataSource::Remote, keep_node_ids).await
 }
 
+// positions are only kept for episodes that still exist under the root, the rest of the
+// ordering has nothing local to sort
+// rows are diffed against what is stored, so a resync that keeps every position leaves the
+// table alone. returns whether anything changed.
+pub async fn replace_node_ordering_positions(
+    pool: &impl ConnectionTrait,
+    root_id: &str,
+    episode_nodes: &[nodes::Model],
+    positions: &[ItemPosition],
+    now: i64,
+) -> anyhow::Result<bool> {
+    let episode_ids = episode_nodes
+        .iter()
+        .map(|node| node.id.as_str())
+        .collect::<HashSet<_>>();
+    let wanted = positions
+        .iter()
+        .filter(|position| episode_ids.contains(position.item_id.as_str()))
+        .map(|position| {
+            (
+                position.item_id.as_str(),
+                (
+                    i64::from(position.season_number),
+                    i64::from(position.episode_number),
+                ),
+            )
+        })
+        .collect::<HashMap<_, _>>();
+
+    let existing = node_ordering_positions::Entity::find()
+        .filter(node_ordering_positions::Column::RootId.eq(root_id.to_string()))
+        .all(pool)
+        .await?;
+    let (kept, stale): (Vec<_>, Vec<_>) = existing.into_iter().partition(|row| {
+        wanted.get(row.node_id.as_str()) == Some(&(row.season_number, row.episode_number))
+    });
+    let kept = kept
+        .iter()
+        .map(|row| row.node_id.as_str())
+        .collect::<HashSet<_>>();
+    let rows = wanted
+        .iter()
+        .filter(|(node_id, _)| !kept.contains(*node_id))
+        .map(
+            |(node_id, (season_number, episode_number))| node_ordering_positions::ActiveModel {
+                node_id: Set((*node_id).to_string()),
+                root_id: Set(root_id.to_string()),
+                season_number: Set(*season_number),
+                episode_number: Set(*episode_number),
+                created_at: Set(now),
+            },
+        )
+        .collect::<Vec<_>>();
+
+    let changed = !stale.is_empty() || !rows.is_empty();
+    if !stale.is_empty() {
+        node_ordering_positions::Entity::delete_many()
+            .filter(
+                node_ordering_positions::Column::NodeId
+                    .is_in(stale.into_iter().map(|row| row.node_id)),
+            )
+            .exec(pool)
+            .await?;
+    }
+    if !rows.is_empty() {
+        node_ordering_positions::Entity::insert_many(rows)
+            .exec(pool)
+            .await?;
+    }
+
+    Ok(changed)
+}
+
+pub async fn replace_missing_episodes(
+    pool: &impl ConnectionTrait,
+    root_id: &str,
+    missing: &[MissingEpisode],
+    now: i64,
+) -> anyhow::Result<()> {
+    node_missing_episodes::Entity::delete_many()
+        .filter(node_missing_episodes::Column::RootId.eq(root_id.to_string()))
+        .exec(pool)
+        .await?;
+
+    let rows = missing
+        .iter()
+        .map(|episode| node_missing_episodes::ActiveModel {
+            root_id: Set(root_id.to_string()),
+            season_number: Set(i64::from(episode.season_number)),
+            episode_number: Set(i64::from(episode.episode_number)),
+            name: Set(episode.name.clone()),
+            description: Set(episode.description.clone()),
+            first_aired: Set(episode.first_aired),
+            created_at: Set(now),
+        })
+        .collect::<Vec<_>>();
+    // a provider can list the same number twice, the first listing wins
+    for chunk in rows.chunks(MISSING_EPISODE_INSERT_CHUNK_SIZE) {
+        node_missing_episodes::Entity::insert_many(chunk.to_vec())
+            .on_conflict(
+                OnConflict::columns([
+                    node_missing_episodes::Column::RootId,
+                    node_missing_episodes::Column::SeasonNumber,
+                    node_missing_episodes::Column::EpisodeNumber,
+                ])
+                .do_nothing()
+                .to_owned(),
+            )
+            .do_nothing()
+            .exec(pool)
+            .await?;
+    }
+
+    Ok(())
+}
+
 pub async fn clear_root_cast(pool: &impl ConnectionTrait, root_id: &str) -> anyhow::Result<()> {
     root_node_cast::Entity::delete_many()
         .filter(root_node_cast::Column::RootNodeId.eq(root_id.to_string()))
@@ -245,6 +377,7 @@ pub async fn replace_root_cast(
     root_id: &str,
     provider_id: &str,
     cast: &[CastCredit],
+    crew: &[CastCredit],
     people_metadata: &[PersonMetadata],
     now: i64,
 ) -> anyhow::Result<()> {
@@ -253,7 +386,7 @@ pub async fn replace_root_cast(
         .exec(pool)
         .await?;
 
-    if cast.is_empty() {
+    if cast.is_empty() && crew.is_empty() {
         return Ok(());
     }
 
@@ -263,7 +396,7 @@ pub async fn replace_root_cast(
         .collect::<HashMap<_, _>>();
 
     let mut person_id_by_provider_person_id = HashMap::new();
-    for credit in cast {
+    for credit in cast.iter().chain(crew) {
         if person_id_by_provider_person_id.contains_key(credit.provider_person_id.as_str()) {
             continue;
         }
@@ -283,8 +416,9 @@ pub async fn replace_root_cast(
         person_id_by_provider_person_id.insert(credit.provider_person_id.clone(), person_id);
     }
 
-    root_node_cast::Entity::insert_many(cast.iter().enumerate().map(|(position, credit)| {
-        root_node_cast::ActiveModel {
+    // crew rows are positioned after the cast and told apart by their job
+    root_node_cast::Entity::insert_many(cast.iter().chain(crew).enumerate().map(
+        |(position, credit)| root_node_cast::ActiveModel {
             id: Set(ids::generate_ulid()),
             root_node_id: Set(root_id.to_string()),
             person_id: Set(
@@ -292,10 +426,11 @@ pub async fn replace_root_cast(
             ),
             character_name: Set(credit.character_name.clone()),
             department: Set(credit.department.clone()),
+            job: Set(credit.job.clone()),
             position: Set(position as i64),
             created_at: Set(now),
-        }
-    }))
+        },
+    ))
     .exec(pool)
     .await?;
 
@@ -329,10 +464,11 @@ async fn delete_metadata_for_root_except(
     Ok(())
 }
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 struct MetadataFields {
     imdb_id: Option<String>,
     tmdb_id: Option<i64>,
+    anidb_id: Option<i64>,
     name: String,
     description: Option<String>,
     score_display: Option<String>,
@@ -342,16 +478,28 @@ struct MetadataFields {
     status: Option<node_metadata::MetadataStatus>,
     tagline: Option<String>,
     next_aired: Option<i64>,
+    original_title: Option<String>,
+    original_language: Option<String>,
+    runtime_minutes: Option<i64>,
+    budget: Option<i64>,
     genres: Vec<MetadataGenre>,
     content_ratings: Vec<ContentRating>,
+    studios: Vec<MetadataCompany>,
+    networks: Vec<MetadataCompany>,
+    keywords: Vec<MetadataKeyword>,
     recommendations: Vec<Recommendation>,
     images: ImageSet,
+    localizations: Vec<LocalizedText>,
+    collection: Option<MovieCollection>,
 }
 
 fn metadata_fields_from_series(metadata: &SeriesMetadata) -> MetadataFields {
     MetadataFields {
         imdb_id: metadata.imdb_id.clone(),
         tmdb_id: metadata.tmdb_id.and_then(|value| i64::try_from(value).ok()),
+        anidb_id: metadata
+            .anidb_id
+            .and_then(|value| i64::try_from(value).ok()),
         name: metadata.name.clone(),
         description: metadata.description.clone(),
         score_display: metadata.score_display.clone(),
@@ -361,10 +509,19 @@ fn metadata_fields_from_series(metadata: &SeriesMetadata) -> MetadataFields {
         status: map_status(metadata.status),
         tagline: metadata.tagline.clone(),
         next_aired: metadata.next_aired,
+        original_title: metadata.original_title.clone(),
+        original_language: metadata.original_language.clone(),
+        runtime_minutes: metadata.runtime_minutes,
+        budget: None,
         genres: metadata.genres.clone(),
         content_ratings: metadata.content_ratings.clone(),
+        studios: metadata.studios.clone(),
+        networks: metadata.networks.clone(),
+        keywords: metadata.keywords.clone(),
         recommendations: metadata.recommendations.clone(),
         images: metadata.images.clone(),
+        localizations: metadata.localizations.clone(),
+        collection: None,
     }
 }
 
@@ -372,6 +529,7 @@ fn metadata_fields_from_movie(metadata: &MovieMetadata) -> MetadataFields {
     MetadataFields {
         imdb_id: metadata.imdb_id.clone(),
         tmdb_id: metadata.tmdb_id.and_then(|value| i64::try_from(value).ok()),
+        anidb_id: None,
         name: metadata.name.clone(),
         description: metadata.description.clone(),
         score_display: metadata.score_display.clone(),
@@ -381,10 +539,19 @@ fn metadata_fields_from_movie(metadata: &MovieMetadata) -> MetadataFields {
         status: map_status(metadata.status),
         tagline: metadata.tagline.clone(),
         next_aired: None,
+        original_title: metadata.original_title.clone(),
+        original_language: metadata.original_language.clone(),
+        runtime_minutes: metadata.runtime_minutes,
+        budget: metadata.budget,
         genres: metadata.genres.clone(),
         content_ratings: metadata.content_ratings.clone(),
+        studios: metadata.studios.clone(),
+        networks: Vec::new(),
+        keywords: metadata.keywords.clone(),
         recommendations: metadata.recommendations.clone(),
         images: metadata.images.clone(),
+        localizations: metadata.localizations.clone(),
+        collection: metadata.collection.clone(),
     }
 }
 
@@ -402,6 +569,7 @@ async fn upsert_remote_node_metadata(
         provider_id: Set(provider_id.to_string()),
         imdb_id: Set(metadata.imdb_id.clone()),
         tmdb_id: Set(metadata.tmdb_id),
+        anidb_id: Set(metadata.anidb_id),
         name: Set(metadata.name.clone()),
         description: Set(metadata.description.clone()),
         score_display: Set(metadata.score_display.clone()),
@@ -411,6 +579,7 @@ async fn upsert_remote_node_metadata(
         status: Set(metadata.status),
         tagline: Set(metadata.tagline.clone()),
         next_aired: Set(metadata.next_aired),
+        locked_fields: Set(0),
         created_at: Set(now),
         updated_at: Set(now),
     })
@@ -420,6 +589,7 @@ async fn upsert_remote_node_metadata(
                 node_metadata::Column::ProviderId,
                 node_metadata::Column::ImdbId,
                 node_metadata::Column::TmdbId,
+                node_metadata::Column::AnidbId,
                 node_metadata::Column::Name,
                 node_metadata::Column::Description,
                 node_metadata::Column::ScoreDisplay,
@@ -470,9 +640,58 @@ async fn replace_metadata_children(
         .filter(node_metadata_content_ratings::Column::NodeMetadataId.eq(metadata_id.to_string()))
         .exec(pool)
         .await?;
+    node_metadata_localizations::Entity::delete_many()
+        .filter(node_metadata_localizations::Column::NodeMetadataId.eq(metadata_id.to_string()))
+        .exec(pool)
+        .await?;
+    node_metadata_collections::Entity::delete_many()
+        .filter(node_metadata_collections::Column::NodeMetadataId.eq(metadata_id.to_string()))
+        .exec(pool)
+        .await?;
+    node_metadata_details::Entity::delete_many()
+        .filter(node_metadata_details::Column::NodeMetadataId.eq(metadata_id.to_string()))
+        .exec(pool)
+        .await?;
+    node_metadata_companies::Entity::delete_many()
+        .filter(node_metadata_companies::Column::NodeMetadataId.eq(metadata_id.to_string()))
+        .exec(pool)
+        .await?;
+    node_metadata_keywords::Entity::delete_many()
+        .filter(node_metadata_keywords::Column::NodeMetadataId.eq(metadata_id.to_string()))
+        .exec(pool)
+        .await?;
 
     insert_metadata_images(pool, metadata_id, metadata.images, now).await?;
 
+    if let Some(collection) = metadata.collection {
+        let poster_asset_id = ensure_remote_asset(
+            pool,
+            collection.poster_url.as_deref(),
+            AssetKind::Poster,
+            now,
+        )
+        .await?;
+        let backdrop_asset_id = ensure_remote_asset(
+            pool,
+            collection.backdrop_url.as_deref(),
+            AssetKind::Backdrop,
+            now,
+        )
+        .await?;
+        node_metadata_collections::ActiveModel {
+            node_metadata_id: Set(metadata_id.to_string()),
+            provider_id: Set(collection.provider_id),
+            external_id: Set(collection.external_id),
+            name: Set(collection.name),
+            description: Set(collection.description),
+            poster_asset_id: Set(poster_asset_id),
+            backdrop_asset_id: Set(backdrop_asset_id),
+            created_at: Set(now),
+        }
+        .insert(pool)
+        .await?;
+    }
+
     if !metadata.recommendations.is_empty() {
         node_metadata_recommendations::Entity::insert_many(
             metadata
@@ -531,6 +750,7 @@ async fn replace_metadata_children(
                         rating: Set(row.rating),
                         release_date: Set(row.release_date),
                         release_type: Set(row.release_type),
+                        descriptors: Set(serde_json::json!(row.descriptors).to_string()),
                         position: Set(position as i64),
                         created_at: Set(now),
                     },
@@ -540,6 +760,90 @@ async fn replace_metadata_children(
         .await?;
     }
 
+    if metadata.original_title.is_some()
+        || metadata.original_language.is_some()
+        || metadata.runtime_minutes.is_some()
+        || metadata.budget.is_some()
+    {
+        node_metadata_details::ActiveModel {
+            node_metadata_id: Set(metadata_id.to_string()),
+            original_title: Set(metadata.original_title),
+            original_language: Set(metadata.original_language),
+            runtime_minutes: Set(metadata.runtime_minutes),
+            budget: Set(metadata.budget),
+            created_at: Set(now),
+        }
+        .insert(pool)
+        .await?;
+    }
+
+    let companies = [
+        (NodeMetadataCompanyKind::Studio, metadata.studios),
+        (NodeMetadataCompanyKind::Network, metadata.networks),
+    ]
+    .into_iter()
+    .flat_map(|(kind, rows)| {
+        rows.into_iter().enumerate().map(move |(position, row)| {
+            node_metadata_companies::ActiveModel {
+                id: Set(ids::generate_ulid()),
+                node_metadata_id: Set(metadata_id.to_string()),
+                kind: Set(kind),
+                provider_id: Set(row.provider_id),
+                external_id: Set(row.external_id),
+                name: Set(row.name),
+                origin_country: Set(row.origin_country),
+                position: Set(position as i64),
+                created_at: Set(now),
+            }
+        })
+    })
+    .collect::<Vec<_>>();
+    if !companies.is_empty() {
+        node_metadata_companies::Entity::insert_many(companies)
+            .exec(pool)
+            .await?;
+    }
+
+    if !metadata.keywords.is_empty() {
+        node_metadata_keywords::Entity::insert_many(metadata.keywords.into_iter().enumerate().map(
+            |(position, row)| node_metadata_keywords::ActiveModel {
+                id: Set(ids::generate_ulid()),
+                node_metadata_id: Set(metadata_id.to_string()),
+                provider_id: Set(row.provider_id),
+                external_id: Set(row.external_id),
+                name: Set(row.name),
+                position: Set(position as i64),
+                created_at: Set(now),
+            },
+        ))
+        .exec(pool)
+        .await?;
+    }
+
+    // a provider may repeat a language, only its first entry is kept
+    let mut seen_languages = HashSet::new();
+    let localizations = metadata
+        .localizations
+        .into_iter()
+        .filter(|row| {
+            let has_text = row.name.is_some() || row.description.is_some() || row.tagline.is_some();
+            has_text && seen_languages.insert(row.language.to_lowercase())
+        })
+        .map(|row| node_metadata_localizations::ActiveModel {
+            node_metadata_id: Set(metadata_id.to_string()),
+            language: Set(row.language),
+            name: Set(row.name),
+            description: Set(row.description),
+            tagline: Set(row.tagline),
+            created_at: Set(now),
+        })
+        .collect::<Vec<_>>();
+    if !localizations.is_empty() {
+        node_metadata_localizations::Entity::insert_many(localizations)
+            .exec(pool)
+            .await?;
+    }
+
     Ok(())
 }
 
@@ -559,12 +863,7 @@ async fn insert_metadata_images(
     let mut rows = Vec::new();
     for (kind, group) in grouped {
         for (position, image) in group.into_iter().enumerate() {
-            let asset_kind = match kind {
-                NodeMetadataImageKind::Poster => AssetKind::Poster,
-                NodeMetadataImageKind::Thumbnail => AssetKind::Thumbnail,
-                NodeMetadataImageKind::Backdrop => AssetKind::Backdrop,
-                NodeMetadataImageKind::Logo => AssetKind::Logo,
-            };
+            let asset_kind = AssetKind::from(kind);
             let asset_id =
                 ensure_remote_asset(pool, Some(image.url.as_str()), asset_kind, now).await?;
             let Some(asset_id) = asset_id else {
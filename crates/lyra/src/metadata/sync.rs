@@ -1,20 +1,33 @@
+use crate::collections::{reconcile_franchise_collections_for, root_franchise_keys};
 use crate::entities::{
     jobs::JobKind, metadata_source::MetadataSource, node_metadata, nodes, nodes::NodeKind,
+    root_episode_orderings, root_matches, users,
 };
 use crate::jobs::delete_job_row;
-use crate::metadata::remote::{MatchedRoot, lookup_series_items, match_root};
+use crate::metadata::remote::{
+    MatchedRoot, load_root_file_paths, load_root_match_hint, lookup_series_items, match_root,
+};
 use crate::metadata::store::{
     clear_remote_node_metadata_for_root, clear_remote_node_metadata_for_root_except,
-    clear_root_cast, replace_root_cast, upsert_remote_episode_metadata_for_batch,
-    upsert_remote_node_metadata_from_movie, upsert_remote_node_metadata_from_series,
-    upsert_remote_season_metadata_for_batch,
+    clear_root_cast, replace_missing_episodes, replace_node_ordering_positions, replace_root_cast,
+    upsert_remote_episode_metadata_for_batch, upsert_remote_node_metadata_from_movie,
+    upsert_remote_node_metadata_from_series, upsert_remote_season_metadata_for_batch,
+};
+use crate::scanner::recompute_root_orders_with_sqlx;
+use anyhow::Context;
+use lyra_metadata::{
+    CastCredit, ItemPosition, MetadataProvider, OfflineCacheMiss, RootMatchHint, SeriesCandidate,
+    SeriesItemsResult,
 };
-use lyra_metadata::MetadataProvider;
 use sea_orm::{
     ActiveModelTrait, ActiveValue::Set, ColumnTrait, ConnectionTrait, DatabaseConnection,
     EntityTrait, QueryFilter, QueryOrder,
 };
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    sync::Arc,
+};
 
 pub async fn mark_root_dirty(pool: &impl ConnectionTrait, root_id: &str) -> anyhow::Result<()> {
     delete_job_row(pool, JobKind::NodeSyncMetadataRoot, root_id).await
@@ -31,9 +44,51 @@ pub async fn sync_root(
     let season_nodes = load_root_nodes(pool, &root.id, NodeKind::Season).await?;
     let episode_nodes = load_root_nodes(pool, &root.id, NodeKind::Episode).await?;
     let mut errors = Vec::new();
+    // kept as an error so the job can tell an offline cache miss from a real failure
+    let mut cache_miss = None;
+    let pinned = root_matches::Entity::find_by_id(root.id.clone())
+        .one(pool)
+        .await?;
+    if let Some(pinned) = &pinned
+        && !providers
+            .iter()
+            .any(|provider| provider.id() == pinned.provider_id)
+    {
+        anyhow::bail!(
+            "root {} is pinned to unavailable provider {}",
+            root.id,
+            pinned.provider_id
+        );
+    }
 
+    let ordering = root_episode_orderings::Entity::find_by_id(root.id.clone())
+        .one(pool)
+        .await?;
+    // franchises the movie leaves need rebuilding as much as the ones it joins
+    let previous_franchises = root_franchise_keys(pool, &root.id).await?;
+
+    let languages = load_metadata_languages(pool).await?;
     for provider in providers {
-        let matched = match match_root(pool, provider.as_ref(), root).await {
+        provider.set_metadata_languages(&languages);
+    }
+
+    let file_paths = load_root_file_paths(pool, &root.id).await?;
+    let mut hint = load_root_match_hint(pool, root).await?;
+    hint.file_paths = file_paths.values().flatten().cloned().collect();
+    hint.file_paths.sort();
+
+    for (index, provider) in providers.iter().enumerate() {
+        // a pinned root only syncs from the provider it was pinned to
+        let pinned_id = match &pinned {
+            Some(pinned) if pinned.provider_id != provider.id() => continue,
+            Some(pinned) => Some(pinned.external_id.clone()),
+            None => None,
+        };
+        let provider_hint = RootMatchHint {
+            pinned_id: pinned_id.clone(),
+            ..hint.clone()
+        };
+        let matched = match match_root(provider.as_ref(), root.kind, provider_hint).await {
             Ok(Some(matched)) => matched,
             Ok(None) => continue,
             Err(error) => {
@@ -41,12 +96,54 @@ pub async fn sync_root(
                     "provider {} failed to match: {error:#}",
                     provider.id()
                 ));
+                if cache_miss.is_none() && error.downcast_ref::<OfflineCacheMiss>().is_some() {
+                    cache_miss = Some(error);
+                }
                 continue;
             }
         };
 
+        // the first provider to match owns the root, lower priority providers only fill in
+        // fields it left empty. pinned roots stick to the pinned provider entirely.
+        let fillers = if pinned_id.is_some() {
+            &[][..]
+        } else {
+            &providers[index + 1..]
+        };
+
         match matched {
-            MatchedRoot::Movie { metadata } => {
+            MatchedRoot::Movie { mut metadata } => {
+                let mut cast_provider = provider.as_ref();
+                for filler in fillers {
+                    let filler_hint =
+                        gap_fill_hint(&hint, metadata.imdb_id.clone(), metadata.tmdb_id);
+                    match match_root(filler.as_ref(), root.kind, filler_hint).await {
+                        Ok(Some(MatchedRoot::Movie {
+                            metadata: mut extra,
+                        })) => {
+                            // cast and crew are keyed by one provider's person ids, so they move
+                            // together
+                            if metadata.cast.is_empty()
+                                && metadata.crew.is_empty()
+                                && (!extra.cast.is_empty() || !extra.crew.is_empty())
+                            {
+                                metadata.cast = std::mem::take(&mut extra.cast);
+                                metadata.crew = std::mem::take(&mut extra.crew);
+                                cast_provider = filler.as_ref();
+                            }
+                            metadata.fill_gaps(extra);
+                        }
+                        Ok(_) => {}
+                        Err(error) => {
+                            tracing::warn!(
+                                root_id = %root.id,
+                                provider = filler.id(),
+                                "failed to fill metadata gaps: {error:#}"
+                            );
+                        }
+                    }
+                }
+
                 upsert_remote_node_metadata_from_movie(
                     pool,
                     &root.id,
@@ -55,28 +152,75 @@ pub async fn sync_root(
                     now,
                 )
                 .await?;
-                let people = provider
-                    .lookup_people_metadata(
-                        &metadata
-                            .cast
-                            .iter()
-                            .map(|credit| credit.provider_person_id.clone())
-                            .collect::<Vec<_>>(),
-                    )
-                    .await?;
-                replace_root_cast(pool, &root.id, provider.id(), &metadata.cast, &people, now)
+                let people = cast_provider
+                    .lookup_people_metadata(&credited_person_ids(&metadata.cast, &metadata.crew))
                     .await?;
+                replace_root_cast(
+                    pool,
+                    &root.id,
+                    cast_provider.id(),
+                    &metadata.cast,
+                    &metadata.crew,
+                    &people,
+                    now,
+                )
+                .await?;
                 clear_remote_node_metadata_for_root_except(pool, &root.id, &[root.id.clone()])
                     .await?;
+                let mut franchises = root_franchise_keys(pool, &root.id).await?;
+                franchises.extend(previous_franchises);
+                reconcile_franchise_collections_for(pool, &franchises, now).await?;
                 return Ok(());
             }
             MatchedRoot::Series {
                 candidate,
-                metadata,
+                mut metadata,
             } => {
-                let items =
-                    lookup_series_items(provider.as_ref(), &root.id, &candidate, &episode_nodes)
-                        .await?;
+                // fillers are matched before any episodes are looked up, so an ordering picked
+                // from one of them can number the episodes for every provider
+                let mut sources = vec![(provider.as_ref(), candidate)];
+                let mut cast_provider = provider.as_ref();
+                for filler in fillers {
+                    let filler_hint =
+                        gap_fill_hint(&hint, metadata.imdb_id.clone(), metadata.tmdb_id);
+                    match match_root(filler.as_ref(), root.kind, filler_hint).await {
+                        Ok(Some(MatchedRoot::Series {
+                            candidate: extra_candidate,
+                            metadata: mut extra,
+                        })) => {
+                            // cast and crew are keyed by one provider's person ids, so they move
+                            // together
+                            if metadata.cast.is_empty()
+                                && metadata.crew.is_empty()
+                                && (!extra.cast.is_empty() || !extra.crew.is_empty())
+                            {
+                                metadata.cast = std::mem::take(&mut extra.cast);
+                                metadata.crew = std::mem::take(&mut extra.crew);
+                                cast_provider = filler.as_ref();
+                            }
+                            metadata.fill_gaps(extra);
+                            sources.push((filler.as_ref(), extra_candidate));
+                        }
+                        Ok(_) => {}
+                        Err(error) => {
+                            tracing::warn!(
+                                root_id = %root.id,
+                                provider = filler.id(),
+                                "failed to fill metadata gaps: {error:#}"
+                            );
+                        }
+                    }
+                }
+
+                let items = lookup_root_series_items(
+                    &root.id,
+                    &sources,
+                    ordering.as_ref(),
+                    &episode_nodes,
+                    &file_paths,
+                )
+                .await?;
+
                 upsert_remote_node_metadata_from_series(
                     pool,
                     &root.id,
@@ -85,17 +229,19 @@ pub async fn sync_root(
                     now,
                 )
                 .await?;
-                let people = provider
-                    .lookup_people_metadata(
-                        &metadata
-                            .cast
-                            .iter()
-                            .map(|credit| credit.provider_person_id.clone())
-                            .collect::<Vec<_>>(),
-                    )
-                    .await?;
-                replace_root_cast(pool, &root.id, provider.id(), &metadata.cast, &people, now)
+                let people = cast_provider
+                    .lookup_people_metadata(&credited_person_ids(&metadata.cast, &metadata.crew))
                     .await?;
+                replace_root_cast(
+                    pool,
+                    &root.id,
+                    cast_provider.id(),
+                    &metadata.cast,
+                    &metadata.crew,
+                    &people,
+                    now,
+                )
+                .await?;
 
                 let mut matched_node_ids = vec![root.id.clone()];
                 matched_node_ids.extend(
@@ -127,6 +273,9 @@ pub async fn sync_root(
                     now,
                 )
                 .await?;
+                apply_ordering_positions(pool, &root.id, &episode_nodes, &items.positions, now)
+                    .await?;
+                replace_missing_episodes(pool, &root.id, &items.missing, now).await?;
 
                 clear_remote_node_metadata_for_root_except(pool, &root.id, &matched_node_ids)
                     .await?;
@@ -135,18 +284,202 @@ pub async fn sync_root(
         }
     }
 
-    clear_remote_node_metadata_for_root(pool, &root.id).await?;
-    clear_root_cast(pool, &root.id).await?;
-
+    // a provider that errored (offline, rate limited, cache miss) says nothing about whether the
+    // root still matches, so existing metadata is only dropped after a clean miss
     if errors.is_empty() {
+        clear_remote_node_metadata_for_root(pool, &root.id).await?;
+        clear_root_cast(pool, &root.id).await?;
+        apply_ordering_positions(pool, &root.id, &episode_nodes, &[], now).await?;
+        replace_missing_episodes(pool, &root.id, &[], now).await?;
+        reconcile_franchise_collections_for(pool, &previous_franchises, now).await?;
         anyhow::bail!("no metadata provider matched root {}", root.id);
     }
 
-    anyhow::bail!(
+    let message = format!(
         "metadata sync failed for root {}: {}",
         root.id,
         errors.join("; ")
     );
+    match cache_miss {
+        Some(error) => Err(error.context(message)),
+        None => Err(anyhow::anyhow!(message)),
+    }
+}
+
+// ids found by the primary provider (usually from an nfo) are much more reliable than the
+// parsed file name, so later providers match against them
+fn gap_fill_hint(
+    hint: &RootMatchHint,
+    imdb_id: Option<String>,
+    tmdb_id: Option<u64>,
+) -> RootMatchHint {
+    RootMatchHint {
+        imdb_id: imdb_id.or_else(|| hint.imdb_id.clone()),
+        tmdb_id: tmdb_id.or(hint.tmdb_id),
+        pinned_id: None,
+        ..hint.clone()
+    }
+}
+
+// people credited more than once (directing and writing, say) are only looked up once
+fn credited_person_ids(cast: &[CastCredit], crew: &[CastCredit]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    cast.iter()
+        .chain(crew)
+        .filter(|credit| seen.insert(credit.provider_person_id.as_str()))
+        .map(|credit| credit.provider_person_id.clone())
+        .collect()
+}
+
+// episode positions from the chosen ordering feed into node order, so "next episode" follows
+// the ordering rather than the parsed numbers
+async fn apply_ordering_positions(
+    pool: &DatabaseConnection,
+    root_id: &str,
+    episode_nodes: &[nodes::Model],
+    positions: &[ItemPosition],
+    now: i64,
+) -> anyhow::Result<()> {
+    if replace_node_ordering_positions(pool, root_id, episode_nodes, positions, now).await? {
+        recompute_root_orders_with_sqlx(pool, root_id, now).await?;
+    }
+    Ok(())
+}
+
+// orderings are provider specific. the provider the picked ordering belongs to numbers the
+// episodes, and every other provider is asked about each episode under its position in that
+// ordering, so gap filling still lines up. without a usable ordering everyone reads the local
+// numbers. the primary provider comes first in `sources` and its errors fail the sync.
+async fn lookup_root_series_items(
+    root_id: &str,
+    sources: &[(&dyn MetadataProvider, SeriesCandidate)],
+    ordering: Option<&root_episode_orderings::Model>,
+    episode_nodes: &[nodes::Model],
+    file_paths: &HashMap<String, Vec<PathBuf>>,
+) -> anyhow::Result<SeriesItemsResult> {
+    let ordering_source = ordering.and_then(|ordering| {
+        sources
+            .iter()
+            .position(|(provider, _)| provider.id() == ordering.provider_id)
+            .map(|index| (index, ordering.ordering_id.clone()))
+    });
+
+    let mut ordered = None;
+    let mut ordered_nodes = None;
+    if let Some((index, ordering_id)) = ordering_source {
+        let (provider, candidate) = &sources[index];
+        let items = lookup_series_items(
+            *provider,
+            root_id,
+            candidate,
+            episode_nodes,
+            file_paths,
+            Some(ordering_id.clone()),
+        )
+        .await
+        .with_context(|| {
+            format!(
+                "failed to look up ordering {ordering_id} from {}",
+                provider.id()
+            )
+        })?;
+        ordered_nodes = Some(episodes_in_ordering(episode_nodes, &items.positions));
+        ordered = Some((index, items));
+    }
+    let numbering = ordered
+        .as_ref()
+        .map(|(_, items)| (items.positions.clone(), items.missing.clone()));
+
+    let mut results = Vec::with_capacity(sources.len());
+    for (index, (provider, candidate)) in sources.iter().enumerate() {
+        if let Some((_, items)) = ordered.take_if(|(ordered_index, _)| *ordered_index == index) {
+            results.push(items);
+            continue;
+        }
+
+        let result = lookup_series_items(
+            *provider,
+            root_id,
+            candidate,
+            ordered_nodes.as_deref().unwrap_or(episode_nodes),
+            file_paths,
+            None,
+        )
+        .await;
+        match result {
+            Ok(items) => results.push(items),
+            Err(error) if index == 0 => return Err(error),
+            Err(error) => {
+                tracing::warn!(
+                    root_id,
+                    provider = provider.id(),
+                    "failed to fill episode gaps: {error:#}"
+                );
+            }
+        }
+    }
+
+    let mut results = results.into_iter();
+    let mut items = results
+        .next()
+        .context("no provider to look up episodes from")?;
+    for extra in results {
+        items.fill_gaps(extra);
+    }
+    // positions and missing episodes only mean something in the ordering they were numbered in
+    if let Some((positions, missing)) = numbering {
+        items.positions = positions;
+        items.missing = missing;
+    }
+    Ok(items)
+}
+
+// episodes the ordering doesn't place are left out, their local numbers would point other
+// providers at the wrong episodes
+fn episodes_in_ordering(
+    episode_nodes: &[nodes::Model],
+    positions: &[ItemPosition],
+) -> Vec<nodes::Model> {
+    let positions = positions
+        .iter()
+        .map(|position| (position.item_id.as_str(), position))
+        .collect::<HashMap<_, _>>();
+    episode_nodes
+        .iter()
+        .filter_map(|node| {
+            let position = positions.get(node.id.as_str())?;
+            Some(nodes::Model {
+                season_number: Some(i64::from(position.season_number)),
+                episode_number: Some(i64::from(position.episode_number)),
+                ..node.clone()
+            })
+        })
+        .collect()
+}
+
+// every language some user reads metadata in, the ones most users share first
+async fn load_metadata_languages(pool: &DatabaseConnection) -> anyhow::Result<Vec<String>> {
+    let mut languages = Vec::<(String, usize)>::new();
+    for user in users::Entity::find()
+        .order_by_asc(users::Column::Id)
+        .all(pool)
+        .await?
+    {
+        for language in user.metadata_languages() {
+            match languages
+                .iter_mut()
+                .find(|(known, _)| known.eq_ignore_ascii_case(&language))
+            {
+                Some((_, count)) => *count += 1,
+                None => languages.push((language, 1)),
+            }
+        }
+    }
+    languages.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    Ok(languages
+        .into_iter()
+        .map(|(language, _)| language)
+        .collect())
 }
 
 async fn load_root_nodes(
@@ -303,19 +636,22 @@ async fn update_remote_air_dates(
 mod tests {
     use super::*;
     use crate::entities::{
-        libraries, metadata_source::MetadataSource, node_metadata, people, root_node_cast,
+        libraries, metadata_source::MetadataSource, node_image_selections, node_metadata,
+        node_metadata_images, node_metadata_images::NodeMetadataImageKind, node_missing_episodes,
+        node_ordering_positions, people, root_node_cast,
     };
+    use crate::graphql::query::{NodeFilter, build_node_query_for_viewer};
+    use crate::metadata::remote::lookup_root_episode_orderings;
     use async_trait::async_trait;
     use lyra_metadata::{
-        CastCredit, EpisodeMetadata, ImageSet, MetadataProvider, MovieCandidate, MovieMetadata,
-        MovieRootMatchRequest, PersonMetadata, Scored, SeasonMetadata, SeriesCandidate,
-        SeriesItemsRequest, SeriesItemsResult, SeriesMetadata, SeriesRootMatchRequest,
+        CastCredit, EpisodeMetadata, EpisodeOrdering, ImageSet, MetadataCompany, MetadataImage,
+        MetadataImageKind, MetadataKeyword, MetadataProvider, MissingEpisode, MovieCandidate,
+        MovieMetadata, MovieRootMatchRequest, PersonMetadata, Scored, SeasonMetadata,
+        SeriesCandidate, SeriesItemsRequest, SeriesItemsResult, SeriesMetadata,
+        SeriesRootMatchRequest,
     };
     use sea_orm::{ActiveValue::Set, Database};
-    use std::sync::{
-        Arc,
-        atomic::{AtomicUsize, Ordering},
-    };
+    use std::sync::{Arc, Mutex};
 
     async fn setup_test_db() -> anyhow::Result<DatabaseConnection> {
         let pool = Database::connect("sqlite::memory:").await?;
@@ -331,6 +667,8 @@ mod tests {
             path: Set("/library".to_owned()),
             name: Set("Library".to_owned()),
             pinned: Set(false),
+            recordings: Set(false),
+            anime: Set(false),
             last_scanned_at: Set(None),
             unavailable_at: Set(None),
             created_at: Set(0),
@@ -361,6 +699,7 @@ mod tests {
             order: Set(order),
             season_number: Set(season_number),
             episode_number: Set(episode_number),
+            extra_type: Set(None),
             last_added_at: Set(0),
             last_fingerprint_version: Set(None),
             unavailable_at: Set(None),
@@ -400,13 +739,38 @@ mod tests {
         id: &'static str,
         match_result: MatchResult,
         cast: Vec<CastCredit>,
+        crew: Vec<CastCredit>,
         people_metadata: Vec<PersonMetadata>,
-        series_items_calls: AtomicUsize,
+        score: Option<f64>,
+        orderings: Vec<EpisodeOrdering>,
+        series_items_requests: Mutex<Vec<SeriesItemsRequest>>,
+        metadata_languages: Mutex<Vec<String>>,
+    }
+
+    impl FakeProvider {
+        fn new(id: &'static str, match_result: MatchResult) -> Self {
+            Self {
+                id,
+                match_result,
+                cast: Vec::new(),
+                crew: Vec::new(),
+                people_metadata: Vec::new(),
+                score: None,
+                orderings: Vec::new(),
+                series_items_requests: Mutex::new(Vec::new()),
+                metadata_languages: Mutex::new(Vec::new()),
+            }
+        }
+
+        fn series_items_calls(&self) -> usize {
+            self.series_items_requests.lock().unwrap().len()
+        }
     }
 
     enum MatchResult {
         NoMatch,
         Series,
+        Offline,
     }
 
     #[async_trait]
@@ -415,17 +779,28 @@ mod tests {
             self.id
         }
 
+        fn set_metadata_languages(&self, languages: &[String]) {
+            *self.metadata_languages.lock().unwrap() = languages.to_vec();
+        }
+
         async fn match_series_root(
             &self,
             _req: SeriesRootMatchRequest,
         ) -> anyhow::Result<Vec<Scored<SeriesCandidate>>> {
             match self.match_result {
                 MatchResult::NoMatch => Ok(Vec::new()),
+                MatchResult::Offline => Err(anyhow::Error::new(OfflineCacheMiss {
+                    key: "/search/tv".to_owned(),
+                })
+                .context("TMDb request failed")),
                 MatchResult::Series => Ok(vec![Scored {
                     value: SeriesCandidate {
-                        tmdb_id: 1,
+                        tmdb_id: Some(1),
                         name: "Matched Show".to_owned(),
                         first_air_year: None,
+                        poster_url: None,
+                        source_path: None,
+                        anidb_id: None,
                     },
                     score: 1.0,
                 }]),
@@ -439,20 +814,53 @@ mod tests {
             Ok(SeriesMetadata {
                 imdb_id: Some("tt1234567".to_owned()),
                 tmdb_id: Some(1),
+                anidb_id: None,
                 name: "Matched Show".to_owned(),
                 description: Some(format!("series from {}", self.id)),
-                score_display: None,
-                score_normalized: None,
+                score_display: lyra_metadata::score_display(self.score),
+                score_normalized: lyra_metadata::score_normalized(self.score),
                 first_aired: None,
                 last_aired: None,
                 status: None,
                 tagline: None,
                 next_aired: None,
+                original_title: None,
+                original_language: Some("ja".to_owned()),
+                runtime_minutes: None,
                 genres: Vec::new(),
                 content_ratings: Vec::new(),
+                studios: Vec::new(),
+                networks: vec![MetadataCompany {
+                    provider_id: self.id.to_owned(),
+                    external_id: Some("49".to_owned()),
+                    name: "HBO".to_owned(),
+                    origin_country: Some("US".to_owned()),
+                }],
+                keywords: vec![MetadataKeyword {
+                    provider_id: self.id.to_owned(),
+                    external_id: None,
+                    name: "based on novel".to_owned(),
+                }],
                 cast: self.cast.clone(),
+                crew: self.crew.clone(),
                 recommendations: Vec::new(),
-                images: ImageSet::default(),
+                images: ImageSet {
+                    posters: ["first", "second"]
+                        .into_iter()
+                        .map(|name| MetadataImage {
+                            kind: MetadataImageKind::Poster,
+                            url: format!("https://images.test/{}/{name}.jpg", self.id),
+                            language: None,
+                            vote_average: None,
+                            vote_count: None,
+                            width: None,
+                            height: None,
+                            file_type: None,
+                        })
+                        .collect(),
+                    ..ImageSet::default()
+                },
+                localizations: Vec::new(),
             })
         }
 
@@ -460,7 +868,22 @@ mod tests {
             &self,
             req: SeriesItemsRequest,
         ) -> anyhow::Result<SeriesItemsResult> {
-            self.series_items_calls.fetch_add(1, Ordering::Relaxed);
+            self.series_items_requests.lock().unwrap().push(req.clone());
+            // the fake ordering plays the local episodes in reverse and knows all of them
+            let ordered = req.ordering_id.is_some();
+            let positions = if ordered {
+                req.items
+                    .iter()
+                    .enumerate()
+                    .map(|(index, item)| ItemPosition {
+                        item_id: item.item_id.clone(),
+                        season_number: 1,
+                        episode_number: (req.items.len() - index) as i32,
+                    })
+                    .collect()
+            } else {
+                Vec::new()
+            };
 
             Ok(SeriesItemsResult {
                 seasons: vec![SeasonMetadata {
@@ -479,14 +902,15 @@ mod tests {
                     content_ratings: Vec::new(),
                     recommendations: Vec::new(),
                     images: ImageSet::default(),
+                    localizations: Vec::new(),
                 }],
                 episodes: req
                     .items
                     .into_iter()
-                    .filter(|item| item.item_id == "episode-1")
+                    .filter(|item| ordered || item.item_id == "episode-1")
                     .map(|item| EpisodeMetadata {
+                        name: item.name.clone(),
                         item_id: item.item_id,
-                        name: "Episode 1".to_owned(),
                         description: Some(format!("episode from {}", self.id)),
                         score_display: None,
                         score_normalized: None,
@@ -499,11 +923,27 @@ mod tests {
                         content_ratings: Vec::new(),
                         recommendations: Vec::new(),
                         images: ImageSet::default(),
+                        localizations: Vec::new(),
                     })
                     .collect(),
+                positions,
+                missing: vec![MissingEpisode {
+                    season_number: 1,
+                    episode_number: 3,
+                    name: Some("Episode 3".to_owned()),
+                    description: None,
+                    first_aired: Some(0),
+                }],
             })
         }
 
+        async fn lookup_episode_orderings(
+            &self,
+            _candidate: &SeriesCandidate,
+        ) -> anyhow::Result<Vec<EpisodeOrdering>> {
+            Ok(self.orderings.clone())
+        }
+
         async fn lookup_people_metadata(
             &self,
             provider_person_ids: &[String],
@@ -607,25 +1047,13 @@ mod tests {
         .exec(&pool)
         .await?;
 
-        let first = Arc::new(FakeProvider {
-            id: "first",
-            match_result: MatchResult::NoMatch,
-            cast: Vec::new(),
-            people_metadata: Vec::new(),
-            series_items_calls: AtomicUsize::new(0),
-        });
-        let second = Arc::new(FakeProvider {
-            id: "second",
-            match_result: MatchResult::Series,
-            cast: Vec::new(),
-            people_metadata: Vec::new(),
-            series_items_calls: AtomicUsize::new(0),
-        });
+        let first = Arc::new(FakeProvider::new("first", MatchResult::NoMatch));
+        let second = Arc::new(FakeProvider::new("second", MatchResult::Series));
 
         sync_root(&pool, &[first.clone(), second.clone()], &root).await?;
 
-        assert_eq!(first.series_items_calls.load(Ordering::Relaxed), 0);
-        assert_eq!(second.series_items_calls.load(Ordering::Relaxed), 1);
+        assert_eq!(first.series_items_calls(), 0);
+        assert_eq!(second.series_items_calls(), 1);
 
         let remote_rows = node_metadata::Entity::find()
             .filter(node_metadata::Column::Source.eq(MetadataSource::Remote))
@@ -639,6 +1067,133 @@ mod tests {
         assert!(remote_rows.iter().any(|row| row.node_id == "episode-1"));
         assert!(!remote_rows.iter().any(|row| row.node_id == "episode-2"));
 
+        let missing = node_missing_episodes::Entity::find().all(&pool).await?;
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].root_id, "root");
+        assert_eq!(
+            (missing[0].season_number, missing[0].episode_number),
+            (1, 3)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn synced_networks_keywords_and_languages_filter_nodes() -> anyhow::Result<()> {
+        let pool = setup_test_db().await?;
+        insert_library(&pool).await?;
+        let root = insert_node(
+            &pool,
+            "root",
+            "root",
+            None,
+            NodeKind::Series,
+            "Show",
+            None,
+            None,
+            0,
+        )
+        .await?;
+        insert_node(
+            &pool,
+            "unsynced",
+            "unsynced",
+            None,
+            NodeKind::Series,
+            "Unsynced",
+            None,
+            None,
+            0,
+        )
+        .await?;
+        insert_local_metadata(&pool, "root", "Show").await?;
+        insert_local_metadata(&pool, "unsynced", "Unsynced").await?;
+
+        let provider = Arc::new(FakeProvider::new("tmdb", MatchResult::Series));
+        sync_root(&pool, &[provider], &root).await?;
+
+        for filter in [
+            NodeFilter {
+                network: Some(" hbo ".to_owned()),
+                ..Default::default()
+            },
+            NodeFilter {
+                keyword: Some("Based on Novel".to_owned()),
+                ..Default::default()
+            },
+            NodeFilter {
+                original_language: Some("ja".to_owned()),
+                ..Default::default()
+            },
+        ] {
+            let matched = build_node_query_for_viewer(&pool, None, "viewer", &filter)
+                .await
+                .map_err(|error| anyhow::anyhow!(error.message))?
+                .all(&pool)
+                .await?;
+            assert_eq!(
+                matched
+                    .iter()
+                    .map(|node| node.id.as_str())
+                    .collect::<Vec<_>>(),
+                ["root"]
+            );
+        }
+        let unmatched = build_node_query_for_viewer(
+            &pool,
+            None,
+            "viewer",
+            &NodeFilter {
+                studio: Some("hbo".to_owned()),
+                ..Default::default()
+            },
+        )
+        .await
+        .map_err(|error| anyhow::anyhow!(error.message))?
+        .all(&pool)
+        .await?;
+        assert!(unmatched.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn sync_root_only_uses_the_pinned_provider() -> anyhow::Result<()> {
+        let pool = setup_test_db().await?;
+        insert_library(&pool).await?;
+        let root = insert_node(
+            &pool,
+            "root",
+            "root",
+            None,
+            NodeKind::Series,
+            "Show",
+            None,
+            None,
+            0,
+        )
+        .await?;
+        insert_local_metadata(&pool, "root", "Show").await?;
+        root_matches::Entity::insert(root_matches::ActiveModel {
+            root_id: Set("root".to_owned()),
+            provider_id: Set("second".to_owned()),
+            external_id: Set("1".to_owned()),
+            created_at: Set(0),
+        })
+        .exec(&pool)
+        .await?;
+
+        let first = Arc::new(FakeProvider::new("first", MatchResult::Series));
+        let second = Arc::new(FakeProvider::new("second", MatchResult::Series));
+
+        sync_root(&pool, &[first.clone(), second.clone()], &root).await?;
+
+        assert_eq!(first.series_items_calls(), 0);
+        assert_eq!(second.series_items_calls(), 1);
+
+        let unavailable = sync_root(&pool, &[first], &root).await;
+        assert!(unavailable.is_err());
+
         Ok(())
     }
 
@@ -673,13 +1228,7 @@ mod tests {
         .exec(&pool)
         .await?;
 
-        let provider = Arc::new(FakeProvider {
-            id: "nomatch",
-            match_result: MatchResult::NoMatch,
-            cast: Vec::new(),
-            people_metadata: Vec::new(),
-            series_items_calls: AtomicUsize::new(0),
-        });
+        let provider = Arc::new(FakeProvider::new("nomatch", MatchResult::NoMatch));
 
         let result = sync_root(&pool, &[provider], &root).await;
         assert!(result.is_err());
@@ -693,6 +1242,40 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn offline_cache_misses_survive_as_errors_and_keep_existing_metadata()
+    -> anyhow::Result<()> {
+        let pool = setup_test_db().await?;
+        insert_library(&pool).await?;
+        let root = insert_two_episode_show(&pool).await?;
+        let online: Arc<dyn MetadataProvider> =
+            Arc::new(FakeProvider::new("tmdb", MatchResult::Series));
+        sync_root(&pool, &[online], &root).await?;
+
+        let offline: Arc<dyn MetadataProvider> =
+            Arc::new(FakeProvider::new("tmdb", MatchResult::Offline));
+        let error = sync_root(&pool, &[offline], &root).await.unwrap_err();
+        assert!(error.downcast_ref::<OfflineCacheMiss>().is_some());
+        assert!(
+            node_metadata::Entity::find()
+                .filter(node_metadata::Column::Source.eq(MetadataSource::Remote))
+                .one(&pool)
+                .await?
+                .is_some()
+        );
+
+        let error = sync_root(
+            &pool,
+            &[Arc::new(FakeProvider::new("tmdb", MatchResult::NoMatch))],
+            &root,
+        )
+        .await
+        .unwrap_err();
+        assert!(error.downcast_ref::<OfflineCacheMiss>().is_none());
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn sync_root_reuses_people_across_roots_and_replaces_root_cast() -> anyhow::Result<()> {
         let pool = setup_test_db().await?;
@@ -726,13 +1309,19 @@ mod tests {
         insert_local_metadata(&pool, "root-2", "Show Two").await?;
 
         let provider: Arc<dyn MetadataProvider> = Arc::new(FakeProvider {
-            id: "tmdb",
-            match_result: MatchResult::Series,
             cast: vec![CastCredit {
                 provider_person_id: "7".to_owned(),
                 name: "Shared Actor".to_owned(),
                 character_name: Some("Lead".to_owned()),
                 department: None,
+                job: None,
+            }],
+            crew: vec![CastCredit {
+                provider_person_id: "7".to_owned(),
+                name: "Shared Actor".to_owned(),
+                character_name: None,
+                department: Some("Directing".to_owned()),
+                job: Some("Director".to_owned()),
             }],
             people_metadata: vec![PersonMetadata {
                 provider_person_id: "7".to_owned(),
@@ -741,7 +1330,7 @@ mod tests {
                 description: Some("Biography".to_owned()),
                 profile_image_url: Some("https://image.tmdb.org/t/p/w342/profile.jpg".to_owned()),
             }],
-            series_items_calls: AtomicUsize::new(0),
+            ..FakeProvider::new("tmdb", MatchResult::Series)
         });
 
         sync_root(&pool, std::slice::from_ref(&provider), &root_one).await?;
@@ -755,11 +1344,332 @@ mod tests {
 
         let cast_rows = root_node_cast::Entity::find()
             .order_by_asc(root_node_cast::Column::RootNodeId)
+            .order_by_asc(root_node_cast::Column::Position)
+            .all(&pool)
+            .await?;
+        assert_eq!(cast_rows.len(), 4);
+        assert!(
+            cast_rows
+                .iter()
+                .all(|row| row.person_id == people_rows[0].id)
+        );
+        assert_eq!(cast_rows[0].job, None);
+        assert_eq!(cast_rows[1].job.as_deref(), Some("Director"));
+        assert_eq!(cast_rows[1].position, 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn sync_root_prefers_nfo_metadata_and_fills_gaps_from_tmdb() -> anyhow::Result<()> {
+        let pool = setup_test_db().await?;
+        insert_library(&pool).await?;
+        let root = insert_node(
+            &pool,
+            "root",
+            "root",
+            None,
+            NodeKind::Series,
+            "Show",
+            None,
+            None,
+            0,
+        )
+        .await?;
+        insert_node(
+            &pool,
+            "season-1",
+            "root",
+            Some("root"),
+            NodeKind::Season,
+            "Season 1",
+            Some(1),
+            None,
+            1,
+        )
+        .await?;
+        insert_node(
+            &pool,
+            "episode-1",
+            "root",
+            Some("season-1"),
+            NodeKind::Episode,
+            "Episode 1",
+            Some(1),
+            Some(1),
+            2,
+        )
+        .await?;
+        insert_local_metadata(&pool, "root", "Show").await?;
+
+        let nfo: Arc<dyn MetadataProvider> =
+            Arc::new(FakeProvider::new("nfo", MatchResult::Series));
+        let tmdb: Arc<dyn MetadataProvider> = Arc::new(FakeProvider {
+            cast: vec![CastCredit {
+                provider_person_id: "7".to_owned(),
+                name: "Actor".to_owned(),
+                character_name: None,
+                department: None,
+                job: None,
+            }],
+            score: Some(8.2),
+            ..FakeProvider::new("tmdb", MatchResult::Series)
+        });
+
+        sync_root(&pool, &[nfo, tmdb], &root).await?;
+
+        let remote_rows = node_metadata::Entity::find()
+            .filter(node_metadata::Column::Source.eq(MetadataSource::Remote))
+            .order_by_asc(node_metadata::Column::NodeId)
             .all(&pool)
             .await?;
-        assert_eq!(cast_rows.len(), 2);
-        assert_eq!(cast_rows[0].person_id, people_rows[0].id);
-        assert_eq!(cast_rows[1].person_id, people_rows[0].id);
+        assert_eq!(remote_rows.len(), 3);
+        assert!(remote_rows.iter().all(|row| row.provider_id == "nfo"));
+        assert_eq!(
+            remote_rows
+                .iter()
+                .map(|row| row.description.as_deref())
+                .collect::<Vec<_>>(),
+            [
+                Some("episode from nfo"),
+                Some("series from nfo"),
+                Some("season from nfo")
+            ]
+        );
+        assert_eq!(remote_rows[1].score_display.as_deref(), Some("8.2/10"));
+        assert_eq!(remote_rows[1].score_normalized, Some(82));
+
+        // the nfo had no cast, so the filler's cast comes along with its person ids
+        let people_rows = people::Entity::find().all(&pool).await?;
+        assert_eq!(people_rows.len(), 1);
+        assert_eq!(people_rows[0].provider_id, "tmdb");
+
+        Ok(())
+    }
+
+    async fn insert_two_episode_show(pool: &DatabaseConnection) -> anyhow::Result<nodes::Model> {
+        let root = insert_node(
+            pool,
+            "root",
+            "root",
+            None,
+            NodeKind::Series,
+            "Show",
+            None,
+            None,
+            0,
+        )
+        .await?;
+        insert_node(
+            pool,
+            "season-1",
+            "root",
+            Some("root"),
+            NodeKind::Season,
+            "Season 1",
+            Some(1),
+            None,
+            1,
+        )
+        .await?;
+        for episode_number in 1..=2 {
+            insert_node(
+                pool,
+                &format!("episode-{episode_number}"),
+                "root",
+                Some("season-1"),
+                NodeKind::Episode,
+                &format!("Episode {episode_number}"),
+                Some(1),
+                Some(episode_number),
+                episode_number + 1,
+            )
+            .await?;
+        }
+        insert_local_metadata(pool, "root", "Show").await?;
+        Ok(root)
+    }
+
+    #[tokio::test]
+    async fn sync_root_hands_providers_every_language_users_read() -> anyhow::Result<()> {
+        let pool = setup_test_db().await?;
+        insert_library(&pool).await?;
+        let root = insert_two_episode_show(&pool).await?;
+        for (id, languages) in [
+            ("user-1", r#"["ja","en-US"]"#),
+            ("user-2", r#"["de","ja"]"#),
+            ("user-3", "[]"),
+        ] {
+            users::Entity::insert(users::ActiveModel {
+                id: Set(id.to_owned()),
+                username: Set(id.to_owned()),
+                password_hash: Set(Some("hash".to_owned())),
+                permissions: Set(0),
+                preferred_metadata_languages: Set(languages.to_owned()),
+                ..Default::default()
+            })
+            .exec(&pool)
+            .await?;
+        }
+
+        let provider = Arc::new(FakeProvider::new("tmdb", MatchResult::Series));
+        let providers: [Arc<dyn MetadataProvider>; 1] = [provider.clone()];
+        sync_root(&pool, &providers, &root).await?;
+
+        assert_eq!(
+            *provider.metadata_languages.lock().unwrap(),
+            ["ja", "en-US", "de"]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn sync_root_numbers_every_provider_by_an_ordering_from_a_filler() -> anyhow::Result<()> {
+        let pool = setup_test_db().await?;
+        insert_library(&pool).await?;
+        let root = insert_two_episode_show(&pool).await?;
+        root_episode_orderings::Entity::insert(root_episode_orderings::ActiveModel {
+            root_id: Set("root".to_owned()),
+            provider_id: Set("tmdb".to_owned()),
+            ordering_id: Set("dvd".to_owned()),
+            created_at: Set(0),
+        })
+        .exec(&pool)
+        .await?;
+
+        let nfo = Arc::new(FakeProvider::new("nfo", MatchResult::Series));
+        let tmdb = Arc::new(FakeProvider::new("tmdb", MatchResult::Series));
+        sync_root(&pool, &[nfo.clone(), tmdb.clone()], &root).await?;
+
+        let tmdb_requests = tmdb.series_items_requests.lock().unwrap().clone();
+        assert_eq!(tmdb_requests.len(), 1);
+        assert_eq!(tmdb_requests[0].ordering_id.as_deref(), Some("dvd"));
+        // the primary provider is asked about each episode at its place in the ordering
+        let nfo_requests = nfo.series_items_requests.lock().unwrap().clone();
+        assert_eq!(nfo_requests.len(), 1);
+        assert_eq!(nfo_requests[0].ordering_id, None);
+        assert_eq!(
+            nfo_requests[0]
+                .items
+                .iter()
+                .map(|item| (
+                    item.item_id.as_str(),
+                    item.season_number,
+                    item.episode_number
+                ))
+                .collect::<Vec<_>>(),
+            [
+                ("episode-1", Some(1), Some(2)),
+                ("episode-2", Some(1), Some(1))
+            ]
+        );
+
+        // nfo wins where it has metadata and the ordering's provider fills in the rest
+        let episode_rows = node_metadata::Entity::find()
+            .filter(node_metadata::Column::Source.eq(MetadataSource::Remote))
+            .filter(node_metadata::Column::NodeId.is_in(["episode-1", "episode-2"]))
+            .order_by_asc(node_metadata::Column::NodeId)
+            .all(&pool)
+            .await?;
+        assert_eq!(
+            episode_rows
+                .iter()
+                .map(|row| (row.provider_id.as_str(), row.description.as_deref()))
+                .collect::<Vec<_>>(),
+            [
+                ("nfo", Some("episode from nfo")),
+                ("nfo", Some("episode from tmdb"))
+            ]
+        );
+
+        let orders = load_root_nodes(&pool, "root", NodeKind::Episode)
+            .await?
+            .into_iter()
+            .map(|node| node.id)
+            .collect::<Vec<_>>();
+        assert_eq!(orders, ["episode-2", "episode-1"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn ordering_positions_only_change_rows_that_moved() -> anyhow::Result<()> {
+        let pool = setup_test_db().await?;
+        insert_library(&pool).await?;
+        insert_two_episode_show(&pool).await?;
+        let episodes = load_root_nodes(&pool, "root", NodeKind::Episode).await?;
+        let position = |item_id: &str, episode_number| ItemPosition {
+            item_id: item_id.to_owned(),
+            season_number: 1,
+            episode_number,
+        };
+
+        let positions = [position("episode-1", 2), position("episode-2", 1)];
+        assert!(replace_node_ordering_positions(&pool, "root", &episodes, &positions, 1).await?);
+        assert!(!replace_node_ordering_positions(&pool, "root", &episodes, &positions, 2).await?);
+
+        // positions for nodes outside the root are ignored
+        let positions = [position("episode-1", 2), position("elsewhere", 1)];
+        assert!(replace_node_ordering_positions(&pool, "root", &episodes, &positions, 3).await?);
+        let rows = node_ordering_positions::Entity::find().all(&pool).await?;
+        assert_eq!(rows.len(), 1);
+        assert_eq!(
+            (
+                rows[0].node_id.as_str(),
+                rows[0].episode_number,
+                rows[0].created_at
+            ),
+            ("episode-1", 2, 1)
+        );
+
+        assert!(replace_node_ordering_positions(&pool, "root", &episodes, &[], 4).await?);
+        assert!(
+            node_ordering_positions::Entity::find()
+                .all(&pool)
+                .await?
+                .is_empty()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn episode_orderings_are_offered_by_every_provider() -> anyhow::Result<()> {
+        let pool = setup_test_db().await?;
+        insert_library(&pool).await?;
+        let root = insert_two_episode_show(&pool).await?;
+        let nfo: Arc<dyn MetadataProvider> =
+            Arc::new(FakeProvider::new("nfo", MatchResult::Series));
+        let tmdb: Arc<dyn MetadataProvider> = Arc::new(FakeProvider {
+            orderings: vec![EpisodeOrdering {
+                id: "dvd".to_owned(),
+                name: "DVD Order".to_owned(),
+                kind: "dvd".to_owned(),
+                description: None,
+                group_count: 1,
+                episode_count: 2,
+            }],
+            ..FakeProvider::new("tmdb", MatchResult::Series)
+        });
+        let providers = [nfo, tmdb];
+
+        // nothing to ask about until the root has matched
+        assert!(
+            lookup_root_episode_orderings(&pool, &providers, &root)
+                .await?
+                .is_empty()
+        );
+
+        sync_root(&pool, &providers, &root).await?;
+        let orderings = lookup_root_episode_orderings(&pool, &providers, &root).await?;
+        assert_eq!(
+            orderings
+                .iter()
+                .map(|(provider_id, ordering)| (provider_id.as_str(), ordering.id.as_str()))
+                .collect::<Vec<_>>(),
+            [("tmdb", "dvd")]
+        );
 
         Ok(())
     }
@@ -896,4 +1806,62 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn picked_image_survives_resync() -> anyhow::Result<()> {
+        let pool = setup_test_db().await?;
+        insert_library(&pool).await?;
+        let root = insert_node(
+            &pool,
+            "root",
+            "root",
+            None,
+            NodeKind::Series,
+            "Show",
+            None,
+            None,
+            0,
+        )
+        .await?;
+        insert_local_metadata(&pool, "root", "Show").await?;
+        let provider: Arc<dyn MetadataProvider> =
+            Arc::new(FakeProvider::new("first", MatchResult::Series));
+
+        sync_root(&pool, std::slice::from_ref(&provider), &root).await?;
+        let second_poster = node_metadata_images::Entity::find()
+            .filter(node_metadata_images::Column::Kind.eq(NodeMetadataImageKind::Poster))
+            .filter(node_metadata_images::Column::Position.eq(1))
+            .one(&pool)
+            .await?
+            .expect("second poster");
+        node_image_selections::Entity::insert(node_image_selections::ActiveModel {
+            node_id: Set("root".to_owned()),
+            kind: Set(NodeMetadataImageKind::Poster),
+            asset_id: Set(second_poster.asset_id.clone()),
+            created_at: Set(0),
+        })
+        .exec(&pool)
+        .await?;
+
+        sync_root(&pool, &[provider], &root).await?;
+
+        // the image rows are rebuilt on every sync but point at the same assets
+        let resynced_poster = node_metadata_images::Entity::find()
+            .filter(node_metadata_images::Column::Kind.eq(NodeMetadataImageKind::Poster))
+            .filter(node_metadata_images::Column::Position.eq(1))
+            .one(&pool)
+            .await?
+            .expect("second poster");
+        let selection = node_image_selections::Entity::find_by_id((
+            "root".to_owned(),
+            NodeMetadataImageKind::Poster,
+        ))
+        .one(&pool)
+        .await?
+        .expect("selection");
+        assert_ne!(resynced_poster.id, second_poster.id);
+        assert_eq!(selection.asset_id, resynced_poster.asset_id);
+
+        Ok(())
+    }
 }
@@ -1,4 +1,4 @@
-use crate::entities::{files, node_closure, nodes};
+use crate::entities::{files, node_closure, nodes, nodes::ExtraType};
 use crate::ids;
 use crate::metadata::{
     LocalMetadataPlan, NodeLocalMetadataInput, upsert_node_local_metadata_input,
@@ -16,6 +16,7 @@ pub struct WantedNode {
     pub name: String,
     pub season_number: Option<i64>,
     pub episode_number: Option<i64>,
+    pub extra_type: Option<ExtraType>,
     pub last_added_at: i64,
     pub attached_file_ids: Vec<String>,
 }
@@ -38,8 +39,17 @@ struct FileRecommendation {
     root_name: String,
     root_imdb_id: Option<String>,
     root_tmdb_id: Option<i64>,
+    root_anidb_id: Option<i64>,
     season: Option<(String, i64, String)>,
     episodes: Vec<(String, i64, String)>,
+    extra: Option<ExtraRecommendation>,
+}
+
+struct ExtraRecommendation {
+    extra_type: ExtraType,
+    name: String,
+    // roots the extra may belong to, in order of preference
+    root_ids: Vec<String>,
 }
 
 pub fn build_root_derivation_plans(
@@ -47,6 +57,7 @@ pub fn build_root_derivation_plans(
     input: &[(files::Model, ParsedFile)],
 ) -> HashMap<String, RootDerivationPlan> {
     let mut plans = HashMap::new();
+    let mut extras = Vec::new();
 
     for (file, parsed) in input {
         let Some(rec) = derive_file_recommendation(library_root, &file.relative_path, parsed)
@@ -54,6 +65,11 @@ pub fn build_root_derivation_plans(
             continue;
         };
 
+        if let Some(extra) = rec.extra {
+            extras.push((file, extra));
+            continue;
+        }
+
         let plan = plans
             .entry(rec.root_id.clone())
             .or_insert_with(|| RootDerivationPlan {
@@ -77,6 +93,7 @@ pub fn build_root_derivation_plans(
                 name: rec.root_name.clone(),
                 season_number: None,
                 episode_number: None,
+                extra_type: None,
                 last_added_at: file.discovered_at,
                 attached_file_ids: Vec::new(),
             },
@@ -88,6 +105,7 @@ pub fn build_root_derivation_plans(
                 name: rec.root_name.clone(),
                 imdb_id: rec.root_imdb_id.clone(),
                 tmdb_id: rec.root_tmdb_id,
+                anidb_id: rec.root_anidb_id,
             },
         );
 
@@ -111,6 +129,7 @@ pub fn build_root_derivation_plans(
                     name: season_name.clone(),
                     season_number: Some(*season_number),
                     episode_number: None,
+                    extra_type: None,
                     last_added_at: file.discovered_at,
                     attached_file_ids: Vec::new(),
                 },
@@ -122,6 +141,7 @@ pub fn build_root_derivation_plans(
                     name: season_name.clone(),
                     imdb_id: None,
                     tmdb_id: None,
+                    anidb_id: None,
                 },
             );
         }
@@ -137,6 +157,7 @@ pub fn build_root_derivation_plans(
                     name: episode_name.clone(),
                     season_number: rec.season.as_ref().map(|(_, number, _)| *number),
                     episode_number: Some(episode_number),
+                    extra_type: None,
                     last_added_at: file.discovered_at,
                     attached_file_ids: vec![file.id.clone()],
                 },
@@ -148,11 +169,54 @@ pub fn build_root_derivation_plans(
                     name: episode_name,
                     imdb_id: None,
                     tmdb_id: None,
+                    anidb_id: None,
                 },
             );
         }
     }
 
+    // extras never create their root, a trailer without its movie has nothing to hang off
+    for (file, extra) in extras {
+        let Some(root_id) = extra
+            .root_ids
+            .into_iter()
+            .find(|root_id| plans.contains_key(root_id))
+        else {
+            continue;
+        };
+        let Some(plan) = plans.get_mut(&root_id) else {
+            continue;
+        };
+
+        let extra_key = format!("extra {:?} {}", extra.extra_type, extra.name.to_lowercase());
+        let extra_id = ids::generate_hashid([root_id.as_str(), extra_key.as_str()]);
+        ensure_materialized_node(
+            &mut plan.materialization.wanted_nodes,
+            WantedNode {
+                id: extra_id.clone(),
+                root_id: root_id.clone(),
+                parent_id: Some(root_id),
+                kind: nodes::NodeKind::Extra,
+                name: extra.name.clone(),
+                season_number: None,
+                episode_number: None,
+                extra_type: Some(extra.extra_type),
+                last_added_at: file.discovered_at,
+                attached_file_ids: vec![file.id.clone()],
+            },
+        );
+        upsert_node_local_metadata_input(
+            &mut plan.local_metadata,
+            NodeLocalMetadataInput {
+                node_id: extra_id,
+                name: extra.name,
+                imdb_id: None,
+                tmdb_id: None,
+                anidb_id: None,
+            },
+        );
+    }
+
     plans
 }
 
@@ -168,10 +232,17 @@ pub fn group_parsed_files_by_root(
             continue;
         };
 
-        grouped
-            .entry(rec.root_id)
-            .or_insert_with(Vec::new)
-            .push((file.clone(), parsed.clone()));
+        // an extra goes to every root it could belong to, the one that exists picks it up
+        let root_ids = match rec.extra {
+            Some(extra) => extra.root_ids,
+            None => vec![rec.root_id],
+        };
+        for root_id in root_ids {
+            grouped
+                .entry(root_id)
+                .or_insert_with(Vec::new)
+                .push((file.clone(), parsed.clone()));
+        }
     }
 
     grouped
@@ -184,6 +255,7 @@ fn ensure_materialized_node(nodes_by_id: &mut HashMap<String, WantedNode>, next:
         existing.kind = next.kind;
         existing.season_number = next.season_number;
         existing.episode_number = next.episode_number;
+        existing.extra_type = next.extra_type;
         existing.last_added_at = existing.last_added_at.max(next.last_added_at);
         for file_id in next.attached_file_ids {
             if !existing.attached_file_ids.contains(&file_id) {
@@ -340,6 +412,17 @@ pub fn verify_root_nodes(nodes: &[WantedNode]) -> anyhow::Result<()> {
                     anyhow::bail!("duplicate episode number under {}", parent_id);
                 }
             }
+            nodes::NodeKind::Extra => {
+                if node.extra_type.is_none()
+                    || node.season_number.is_some()
+                    || node.episode_number.is_some()
+                {
+                    anyhow::bail!("extra node {} has invalid fields", node.id);
+                }
+                if node.parent_id.as_ref() != Some(&node.root_id) {
+                    anyhow::bail!("extra {} parent must be its root", node.id);
+                }
+            }
         }
 
         if let Some(parent_id) = &node.parent_id {
@@ -366,9 +449,13 @@ pub fn verify_root_nodes(nodes: &[WantedNode]) -> anyhow::Result<()> {
 
     for node in nodes {
         let kinds = child_kinds.get(&node.id).cloned().unwrap_or_default();
-        if matches!(node.kind, nodes::NodeKind::Movie | nodes::NodeKind::Episode)
-            && !kinds.is_empty()
-        {
+        // extras are the only children a movie can have
+        let has_children = match node.kind {
+            nodes::NodeKind::Movie => kinds.iter().any(|kind| *kind != nodes::NodeKind::Extra),
+            nodes::NodeKind::Episode | nodes::NodeKind::Extra => !kinds.is_empty(),
+            nodes::NodeKind::Series | nodes::NodeKind::Season => false,
+        };
+        if has_children {
             anyhow::bail!("playable node {} has children", node.id);
         }
         if node.kind == nodes::NodeKind::Series
@@ -407,7 +494,7 @@ fn derive_file_recommendation(
     let episode_numbers = parsed_episode_numbers(parsed);
     let root_kind = if season_number.is_some() || !episode_numbers.is_empty() {
         nodes::NodeKind::Series
-    } else if parsed.start_year.is_some() {
+    } else if parsed.start_year.is_some() || parsed.extra.is_some() {
         nodes::NodeKind::Movie
     } else {
         tracing::warn!(relative_path, root = %library_root.display(), "could not determine media kind");
@@ -419,13 +506,40 @@ fn derive_file_recommendation(
         return None;
     };
 
-    let root_kind_key = format!("{root_kind:?}");
     let title_key = title.to_lowercase();
-    let root_id = ids::generate_hashid([
-        first_dir_past_root_dir.as_str(),
-        root_kind_key.as_str(),
-        title_key.as_str(),
-    ]);
+    let root_id_for = |kind: nodes::NodeKind| {
+        let kind_key = format!("{kind:?}");
+        ids::generate_hashid([
+            first_dir_past_root_dir.as_str(),
+            kind_key.as_str(),
+            title_key.as_str(),
+        ])
+    };
+    let root_id = root_id_for(root_kind);
+    if let Some(extra) = &parsed.extra {
+        // "Movie/Trailers/Teaser.mkv" and "Show/Extras/Bloopers.mkv" look the same without a
+        // year, so the extra goes to whichever of the two roots exists
+        let mut root_ids = vec![root_id.clone()];
+        if root_kind == nodes::NodeKind::Movie && parsed.start_year.is_none() {
+            root_ids.push(root_id_for(nodes::NodeKind::Series));
+        }
+
+        return Some(FileRecommendation {
+            root_id,
+            root_kind,
+            root_name: title.to_string(),
+            root_imdb_id: None,
+            root_tmdb_id: None,
+            root_anidb_id: None,
+            season: None,
+            episodes: Vec::new(),
+            extra: Some(ExtraRecommendation {
+                extra_type: extra.extra_type.into(),
+                name: extra.title.clone(),
+                root_ids,
+            }),
+        });
+    }
     if root_kind == nodes::NodeKind::Movie {
         return Some(FileRecommendation {
             root_id,
@@ -433,8 +547,10 @@ fn derive_file_recommendation(
             root_name: title.to_string(),
             root_imdb_id: parsed.imdb_id.clone(),
             root_tmdb_id: parsed.tmdb_id.and_then(|value| i64::try_from(value).ok()),
+            root_anidb_id: parsed.anidb_id.and_then(|value| i64::try_from(value).ok()),
             season: None,
             episodes: Vec::new(),
+            extra: None,
         });
     }
 
@@ -455,6 +571,7 @@ fn derive_file_recommendation(
         root_name: title.to_string(),
         root_imdb_id: parsed.imdb_id.clone(),
         root_tmdb_id: parsed.tmdb_id.and_then(|value| i64::try_from(value).ok()),
+        root_anidb_id: parsed.anidb_id.and_then(|value| i64::try_from(value).ok()),
         season,
         episodes: episode_numbers
             .into_iter()
@@ -467,6 +584,7 @@ fn derive_file_recommendation(
                 )
             })
             .collect(),
+        extra: None,
     })
 }
 
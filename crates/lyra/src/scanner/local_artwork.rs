@@ -0,0 +1,379 @@
+use crate::assets::create_local_asset_from_bytes;
+use crate::entities::{
+    assets::AssetKind, files, node_files, node_local_images,
+    node_metadata_images::NodeMetadataImageKind, nodes, nodes::NodeKind,
+};
+use lazy_static::lazy_static;
+use regex::Regex;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, DatabaseConnection, EntityTrait, FromQueryResult, JoinType,
+    QueryFilter, QueryOrder, QuerySelect, RelationTrait,
+};
+use std::collections::HashMap;
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp"];
+const ROOT_IMAGE_NAMES: &[(NodeMetadataImageKind, &[&str])] = &[
+    (
+        NodeMetadataImageKind::Poster,
+        &["poster", "folder", "cover"],
+    ),
+    (NodeMetadataImageKind::Backdrop, &["fanart", "backdrop"]),
+    (NodeMetadataImageKind::Logo, &["logo", "clearlogo"]),
+    (NodeMetadataImageKind::Thumbnail, &["landscape", "thumb"]),
+];
+
+lazy_static! {
+    static ref SEASON_DIR_REGEX: Regex =
+        Regex::new(r"(?i)^(?:(?:season|series|s)[\s._-]*\d{1,4}|specials)$").unwrap();
+}
+
+#[derive(Clone, Debug)]
+struct LocalImageFile {
+    path: PathBuf,
+    size_bytes: i64,
+    modified_at: i64,
+}
+
+// image files seen during a library walk, keyed by lowercased path so "Poster.JPG" still matches
+#[derive(Debug, Default)]
+pub(super) struct LocalImageIndex {
+    images: HashMap<String, LocalImageFile>,
+}
+
+impl LocalImageIndex {
+    pub fn is_image_path(path: &Path) -> bool {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| {
+                IMAGE_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str())
+            })
+    }
+
+    pub fn insert(&mut self, path: PathBuf, metadata: &Metadata) {
+        let modified_at = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_secs() as i64)
+            .unwrap_or(0);
+        self.images.insert(
+            path.to_string_lossy().to_lowercase(),
+            LocalImageFile {
+                path,
+                size_bytes: metadata.len() as i64,
+                modified_at,
+            },
+        );
+    }
+
+    fn find(&self, dir: &Path, names: &[&str]) -> Option<&LocalImageFile> {
+        names.iter().find_map(|name| {
+            IMAGE_EXTENSIONS.iter().find_map(|extension| {
+                let candidate = dir.join(format!("{name}.{extension}"));
+                self.images.get(&candidate.to_string_lossy().to_lowercase())
+            })
+        })
+    }
+}
+
+#[derive(Debug, FromQueryResult)]
+struct NodeFilePathRow {
+    node_id: String,
+    root_id: String,
+    parent_id: Option<String>,
+    kind: NodeKind,
+    season_number: Option<i64>,
+    relative_path: String,
+}
+
+// matches conventional kodi/jellyfin artwork names against the library's nodes and imports
+// anything new or changed. runs after every successful scan so edited or removed images are
+// picked up without touching the media files.
+pub(super) async fn sync_local_artwork(
+    pool: &DatabaseConnection,
+    library_id: &str,
+    library_root: &Path,
+    images: &LocalImageIndex,
+) -> anyhow::Result<()> {
+    let rows = node_files::Entity::find()
+        .join(JoinType::InnerJoin, node_files::Relation::Nodes.def())
+        .join(JoinType::InnerJoin, node_files::Relation::Files.def())
+        .filter(nodes::Column::LibraryId.eq(library_id))
+        .filter(files::Column::UnavailableAt.is_null())
+        .select_only()
+        .column_as(nodes::Column::Id, "node_id")
+        .column_as(nodes::Column::RootId, "root_id")
+        .column_as(nodes::Column::ParentId, "parent_id")
+        .column_as(nodes::Column::Kind, "kind")
+        .column_as(nodes::Column::SeasonNumber, "season_number")
+        .column_as(files::Column::RelativePath, "relative_path")
+        .order_by_asc(nodes::Column::Order)
+        .order_by_asc(node_files::Column::Order)
+        .into_model::<NodeFilePathRow>()
+        .all(pool)
+        .await?;
+
+    let mut wanted = HashMap::new();
+    for row in &rows {
+        collect_wanted_images(&mut wanted, images, library_root, row);
+    }
+
+    let existing = node_local_images::Entity::find()
+        .join(
+            JoinType::InnerJoin,
+            node_local_images::Relation::Nodes.def(),
+        )
+        .filter(nodes::Column::LibraryId.eq(library_id))
+        .all(pool)
+        .await?;
+
+    let now = chrono::Utc::now().timestamp();
+    for ((node_id, kind), image) in &wanted {
+        let unchanged = existing.iter().any(|row| {
+            row.node_id == *node_id
+                && row.kind == *kind
+                && row.path == image.path.to_string_lossy()
+                && row.size_bytes == image.size_bytes
+                && row.modified_at == image.modified_at
+        });
+        if unchanged {
+            continue;
+        }
+
+        if let Err(error) = import_image(pool, node_id, *kind, image, now).await {
+            tracing::warn!(
+                node_id,
+                path = %image.path.display(),
+                error = ?error,
+                "failed to import local artwork"
+            );
+        }
+    }
+
+    for row in existing {
+        if wanted.contains_key(&(row.node_id.clone(), row.kind)) {
+            continue;
+        }
+        node_local_images::Entity::delete_by_id((row.node_id, row.kind))
+            .exec(pool)
+            .await?;
+    }
+
+    Ok(())
+}
+
+fn collect_wanted_images<'a>(
+    wanted: &mut HashMap<(String, NodeMetadataImageKind), &'a LocalImageFile>,
+    images: &'a LocalImageIndex,
+    library_root: &Path,
+    row: &NodeFilePathRow,
+) {
+    let path = library_root.join(&row.relative_path);
+    let (Some(dir), Some(stem)) = (path.parent(), path.file_stem()) else {
+        return;
+    };
+    let stem = stem.to_string_lossy();
+    // folder level names only make sense when the media has a folder of its own
+    let has_own_dir = |dir: &Path| dir != library_root && dir.starts_with(library_root);
+    let mut want =
+        |node_id: &str, kind: NodeMetadataImageKind, image: Option<&'a LocalImageFile>| {
+            if let Some(image) = image {
+                wanted.entry((node_id.to_owned(), kind)).or_insert(image);
+            }
+        };
+
+    match row.kind {
+        NodeKind::Movie => {
+            for (kind, names) in ROOT_IMAGE_NAMES {
+                let prefixed = names
+                    .iter()
+                    .map(|name| format!("{stem}-{name}"))
+                    .collect::<Vec<_>>();
+                let prefixed = prefixed.iter().map(String::as_str).collect::<Vec<_>>();
+                want(&row.node_id, *kind, images.find(dir, &prefixed));
+                if has_own_dir(dir) {
+                    want(&row.node_id, *kind, images.find(dir, names));
+                }
+            }
+        }
+        NodeKind::Episode => {
+            let thumb_name = format!("{stem}-thumb");
+            want(
+                &row.node_id,
+                NodeMetadataImageKind::Thumbnail,
+                images.find(dir, &[thumb_name.as_str()]),
+            );
+
+            let is_season_dir = dir
+                .file_name()
+                .is_some_and(|name| SEASON_DIR_REGEX.is_match(&name.to_string_lossy()));
+            let show_dir = if is_season_dir {
+                dir.parent()
+            } else {
+                Some(dir)
+            };
+
+            if let Some(season_node_id) = row
+                .parent_id
+                .as_deref()
+                .filter(|parent_id| *parent_id != row.root_id)
+            {
+                if let (Some(show_dir), Some(season_number)) = (show_dir, row.season_number) {
+                    let names = season_image_names(season_number);
+                    let names = names.iter().map(String::as_str).collect::<Vec<_>>();
+                    want(
+                        season_node_id,
+                        NodeMetadataImageKind::Poster,
+                        images.find(show_dir, &names),
+                    );
+                }
+                if is_season_dir {
+                    want(
+                        season_node_id,
+                        NodeMetadataImageKind::Poster,
+                        images.find(dir, &["poster", "folder", "cover"]),
+                    );
+                }
+            }
+
+            if let Some(show_dir) = show_dir.filter(|show_dir| has_own_dir(show_dir)) {
+                for (kind, names) in ROOT_IMAGE_NAMES {
+                    want(&row.root_id, *kind, images.find(show_dir, names));
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn season_image_names(season_number: i64) -> Vec<String> {
+    let mut names = vec![format!("season{season_number:02}-poster")];
+    if season_number == 0 {
+        names.push("season-specials-poster".to_owned());
+    }
+    names
+}
+
+async fn import_image(
+    pool: &DatabaseConnection,
+    node_id: &str,
+    kind: NodeMetadataImageKind,
+    image: &LocalImageFile,
+    now: i64,
+) -> anyhow::Result<()> {
+    let bytes = tokio::fs::read(&image.path).await?;
+    let asset_kind = AssetKind::from(kind);
+    let asset = create_local_asset_from_bytes(pool, &bytes, asset_kind).await?;
+
+    node_local_images::Entity::insert(node_local_images::ActiveModel {
+        node_id: Set(node_id.to_owned()),
+        kind: Set(kind),
+        asset_id: Set(asset.id),
+        path: Set(image.path.to_string_lossy().to_string()),
+        size_bytes: Set(image.size_bytes),
+        modified_at: Set(image.modified_at),
+        created_at: Set(now),
+    })
+    .on_conflict(
+        OnConflict::columns([
+            node_local_images::Column::NodeId,
+            node_local_images::Column::Kind,
+        ])
+        .update_columns([
+            node_local_images::Column::AssetId,
+            node_local_images::Column::Path,
+            node_local_images::Column::SizeBytes,
+            node_local_images::Column::ModifiedAt,
+        ])
+        .to_owned(),
+    )
+    .exec(pool)
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(paths: &[&str]) -> LocalImageIndex {
+        let mut index = LocalImageIndex::default();
+        for path in paths {
+            index.images.insert(
+                path.to_lowercase(),
+                LocalImageFile {
+                    path: PathBuf::from(path),
+                    size_bytes: 1,
+                    modified_at: 1,
+                },
+            );
+        }
+        index
+    }
+
+    fn episode_row(relative_path: &str) -> NodeFilePathRow {
+        NodeFilePathRow {
+            node_id: "episode".to_owned(),
+            root_id: "root".to_owned(),
+            parent_id: Some("season".to_owned()),
+            kind: NodeKind::Episode,
+            season_number: Some(1),
+            relative_path: relative_path.to_owned(),
+        }
+    }
+
+    #[test]
+    fn episode_rows_pick_up_show_season_and_episode_artwork() {
+        let images = index(&[
+            "/tv/Show/Poster.JPG",
+            "/tv/Show/fanart.jpg",
+            "/tv/Show/season01-poster.png",
+            "/tv/Show/Season 1/Show S01E01-thumb.jpg",
+            "/tv/folder.jpg",
+        ]);
+        let mut wanted = HashMap::new();
+        collect_wanted_images(
+            &mut wanted,
+            &images,
+            Path::new("/tv"),
+            &episode_row("Show/Season 1/Show S01E01.mkv"),
+        );
+
+        let path_for = |node_id: &str, kind| {
+            wanted
+                .get(&(node_id.to_owned(), kind))
+                .map(|image| image.path.to_string_lossy().to_string())
+        };
+        assert_eq!(
+            path_for("root", NodeMetadataImageKind::Poster).as_deref(),
+            Some("/tv/Show/Poster.JPG")
+        );
+        assert_eq!(
+            path_for("root", NodeMetadataImageKind::Backdrop).as_deref(),
+            Some("/tv/Show/fanart.jpg")
+        );
+        assert_eq!(
+            path_for("season", NodeMetadataImageKind::Poster).as_deref(),
+            Some("/tv/Show/season01-poster.png")
+        );
+        assert_eq!(
+            path_for("episode", NodeMetadataImageKind::Thumbnail).as_deref(),
+            Some("/tv/Show/Season 1/Show S01E01-thumb.jpg")
+        );
+
+        // loose files in the library root never take folder level artwork
+        let mut wanted = HashMap::new();
+        collect_wanted_images(
+            &mut wanted,
+            &images,
+            Path::new("/tv"),
+            &episode_row("Show S01E02.mkv"),
+        );
+        assert!(wanted.is_empty());
+    }
+}
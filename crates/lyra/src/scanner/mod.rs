@@ -1,12 +1,16 @@
 mod derive_nodes;
+mod local_artwork;
 mod reconcile;
 
+pub(crate) use reconcile::recompute_root_orders_with_sqlx;
+
 use crate::activity::{ActivityHandle, ActivityKind};
 use crate::config::get_config;
 use crate::content_update::CONTENT_UPDATE;
 use crate::entities::{files, libraries};
 use crate::ids;
 use crate::scanner::derive_nodes::group_parsed_files_by_root;
+use crate::scanner::local_artwork::{LocalImageIndex, sync_local_artwork};
 use crate::scanner::reconcile::{
     find_roots_for_file_ids, parse_file_rows, reconcile_root, refresh_library_node_availability,
 };
@@ -86,11 +90,19 @@ async fn scan_library(
     let scan_start_time = chrono::Utc::now().timestamp();
     let library_path = PathBuf::from(&library.path);
     let mut new_file_ids = HashSet::new();
+    let mut local_images = LocalImageIndex::default();
     let library_available = library_has_top_level_directories(&library_path).await;
 
     match library_available {
         Ok(true) => {
-            let scan_result = scan_directory(pool, library, &library_path, scan_start_time).await;
+            let scan_result = scan_directory(
+                pool,
+                library,
+                &library_path,
+                scan_start_time,
+                &mut local_images,
+            )
+            .await;
             if let Ok(scanned_new_file_ids) = &scan_result {
                 new_file_ids.extend(scanned_new_file_ids.iter().cloned());
             }
@@ -185,6 +197,16 @@ async fn scan_library(
                 }
             }
 
+            if let Err(error) =
+                sync_local_artwork(pool, &library.id, &library_path, &local_images).await
+            {
+                tracing::warn!(
+                    library_id = %library.id,
+                    error = ?error,
+                    "failed to sync local artwork"
+                );
+            }
+
             refresh_library_node_availability(pool, &library.id, scan_start_time).await?;
             finalize_library_scan(pool, library, scan_start_time, None).await?;
         }
@@ -282,6 +304,7 @@ async fn scan_directory(
     library: &libraries::Model,
     root_dir: &StdPath,
     scan_start_time: i64,
+    local_images: &mut LocalImageIndex,
 ) -> anyhow::Result<HashSet<String>> {
     let mut pending = Vec::with_capacity(SCAN_BATCH_SIZE);
     let mut dirs = vec![root_dir.to_path_buf()];
@@ -298,6 +321,12 @@ async fn scan_directory(
             if !path.is_file() {
                 continue;
             }
+            if LocalImageIndex::is_image_path(&path) {
+                if let Ok(metadata) = entry.metadata().await {
+                    local_images.insert(path, &metadata);
+                }
+                continue;
+            }
 
             let Some(candidate) = scan_file_candidate(library, &path, root_dir).await? else {
                 continue;
@@ -390,7 +419,11 @@ async fn flush_scan_batch(
             relative_path: Set(candidate.relative_path.clone()),
             size_bytes: Set(candidate.size_bytes),
             audio_fingerprint: Set(None),
+            audio_tail_fingerprint: Set(None),
+            audio_body_fingerprint: Set(None),
+            commercials_scanned_at: Set(None),
             segments_json: Set(None),
+            segments_sidecar_hash: Set(None),
             keyframes_json: Set(None),
             scanned_at: Set(Some(scan_start_time)),
             unavailable_at: Set(None),
@@ -443,6 +476,8 @@ mod tests {
             path: Set("/library".to_owned()),
             name: Set("Library".to_owned()),
             pinned: Set(false),
+            recordings: Set(false),
+            anime: Set(false),
             last_scanned_at: Set(None),
             unavailable_at: Set(None),
             created_at: Set(0),
@@ -465,7 +500,11 @@ mod tests {
             relative_path: Set(relative_path.to_owned()),
             size_bytes: Set(size_bytes),
             audio_fingerprint: Set(None),
+            audio_tail_fingerprint: Set(None),
+            audio_body_fingerprint: Set(None),
+            commercials_scanned_at: Set(None),
             segments_json: Set(None),
+            segments_sidecar_hash: Set(None),
             keyframes_json: Set(None),
             unavailable_at: Set(None),
             scanned_at: Set(Some(discovered_at)),
@@ -59,11 +59,16 @@ pub(crate) async fn reconcile_root(
     root_id: &str,
     extra_rows: Vec<(files::Model, ParsedFile)>,
 ) -> anyhow::Result<()> {
+    // extras are handed to every root they could belong to, only the one that exists takes them
+    let only_extras = extra_rows.iter().all(|(_, parsed)| parsed.extra.is_some());
     let existing_file_rows = load_root_file_rows(pool, library_id, root_id).await?;
     let parsed_existing_rows = parse_file_rows(&existing_file_rows).await;
     let parsed_rows = merge_parsed_file_rows(parsed_existing_rows, extra_rows);
     let root_plans = build_root_derivation_plans(library_root, &parsed_rows);
     let Some(plan) = root_plans.get(root_id) else {
+        if existing_file_rows.is_empty() && only_extras {
+            return Ok(());
+        }
         tracing::warn!(
             root_id,
             "touched root has no derived plan after reconciliation input"
@@ -196,6 +201,7 @@ pub(crate) async fn materialize_touched_root(
             order: Set(TEMP_NODE_ORDER_OFFSET + temp_order as i64),
             season_number: Set(wanted.season_number),
             episode_number: Set(wanted.episode_number),
+            extra_type: Set(wanted.extra_type),
             last_added_at: Set(wanted.last_added_at),
             last_fingerprint_version: Set(None),
             unavailable_at: Set(None),
@@ -216,6 +222,7 @@ pub(crate) async fn materialize_touched_root(
                         nodes::Column::Order,
                         nodes::Column::SeasonNumber,
                         nodes::Column::EpisodeNumber,
+                        nodes::Column::ExtraType,
                         nodes::Column::LastAddedAt,
                         nodes::Column::UnavailableAt,
                         nodes::Column::UpdatedAt,
@@ -341,7 +348,7 @@ pub(crate) async fn refresh_library_node_availability(
     let mut available_by_node_id = HashMap::<String, bool>::with_capacity(library_nodes.len());
     for node in &library_nodes {
         let is_available = match node.kind {
-            nodes::NodeKind::Movie | nodes::NodeKind::Episode => {
+            nodes::NodeKind::Movie | nodes::NodeKind::Episode | nodes::NodeKind::Extra => {
                 available_file_node_ids.contains(&node.id)
             }
             nodes::NodeKind::Series | nodes::NodeKind::Season => child_ids_by_parent
@@ -390,7 +397,7 @@ pub(crate) async fn refresh_library_node_availability(
     Ok(())
 }
 
-async fn recompute_root_orders_with_sqlx(
+pub(crate) async fn recompute_root_orders_with_sqlx(
     pool: &DatabaseConnection,
     root_id: &str,
     now: i64,
@@ -409,17 +416,19 @@ async fn recompute_root_orders_with_sqlx(
         r#"
         WITH ranked AS (
             SELECT
-                id,
+                n.id,
                 row_number() OVER (
                     ORDER BY
-                        CASE WHEN parent_id IS NULL THEN 0 ELSE 1 END,
-                        COALESCE(season_number, 0),
-                        CASE WHEN kind = 2 THEN 0 ELSE 1 END,
-                        COALESCE(episode_number, 0),
-                        id
+                        CASE WHEN n.parent_id IS NULL THEN 0 ELSE 1 END,
+                        CASE WHEN n.kind = 4 THEN 1 ELSE 0 END,
+                        COALESCE(p.season_number, n.season_number, 0),
+                        CASE WHEN n.kind = 2 THEN 0 ELSE 1 END,
+                        COALESCE(p.episode_number, n.episode_number, 0),
+                        n.id
                 ) - 1 AS new_order
-            FROM nodes
-            WHERE root_id = ?
+            FROM nodes n
+            LEFT JOIN node_ordering_positions p ON p.node_id = n.id
+            WHERE n.root_id = ?
         )
         UPDATE nodes
         SET "order" = (
@@ -449,7 +458,7 @@ async fn recompute_root_orders_with_sqlx(
             INNER JOIN files f ON f.id = nf.file_id
             INNER JOIN nodes n ON n.id = nf.node_id
             WHERE n.root_id = ?
-            AND n.kind IN (0, 3)
+            AND n.kind IN (0, 3, 4)
         )
         UPDATE node_files
         SET
@@ -464,7 +473,7 @@ async fn recompute_root_orders_with_sqlx(
             SELECT id
             FROM nodes
             WHERE root_id = ?
-            AND kind IN (0, 3)
+            AND kind IN (0, 3, 4)
         )
         "#,
         root_id,
@@ -482,10 +491,13 @@ async fn recompute_root_orders_with_sqlx(
 mod tests {
     use super::*;
     use crate::{
-        entities::{jobs, libraries, metadata_source::MetadataSource, node_metadata},
+        entities::{
+            jobs, libraries, metadata_source::MetadataSource, node_metadata, nodes::ExtraType,
+        },
         metadata::NodeLocalMetadataInput,
-        scanner::derive_nodes::WantedNode,
+        scanner::derive_nodes::{WantedNode, group_parsed_files_by_root},
     };
+    use lyra_parser::ParsedExtra;
     use sea_orm::{ActiveEnum, Database, QueryOrder};
 
     async fn setup_test_db() -> anyhow::Result<DatabaseConnection> {
@@ -503,6 +515,8 @@ mod tests {
             path: Set("/library".to_owned()),
             name: Set("Library".to_owned()),
             pinned: Set(false),
+            recordings: Set(false),
+            anime: Set(false),
             last_scanned_at: Set(None),
             unavailable_at: Set(None),
             created_at: Set(0),
@@ -525,7 +539,11 @@ mod tests {
             relative_path: Set(relative_path.to_owned()),
             size_bytes: Set(size_bytes),
             audio_fingerprint: Set(None),
+            audio_tail_fingerprint: Set(None),
+            audio_body_fingerprint: Set(None),
+            commercials_scanned_at: Set(None),
             segments_json: Set(None),
+            segments_sidecar_hash: Set(None),
             keyframes_json: Set(None),
             unavailable_at: Set(None),
             scanned_at: Set(Some(discovered_at)),
@@ -537,6 +555,24 @@ mod tests {
         Ok(())
     }
 
+    fn parsed_file(name: &str, start_year: Option<u32>, extra: Option<ParsedExtra>) -> ParsedFile {
+        ParsedFile {
+            name: Some(name.to_owned()),
+            episode_title: None,
+            season_numbers: Vec::new(),
+            episode_numbers: Vec::new(),
+            start_year,
+            end_year: None,
+            edition: None,
+            imdb_id: None,
+            tmdb_id: None,
+            tvdb_id: None,
+            anidb_id: None,
+            trakt_id: None,
+            extra,
+        }
+    }
+
     fn local_plan(
         root_id: &str,
         rows: &[(&str, &str, Option<&str>, Option<i64>)],
@@ -553,6 +589,7 @@ mod tests {
                             name: (*name).to_owned(),
                             imdb_id: imdb_id.map(str::to_owned),
                             tmdb_id: *tmdb_id,
+                            anidb_id: None,
                         },
                     )
                 })
@@ -583,6 +620,7 @@ mod tests {
                         name: "Show".to_owned(),
                         season_number: None,
                         episode_number: None,
+                        extra_type: None,
                         last_added_at: 11,
                         attached_file_ids: Vec::new(),
                     },
@@ -597,6 +635,7 @@ mod tests {
                         name: "Season 1".to_owned(),
                         season_number: Some(1),
                         episode_number: None,
+                        extra_type: None,
                         last_added_at: 11,
                         attached_file_ids: Vec::new(),
                     },
@@ -611,6 +650,7 @@ mod tests {
                         name: "Episode 1".to_owned(),
                         season_number: Some(1),
                         episode_number: Some(1),
+                        extra_type: None,
                         last_added_at: 11,
                         attached_file_ids: vec!["file-small".to_owned(), "file-large".to_owned()],
                     },
@@ -665,6 +705,73 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn extras_attach_to_a_movie_without_a_year_in_its_folder() -> anyhow::Result<()> {
+        let pool = setup_test_db().await?;
+        insert_library(&pool).await?;
+        insert_file(&pool, "movie-file", "Movie/Movie (2010).mkv", 300, 20).await?;
+        insert_file(&pool, "trailer-file", "Movie/Trailers/Teaser.mkv", 30, 20).await?;
+
+        let file = |id: &str| files::Entity::find_by_id(id.to_owned()).one(&pool);
+        let movie_file = file("movie-file").await?.expect("movie file missing");
+        let trailer_file = file("trailer-file").await?.expect("trailer file missing");
+        // the trailer is parsed as the folder it sits in, which has no year
+        let parsed_rows = vec![
+            (movie_file, parsed_file("Movie", Some(2010), None)),
+            (
+                trailer_file,
+                parsed_file(
+                    "Movie",
+                    None,
+                    Some(ParsedExtra {
+                        extra_type: lyra_parser::ExtraType::Trailer,
+                        title: "Teaser".to_owned(),
+                    }),
+                ),
+            ),
+        ];
+
+        // the trailer is offered to a movie and a series of that name, only the movie exists
+        let library_root = StdPath::new("/library");
+        assert_eq!(
+            group_parsed_files_by_root(library_root, &parsed_rows).len(),
+            2
+        );
+        let plans = build_root_derivation_plans(library_root, &parsed_rows);
+        assert_eq!(plans.len(), 1);
+        for plan in plans.values() {
+            materialize_touched_root(&pool, "lib", &plan.materialization, &plan.local_metadata)
+                .await?;
+        }
+
+        let rows = nodes::Entity::find()
+            .order_by_asc(nodes::Column::Order)
+            .all(&pool)
+            .await?;
+        assert_eq!(
+            rows.iter().map(|row| row.kind).collect::<Vec<_>>(),
+            vec![nodes::NodeKind::Movie, nodes::NodeKind::Extra]
+        );
+        let (movie, trailer) = (&rows[0], &rows[1]);
+        assert_eq!(trailer.parent_id.as_deref(), Some(movie.id.as_str()));
+        assert_eq!(trailer.root_id, movie.id);
+        assert_eq!(trailer.extra_type, Some(ExtraType::Trailer));
+
+        // the trailer isn't a version of the movie
+        let links = node_files::Entity::find()
+            .filter(node_files::Column::NodeId.eq(movie.id.clone()))
+            .all(&pool)
+            .await?;
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].file_id, "movie-file");
+        assert_eq!(
+            find_roots_for_file_ids(&pool, &["trailer-file".to_owned()]).await?,
+            HashSet::from([movie.id.clone()])
+        );
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn materialize_touched_root_replaces_local_rows_without_duplicates_and_clears_retry()
     -> anyhow::Result<()> {
@@ -682,6 +789,7 @@ mod tests {
             order: Set(0),
             season_number: Set(None),
             episode_number: Set(None),
+            extra_type: Set(None),
             last_added_at: Set(1),
             last_fingerprint_version: Set(None),
             unavailable_at: Set(None),
@@ -719,6 +827,7 @@ mod tests {
                     name: "New Movie".to_owned(),
                     season_number: None,
                     episode_number: None,
+                    extra_type: None,
                     last_added_at: 20,
                     attached_file_ids: vec!["movie-file".to_owned()],
                 },
@@ -780,6 +889,7 @@ mod tests {
                 order: Set(0),
                 season_number: Set(None),
                 episode_number: Set(None),
+                extra_type: Set(None),
                 last_added_at: Set(1),
                 last_fingerprint_version: Set(None),
                 unavailable_at: Set(None),
@@ -796,6 +906,7 @@ mod tests {
                 order: Set(1),
                 season_number: Set(None),
                 episode_number: Set(Some(1)),
+                extra_type: Set(None),
                 last_added_at: Set(1),
                 last_fingerprint_version: Set(None),
                 unavailable_at: Set(None),
@@ -819,6 +930,7 @@ mod tests {
                         name: "Show".to_owned(),
                         season_number: None,
                         episode_number: None,
+                        extra_type: None,
                         last_added_at: 30,
                         attached_file_ids: Vec::new(),
                     },
@@ -833,6 +945,7 @@ mod tests {
                         name: "Season 1".to_owned(),
                         season_number: Some(1),
                         episode_number: None,
+                        extra_type: None,
                         last_added_at: 30,
                         attached_file_ids: Vec::new(),
                     },
@@ -847,6 +960,7 @@ mod tests {
                         name: "Episode 2".to_owned(),
                         season_number: Some(1),
                         episode_number: Some(2),
+                        extra_type: None,
                         last_added_at: 30,
                         attached_file_ids: vec!["new-file".to_owned()],
                     },
@@ -891,6 +1005,7 @@ mod tests {
             tvdb_id: None,
             anidb_id: None,
             trakt_id: None,
+            extra: None,
         };
         let old_file = files::Model {
             id: "old".to_owned(),
@@ -901,7 +1016,11 @@ mod tests {
             width: None,
             edition_name: None,
             audio_fingerprint: None,
+            audio_tail_fingerprint: None,
+            audio_body_fingerprint: None,
+            commercials_scanned_at: None,
             segments_json: None,
+            segments_sidecar_hash: None,
             keyframes_json: None,
             unavailable_at: Some(10),
             scanned_at: Some(10),
@@ -917,7 +1036,11 @@ mod tests {
             width: None,
             edition_name: None,
             audio_fingerprint: None,
+            audio_tail_fingerprint: None,
+            audio_body_fingerprint: None,
+            commercials_scanned_at: None,
             segments_json: None,
+            segments_sidecar_hash: None,
             keyframes_json: None,
             unavailable_at: None,
             scanned_at: Some(20),
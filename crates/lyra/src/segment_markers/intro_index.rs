@@ -0,0 +1,265 @@
+use super::{StoredFileSegment, StoredFileSegmentKind};
+use crate::{
+    entities::{files, intro_fingerprints, node_files, nodes, nodes::NodeKind},
+    ids, json_encoding,
+};
+use anyhow::Context;
+use lyra_marker::{Fingerprint, IntroRange, match_known_intros};
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, EntityTrait,
+    FromQueryResult, JoinType, QueryFilter, QuerySelect, RelationTrait,
+};
+use std::collections::HashSet;
+
+// keeps a root from filling the index when every season has a slightly different intro
+const MAX_INDEXED_INTROS_PER_ROOT: usize = 8;
+
+// intros found for a root on earlier runs plus the shared idents that apply to every root
+pub struct KnownIntros {
+    root_id: String,
+    entries: Vec<(intro_fingerprints::Model, Fingerprint)>,
+}
+
+impl KnownIntros {
+    pub async fn load(db: &DatabaseConnection, root_id: &str) -> anyhow::Result<Self> {
+        let rows = intro_fingerprints::Entity::find()
+            .filter(
+                Condition::any()
+                    .add(intro_fingerprints::Column::RootId.eq(root_id.to_string()))
+                    .add(intro_fingerprints::Column::Shared.eq(true)),
+            )
+            .all(db)
+            .await?;
+
+        let mut entries = Vec::with_capacity(rows.len());
+        for row in rows {
+            match Fingerprint::from_bytes(row.fingerprint.clone()) {
+                Ok(fingerprint) => entries.push((row, fingerprint)),
+                // fingerprints from an older fingerprint version no longer decode
+                Err(error) => {
+                    tracing::warn!(id = row.id, error = ?error, "skipping invalid indexed intro");
+                }
+            }
+        }
+
+        Ok(Self {
+            root_id: root_id.to_string(),
+            entries,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub async fn match_intro(
+        &self,
+        fingerprint: &Fingerprint,
+    ) -> anyhow::Result<Option<IntroRange>> {
+        let known = self
+            .entries
+            .iter()
+            .map(|(_, fingerprint)| fingerprint.clone())
+            .collect::<Vec<_>>();
+        Ok(match_known_intros(fingerprint, &known)
+            .await?
+            .map(|matched| matched.intro))
+    }
+
+    // stores intros that don't match anything already indexed for the root. `detected` holds
+    // the source file id and the intro cut out of that file's fingerprint.
+    pub async fn index_detected(
+        &self,
+        db: &DatabaseConnection,
+        detected: Vec<(String, Fingerprint)>,
+    ) -> anyhow::Result<()> {
+        let mut root_known = self
+            .entries
+            .iter()
+            .filter(|(row, _)| row.root_id == self.root_id)
+            .map(|(_, fingerprint)| fingerprint.clone())
+            .collect::<Vec<_>>();
+
+        for (file_id, clip) in detected {
+            if root_known.len() >= MAX_INDEXED_INTROS_PER_ROOT {
+                break;
+            }
+
+            if match_known_intros(&clip, &root_known).await?.is_some() {
+                continue;
+            }
+
+            let duration_ms = (clip.duration_seconds()? * 1000.0).round() as i64;
+            intro_fingerprints::Entity::insert(intro_fingerprints::ActiveModel {
+                id: Set(ids::generate_ulid()),
+                root_id: Set(self.root_id.clone()),
+                shared: Set(false),
+                label: Set(None),
+                fingerprint: Set(clip.as_bytes().to_vec()),
+                duration_ms: Set(duration_ms),
+                source_file_id: Set(Some(file_id.clone())),
+                ..Default::default()
+            })
+            .exec(db)
+            .await
+            .with_context(|| format!("failed to index intro from file {file_id}"))?;
+            root_known.push(clip);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, FromQueryResult)]
+struct RootFileSegmentsRow {
+    root_id: String,
+    segments_json: Option<Vec<u8>>,
+}
+
+// roots with an available episode or movie that has no intro yet. those are the only roots a
+// newly shared intro can change, everything else already has intros for every file.
+pub async fn roots_missing_intros(db: &impl ConnectionTrait) -> anyhow::Result<Vec<String>> {
+    let rows = node_files::Entity::find()
+        .join(JoinType::InnerJoin, node_files::Relation::Nodes.def())
+        .join(JoinType::InnerJoin, node_files::Relation::Files.def())
+        .filter(nodes::Column::Kind.is_in([NodeKind::Episode, NodeKind::Movie]))
+        .filter(files::Column::UnavailableAt.is_null())
+        .select_only()
+        .column_as(nodes::Column::RootId, "root_id")
+        .column_as(files::Column::SegmentsJson, "segments_json")
+        .into_model::<RootFileSegmentsRow>()
+        .all(db)
+        .await?;
+
+    let mut root_ids = HashSet::new();
+    for row in rows {
+        if root_ids.contains(&row.root_id) {
+            continue;
+        }
+
+        let has_intro = row
+            .segments_json
+            .as_deref()
+            .and_then(|payload| {
+                json_encoding::decode_json_zstd::<Vec<StoredFileSegment>>(payload).ok()
+            })
+            .is_some_and(|segments| {
+                segments
+                    .iter()
+                    .any(|segment| segment.kind == StoredFileSegmentKind::Intro)
+            });
+        if !has_intro {
+            root_ids.insert(row.root_id);
+        }
+    }
+
+    let mut root_ids = root_ids.into_iter().collect::<Vec<_>>();
+    root_ids.sort();
+    Ok(root_ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::roots_missing_intros;
+    use crate::{
+        entities::{files, libraries, node_files, nodes, nodes::NodeKind},
+        segment_markers::{StoredFileSegment, StoredFileSegmentKind, store_file_segments},
+    };
+    use sea_orm::{ActiveValue::Set, Database, DatabaseConnection, EntityTrait};
+
+    async fn setup_test_db() -> anyhow::Result<DatabaseConnection> {
+        let pool = Database::connect("sqlite::memory:").await?;
+        sqlx::migrate!("../../migrations")
+            .run(pool.get_sqlite_connection_pool())
+            .await?;
+
+        libraries::Entity::insert(libraries::ActiveModel {
+            id: Set("lib".to_owned()),
+            path: Set("/lib".to_owned()),
+            name: Set("lib".to_owned()),
+            pinned: Set(false),
+            recordings: Set(false),
+            anime: Set(false),
+            last_scanned_at: Set(None),
+            unavailable_at: Set(None),
+            created_at: Set(0),
+        })
+        .exec(&pool)
+        .await?;
+        Ok(pool)
+    }
+
+    // a movie root with a single available file, optionally with an intro segment
+    async fn insert_movie(
+        pool: &DatabaseConnection,
+        id: &str,
+        has_intro: bool,
+    ) -> anyhow::Result<()> {
+        nodes::Entity::insert(nodes::ActiveModel {
+            id: Set(id.to_owned()),
+            library_id: Set("lib".to_owned()),
+            root_id: Set(id.to_owned()),
+            parent_id: Set(None),
+            kind: Set(NodeKind::Movie),
+            name: Set(id.to_owned()),
+            order: Set(0),
+            season_number: Set(None),
+            episode_number: Set(None),
+            extra_type: Set(None),
+            last_added_at: Set(0),
+            last_fingerprint_version: Set(Some(0)),
+            unavailable_at: Set(None),
+            created_at: Set(0),
+            updated_at: Set(0),
+        })
+        .exec(pool)
+        .await?;
+        files::Entity::insert(files::ActiveModel {
+            id: Set(format!("file-{id}")),
+            library_id: Set("lib".to_owned()),
+            relative_path: Set(format!("{id}.mkv")),
+            size_bytes: Set(0),
+            discovered_at: Set(0),
+            ..Default::default()
+        })
+        .exec(pool)
+        .await?;
+        node_files::Entity::insert(node_files::ActiveModel {
+            node_id: Set(id.to_owned()),
+            file_id: Set(format!("file-{id}")),
+            order: Set(0),
+            created_at: Set(0),
+            updated_at: Set(0),
+        })
+        .exec(pool)
+        .await?;
+
+        let kind = if has_intro {
+            StoredFileSegmentKind::Intro
+        } else {
+            StoredFileSegmentKind::Credits
+        };
+        store_file_segments(
+            pool,
+            &format!("file-{id}"),
+            &[StoredFileSegment {
+                kind,
+                start_ms: 0,
+                end_ms: 30_000,
+                locked: false,
+            }],
+        )
+        .await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn only_roots_with_files_missing_intros_are_returned() -> anyhow::Result<()> {
+        let pool = setup_test_db().await?;
+        insert_movie(&pool, "with-intro", true).await?;
+        insert_movie(&pool, "without-intro", false).await?;
+
+        assert_eq!(roots_missing_intros(&pool).await?, vec!["without-intro"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,117 @@
+use crate::{
+    entities::{file_probe, files, jobs as jobs_entity, libraries},
+    jobs::{Job, JobLease, JobOutcome, JobScheduling},
+    media::{get_job_file_path, load_cached_probe},
+    segment_markers::{
+        StoredFileSegmentKind, commercial_segment_from_range, load_file_segments,
+        merge_locked_segments, store_file_segments,
+    },
+};
+use anyhow::Context;
+use lyra_marker::detect_commercials;
+use sea_orm::{
+    ActiveValue::Set,
+    ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Select,
+    TransactionTrait,
+    sea_query::{Expr, Query},
+};
+
+#[derive(Debug, Default)]
+pub struct FileCommercialsJob;
+
+#[async_trait::async_trait]
+impl Job for FileCommercialsJob {
+    type Entity = files::Entity;
+    type Model = files::Model;
+
+    const JOB_KIND: jobs_entity::JobKind = jobs_entity::JobKind::FileDetectCommercials;
+    const SCHEDULING: JobScheduling = JobScheduling::Heavy(2);
+
+    fn query(&self) -> Select<Self::Entity> {
+        files::Entity::find()
+            .filter(files::Column::UnavailableAt.is_null())
+            .filter(files::Column::CommercialsScannedAt.is_null())
+            .filter(
+                Expr::col((files::Entity, files::Column::LibraryId)).in_subquery(
+                    Query::select()
+                        .column(libraries::Column::Id)
+                        .from(libraries::Entity)
+                        .and_where(
+                            Expr::col((libraries::Entity, libraries::Column::Recordings)).eq(true),
+                        )
+                        .to_owned(),
+                ),
+            )
+            .filter(
+                Expr::col((files::Entity, files::Column::Id)).in_subquery(
+                    Query::select()
+                        .column(file_probe::Column::FileId)
+                        .from(file_probe::Entity)
+                        .to_owned(),
+                ),
+            )
+            .order_by_asc(files::Column::Id)
+    }
+
+    fn target_id(&self, target: &Self::Model) -> String {
+        target.id.clone()
+    }
+
+    async fn run(
+        &self,
+        db: &DatabaseConnection,
+        file: Self::Model,
+        ctx: &JobLease,
+    ) -> anyhow::Result<JobOutcome> {
+        let Some(file_path) = get_job_file_path(db, &file, Self::JOB_KIND).await? else {
+            return Ok(JobOutcome::Complete);
+        };
+
+        let probe_data = load_cached_probe(db, &file.id)
+            .await?
+            .context("commercial detection requires cached probe data")?;
+        let Some(detection) =
+            detect_commercials(&file_path, &probe_data, ctx.get_cancellation_token()).await?
+        else {
+            return Ok(JobOutcome::Cancelled);
+        };
+        if ctx.is_cancelled() {
+            return Ok(JobOutcome::Cancelled);
+        }
+
+        let detected = detection
+            .commercials
+            .into_iter()
+            .filter_map(commercial_segment_from_range)
+            .collect::<Vec<_>>();
+
+        let tx = db.begin().await?;
+        // re-read the segments inside the transaction so an intro detection run that finished
+        // in the meantime isn't overwritten
+        let current = files::Entity::find_by_id(file.id.clone())
+            .one(&tx)
+            .await?
+            .context("file disappeared during commercial detection")?;
+        let (locked, others): (Vec<_>, Vec<_>) = load_file_segments(&current)?
+            .into_iter()
+            .partition(|segment| segment.locked);
+        let mut detected_segments = others
+            .into_iter()
+            .filter(|segment| segment.kind != StoredFileSegmentKind::Commercial)
+            .collect::<Vec<_>>();
+        detected_segments.extend(detected);
+        let segments = merge_locked_segments(locked, detected_segments);
+        store_file_segments(&tx, &file.id, &segments).await?;
+
+        files::Entity::update(files::ActiveModel {
+            id: Set(file.id),
+            commercials_scanned_at: Set(Some(chrono::Utc::now().timestamp())),
+            ..Default::default()
+        })
+        .exec(&tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(JobOutcome::Complete)
+    }
+}
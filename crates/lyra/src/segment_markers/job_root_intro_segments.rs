@@ -1,16 +1,25 @@
 use crate::{
     entities::{files, jobs as jobs_entity, libraries, node_files, nodes, nodes::NodeKind},
     jobs::{Job, JobLease, JobOutcome, JobScheduling},
-    json_encoding, media,
+    media,
+    segment_markers::{
+        StoredFileSegment, StoredFileSegmentKind,
+        intro_index::KnownIntros,
+        load_file_segments,
+        sidecar::{self, SidecarSegments},
+        store_file_segments,
+    },
 };
 use anyhow::Context;
-use lyra_marker::{Fingerprint, detect_intros, fingerprint};
+use lyra_marker::{
+    CreditsInput, Fingerprint, RecapInput, detect_credits, detect_credits_fallback, detect_intros,
+    detect_recaps, fingerprint, fingerprint_body, fingerprint_tail,
+};
 use sea_orm::{
     ActiveValue::Set, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, EntityTrait,
     FromQueryResult, JoinType, QueryFilter, QueryOrder, QuerySelect, RelationTrait, Select,
-    sea_query::Expr,
+    TransactionTrait, sea_query::Expr,
 };
-use sqlx::{QueryBuilder, Sqlite};
 use std::{
     collections::{HashMap, HashSet},
     path::PathBuf,
@@ -24,6 +33,9 @@ struct RootFile {
     file_id: String,
     file_path: PathBuf,
     fingerprint: Option<Fingerprint>,
+    tail_fingerprint: Option<Fingerprint>,
+    body_fingerprint: Option<Fingerprint>,
+    sidecar_hash: Option<String>,
 }
 
 #[derive(Debug, FromQueryResult)]
@@ -32,6 +44,17 @@ struct RootFileQueryRow {
     relative_path: String,
     library_path: String,
     audio_fingerprint: Option<Vec<u8>>,
+    audio_tail_fingerprint: Option<Vec<u8>>,
+    audio_body_fingerprint: Option<Vec<u8>>,
+    segments_sidecar_hash: Option<String>,
+}
+
+#[derive(Debug)]
+struct FileSegmentsUpdate {
+    file_id: String,
+    detected: Vec<StoredFileSegment>,
+    // set when the file's sidecar is new or changed since it was last imported
+    sidecar: Option<SidecarSegments>,
 }
 
 #[async_trait::async_trait]
@@ -45,7 +68,7 @@ impl Job for RootIntroSegmentsJob {
     fn query(&self) -> Select<Self::Entity> {
         nodes::Entity::find()
             .filter(nodes::Column::ParentId.is_null())
-            .filter(nodes::Column::Kind.eq(NodeKind::Series))
+            .filter(nodes::Column::Kind.is_in([NodeKind::Series, NodeKind::Movie]))
             .filter(
                 Condition::any()
                     .add(nodes::Column::LastFingerprintVersion.is_null())
@@ -67,90 +90,316 @@ impl Job for RootIntroSegmentsJob {
         root: Self::Model,
         ctx: &JobLease,
     ) -> anyhow::Result<JobOutcome> {
-        let mut root_files = load_root_files(db, &root.id).await?;
-        let mut detection_inputs = Vec::with_capacity(root_files.len());
-        for file in &mut root_files {
-            if ctx.is_cancelled() {
-                return Ok(JobOutcome::Cancelled);
+        let segments_by_file = match root.kind {
+            NodeKind::Movie => detect_movie_segments(db, &root.id, ctx).await?,
+            _ => detect_series_segments(db, &root.id, ctx).await?,
+        };
+        let Some(segments_by_file) = segments_by_file else {
+            return Ok(JobOutcome::Cancelled);
+        };
+
+        store_segment_updates(db, segments_by_file).await?;
+
+        if root.last_fingerprint_version != Some(root.last_added_at) {
+            store_last_fingerprint_version(db, &root.id, Some(root.last_added_at)).await?;
+        }
+
+        Ok(JobOutcome::Complete)
+    }
+}
+
+async fn detect_series_segments(
+    db: &DatabaseConnection,
+    root_id: &str,
+    ctx: &JobLease,
+) -> anyhow::Result<Option<Vec<FileSegmentsUpdate>>> {
+    let mut root_files = load_root_files(db, root_id, NodeKind::Episode).await?;
+    let mut detection_inputs = Vec::with_capacity(root_files.len());
+    let mut credits_inputs = Vec::with_capacity(root_files.len());
+    let mut body_fingerprints = Vec::with_capacity(root_files.len());
+    for file in &mut root_files {
+        if ctx.is_cancelled() {
+            return Ok(None);
+        }
+
+        let probe_data = media::load_cached_probe(db, &file.file_id)
+            .await?
+            .with_context(|| format!("missing cached probe data for file {}", file.file_id))?;
+        let duration_seconds = probe_data
+            .duration_secs
+            .with_context(|| format!("missing duration for file {}", file.file_id))?;
+
+        let fingerprint = match file.fingerprint.clone() {
+            Some(fingerprint) => fingerprint,
+            None => {
+                let Some(fingerprint) =
+                    fingerprint(&file.file_path, &probe_data, ctx.get_cancellation_token()).await?
+                else {
+                    return Ok(None);
+                };
+
+                store_audio_fingerprint(db, &file.file_id, fingerprint.as_bytes()).await?;
+                file.fingerprint = Some(fingerprint.clone());
+                fingerprint
             }
+        };
+
+        let tail_fingerprint = match file.tail_fingerprint.clone() {
+            Some(fingerprint) => fingerprint,
+            None => {
+                let Some(fingerprint) =
+                    fingerprint_tail(&file.file_path, &probe_data, ctx.get_cancellation_token())
+                        .await?
+                else {
+                    return Ok(None);
+                };
+
+                store_audio_tail_fingerprint(db, &file.file_id, fingerprint.as_bytes()).await?;
+                file.tail_fingerprint = Some(fingerprint.clone());
+                fingerprint
+            }
+        };
+
+        let body_fingerprint = match file.body_fingerprint.clone() {
+            Some(fingerprint) => fingerprint,
+            None => {
+                let Some(fingerprint) =
+                    fingerprint_body(&file.file_path, &probe_data, ctx.get_cancellation_token())
+                        .await?
+                else {
+                    return Ok(None);
+                };
+
+                store_audio_body_fingerprint(db, &file.file_id, fingerprint.as_bytes()).await?;
+                file.body_fingerprint = Some(fingerprint.clone());
+                fingerprint
+            }
+        };
 
+        detection_inputs.push((file.file_path.clone(), fingerprint));
+        body_fingerprints.push(body_fingerprint);
+        credits_inputs.push(CreditsInput {
+            path: file.file_path.clone(),
+            fingerprint: tail_fingerprint,
+            duration_seconds,
+        });
+    }
+
+    let Some(intros) = detect_intros(&detection_inputs, ctx.get_cancellation_token()).await? else {
+        return Ok(None);
+    };
+    let Some(credits) = detect_credits(&credits_inputs, ctx.get_cancellation_token()).await? else {
+        return Ok(None);
+    };
+
+    let mut intros_by_path = intros
+        .into_iter()
+        .map(|detection| (detection.path, detection.intro))
+        .collect::<HashMap<_, _>>();
+    let credits_by_path = credits
+        .into_iter()
+        .map(|detection| (detection.path.clone(), detection))
+        .collect::<HashMap<_, _>>();
+
+    // files the season-wide pass missed (short seasons, new episodes) are checked against
+    // intros indexed on earlier runs and shared idents, and new intros are indexed.
+    let known_intros = KnownIntros::load(db, root_id).await?;
+    let mut detected_intros = Vec::new();
+    for (file, (path, fingerprint)) in root_files.iter().zip(&detection_inputs) {
+        let intro = intros_by_path
+            .get_mut(path)
+            .with_context(|| format!("intro detection output missing file '{}'", path.display()))?;
+        match intro {
+            Some(range) => detected_intros.push((
+                file.file_id.clone(),
+                fingerprint.slice_seconds(range.start_seconds, range.end_seconds)?,
+            )),
+            None => *intro = known_intros.match_intro(fingerprint).await?,
+        }
+    }
+    known_intros.index_detected(db, detected_intros).await?;
+
+    // recaps are matched against neighbouring episodes with the shared intro/credits excluded,
+    // so they run after the season-wide detectors.
+    let mut recap_inputs = Vec::with_capacity(root_files.len());
+    for (((path, fingerprint), credits_input), body_fingerprint) in detection_inputs
+        .into_iter()
+        .zip(credits_inputs)
+        .zip(body_fingerprints)
+    {
+        let intro = intros_by_path.get(&path).copied().flatten();
+        let credits = credits_by_path.get(&path).with_context(|| {
+            format!("credits detection output missing file '{}'", path.display())
+        })?;
+
+        recap_inputs.push(RecapInput {
+            path,
+            fingerprint,
+            body_fingerprint,
+            tail_fingerprint: credits_input.fingerprint,
+            duration_seconds: credits_input.duration_seconds,
+            intro,
+            credits: credits.credits,
+        });
+    }
+
+    let Some(recaps) = detect_recaps(&recap_inputs, ctx.get_cancellation_token()).await? else {
+        return Ok(None);
+    };
+
+    let mut output = Vec::with_capacity(root_files.len());
+    for ((file, recap), recap_input) in root_files.into_iter().zip(recaps).zip(&recap_inputs) {
+        let intro = intros_by_path.get(&file.file_path).copied().flatten();
+        let credits = credits_by_path
+            .get(&file.file_path)
+            .and_then(|detection| detection.credits);
+
+        let segments = recap
+            .recap
+            .and_then(super::recap_segment_from_range)
+            .into_iter()
+            .chain(intro.and_then(super::intro_segment_from_range))
+            .chain(credits.and_then(super::credits_segment_from_range))
+            .chain(recap.preview.and_then(super::preview_segment_from_range))
+            .collect::<Vec<_>>();
+        let sidecar = read_changed_sidecar(&file, recap_input.duration_seconds).await;
+        output.push(FileSegmentsUpdate {
+            file_id: file.file_id,
+            detected: segments,
+            sidecar,
+        });
+    }
+
+    Ok(Some(output))
+}
+
+// movies have nothing to match against, so credits come from the black frame/silence fallback
+// and intros only from idents an admin has shared across roots
+async fn detect_movie_segments(
+    db: &DatabaseConnection,
+    root_id: &str,
+    ctx: &JobLease,
+) -> anyhow::Result<Option<Vec<FileSegmentsUpdate>>> {
+    let root_files = load_root_files(db, root_id, NodeKind::Movie).await?;
+    let known_intros = KnownIntros::load(db, root_id).await?;
+    let mut output = Vec::with_capacity(root_files.len());
+    for file in root_files {
+        if ctx.is_cancelled() {
+            return Ok(None);
+        }
+
+        let probe_data = media::load_cached_probe(db, &file.file_id)
+            .await?
+            .with_context(|| format!("missing cached probe data for file {}", file.file_id))?;
+
+        let mut intro = None;
+        if !known_intros.is_empty() {
             let fingerprint = match file.fingerprint.clone() {
                 Some(fingerprint) => fingerprint,
                 None => {
-                    let probe_data = media::load_cached_probe(db, &file.file_id)
-                        .await?
-                        .with_context(|| {
-                            format!("missing cached probe data for file {}", file.file_id)
-                        })?;
                     let Some(fingerprint) =
                         fingerprint(&file.file_path, &probe_data, ctx.get_cancellation_token())
                             .await?
                     else {
-                        return Ok(JobOutcome::Cancelled);
+                        return Ok(None);
                     };
 
                     store_audio_fingerprint(db, &file.file_id, fingerprint.as_bytes()).await?;
-                    file.fingerprint = Some(fingerprint.clone());
                     fingerprint
                 }
             };
-
-            detection_inputs.push((file.file_path.clone(), fingerprint));
+            intro = known_intros.match_intro(&fingerprint).await?;
         }
-
-        let Some(detections) =
-            detect_intros(&detection_inputs, ctx.get_cancellation_token()).await?
+        let Some(detection) =
+            detect_credits_fallback(&file.file_path, &probe_data, ctx.get_cancellation_token())
+                .await?
         else {
-            return Ok(JobOutcome::Cancelled);
+            return Ok(None);
         };
 
-        let detections_by_path = detections
+        let segments = intro
+            .and_then(super::intro_segment_from_range)
             .into_iter()
-            .map(|detection| (detection.path.clone(), detection))
-            .collect::<HashMap<_, _>>();
-
-        let mut segment_updates = Vec::with_capacity(root_files.len());
-        for file in &root_files {
-            let detection = detections_by_path.get(&file.file_path).with_context(|| {
-                format!(
-                    "intro detection output missing file '{}'",
-                    file.file_path.display()
-                )
-            })?;
-            let segments = detection
-                .intro
-                .and_then(super::intro_segment_from_range)
-                .into_iter()
-                .collect::<Vec<_>>();
-
-            let payload = json_encoding::encode_json_zstd(&segments).with_context(|| {
-                format!("failed to encode intro segments for file {}", file.file_id)
-            })?;
-            segment_updates.push((file.file_id.clone(), payload));
-        }
-        store_segments_bulk(db, &segment_updates).await?;
+            .chain(
+                detection
+                    .credits
+                    .and_then(super::credits_segment_from_range),
+            )
+            .collect::<Vec<_>>();
+        let duration_seconds = probe_data.duration_secs.unwrap_or_default();
+        let sidecar = read_changed_sidecar(&file, duration_seconds).await;
+        output.push(FileSegmentsUpdate {
+            file_id: file.file_id,
+            detected: segments,
+            sidecar,
+        });
+    }
 
-        if root.last_fingerprint_version != Some(root.last_added_at) {
-            store_last_fingerprint_version(db, &root.id, Some(root.last_added_at)).await?;
+    Ok(Some(output))
+}
+
+// sidecars from other tools are imported whenever their contents change since the last import
+async fn read_changed_sidecar(file: &RootFile, duration_seconds: f64) -> Option<SidecarSegments> {
+    let duration_ms = (duration_seconds > 0.0).then_some((duration_seconds * 1000.0) as i64);
+    match sidecar::read_sidecar_segments(&file.file_path, duration_ms).await {
+        Ok(Some(sidecar)) if file.sidecar_hash.as_deref() != Some(sidecar.hash.as_str()) => {
+            Some(sidecar)
         }
+        Ok(_) => None,
+        Err(error) => {
+            tracing::warn!(file_id = file.file_id, error = ?error, "failed to read segment sidecar");
+            None
+        }
+    }
+}
 
-        Ok(JobOutcome::Complete)
+// hand-edited segments win over detection and a changed sidecar replaces locked segments of
+// the kinds it has. commercials come from their own per-file job and survive this one rerunning.
+fn merge_file_segments(
+    current: Vec<StoredFileSegment>,
+    mut detected: Vec<StoredFileSegment>,
+    sidecar: Option<Vec<StoredFileSegment>>,
+) -> Vec<StoredFileSegment> {
+    let (mut locked, unlocked): (Vec<_>, Vec<_>) =
+        current.into_iter().partition(|segment| segment.locked);
+    if let Some(sidecar) = sidecar {
+        locked = super::merge_locked_segments(sidecar, locked);
     }
+
+    detected.extend(
+        unlocked
+            .into_iter()
+            .filter(|segment| segment.kind == StoredFileSegmentKind::Commercial),
+    );
+    super::merge_locked_segments(locked, detected)
 }
 
-async fn load_root_files(db: &DatabaseConnection, root_id: &str) -> anyhow::Result<Vec<RootFile>> {
+async fn load_root_files(
+    db: &DatabaseConnection,
+    root_id: &str,
+    kind: NodeKind,
+) -> anyhow::Result<Vec<RootFile>> {
     let rows = node_files::Entity::find()
         .join(JoinType::InnerJoin, node_files::Relation::Nodes.def())
         .join(JoinType::InnerJoin, node_files::Relation::Files.def())
         .join(JoinType::InnerJoin, files::Relation::Libraries.def())
         .filter(nodes::Column::RootId.eq(root_id.to_string()))
-        .filter(nodes::Column::Kind.eq(NodeKind::Episode))
+        .filter(nodes::Column::Kind.eq(kind))
         .filter(files::Column::UnavailableAt.is_null())
         .select_only()
         .column_as(files::Column::Id, "file_id")
         .column_as(files::Column::RelativePath, "relative_path")
         .column_as(libraries::Column::Path, "library_path")
         .column_as(files::Column::AudioFingerprint, "audio_fingerprint")
+        .column_as(
+            files::Column::AudioTailFingerprint,
+            "audio_tail_fingerprint",
+        )
+        .column_as(
+            files::Column::AudioBodyFingerprint,
+            "audio_body_fingerprint",
+        )
+        .column_as(files::Column::SegmentsSidecarHash, "segments_sidecar_hash")
         .order_by_asc(nodes::Column::Order)
         .order_by_asc(files::Column::Id)
         .into_model::<RootFileQueryRow>()
@@ -176,6 +425,17 @@ async fn load_root_files(db: &DatabaseConnection, root_id: &str) -> anyhow::Resu
                 .map(Fingerprint::from_bytes)
                 .transpose()
                 .with_context(|| format!("invalid stored fingerprint for file {}", file_id))?,
+            tail_fingerprint: row
+                .audio_tail_fingerprint
+                .map(Fingerprint::from_bytes)
+                .transpose()
+                .with_context(|| format!("invalid stored tail fingerprint for file {}", file_id))?,
+            body_fingerprint: row
+                .audio_body_fingerprint
+                .map(Fingerprint::from_bytes)
+                .transpose()
+                .with_context(|| format!("invalid stored body fingerprint for file {}", file_id))?,
+            sidecar_hash: row.segments_sidecar_hash,
         });
     }
 
@@ -198,34 +458,72 @@ async fn store_audio_fingerprint(
     Ok(())
 }
 
-async fn store_segments_bulk(
+async fn store_audio_tail_fingerprint(
+    db: &impl ConnectionTrait,
+    file_id: &str,
+    fingerprint: &[u8],
+) -> anyhow::Result<()> {
+    files::Entity::update(files::ActiveModel {
+        id: Set(file_id.to_string()),
+        audio_tail_fingerprint: Set(Some(fingerprint.to_vec())),
+        ..Default::default()
+    })
+    .exec(db)
+    .await?;
+
+    Ok(())
+}
+
+async fn store_audio_body_fingerprint(
+    db: &impl ConnectionTrait,
+    file_id: &str,
+    fingerprint: &[u8],
+) -> anyhow::Result<()> {
+    files::Entity::update(files::ActiveModel {
+        id: Set(file_id.to_string()),
+        audio_body_fingerprint: Set(Some(fingerprint.to_vec())),
+        ..Default::default()
+    })
+    .exec(db)
+    .await?;
+
+    Ok(())
+}
+
+async fn store_segment_updates(
     db: &DatabaseConnection,
-    updates: &[(String, Vec<u8>)],
+    updates: Vec<FileSegmentsUpdate>,
 ) -> anyhow::Result<()> {
     if updates.is_empty() {
         return Ok(());
     }
 
-    let mut builder: QueryBuilder<'_, Sqlite> =
-        QueryBuilder::new("UPDATE files SET segments_json = CASE id");
-    for (file_id, payload) in updates {
-        builder.push(" WHEN ");
-        builder.push_bind(file_id);
-        builder.push(" THEN ");
-        builder.push_bind(payload);
-    }
-    builder.push(" END WHERE id IN (");
+    let tx = db.begin().await?;
+    for update in updates {
+        // re-read the segments inside the transaction so commercials and hand edits saved
+        // while detection was running aren't overwritten
+        let Some(current) = files::Entity::find_by_id(update.file_id.clone())
+            .one(&tx)
+            .await?
+        else {
+            continue;
+        };
+        let current = load_file_segments(&current).unwrap_or_else(|error| {
+            tracing::warn!(file_id = update.file_id, error = ?error, "failed to decode stored file segments");
+            Vec::new()
+        });
 
-    let mut separated = builder.separated(", ");
-    for (file_id, _) in updates {
-        separated.push_bind(file_id);
+        let (sidecar_hash, sidecar_segments) = match update.sidecar {
+            Some(sidecar) => (Some(sidecar.hash), Some(sidecar.segments)),
+            None => (None, None),
+        };
+        let segments = merge_file_segments(current, update.detected, sidecar_segments);
+        store_file_segments(&tx, &update.file_id, &segments).await?;
+        if let Some(hash) = sidecar_hash {
+            super::store_sidecar_hash(&tx, &update.file_id, &hash).await?;
+        }
     }
-    separated.push_unseparated(")");
-
-    builder
-        .build()
-        .execute(db.get_sqlite_connection_pool())
-        .await?;
+    tx.commit().await?;
 
     Ok(())
 }
@@ -245,3 +543,96 @@ async fn store_last_fingerprint_version(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{RootFile, merge_file_segments, read_changed_sidecar};
+    use crate::segment_markers::{StoredFileSegment, StoredFileSegmentKind, sidecar};
+
+    fn segment(kind: StoredFileSegmentKind, start_ms: i64, end_ms: i64) -> StoredFileSegment {
+        StoredFileSegment {
+            kind,
+            start_ms,
+            end_ms,
+            locked: true,
+        }
+    }
+
+    fn detected(kind: StoredFileSegmentKind, start_ms: i64, end_ms: i64) -> StoredFileSegment {
+        StoredFileSegment {
+            locked: false,
+            ..segment(kind, start_ms, end_ms)
+        }
+    }
+
+    #[tokio::test]
+    async fn sidecars_are_only_read_back_when_they_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("episode.mkv");
+        let edl = "10\t70\t3\tIntro\n";
+        tokio::fs::write(sidecar::edl_path(&file_path), edl)
+            .await
+            .unwrap();
+
+        let file = |sidecar_hash: Option<String>| RootFile {
+            file_id: "file".to_string(),
+            file_path: file_path.clone(),
+            fingerprint: None,
+            tail_fingerprint: None,
+            body_fingerprint: None,
+            sidecar_hash,
+        };
+
+        let sidecar = read_changed_sidecar(&file(None), 1_400.0)
+            .await
+            .expect("new sidecar");
+        assert_eq!(sidecar.hash, sidecar::sidecar_hash(edl));
+        assert_eq!(
+            sidecar.segments,
+            vec![segment(StoredFileSegmentKind::Intro, 10_000, 70_000)]
+        );
+
+        let unchanged =
+            read_changed_sidecar(&file(Some(sidecar::sidecar_hash(edl))), 1_400.0).await;
+        assert_eq!(unchanged, None);
+    }
+
+    #[test]
+    fn detection_keeps_hand_edits_and_commercials() {
+        let current = vec![
+            segment(StoredFileSegmentKind::Intro, 0, 60_000),
+            detected(StoredFileSegmentKind::Credits, 1_100_000, 1_300_000),
+            detected(StoredFileSegmentKind::Commercial, 600_000, 720_000),
+        ];
+        let merged = merge_file_segments(
+            current.clone(),
+            vec![
+                detected(StoredFileSegmentKind::Intro, 5_000, 65_000),
+                detected(StoredFileSegmentKind::Credits, 1_200_000, 1_300_000),
+            ],
+            None,
+        );
+        assert_eq!(
+            merged,
+            vec![
+                segment(StoredFileSegmentKind::Intro, 0, 60_000),
+                detected(StoredFileSegmentKind::Commercial, 600_000, 720_000),
+                detected(StoredFileSegmentKind::Credits, 1_200_000, 1_300_000),
+            ]
+        );
+
+        // a changed sidecar replaces the hand-edited intro it covers
+        let merged = merge_file_segments(
+            current,
+            Vec::new(),
+            Some(vec![segment(StoredFileSegmentKind::Intro, 10_000, 70_000)]),
+        );
+        assert_eq!(
+            merged,
+            vec![
+                segment(StoredFileSegmentKind::Intro, 10_000, 70_000),
+                detected(StoredFileSegmentKind::Commercial, 600_000, 720_000),
+            ]
+        );
+    }
+}
@@ -1,17 +1,27 @@
+mod intro_index;
+mod job_file_commercials;
 mod job_root_intro_segments;
+pub mod sidecar;
 
-use sea_orm::DatabaseConnection;
-use std::sync::Arc;
+use crate::{entities::files, json_encoding};
+use anyhow::Context;
+use sea_orm::{ActiveValue::Set, ConnectionTrait, DatabaseConnection, EntityTrait};
+use std::{collections::HashSet, sync::Arc};
 use tokio::sync::Notify;
 use tokio_util::sync::CancellationToken;
 
-use lyra_marker::IntroRange;
+pub use intro_index::roots_missing_intros;
+use lyra_marker::{CommercialRange, CreditsRange, IntroRange, PreviewRange, RecapRange};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum StoredFileSegmentKind {
     Intro,
+    Credits,
+    Recap,
+    Preview,
+    Commercial,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -20,30 +30,135 @@ pub struct StoredFileSegment {
     pub kind: StoredFileSegmentKind,
     pub start_ms: i64,
     pub end_ms: i64,
+    // locked segments were set by hand or imported from a sidecar and are kept as-is
+    // when detection reruns
+    #[serde(default)]
+    pub locked: bool,
 }
 
-impl StoredFileSegment {
-    pub const fn intro(start_ms: i64, end_ms: i64) -> Self {
-        Self {
-            kind: StoredFileSegmentKind::Intro,
-            start_ms,
-            end_ms,
-        }
-    }
+pub fn intro_segment_from_range(range: IntroRange) -> Option<StoredFileSegment> {
+    segment_from_seconds(
+        StoredFileSegmentKind::Intro,
+        range.start_seconds,
+        range.end_seconds,
+    )
 }
 
-pub fn intro_segment_from_range(range: IntroRange) -> Option<StoredFileSegment> {
-    if !range.start_seconds.is_finite() || !range.end_seconds.is_finite() {
+pub fn credits_segment_from_range(range: CreditsRange) -> Option<StoredFileSegment> {
+    segment_from_seconds(
+        StoredFileSegmentKind::Credits,
+        range.start_seconds,
+        range.end_seconds,
+    )
+}
+
+pub fn recap_segment_from_range(range: RecapRange) -> Option<StoredFileSegment> {
+    segment_from_seconds(
+        StoredFileSegmentKind::Recap,
+        range.start_seconds,
+        range.end_seconds,
+    )
+}
+
+pub fn preview_segment_from_range(range: PreviewRange) -> Option<StoredFileSegment> {
+    segment_from_seconds(
+        StoredFileSegmentKind::Preview,
+        range.start_seconds,
+        range.end_seconds,
+    )
+}
+
+pub fn commercial_segment_from_range(range: CommercialRange) -> Option<StoredFileSegment> {
+    segment_from_seconds(
+        StoredFileSegmentKind::Commercial,
+        range.start_seconds,
+        range.end_seconds,
+    )
+}
+
+fn segment_from_seconds(
+    kind: StoredFileSegmentKind,
+    start_seconds: f32,
+    end_seconds: f32,
+) -> Option<StoredFileSegment> {
+    if !start_seconds.is_finite() || !end_seconds.is_finite() {
         return None;
     }
 
-    let start_ms = (f64::from(range.start_seconds).max(0.0) * 1000.0).round() as i64;
-    let end_ms = (f64::from(range.end_seconds).max(0.0) * 1000.0).round() as i64;
+    let start_ms = (f64::from(start_seconds).max(0.0) * 1000.0).round() as i64;
+    let end_ms = (f64::from(end_seconds).max(0.0) * 1000.0).round() as i64;
     if end_ms <= start_ms {
         return None;
     }
 
-    Some(StoredFileSegment::intro(start_ms, end_ms))
+    Some(StoredFileSegment {
+        kind,
+        start_ms,
+        end_ms,
+        locked: false,
+    })
+}
+
+// keeps every locked segment and drops detected segments of the same kind, so fixing an
+// intro by hand doesn't stop credits from being detected.
+pub fn merge_locked_segments(
+    locked: Vec<StoredFileSegment>,
+    detected: Vec<StoredFileSegment>,
+) -> Vec<StoredFileSegment> {
+    let locked_kinds = locked
+        .iter()
+        .map(|segment| segment.kind)
+        .collect::<HashSet<_>>();
+    let mut segments = locked;
+    segments.extend(
+        detected
+            .into_iter()
+            .filter(|segment| !locked_kinds.contains(&segment.kind)),
+    );
+    segments.sort_by_key(|segment| (segment.start_ms, segment.end_ms));
+    segments
+}
+
+pub fn load_file_segments(file: &files::Model) -> anyhow::Result<Vec<StoredFileSegment>> {
+    if file.segments_json.is_none() {
+        return Ok(Vec::new());
+    }
+
+    file.decode_segments()
+}
+
+pub async fn store_file_segments(
+    db: &impl ConnectionTrait,
+    file_id: &str,
+    segments: &[StoredFileSegment],
+) -> anyhow::Result<()> {
+    let payload = json_encoding::encode_json_zstd(&segments)
+        .with_context(|| format!("failed to encode segments for file {file_id}"))?;
+    files::Entity::update(files::ActiveModel {
+        id: Set(file_id.to_string()),
+        segments_json: Set(Some(payload)),
+        ..Default::default()
+    })
+    .exec(db)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn store_sidecar_hash(
+    db: &impl ConnectionTrait,
+    file_id: &str,
+    hash: &str,
+) -> anyhow::Result<()> {
+    files::Entity::update(files::ActiveModel {
+        id: Set(file_id.to_string()),
+        segments_sidecar_hash: Set(Some(hash.to_string())),
+        ..Default::default()
+    })
+    .exec(db)
+    .await?;
+
+    Ok(())
 }
 
 pub(crate) fn register_jobs(
@@ -58,6 +173,14 @@ pub(crate) fn register_jobs(
         jobs,
         heavy_jobs,
         pool,
+        wake_signal.clone(),
+        startup_scans_complete.clone(),
+    );
+    crate::jobs::register_job(
+        Arc::new(job_file_commercials::FileCommercialsJob),
+        jobs,
+        heavy_jobs,
+        pool,
         wake_signal,
         startup_scans_complete,
     );
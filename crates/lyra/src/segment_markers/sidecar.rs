@@ -0,0 +1,392 @@
+use super::{StoredFileSegment, StoredFileSegmentKind};
+use crate::assets::hash_bytes_sha256_hex;
+use anyhow::Context;
+use roxmltree::{Document, Node, ParsingOptions};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+// kodi treats action 3 as a skippable commercial break, which is the closest match for
+// intros/credits. mplayer only knows 0 (cut) and 1 (mute). neither has a segment kind, so
+// ours goes in a trailing column both of them ignore.
+const EDL_ACTION_CUT: u8 = 0;
+const EDL_ACTION_COMMERCIAL: u8 = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SidecarSegments {
+    // hash of the sidecar contents, stored on the file so edits to it get picked up
+    pub hash: String,
+    pub segments: Vec<StoredFileSegment>,
+}
+
+pub fn edl_path(file_path: &Path) -> PathBuf {
+    file_path.with_extension("edl")
+}
+
+pub fn chapters_path(file_path: &Path) -> PathBuf {
+    file_path.with_extension("chapters.xml")
+}
+
+// chapters are preferred over edl files because they carry chapter names we can map to
+// segment kinds, while edl entries only say "skip this".
+pub async fn read_sidecar_segments(
+    file_path: &Path,
+    duration_ms: Option<i64>,
+) -> anyhow::Result<Option<SidecarSegments>> {
+    let chapters_path = chapters_path(file_path);
+    if tokio::fs::try_exists(&chapters_path).await? {
+        let content = tokio::fs::read_to_string(&chapters_path)
+            .await
+            .with_context(|| format!("failed to read '{}'", chapters_path.display()))?;
+        let segments = parse_chapters_xml(&content, duration_ms)
+            .with_context(|| format!("failed to parse '{}'", chapters_path.display()))?;
+        return Ok(Some(SidecarSegments {
+            hash: sidecar_hash(&content),
+            segments,
+        }));
+    }
+
+    let edl_path = edl_path(file_path);
+    if tokio::fs::try_exists(&edl_path).await? {
+        let content = tokio::fs::read_to_string(&edl_path)
+            .await
+            .with_context(|| format!("failed to read '{}'", edl_path.display()))?;
+        return Ok(Some(SidecarSegments {
+            hash: sidecar_hash(&content),
+            segments: parse_edl(&content, duration_ms),
+        }));
+    }
+
+    Ok(None)
+}
+
+pub fn sidecar_hash(content: &str) -> String {
+    hash_bytes_sha256_hex(content.as_bytes())
+}
+
+pub fn parse_edl(content: &str, duration_ms: Option<i64>) -> Vec<StoredFileSegment> {
+    let mut segments = Vec::new();
+    for line in content.lines() {
+        let mut parts = line.split_whitespace();
+        let (Some(start), Some(end)) = (parts.next(), parts.next()) else {
+            continue;
+        };
+        let action = parts
+            .next()
+            .and_then(|action| action.parse::<u8>().ok())
+            .unwrap_or(EDL_ACTION_CUT);
+        if action != EDL_ACTION_CUT && action != EDL_ACTION_COMMERCIAL {
+            continue;
+        }
+
+        let (Some(start_ms), Some(end_ms)) = (parse_edl_time(start), parse_edl_time(end)) else {
+            continue;
+        };
+        if end_ms <= start_ms {
+            continue;
+        }
+
+        // files we wrote carry the kind, anything else is guessed from where it sits
+        let kind = parts
+            .next()
+            .and_then(kind_from_chapter_name)
+            .unwrap_or_else(|| kind_from_position(start_ms, duration_ms));
+        segments.push(StoredFileSegment {
+            kind,
+            start_ms,
+            end_ms,
+            locked: true,
+        });
+    }
+
+    segments
+}
+
+pub fn format_edl(segments: &[StoredFileSegment]) -> String {
+    let mut output = String::new();
+    for segment in segments {
+        let _ = writeln!(
+            output,
+            "{:.3}\t{:.3}\t{}\t{}",
+            segment.start_ms as f64 / 1000.0,
+            segment.end_ms as f64 / 1000.0,
+            EDL_ACTION_COMMERCIAL,
+            chapter_name(segment.kind)
+        );
+    }
+
+    output
+}
+
+pub fn parse_chapters_xml(
+    content: &str,
+    duration_ms: Option<i64>,
+) -> anyhow::Result<Vec<StoredFileSegment>> {
+    // mkvtoolnix writes a doctype, which roxmltree rejects by default
+    let document = Document::parse_with_options(
+        content,
+        ParsingOptions {
+            allow_dtd: true,
+            ..ParsingOptions::default()
+        },
+    )
+    .context("invalid chapters xml")?;
+
+    let mut chapters = Vec::new();
+    for atom in document
+        .descendants()
+        .filter(|node| node.has_tag_name("ChapterAtom"))
+    {
+        let Some(start_ms) =
+            child_text(atom, "ChapterTimeStart").and_then(|value| parse_chapter_time(&value))
+        else {
+            continue;
+        };
+        let end_ms =
+            child_text(atom, "ChapterTimeEnd").and_then(|value| parse_chapter_time(&value));
+        let name = atom
+            .children()
+            .filter(|node| node.has_tag_name("ChapterDisplay"))
+            .find_map(|display| child_text(display, "ChapterString"))
+            .unwrap_or_default();
+        chapters.push((start_ms, end_ms, name));
+    }
+
+    chapters.sort_by_key(|(start_ms, _, _)| *start_ms);
+
+    let mut segments = Vec::new();
+    for (index, (start_ms, end_ms, name)) in chapters.iter().enumerate() {
+        let Some(kind) = kind_from_chapter_name(name) else {
+            continue;
+        };
+
+        // chapters without an explicit end run until the next chapter starts
+        let end_ms = end_ms
+            .or_else(|| {
+                chapters
+                    .get(index + 1)
+                    .map(|(next_start, _, _)| *next_start)
+            })
+            .or(duration_ms);
+        let Some(end_ms) = end_ms.filter(|end_ms| *end_ms > *start_ms) else {
+            continue;
+        };
+
+        segments.push(StoredFileSegment {
+            kind,
+            start_ms: *start_ms,
+            end_ms,
+            locked: true,
+        });
+    }
+
+    Ok(segments)
+}
+
+fn child_text(node: Node<'_, '_>, tag_name: &str) -> Option<String> {
+    node.children()
+        .find(|child| child.has_tag_name(tag_name))
+        .and_then(|child| child.text())
+        .map(|text| text.trim().to_string())
+}
+
+pub fn format_chapters_xml(segments: &[StoredFileSegment]) -> String {
+    let mut output = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <!DOCTYPE Chapters SYSTEM \"matroskachapters.dtd\">\n\
+         <Chapters>\n  <EditionEntry>\n",
+    );
+    for segment in segments {
+        let _ = write!(
+            output,
+            "    <ChapterAtom>\n      \
+             <ChapterTimeStart>{}</ChapterTimeStart>\n      \
+             <ChapterTimeEnd>{}</ChapterTimeEnd>\n      \
+             <ChapterDisplay>\n        \
+             <ChapterString>{}</ChapterString>\n        \
+             <ChapterLanguage>eng</ChapterLanguage>\n      \
+             </ChapterDisplay>\n    \
+             </ChapterAtom>\n",
+            format_chapter_time(segment.start_ms),
+            format_chapter_time(segment.end_ms),
+            chapter_name(segment.kind)
+        );
+    }
+    output.push_str("  </EditionEntry>\n</Chapters>\n");
+    output
+}
+
+fn chapter_name(kind: StoredFileSegmentKind) -> &'static str {
+    match kind {
+        StoredFileSegmentKind::Intro => "Intro",
+        StoredFileSegmentKind::Credits => "Credits",
+        StoredFileSegmentKind::Recap => "Recap",
+        StoredFileSegmentKind::Preview => "Preview",
+        StoredFileSegmentKind::Commercial => "Commercial",
+    }
+}
+
+fn kind_from_chapter_name(name: &str) -> Option<StoredFileSegmentKind> {
+    let name = name.trim().to_lowercase();
+    if name.contains("recap") || name.contains("previously") {
+        Some(StoredFileSegmentKind::Recap)
+    } else if name.contains("preview") || name.contains("next episode") || name == "next time" {
+        Some(StoredFileSegmentKind::Preview)
+    } else if name.contains("commercial") || name.contains("advert") {
+        Some(StoredFileSegmentKind::Commercial)
+    } else if name.contains("intro") || name.contains("opening") || name == "op" {
+        Some(StoredFileSegmentKind::Intro)
+    } else if name.contains("credits")
+        || name.contains("ending")
+        || name.contains("outro")
+        || name == "ed"
+    {
+        Some(StoredFileSegmentKind::Credits)
+    } else {
+        None
+    }
+}
+
+// edl entries don't say what they skip, so anything in the first half of the file is
+// assumed to be an intro and anything later the credits.
+fn kind_from_position(start_ms: i64, duration_ms: Option<i64>) -> StoredFileSegmentKind {
+    match duration_ms {
+        Some(duration_ms) if start_ms >= duration_ms / 2 => StoredFileSegmentKind::Credits,
+        _ => StoredFileSegmentKind::Intro,
+    }
+}
+
+fn parse_edl_time(value: &str) -> Option<i64> {
+    if value.contains(':') {
+        return parse_chapter_time(value);
+    }
+
+    let seconds = value.parse::<f64>().ok()?;
+    (seconds.is_finite() && seconds >= 0.0).then(|| (seconds * 1000.0).round() as i64)
+}
+
+fn parse_chapter_time(value: &str) -> Option<i64> {
+    let mut parts = value.trim().rsplitn(3, ':');
+    let seconds = parts.next()?.parse::<f64>().ok()?;
+    let minutes = parts.next().map_or(Ok(0), str::parse::<i64>).ok()?;
+    let hours = parts.next().map_or(Ok(0), str::parse::<i64>).ok()?;
+    if !seconds.is_finite() || seconds < 0.0 || minutes < 0 || hours < 0 {
+        return None;
+    }
+
+    Some((hours * 3600 + minutes * 60) * 1000 + (seconds * 1000.0).round() as i64)
+}
+
+fn format_chapter_time(ms: i64) -> String {
+    let ms = ms.max(0);
+    let hours = ms / 3_600_000;
+    let minutes = (ms / 60_000) % 60;
+    let seconds = (ms / 1000) % 60;
+    let nanos = (ms % 1000) * 1_000_000;
+    format!("{hours:02}:{minutes:02}:{seconds:02}.{nanos:09}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_chapters_xml, format_edl, parse_chapters_xml, parse_edl};
+    use crate::segment_markers::{StoredFileSegment, StoredFileSegmentKind};
+
+    #[test]
+    fn edl_round_trip_classifies_by_position() {
+        let segments = parse_edl("12.5 90.0 3\n100 110 1\n1300.25\t1380 0\n", Some(1_400_000));
+        assert_eq!(
+            segments
+                .iter()
+                .map(|segment| (segment.kind, segment.start_ms, segment.end_ms))
+                .collect::<Vec<_>>(),
+            vec![
+                (StoredFileSegmentKind::Intro, 12_500, 90_000),
+                (StoredFileSegmentKind::Credits, 1_300_250, 1_380_000),
+            ]
+        );
+        assert_eq!(
+            format_edl(&segments),
+            "12.500\t90.000\t3\tIntro\n1300.250\t1380.000\t3\tCredits\n"
+        );
+    }
+
+    #[test]
+    fn edl_round_trip_keeps_every_kind() {
+        let segments = [
+            StoredFileSegmentKind::Recap,
+            StoredFileSegmentKind::Intro,
+            StoredFileSegmentKind::Commercial,
+            StoredFileSegmentKind::Credits,
+            StoredFileSegmentKind::Preview,
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(index, kind)| StoredFileSegment {
+            kind,
+            start_ms: index as i64 * 60_000,
+            end_ms: index as i64 * 60_000 + 30_500,
+            locked: true,
+        })
+        .collect::<Vec<_>>();
+
+        // position alone would call the early segments intros and the late ones credits
+        assert_eq!(parse_edl(&format_edl(&segments), Some(300_000)), segments);
+    }
+
+    #[test]
+    fn chapters_round_trip_keeps_named_segments() {
+        let segments = vec![
+            StoredFileSegment {
+                kind: StoredFileSegmentKind::Recap,
+                start_ms: 0,
+                end_ms: 45_000,
+                locked: true,
+            },
+            StoredFileSegment {
+                kind: StoredFileSegmentKind::Intro,
+                start_ms: 45_000,
+                end_ms: 135_500,
+                locked: true,
+            },
+        ];
+        let xml = format_chapters_xml(&segments);
+        assert!(xml.contains("<ChapterTimeEnd>00:02:15.500000000</ChapterTimeEnd>"));
+        assert_eq!(parse_chapters_xml(&xml, None).unwrap(), segments);
+
+        let unnamed = "<Chapters><EditionEntry>\
+            <ChapterAtom><ChapterTimeStart>00:00:00.000</ChapterTimeStart>\
+            <ChapterDisplay><ChapterString>Chapter 1</ChapterString></ChapterDisplay></ChapterAtom>\
+            <ChapterAtom><ChapterTimeStart>00:01:30.000</ChapterTimeStart>\
+            <ChapterDisplay><ChapterString>Opening</ChapterString></ChapterDisplay></ChapterAtom>\
+            <ChapterAtom><ChapterTimeStart>00:03:00.000</ChapterTimeStart>\
+            <ChapterDisplay><ChapterString>Part A</ChapterString></ChapterDisplay></ChapterAtom>\
+            </EditionEntry></Chapters>";
+        assert_eq!(
+            parse_chapters_xml(unnamed, None).unwrap(),
+            vec![StoredFileSegment {
+                kind: StoredFileSegmentKind::Intro,
+                start_ms: 90_000,
+                end_ms: 180_000,
+                locked: true,
+            }]
+        );
+    }
+
+    #[test]
+    fn chapter_names_are_unescaped_and_bad_xml_is_rejected() {
+        let xml = "<Chapters><EditionEntry><ChapterAtom>\
+            <ChapterTimeStart>00:20:00.000</ChapterTimeStart>\
+            <ChapterTimeEnd>00:21:00.000</ChapterTimeEnd>\
+            <ChapterDisplay><ChapterString>Credits &amp; Stinger</ChapterString></ChapterDisplay>\
+            </ChapterAtom></EditionEntry></Chapters>";
+        assert_eq!(
+            parse_chapters_xml(xml, None).unwrap(),
+            vec![StoredFileSegment {
+                kind: StoredFileSegmentKind::Credits,
+                start_ms: 1_200_000,
+                end_ms: 1_260_000,
+                locked: true,
+            }]
+        );
+        assert!(parse_chapters_xml("<Chapters><ChapterAtom>", None).is_err());
+    }
+}
@@ -2,24 +2,51 @@ use crate::config::get_signing_key;
 use anyhow::Result;
 use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
 use chrono::Utc;
-use ed25519_dalek::{SIGNATURE_LENGTH, Signature, Signer};
+use ed25519_dalek::{SIGNATURE_LENGTH, Signature, Signer, SigningKey};
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use std::time::Duration;
 
+// every token is signed for one purpose. postcard isn't self describing, so without this a token
+// handed out for one thing can decode as another whose payload has a similar shape.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TokenPurpose {
+    Session,
+    Asset,
+    Playback,
+    Calendar,
+}
+
 #[derive(Serialize, Deserialize)]
 struct SignedPayload<T> {
+    purpose: TokenPurpose,
     data: T,
     expires_at: i64,
 }
 
-pub fn sign<T: Serialize>(data: T, expires_in: Duration) -> Result<String> {
-    let signing_key = get_signing_key();
+pub fn sign<T: Serialize>(purpose: TokenPurpose, data: T, expires_in: Duration) -> Result<String> {
+    sign_with_key(get_signing_key(), purpose, data, expires_in)
+}
+
+pub fn verify<T: DeserializeOwned>(purpose: TokenPurpose, token: &str) -> Result<(Duration, T)> {
+    verify_with_key(get_signing_key(), purpose, token)
+}
+
+pub(crate) fn sign_with_key<T: Serialize>(
+    signing_key: &SigningKey,
+    purpose: TokenPurpose,
+    data: T,
+    expires_in: Duration,
+) -> Result<String> {
     let expires_at = Utc::now()
         .checked_add_signed(chrono::Duration::from_std(expires_in)?)
         .expect("failed to calculate expiration time")
         .timestamp();
 
-    let signed_payload = SignedPayload { data, expires_at };
+    let signed_payload = SignedPayload {
+        purpose,
+        data,
+        expires_at,
+    };
     let mut payload = postcard::to_allocvec(&signed_payload).expect("failed to serialize payload");
 
     let signature = signing_key.sign(&payload);
@@ -28,8 +55,11 @@ pub fn sign<T: Serialize>(data: T, expires_in: Duration) -> Result<String> {
     Ok(BASE64_URL_SAFE_NO_PAD.encode(&payload))
 }
 
-pub fn verify<T: DeserializeOwned>(token: &str) -> Result<(Duration, T)> {
-    let signing_key = get_signing_key();
+pub(crate) fn verify_with_key<T: DeserializeOwned>(
+    signing_key: &SigningKey,
+    purpose: TokenPurpose,
+    token: &str,
+) -> Result<(Duration, T)> {
     let payload = BASE64_URL_SAFE_NO_PAD.decode(token)?;
     if payload.len() < SIGNATURE_LENGTH {
         anyhow::bail!("invalid token");
@@ -41,6 +71,9 @@ pub fn verify<T: DeserializeOwned>(token: &str) -> Result<(Duration, T)> {
     signing_key.verify(payload_bytes, &signature)?;
 
     let payload: SignedPayload<T> = postcard::from_bytes(payload_bytes)?;
+    if payload.purpose != purpose {
+        anyhow::bail!("invalid token");
+    }
     if Utc::now().timestamp() >= payload.expires_at {
         anyhow::bail!("token has expired");
     }
@@ -0,0 +1,17 @@
+CREATE TABLE user_ratings (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    node_id TEXT NOT NULL,
+    -- 1-10, half stars on a five star scale map to rating / 2
+    rating INTEGER NOT NULL,
+    review TEXT,
+    created_at INTEGER NOT NULL DEFAULT (unixepoch()),
+    updated_at INTEGER NOT NULL DEFAULT (unixepoch()),
+
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (node_id) REFERENCES nodes(id) ON DELETE CASCADE,
+    UNIQUE (user_id, node_id),
+    CHECK (rating BETWEEN 1 AND 10)
+) STRICT;
+
+CREATE INDEX user_ratings_node_idx ON user_ratings(node_id, rating);
@@ -0,0 +1,4 @@
+ALTER TABLE files ADD COLUMN audio_tail_fingerprint BLOB;
+
+-- force roots back through segment detection so existing libraries pick up credits
+UPDATE nodes SET last_fingerprint_version = NULL WHERE parent_id IS NULL;
@@ -0,0 +1,3 @@
+-- rerun segment detection so existing roots pick up recap and preview segments.
+-- kind 1 is NodeKind::Series, movies have no neighbouring episodes to match recaps against
+UPDATE nodes SET last_fingerprint_version = NULL WHERE parent_id IS NULL AND kind = 1;
@@ -0,0 +1,17 @@
+CREATE TABLE intro_fingerprints (
+    id TEXT PRIMARY KEY,
+    root_id TEXT NOT NULL,
+    -- shared entries are matched against every root, for studio logos and idents
+    shared INTEGER NOT NULL DEFAULT 0,
+    label TEXT,
+    fingerprint BLOB NOT NULL,
+    duration_ms INTEGER NOT NULL,
+    source_file_id TEXT,
+    created_at INTEGER NOT NULL DEFAULT (unixepoch()),
+
+    FOREIGN KEY (root_id) REFERENCES nodes(id) ON DELETE CASCADE,
+    FOREIGN KEY (source_file_id) REFERENCES files(id) ON DELETE SET NULL
+) STRICT;
+
+CREATE INDEX intro_fingerprints_root_idx ON intro_fingerprints(root_id);
+CREATE INDEX intro_fingerprints_shared_idx ON intro_fingerprints(shared) WHERE shared = 1;
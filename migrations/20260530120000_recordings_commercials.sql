@@ -0,0 +1,3 @@
+ALTER TABLE libraries ADD COLUMN recordings INTEGER NOT NULL DEFAULT 0;
+
+ALTER TABLE files ADD COLUMN commercials_scanned_at INTEGER;
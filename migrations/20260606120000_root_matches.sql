@@ -0,0 +1,9 @@
+-- admin-pinned provider matches. root ids are derived from the folder and title, so this
+-- deliberately has no foreign key: a pin survives the root being dropped and re-created by
+-- a later scan.
+CREATE TABLE root_matches (
+    root_id TEXT PRIMARY KEY,
+    provider_id TEXT NOT NULL,
+    external_id TEXT NOT NULL,
+    created_at INTEGER NOT NULL DEFAULT (unixepoch())
+) STRICT;
@@ -0,0 +1,3 @@
+ALTER TABLE libraries ADD COLUMN anime INTEGER NOT NULL DEFAULT 0;
+
+ALTER TABLE node_metadata ADD COLUMN anidb_id INTEGER;
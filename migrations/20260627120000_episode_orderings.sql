@@ -0,0 +1,20 @@
+-- the alternate episode ordering picked for a series root. like root_matches there's no foreign
+-- key, so the choice survives the root being re-created by a later scan.
+CREATE TABLE root_episode_orderings (
+    root_id TEXT PRIMARY KEY,
+    provider_id TEXT NOT NULL,
+    ordering_id TEXT NOT NULL,
+    created_at INTEGER NOT NULL DEFAULT (unixepoch())
+) STRICT;
+
+-- where each episode landed in its root's chosen ordering, rewritten by every metadata sync
+CREATE TABLE node_ordering_positions (
+    node_id TEXT PRIMARY KEY,
+    root_id TEXT NOT NULL,
+    season_number INTEGER NOT NULL,
+    episode_number INTEGER NOT NULL,
+    created_at INTEGER NOT NULL DEFAULT (unixepoch()),
+    FOREIGN KEY (node_id) REFERENCES nodes(id) ON DELETE CASCADE
+) STRICT;
+
+CREATE INDEX node_ordering_positions_root_id_idx ON node_ordering_positions(root_id);
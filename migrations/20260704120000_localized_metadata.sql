@@ -0,0 +1,48 @@
+-- provider text in languages other than the primary one. rows only exist for languages the
+-- provider actually returned, and viewers pick between them by their preferred languages.
+CREATE TABLE node_metadata_localizations (
+    node_metadata_id TEXT NOT NULL,
+    language TEXT NOT NULL,
+    name TEXT,
+    description TEXT,
+    tagline TEXT,
+    created_at INTEGER NOT NULL DEFAULT (unixepoch()),
+    PRIMARY KEY (node_metadata_id, language),
+    FOREIGN KEY (node_metadata_id) REFERENCES node_metadata(id) ON DELETE CASCADE
+) STRICT;
+
+ALTER TABLE users ADD COLUMN preferred_metadata_languages TEXT NOT NULL DEFAULT '[]';
+
+-- kept apart from node_search_fts so both tables can key their rows by their source rowid
+CREATE VIRTUAL TABLE node_localization_search_fts USING fts5(
+    node_id UNINDEXED,
+    node_metadata_id UNINDEXED,
+    title,
+    description,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+CREATE TRIGGER node_metadata_localizations_search_fts_after_insert
+AFTER INSERT ON node_metadata_localizations
+BEGIN
+    INSERT INTO node_localization_search_fts(rowid, node_id, node_metadata_id, title, description)
+    SELECT new.rowid, nm.node_id, nm.id, COALESCE(new.name, ''), COALESCE(new.description, '')
+    FROM node_metadata nm
+    WHERE nm.id = new.node_metadata_id;
+END;
+
+CREATE TRIGGER node_metadata_localizations_search_fts_after_update
+AFTER UPDATE ON node_metadata_localizations
+BEGIN
+    DELETE FROM node_localization_search_fts WHERE rowid = old.rowid;
+    INSERT INTO node_localization_search_fts(rowid, node_id, node_metadata_id, title, description)
+    SELECT new.rowid, nm.node_id, nm.id, COALESCE(new.name, ''), COALESCE(new.description, '')
+    FROM node_metadata nm
+    WHERE nm.id = new.node_metadata_id;
+END;
+
+CREATE TRIGGER node_metadata_localizations_search_fts_after_delete
+AFTER DELETE ON node_metadata_localizations
+BEGIN
+    DELETE FROM node_localization_search_fts WHERE rowid = old.rowid;
+END;
@@ -0,0 +1,3 @@
+-- crew rows share root_node_cast with the cast. they carry the job they did ("Director",
+-- "Screenplay") and leave character_name empty, cast rows leave job empty.
+ALTER TABLE root_node_cast ADD COLUMN job TEXT;
@@ -0,0 +1,160 @@
+-- node_metadata is rebuilt to let user edits in as a third source. migrations run in a
+-- transaction with foreign keys on, so dropping the old table would cascade into every child
+-- table. child rows are stashed and cleared first, then put back once the new table is in place.
+CREATE TEMP TABLE node_metadata_images_stash AS SELECT * FROM node_metadata_images;
+CREATE TEMP TABLE node_metadata_recommendations_stash AS
+    SELECT * FROM node_metadata_recommendations;
+CREATE TEMP TABLE node_metadata_genres_stash AS SELECT * FROM node_metadata_genres;
+CREATE TEMP TABLE node_metadata_content_ratings_stash AS
+    SELECT * FROM node_metadata_content_ratings;
+CREATE TEMP TABLE node_metadata_localizations_stash AS
+    SELECT * FROM node_metadata_localizations;
+
+DELETE FROM node_metadata_images;
+DELETE FROM node_metadata_recommendations;
+DELETE FROM node_metadata_genres;
+DELETE FROM node_metadata_content_ratings;
+DELETE FROM node_metadata_localizations;
+
+DROP TRIGGER node_metadata_search_fts_after_insert;
+DROP TRIGGER node_metadata_search_fts_after_update;
+DROP TRIGGER node_metadata_search_fts_after_delete;
+DROP TRIGGER node_metadata_localizations_search_fts_after_insert;
+DROP TRIGGER node_metadata_localizations_search_fts_after_update;
+
+-- user rows (source 2) hold hand edits. locked_fields is a bitmask of the fields they override,
+-- the rest of the row is ignored so provider rows keep refreshing underneath.
+CREATE TABLE node_metadata_new (
+    id TEXT PRIMARY KEY,
+    node_id TEXT NOT NULL,
+    source INTEGER NOT NULL,
+    provider_id TEXT NOT NULL,
+    imdb_id TEXT,
+    tmdb_id INTEGER,
+    anidb_id INTEGER,
+    name TEXT NOT NULL,
+    description TEXT,
+    score_display TEXT,
+    score_normalized INTEGER,
+    first_aired INTEGER,
+    last_aired INTEGER,
+    status INTEGER,
+    tagline TEXT,
+    next_aired INTEGER,
+    locked_fields INTEGER NOT NULL DEFAULT 0,
+    created_at INTEGER NOT NULL DEFAULT (unixepoch()),
+    updated_at INTEGER NOT NULL DEFAULT (unixepoch()),
+
+    FOREIGN KEY (node_id) REFERENCES nodes(id) ON DELETE CASCADE,
+    CHECK (source IN (0, 1, 2))
+) STRICT;
+
+-- rowids are kept, the search index is keyed by them
+INSERT INTO node_metadata_new (
+    rowid,
+    id,
+    node_id,
+    source,
+    provider_id,
+    imdb_id,
+    tmdb_id,
+    anidb_id,
+    name,
+    description,
+    score_display,
+    score_normalized,
+    first_aired,
+    last_aired,
+    status,
+    tagline,
+    next_aired,
+    created_at,
+    updated_at
+)
+SELECT
+    rowid,
+    id,
+    node_id,
+    source,
+    provider_id,
+    imdb_id,
+    tmdb_id,
+    anidb_id,
+    name,
+    description,
+    score_display,
+    score_normalized,
+    first_aired,
+    last_aired,
+    status,
+    tagline,
+    next_aired,
+    created_at,
+    updated_at
+FROM node_metadata;
+
+DROP TABLE node_metadata;
+ALTER TABLE node_metadata_new RENAME TO node_metadata;
+
+CREATE UNIQUE INDEX node_metadata_unique_provider
+    ON node_metadata(node_id, provider_id);
+CREATE UNIQUE INDEX node_metadata_unique_source_layer
+    ON node_metadata(node_id, source);
+CREATE INDEX node_metadata_imdb_id_idx ON node_metadata(imdb_id);
+CREATE INDEX node_metadata_tmdb_id_idx ON node_metadata(tmdb_id);
+CREATE INDEX node_metadata_node_source_updated_idx
+    ON node_metadata(node_id, source, updated_at);
+
+CREATE TRIGGER node_metadata_search_fts_after_insert
+AFTER INSERT ON node_metadata
+BEGIN
+    INSERT INTO node_search_fts(rowid, node_id, node_metadata_id, title, description)
+    VALUES (new.rowid, new.node_id, new.id, new.name, COALESCE(new.description, ''));
+END;
+
+CREATE TRIGGER node_metadata_search_fts_after_update
+AFTER UPDATE ON node_metadata
+BEGIN
+    DELETE FROM node_search_fts WHERE rowid = old.rowid;
+    INSERT INTO node_search_fts(rowid, node_id, node_metadata_id, title, description)
+    VALUES (new.rowid, new.node_id, new.id, new.name, COALESCE(new.description, ''));
+END;
+
+CREATE TRIGGER node_metadata_search_fts_after_delete
+AFTER DELETE ON node_metadata
+BEGIN
+    DELETE FROM node_search_fts WHERE rowid = old.rowid;
+END;
+
+CREATE TRIGGER node_metadata_localizations_search_fts_after_insert
+AFTER INSERT ON node_metadata_localizations
+BEGIN
+    INSERT INTO node_localization_search_fts(rowid, node_id, node_metadata_id, title, description)
+    SELECT new.rowid, nm.node_id, nm.id, COALESCE(new.name, ''), COALESCE(new.description, '')
+    FROM node_metadata nm
+    WHERE nm.id = new.node_metadata_id;
+END;
+
+CREATE TRIGGER node_metadata_localizations_search_fts_after_update
+AFTER UPDATE ON node_metadata_localizations
+BEGIN
+    DELETE FROM node_localization_search_fts WHERE rowid = old.rowid;
+    INSERT INTO node_localization_search_fts(rowid, node_id, node_metadata_id, title, description)
+    SELECT new.rowid, nm.node_id, nm.id, COALESCE(new.name, ''), COALESCE(new.description, '')
+    FROM node_metadata nm
+    WHERE nm.id = new.node_metadata_id;
+END;
+
+-- localizations go back in after their insert trigger exists, clearing them above also cleared
+-- their search rows
+INSERT INTO node_metadata_images SELECT * FROM node_metadata_images_stash;
+INSERT INTO node_metadata_recommendations SELECT * FROM node_metadata_recommendations_stash;
+INSERT INTO node_metadata_genres SELECT * FROM node_metadata_genres_stash;
+INSERT INTO node_metadata_content_ratings SELECT * FROM node_metadata_content_ratings_stash;
+INSERT INTO node_metadata_localizations SELECT * FROM node_metadata_localizations_stash;
+
+DROP TABLE node_metadata_images_stash;
+DROP TABLE node_metadata_recommendations_stash;
+DROP TABLE node_metadata_genres_stash;
+DROP TABLE node_metadata_content_ratings_stash;
+DROP TABLE node_metadata_localizations_stash;
@@ -0,0 +1,34 @@
+-- artwork picked by hand, either one of the provider or local images or an upload. assets for
+-- provider images are keyed by their source url, so a pick stays valid across syncs.
+CREATE TABLE node_image_selections (
+    node_id TEXT NOT NULL,
+    kind INTEGER NOT NULL,
+    asset_id TEXT NOT NULL,
+    created_at INTEGER NOT NULL DEFAULT (unixepoch()),
+
+    PRIMARY KEY (node_id, kind),
+    FOREIGN KEY (node_id) REFERENCES nodes(id) ON DELETE CASCADE,
+    FOREIGN KEY (asset_id) REFERENCES assets(id) ON DELETE CASCADE
+) STRICT;
+
+DROP VIEW IF EXISTS asset_references;
+
+CREATE VIEW asset_references AS
+SELECT asset_id, 'node_metadata_image' AS ref_kind, node_metadata_id AS ref_id
+FROM node_metadata_images
+UNION ALL
+SELECT asset_id, 'node_local_image' AS ref_kind, node_id AS ref_id
+FROM node_local_images
+UNION ALL
+SELECT asset_id, 'node_image_selection' AS ref_kind, node_id AS ref_id
+FROM node_image_selections
+UNION ALL
+SELECT asset_id, 'file_asset' AS ref_kind, file_id AS ref_id
+FROM file_assets
+UNION ALL
+SELECT asset_id, 'file_subtitle' AS ref_kind, file_id AS ref_id
+FROM file_subtitles
+UNION ALL
+SELECT profile_asset_id AS asset_id, 'person_profile' AS ref_kind, id AS ref_id
+FROM people
+WHERE profile_asset_id IS NOT NULL;
@@ -0,0 +1,137 @@
+-- the franchise a movie belongs to, as its provider describes it. rebuilt with the rest of a
+-- metadata row's children on every sync.
+CREATE TABLE node_metadata_collections (
+    node_metadata_id TEXT PRIMARY KEY,
+    provider_id TEXT NOT NULL,
+    external_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    description TEXT,
+    poster_asset_id TEXT,
+    backdrop_asset_id TEXT,
+    created_at INTEGER NOT NULL DEFAULT (unixepoch()),
+
+    FOREIGN KEY (node_metadata_id) REFERENCES node_metadata(id) ON DELETE CASCADE,
+    FOREIGN KEY (poster_asset_id) REFERENCES assets(id) ON DELETE SET NULL,
+    FOREIGN KEY (backdrop_asset_id) REFERENCES assets(id) ON DELETE SET NULL
+) STRICT;
+
+CREATE INDEX node_metadata_collections_external_idx
+    ON node_metadata_collections(provider_id, external_id);
+
+-- collections is rebuilt to allow franchise rows (kind 3), of which there are many. migrations
+-- run with foreign keys on, so items are stashed and cleared before the old table goes.
+CREATE TEMP TABLE collection_items_stash AS SELECT * FROM collection_items;
+DELETE FROM collection_items;
+
+CREATE TABLE collections_new (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    description TEXT,
+    created_by_id TEXT,
+    -- 0 public, 1 private
+    visibility INTEGER NOT NULL,
+    -- 0 manual, 1 filter
+    resolver_kind INTEGER NOT NULL,
+    -- 0 continue watching, 3 franchise
+    kind INTEGER,
+    filter_json BLOB,
+    show_on_home INTEGER NOT NULL DEFAULT 0,
+    home_position INTEGER NOT NULL DEFAULT 0,
+    pinned INTEGER NOT NULL DEFAULT 0,
+    pinned_position INTEGER NOT NULL DEFAULT 0,
+    -- the provider collection a franchise row mirrors
+    provider_id TEXT,
+    external_id TEXT,
+    created_at INTEGER NOT NULL DEFAULT (unixepoch()),
+    updated_at INTEGER NOT NULL DEFAULT (unixepoch()),
+
+    FOREIGN KEY (created_by_id) REFERENCES users(id) ON DELETE CASCADE,
+    CHECK (visibility IN (0, 1)),
+    CHECK (resolver_kind IN (0, 1)),
+    CHECK (kind IN (0, 3) OR kind IS NULL),
+    CHECK ((kind = 3) = (external_id IS NOT NULL))
+) STRICT;
+
+INSERT INTO collections_new (
+    id,
+    name,
+    description,
+    created_by_id,
+    visibility,
+    resolver_kind,
+    kind,
+    filter_json,
+    show_on_home,
+    home_position,
+    pinned,
+    pinned_position,
+    created_at,
+    updated_at
+)
+SELECT
+    id,
+    name,
+    description,
+    created_by_id,
+    visibility,
+    resolver_kind,
+    kind,
+    filter_json,
+    show_on_home,
+    home_position,
+    pinned,
+    pinned_position,
+    created_at,
+    updated_at
+FROM collections;
+
+DROP TABLE collections;
+ALTER TABLE collections_new RENAME TO collections;
+
+CREATE UNIQUE INDEX collections_kind_idx
+    ON collections(kind)
+    WHERE kind IS NOT NULL AND kind != 3;
+CREATE UNIQUE INDEX collections_external_idx
+    ON collections(provider_id, external_id)
+    WHERE external_id IS NOT NULL;
+CREATE INDEX collections_home_idx
+    ON collections(show_on_home, home_position, created_at);
+CREATE INDEX collections_pinned_idx
+    ON collections(pinned, pinned_position, created_at);
+CREATE INDEX collections_created_by_idx
+    ON collections(created_by_id, visibility, created_at);
+
+INSERT INTO collection_items SELECT * FROM collection_items_stash;
+DROP TABLE collection_items_stash;
+
+DROP VIEW IF EXISTS asset_references;
+
+CREATE VIEW asset_references AS
+SELECT asset_id, 'node_metadata_image' AS ref_kind, node_metadata_id AS ref_id
+FROM node_metadata_images
+UNION ALL
+SELECT asset_id, 'node_local_image' AS ref_kind, node_id AS ref_id
+FROM node_local_images
+UNION ALL
+SELECT asset_id, 'node_image_selection' AS ref_kind, node_id AS ref_id
+FROM node_image_selections
+UNION ALL
+SELECT poster_asset_id AS asset_id, 'node_metadata_collection' AS ref_kind,
+    node_metadata_id AS ref_id
+FROM node_metadata_collections
+WHERE poster_asset_id IS NOT NULL
+UNION ALL
+SELECT backdrop_asset_id AS asset_id, 'node_metadata_collection' AS ref_kind,
+    node_metadata_id AS ref_id
+FROM node_metadata_collections
+WHERE backdrop_asset_id IS NOT NULL
+UNION ALL
+SELECT asset_id, 'file_asset' AS ref_kind, file_id AS ref_id
+FROM file_assets
+UNION ALL
+SELECT asset_id, 'file_subtitle' AS ref_kind, file_id AS ref_id
+FROM file_subtitles
+UNION ALL
+SELECT profile_asset_id AS asset_id, 'person_profile' AS ref_kind, id AS ref_id
+FROM people
+WHERE profile_asset_id IS NOT NULL;
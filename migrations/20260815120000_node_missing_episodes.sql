@@ -0,0 +1,14 @@
+-- episodes the provider lists for a series that no local file covers. rebuilt on every metadata
+-- sync, numbered in the root's selected episode ordering.
+CREATE TABLE node_missing_episodes (
+    root_id TEXT NOT NULL,
+    season_number INTEGER NOT NULL,
+    episode_number INTEGER NOT NULL,
+    name TEXT,
+    description TEXT,
+    first_aired INTEGER,
+    created_at INTEGER NOT NULL DEFAULT (unixepoch()),
+
+    PRIMARY KEY (root_id, season_number, episode_number),
+    FOREIGN KEY (root_id) REFERENCES nodes(id) ON DELETE CASCADE
+) STRICT;
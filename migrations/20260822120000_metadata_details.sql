@@ -0,0 +1,55 @@
+-- details only some providers know. kept out of node_metadata so local and user rows, which
+-- never have them, don't carry empty columns.
+CREATE TABLE node_metadata_details (
+    node_metadata_id TEXT PRIMARY KEY,
+    original_title TEXT,
+    original_language TEXT,
+    runtime_minutes INTEGER,
+    budget INTEGER,
+    created_at INTEGER NOT NULL DEFAULT (unixepoch()),
+
+    FOREIGN KEY (node_metadata_id) REFERENCES node_metadata(id) ON DELETE CASCADE
+) STRICT;
+
+CREATE INDEX node_metadata_details_language_idx
+    ON node_metadata_details(original_language COLLATE NOCASE);
+
+-- production companies and tv networks, told apart by kind
+CREATE TABLE node_metadata_companies (
+    id TEXT PRIMARY KEY,
+    node_metadata_id TEXT NOT NULL,
+    kind INTEGER NOT NULL,
+    provider_id TEXT NOT NULL,
+    external_id TEXT,
+    name TEXT NOT NULL,
+    origin_country TEXT,
+    position INTEGER NOT NULL,
+    created_at INTEGER NOT NULL DEFAULT (unixepoch()),
+
+    FOREIGN KEY (node_metadata_id) REFERENCES node_metadata(id) ON DELETE CASCADE
+) STRICT;
+
+CREATE UNIQUE INDEX node_metadata_companies_position_idx
+    ON node_metadata_companies(node_metadata_id, kind, position);
+CREATE INDEX node_metadata_companies_name_idx
+    ON node_metadata_companies(kind, name COLLATE NOCASE);
+
+CREATE TABLE node_metadata_keywords (
+    id TEXT PRIMARY KEY,
+    node_metadata_id TEXT NOT NULL,
+    provider_id TEXT NOT NULL,
+    external_id TEXT,
+    name TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    created_at INTEGER NOT NULL DEFAULT (unixepoch()),
+
+    FOREIGN KEY (node_metadata_id) REFERENCES node_metadata(id) ON DELETE CASCADE
+) STRICT;
+
+CREATE UNIQUE INDEX node_metadata_keywords_position_idx
+    ON node_metadata_keywords(node_metadata_id, position);
+CREATE INDEX node_metadata_keywords_name_idx
+    ON node_metadata_keywords(name COLLATE NOCASE);
+
+-- json array of the reasons given for a rating
+ALTER TABLE node_metadata_content_ratings ADD COLUMN descriptors TEXT NOT NULL DEFAULT '[]';
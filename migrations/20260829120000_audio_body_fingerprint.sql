@@ -0,0 +1,4 @@
+ALTER TABLE files ADD COLUMN audio_body_fingerprint BLOB;
+
+-- rerun segment detection on series (kind 1) so recaps are matched against whole episodes
+UPDATE nodes SET last_fingerprint_version = NULL WHERE parent_id IS NULL AND kind = 1;
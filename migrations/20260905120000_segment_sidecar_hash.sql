@@ -0,0 +1,3 @@
+-- hash of the last imported .chapters.xml/.edl sidecar, so segment detection re-imports it
+-- when the sidecar changes instead of only when a file has no locked segments
+ALTER TABLE files ADD COLUMN segments_sidecar_hash TEXT;
@@ -0,0 +1,89 @@
+-- no-transaction
+-- extras become nodes under the movie or series they belong to, so they reuse files, progress and
+-- playback like everything else while listings and search skip them by kind. sqlite can't alter a
+-- check constraint, so nodes is rebuilt. foreign keys are off for the whole migration run, see
+-- run_migrations_with_backups, so dropping the old table doesn't cascade into its children.
+PRAGMA foreign_keys = OFF;
+
+CREATE TABLE nodes_new (
+    id TEXT PRIMARY KEY,
+    library_id TEXT NOT NULL,
+    root_id TEXT NOT NULL,
+    parent_id TEXT,
+    -- 0 movie, 1 series, 2 season, 3 episode, 4 extra
+    kind INTEGER NOT NULL,
+    -- trailer, featurette, etc, only set on extras
+    extra_type INTEGER,
+    name TEXT NOT NULL,
+    "order" INTEGER NOT NULL,
+    season_number INTEGER,
+    episode_number INTEGER,
+    last_added_at INTEGER NOT NULL,
+    last_fingerprint_version INTEGER,
+    unavailable_at INTEGER,
+    created_at INTEGER NOT NULL DEFAULT (unixepoch()),
+    updated_at INTEGER NOT NULL DEFAULT (unixepoch()),
+
+    FOREIGN KEY (library_id) REFERENCES libraries(id) ON DELETE CASCADE,
+    FOREIGN KEY (root_id) REFERENCES nodes(id) ON DELETE CASCADE,
+    FOREIGN KEY (parent_id) REFERENCES nodes(id) ON DELETE CASCADE,
+    CHECK (kind IN (0, 1, 2, 3, 4)),
+    CHECK ((kind = 4) = (extra_type IS NOT NULL)),
+    CHECK (
+        (kind = 2 AND season_number IS NOT NULL AND episode_number IS NULL) OR
+        (kind = 3 AND episode_number IS NOT NULL) OR
+        (kind IN (0, 1, 4) AND season_number IS NULL AND episode_number IS NULL)
+    )
+) STRICT;
+
+INSERT INTO nodes_new (
+    id,
+    library_id,
+    root_id,
+    parent_id,
+    kind,
+    name,
+    "order",
+    season_number,
+    episode_number,
+    last_added_at,
+    last_fingerprint_version,
+    unavailable_at,
+    created_at,
+    updated_at
+)
+SELECT
+    id,
+    library_id,
+    root_id,
+    parent_id,
+    kind,
+    name,
+    "order",
+    season_number,
+    episode_number,
+    last_added_at,
+    last_fingerprint_version,
+    unavailable_at,
+    created_at,
+    updated_at
+FROM nodes;
+
+DROP TABLE nodes;
+
+ALTER TABLE nodes_new RENAME TO nodes;
+
+CREATE UNIQUE INDEX nodes_root_order_idx ON nodes(root_id, "order");
+CREATE UNIQUE INDEX nodes_parent_season_number_idx
+    ON nodes(parent_id, season_number)
+    WHERE kind = 2;
+CREATE UNIQUE INDEX nodes_parent_episode_number_idx
+    ON nodes(parent_id, episode_number)
+    WHERE kind = 3;
+CREATE INDEX nodes_library_root_idx ON nodes(library_id, root_id);
+CREATE INDEX nodes_parent_order_idx ON nodes(parent_id, "order");
+CREATE INDEX nodes_root_kind_parent_numbers_idx
+    ON nodes(root_id, kind, parent_id, season_number, episode_number, id);
+CREATE INDEX nodes_library_unavailable_idx ON nodes(library_id, unavailable_at);
+
+PRAGMA foreign_keys = ON;
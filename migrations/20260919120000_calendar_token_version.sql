@@ -0,0 +1,3 @@
+-- calendar feed links carry the version they were signed with, bumping it revokes every link
+-- a user has handed to a calendar app
+ALTER TABLE users ADD COLUMN calendar_token_version INTEGER NOT NULL DEFAULT 0;